@@ -0,0 +1,253 @@
+use crate::rate_limit::RateLimit;
+use crate::script;
+use crate::timelock::Timelock;
+use shared::{DeFiHubError, DeFiResult};
+use bitcoin::opcodes::all::OP_RETURN;
+use bitcoin::secp256k1::{All, Secp256k1};
+use bitcoin::taproot::{ControlBlock, LeafVersion, TapLeafHash, TapTree, TaprootBuilder, TaprootSpendInfo};
+use bitcoin::{Address, Network, ScriptBuf, XOnlyPublicKey};
+use lazy_static::lazy_static;
+use sha2::{Digest, Sha256};
+use std::str::FromStr;
+
+lazy_static! {
+    /// 공용 secp256k1 컨텍스트
+    pub static ref SECP: Secp256k1<All> = Secp256k1::new();
+}
+
+/// 금고 탭트리의 리프 종류
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VaultLeaf {
+    /// 출금 트리거
+    Trigger,
+    /// 타임락 이후 출금 완료
+    Complete,
+    /// 트리거된 출금 취소 (재잠금)
+    Cancel,
+    /// 회수 키로 콜드 주소에 회수
+    Recover,
+    /// 금액을 지정한 부분 출금 트리거
+    PartialTrigger,
+    /// 부분 출금 완료 (잔돈은 금고에 재잠금)
+    PartialComplete,
+    /// 금고 UTXO 병합
+    Consolidate,
+    /// 금고 UTXO를 병합하면서 출금 트리거
+    MergeTrigger,
+}
+
+/// 긴급 회수 경로
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecoveryPath {
+    /// 회수 리프에 서명하는 회수 키
+    pub recovery_key: XOnlyPublicKey,
+
+    /// 회수 자금을 받는 콜드 주소 scriptPubKey
+    pub cold_script: ScriptBuf,
+}
+
+/// OP_CAT 금고 커버넌트 - 소유자 키와 타임락으로 P2TR 출력을 결정한다
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VaultCovenant {
+    /// 트리거/취소에 서명하는 소유자 키
+    pub owner_key: XOnlyPublicKey,
+
+    /// 완료 리프의 타임락
+    pub timelock: Timelock,
+
+    /// 회수 경로 (없으면 회수 리프를 만들지 않는다)
+    pub recovery: Option<RecoveryPath>,
+
+    /// 기간별 출금 한도 (있으면 부분 출금 경로만 남긴다)
+    pub rate_limit: Option<RateLimit>,
+}
+
+impl VaultCovenant {
+    /// 새로운 커버넌트 생성 (상대 타임락)
+    pub fn new(owner_key: XOnlyPublicKey, timelock_blocks: u16) -> Self {
+        Self::with_timelock(owner_key, Timelock::Relative { blocks: timelock_blocks })
+    }
+
+    /// 타임락 방식을 지정해 커버넌트 생성
+    pub fn with_timelock(owner_key: XOnlyPublicKey, timelock: Timelock) -> Self {
+        Self {
+            owner_key,
+            timelock,
+            recovery: None,
+            rate_limit: None,
+        }
+    }
+
+    /// 회수 경로 추가
+    pub fn with_recovery(mut self, recovery: RecoveryPath) -> Self {
+        self.recovery = Some(recovery);
+        self
+    }
+
+    /// 기간별 출금 한도 추가 (상대 타임락 필요)
+    pub fn with_rate_limit(mut self, rate_limit: RateLimit) -> DeFiResult<Self> {
+        rate_limit.validate()?;
+        rate_limit.complete_timelock(self.timelock)?;
+        self.rate_limit = Some(rate_limit);
+        Ok(self)
+    }
+
+    /// 완료 리프가 실제로 쓰는 타임락 (한도가 있으면 기간 이상)
+    pub fn complete_timelock(&self) -> Timelock {
+        match self.rate_limit {
+            Some(limit) => limit.complete_timelock(self.timelock).unwrap_or(self.timelock),
+            None => self.timelock,
+        }
+    }
+
+    /// 리프 스크립트
+    ///
+    /// 회수 경로가 없는 커버넌트의 회수 리프, 한도가 있는 커버넌트의 전액 트리거/완료와 병합 트리거 리프는
+    /// 지출할 수 없는 `OP_RETURN`이며 탭트리에 들어가지 않는다.
+    pub fn leaf_script(&self, leaf: VaultLeaf) -> ScriptBuf {
+        if let Some(limit) = &self.rate_limit {
+            match leaf {
+                VaultLeaf::Trigger | VaultLeaf::Complete | VaultLeaf::MergeTrigger => {
+                    return ScriptBuf::from_bytes(vec![OP_RETURN.to_u8()]);
+                },
+                VaultLeaf::PartialComplete => {
+                    return script::rate_limited_complete_script(self.complete_timelock(), limit.cap.to_sat());
+                },
+                _ => {},
+            }
+        }
+        match leaf {
+            VaultLeaf::Trigger => script::trigger_script(&self.owner_key),
+            VaultLeaf::Complete => script::complete_script(self.timelock),
+            VaultLeaf::Cancel => script::cancel_script(&self.owner_key),
+            VaultLeaf::Recover => match &self.recovery {
+                Some(recovery) => script::recover_script(&recovery.recovery_key, &recovery.cold_script),
+                None => ScriptBuf::from_bytes(vec![OP_RETURN.to_u8()]),
+            },
+            VaultLeaf::PartialTrigger => script::partial_trigger_script(&self.owner_key),
+            VaultLeaf::PartialComplete => script::partial_complete_script(self.timelock),
+            VaultLeaf::Consolidate => script::merge_script(&self.owner_key, false),
+            VaultLeaf::MergeTrigger => script::merge_script(&self.owner_key, true),
+        }
+    }
+
+    /// 리프에 서명해야 하는 키 (완료 리프는 서명이 필요 없다)
+    pub fn signing_key(&self, leaf: VaultLeaf) -> Option<XOnlyPublicKey> {
+        match leaf {
+            VaultLeaf::Trigger
+            | VaultLeaf::Cancel
+            | VaultLeaf::PartialTrigger
+            | VaultLeaf::Consolidate
+            | VaultLeaf::MergeTrigger => Some(self.owner_key),
+            VaultLeaf::Complete | VaultLeaf::PartialComplete => None,
+            VaultLeaf::Recover => self.recovery.as_ref().map(|recovery| recovery.recovery_key),
+        }
+    }
+
+    /// 리프 해시 (sighash 프리이미지의 tapleaf_hash)
+    pub fn leaf_hash(&self, leaf: VaultLeaf) -> TapLeafHash {
+        TapLeafHash::from_script(&self.leaf_script(leaf), LeafVersion::TapScript)
+    }
+
+    /// 탭루트 지출 정보
+    ///
+    /// 트리거는 가장 자주 쓰이므로 깊이 1, 완료는 깊이 2에 둔다.
+    /// 나머지 깊이 2 서브트리에는 취소(회수 경로가 있으면 취소와 회수)를 한쪽에,
+    /// 부분 출금 트리거/완료와 병합/병합 트리거 리프를 다른 쪽에 둔다.
+    /// 한도가 있으면 부분 출금 트리거(깊이 1)와 완료(깊이 2), 병합, 취소(와 회수)만 둔다.
+    pub fn spend_info(&self) -> DeFiResult<TaprootSpendInfo> {
+        self.taproot_builder()?
+            .finalize(&SECP, nums_internal_key())
+            .map_err(|_| DeFiHubError::BitcoinTransaction("Vault taptree is not finalizable".to_string()))
+    }
+
+    /// 탭트리 (PSBT 출력의 `tap_tree` 필드용)
+    pub fn tap_tree(&self) -> DeFiResult<TapTree> {
+        TapTree::try_from(self.taproot_builder()?)
+            .map_err(|_| DeFiHubError::BitcoinTransaction("Vault taptree is incomplete".to_string()))
+    }
+
+    /// 리프 지출용 컨트롤 블록
+    pub fn control_block(&self, leaf: VaultLeaf) -> DeFiResult<ControlBlock> {
+        let script = self.leaf_script(leaf);
+        self.spend_info()?
+            .control_block(&(script, LeafVersion::TapScript))
+            .ok_or_else(|| DeFiHubError::BitcoinTransaction(format!("Missing control block for {:?} leaf", leaf)))
+    }
+
+    /// 금고 scriptPubKey
+    pub fn script_pubkey(&self) -> DeFiResult<ScriptBuf> {
+        let spend_info = self.spend_info()?;
+        Ok(ScriptBuf::new_p2tr_tweaked(spend_info.output_key()))
+    }
+
+    /// 금고 주소
+    pub fn address(&self, network: Network) -> DeFiResult<Address> {
+        let spend_info = self.spend_info()?;
+        Ok(Address::p2tr_tweaked(spend_info.output_key(), network))
+    }
+
+    fn taproot_builder(&self) -> DeFiResult<TaprootBuilder> {
+        if self.rate_limit.is_some() {
+            return self.rate_limited_builder();
+        }
+        let builder = TaprootBuilder::new()
+            .add_leaf(1, self.leaf_script(VaultLeaf::Trigger))
+            .and_then(|b| b.add_leaf(2, self.leaf_script(VaultLeaf::Complete)));
+        let builder = match self.recovery {
+            Some(_) => builder
+                .and_then(|b| b.add_leaf(4, self.leaf_script(VaultLeaf::Cancel)))
+                .and_then(|b| b.add_leaf(4, self.leaf_script(VaultLeaf::Recover))),
+            None => builder.and_then(|b| b.add_leaf(3, self.leaf_script(VaultLeaf::Cancel))),
+        };
+        let builder = builder
+            .and_then(|b| b.add_leaf(5, self.leaf_script(VaultLeaf::PartialTrigger)))
+            .and_then(|b| b.add_leaf(5, self.leaf_script(VaultLeaf::PartialComplete)))
+            .and_then(|b| b.add_leaf(5, self.leaf_script(VaultLeaf::Consolidate)))
+            .and_then(|b| b.add_leaf(5, self.leaf_script(VaultLeaf::MergeTrigger)));
+        builder.map_err(|e| DeFiHubError::BitcoinTransaction(format!("Invalid vault taptree: {}", e)))
+    }
+
+    fn rate_limited_builder(&self) -> DeFiResult<TaprootBuilder> {
+        let builder = TaprootBuilder::new()
+            .add_leaf(1, self.leaf_script(VaultLeaf::PartialTrigger))
+            .and_then(|b| b.add_leaf(2, self.leaf_script(VaultLeaf::PartialComplete)))
+            .and_then(|b| b.add_leaf(3, self.leaf_script(VaultLeaf::Consolidate)));
+        let builder = match self.recovery {
+            Some(_) => builder
+                .and_then(|b| b.add_leaf(4, self.leaf_script(VaultLeaf::Cancel)))
+                .and_then(|b| b.add_leaf(4, self.leaf_script(VaultLeaf::Recover))),
+            None => builder.and_then(|b| b.add_leaf(3, self.leaf_script(VaultLeaf::Cancel))),
+        };
+        builder.map_err(|e| DeFiHubError::BitcoinTransaction(format!("Invalid vault taptree: {}", e)))
+    }
+}
+
+/// BIP-341 NUMS 내부키
+pub fn nums_internal_key() -> XOnlyPublicKey {
+    XOnlyPublicKey::from_slice(&script::NUMS_INTERNAL_KEY).expect("NUMS point is a valid x-only key")
+}
+
+/// 소유자 문자열에서 x-only 키 결정
+///
+/// 64자리 hex x-only 공개키면 그대로 사용하고, 그 외 이름은
+/// 해시-투-커브(try-and-increment)로 비밀키를 아무도 모르는 결정적 키를 만든다.
+pub fn owner_key_from_str(owner: &str) -> XOnlyPublicKey {
+    if let Ok(key) = XOnlyPublicKey::from_str(owner) {
+        return key;
+    }
+
+    let mut counter: u32 = 0;
+    loop {
+        let mut hasher = Sha256::new();
+        hasher.update(b"Purrfect/owner");
+        hasher.update(owner.as_bytes());
+        hasher.update(counter.to_le_bytes());
+        let candidate: [u8; 32] = hasher.finalize().into();
+
+        if let Ok(key) = XOnlyPublicKey::from_slice(&candidate) {
+            return key;
+        }
+        counter += 1;
+    }
+}
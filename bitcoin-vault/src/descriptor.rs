@@ -0,0 +1,192 @@
+//! 금고 출력 디스크립터 (BIP-380/386)
+//!
+//! 금고 리프는 OP_CAT 커버넌트라 miniscript로 표현할 수 없다. 그래서 두 가지를 내보낸다.
+//! - [`VaultCovenant::descriptor`]: 탭루트 출력키를 그대로 쓰는 `rawtr(KEY)#checksum`.
+//!   Bitcoin Core의 `importdescriptors`로 감시 전용 지갑에 넣을 수 있다.
+//! - [`VaultCovenant::tree_descriptor`]: NUMS 내부키와 전체 스크립트 트리를 담은
+//!   `tr(NUMS,{rawleaf(HEX),...})#checksum`. 감사용이며 Bitcoin Core는 `rawleaf`를 읽지 못한다.
+//!
+//! [`parse_descriptor`]는 두 형식 모두 체크섬을 검사하고 scriptPubKey로 되돌린다.
+
+use crate::covenant::{VaultCovenant, SECP};
+use shared::{DeFiHubError, DeFiResult};
+use bitcoin::key::TweakedPublicKey;
+use bitcoin::taproot::{LeafVersion, TaprootBuilder};
+use bitcoin::{ScriptBuf, XOnlyPublicKey};
+use std::str::FromStr;
+
+/// BIP-380 체크섬 입력 문자 집합
+const INPUT_CHARSET: &str = "0123456789()[],'/*abcdefgh@:$%{}IJKLMNOPQRSTUVWXYZ&+-.;<=>?!^_|~ijklmnopqrstuvwxyzABCDEFGH`#\"\\ ";
+
+/// BIP-380 체크섬 문자 집합
+const CHECKSUM_CHARSET: &[u8] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+impl VaultCovenant {
+    /// 감시 전용 지갑용 `rawtr(출력키)` 디스크립터 (체크섬 포함)
+    pub fn descriptor(&self) -> DeFiResult<String> {
+        let output_key = self.spend_info()?.output_key();
+        with_checksum(&format!("rawtr({})", output_key))
+    }
+
+    /// 전체 스크립트 트리를 담은 `tr(NUMS,{rawleaf(...),...})` 디스크립터 (체크섬 포함)
+    pub fn tree_descriptor(&self) -> DeFiResult<String> {
+        let spend_info = self.spend_info()?;
+        let tree = self.tap_tree()?;
+        let leaves: Vec<(u8, String)> = tree
+            .script_leaves()
+            .map(|leaf| (leaf.merkle_branch().len() as u8, format!("rawleaf({})", hex::encode(leaf.script().as_bytes()))))
+            .collect();
+
+        let mut leaves = leaves.into_iter().peekable();
+        let tree = render_tree(&mut leaves, 0)?;
+        with_checksum(&format!("tr({},{})", spend_info.internal_key(), tree))
+    }
+}
+
+/// 깊이 우선 순서의 (깊이, 리프)를 중괄호 트리로 출력
+fn render_tree<I: Iterator<Item = (u8, String)>>(leaves: &mut std::iter::Peekable<I>, depth: u8) -> DeFiResult<String> {
+    let next_depth = leaves
+        .peek()
+        .map(|(leaf_depth, _)| *leaf_depth)
+        .ok_or_else(|| DeFiHubError::BitcoinTransaction("Taptree ended early".to_string()))?;
+    if next_depth == depth {
+        return Ok(leaves.next().map(|(_, leaf)| leaf).unwrap_or_default());
+    }
+    if next_depth < depth {
+        return Err(DeFiHubError::BitcoinTransaction("Taptree leaves are not in depth-first order".to_string()));
+    }
+    let left = render_tree(leaves, depth + 1)?;
+    let right = render_tree(leaves, depth + 1)?;
+    Ok(format!("{{{},{}}}", left, right))
+}
+
+/// BIP-380 디스크립터 체크섬 (8자)
+pub fn descriptor_checksum(descriptor: &str) -> DeFiResult<String> {
+    let mut c: u64 = 1;
+    let mut cls: u64 = 0;
+    let mut clscount = 0;
+    for ch in descriptor.chars() {
+        let pos = INPUT_CHARSET
+            .find(ch)
+            .ok_or_else(|| descriptor_error(format!("invalid character {:?}", ch)))? as u64;
+        c = polymod(c, pos & 31);
+        cls = cls * 3 + (pos >> 5);
+        clscount += 1;
+        if clscount == 3 {
+            c = polymod(c, cls);
+            cls = 0;
+            clscount = 0;
+        }
+    }
+    if clscount > 0 {
+        c = polymod(c, cls);
+    }
+    for _ in 0..8 {
+        c = polymod(c, 0);
+    }
+    c ^= 1;
+
+    Ok((0..8)
+        .map(|j| CHECKSUM_CHARSET[((c >> (5 * (7 - j))) & 31) as usize] as char)
+        .collect())
+}
+
+/// `descriptor#checksum`
+pub fn with_checksum(descriptor: &str) -> DeFiResult<String> {
+    Ok(format!("{}#{}", descriptor, descriptor_checksum(descriptor)?))
+}
+
+/// `rawtr(KEY)` 또는 `tr(KEY,TREE)`(리프는 `rawleaf(HEX)`) 디스크립터를 scriptPubKey로 변환
+///
+/// 체크섬이 있으면 반드시 맞아야 한다.
+pub fn parse_descriptor(descriptor: &str) -> DeFiResult<ScriptBuf> {
+    let body = match descriptor.split_once('#') {
+        Some((body, checksum)) => {
+            if descriptor_checksum(body)? != checksum {
+                return Err(descriptor_error("checksum mismatch"));
+            }
+            body
+        }
+        None => descriptor,
+    };
+
+    if let Some(key) = body.strip_prefix("rawtr(").and_then(|rest| rest.strip_suffix(')')) {
+        let output_key = TweakedPublicKey::dangerous_assume_tweaked(parse_key(key)?);
+        return Ok(ScriptBuf::new_p2tr_tweaked(output_key));
+    }
+
+    let inner = body
+        .strip_prefix("tr(")
+        .and_then(|rest| rest.strip_suffix(')'))
+        .ok_or_else(|| descriptor_error("expected rawtr(...) or tr(...)"))?;
+    let (key, tree) = match inner.split_once(',') {
+        Some((key, tree)) => (key, Some(tree)),
+        None => (inner, None),
+    };
+    let internal_key = parse_key(key)?;
+
+    let mut builder = TaprootBuilder::new();
+    if let Some(tree) = tree {
+        let mut leaves = Vec::new();
+        let rest = parse_tree(tree, 0, &mut leaves)?;
+        if !rest.is_empty() {
+            return Err(descriptor_error(format!("unexpected trailing {:?}", rest)));
+        }
+        for (depth, script) in leaves {
+            builder = builder
+                .add_leaf_with_ver(depth, script, LeafVersion::TapScript)
+                .map_err(|e| descriptor_error(e.to_string()))?;
+        }
+    }
+    let spend_info = builder
+        .finalize(&SECP, internal_key)
+        .map_err(|_| descriptor_error("script tree is incomplete"))?;
+    Ok(ScriptBuf::new_p2tr_tweaked(spend_info.output_key()))
+}
+
+/// 트리 식 하나를 읽어 (깊이, 스크립트)를 모으고 남은 문자열을 돌려준다
+fn parse_tree<'a>(input: &'a str, depth: u8, leaves: &mut Vec<(u8, ScriptBuf)>) -> DeFiResult<&'a str> {
+    if let Some(rest) = input.strip_prefix('{') {
+        let rest = parse_tree(rest, depth + 1, leaves)?;
+        let rest = rest.strip_prefix(',').ok_or_else(|| descriptor_error("expected ',' in script tree"))?;
+        let rest = parse_tree(rest, depth + 1, leaves)?;
+        return rest.strip_prefix('}').ok_or_else(|| descriptor_error("expected '}' in script tree"));
+    }
+
+    let rest = input
+        .strip_prefix("rawleaf(")
+        .ok_or_else(|| descriptor_error("only rawleaf(...) leaves are supported"))?;
+    let end = rest.find(')').ok_or_else(|| descriptor_error("unterminated rawleaf"))?;
+    let script = hex::decode(&rest[..end]).map_err(|e| descriptor_error(e.to_string()))?;
+    leaves.push((depth, ScriptBuf::from_bytes(script)));
+    Ok(&rest[end + 1..])
+}
+
+fn parse_key(key: &str) -> DeFiResult<XOnlyPublicKey> {
+    XOnlyPublicKey::from_str(key).map_err(|e| descriptor_error(format!("invalid x-only key {}: {}", key, e)))
+}
+
+fn polymod(c: u64, val: u64) -> u64 {
+    let c0 = c >> 35;
+    let mut c = ((c & 0x7_ffff_ffff) << 5) ^ val;
+    if c0 & 1 != 0 {
+        c ^= 0xf5_dee5_1989;
+    }
+    if c0 & 2 != 0 {
+        c ^= 0xa9_fdca_3312;
+    }
+    if c0 & 4 != 0 {
+        c ^= 0x1b_ab10_e32d;
+    }
+    if c0 & 8 != 0 {
+        c ^= 0x37_06b1_677a;
+    }
+    if c0 & 16 != 0 {
+        c ^= 0x64_4d62_6ffd;
+    }
+    c
+}
+
+fn descriptor_error(reason: impl Into<String>) -> DeFiHubError {
+    DeFiHubError::InvalidAddress(format!("Invalid descriptor: {}", reason.into()))
+}
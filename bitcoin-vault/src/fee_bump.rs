@@ -0,0 +1,150 @@
+//! CPFP 수수료 올리기
+//!
+//! 금고 트랜잭션은 미리 서명되므로 수수료율이 브로드캐스트하기 훨씬 전에 정해진다.
+//! 모든 커버넌트 트랜잭션에는 누구나 지출할 수 있는 P2A 앵커 출력([`anchor_output`])이 있으므로,
+//! 앵커와 수수료 지갑 UTXO를 함께 지출하는 자식 트랜잭션으로 부모+자식 패키지의 수수료율을
+//! 목표까지 끌어올린다. 목표는 수수료 지갑의 수수료율이며 [`FeeFunding::new`]의 기본값은
+//! [`DEFAULT_FEE_RATE`](shared::constants::DEFAULT_FEE_RATE)다.
+
+use crate::psbt::{anchor_output, dummy_witness, FeeFunding};
+use shared::constants::DUST_AMOUNT;
+use shared::{DeFiHubError, DeFiResult};
+use bitcoin::absolute::LockTime;
+use bitcoin::psbt::Psbt;
+use bitcoin::transaction::Version;
+use bitcoin::{Amount, OutPoint, ScriptBuf, Sequence, Transaction, TxIn, TxOut, Witness};
+
+/// CPFP 패키지 - 자식 PSBT와 부모/자식의 수수료와 크기
+#[derive(Clone, Debug)]
+pub struct CpfpPackage {
+    /// 앵커와 수수료 지갑 UTXO를 지출하는 자식 PSBT (앵커 입력은 이미 완성됨)
+    pub psbt: Psbt,
+
+    /// 부모 수수료
+    pub parent_fee: Amount,
+
+    /// 부모 가상 크기 (vB)
+    pub parent_vsize: u64,
+
+    /// 자식 수수료
+    pub child_fee: Amount,
+
+    /// 서명 후 자식 가상 크기 (vB)
+    pub child_vsize: u64,
+}
+
+impl CpfpPackage {
+    /// 패키지 전체 수수료
+    pub fn package_fee(&self) -> Amount {
+        self.parent_fee + self.child_fee
+    }
+
+    /// 패키지 전체 가상 크기
+    pub fn package_vsize(&self) -> u64 {
+        self.parent_vsize + self.child_vsize
+    }
+
+    /// 패키지 수수료율 (sat/vB)
+    pub fn package_fee_rate(&self) -> f64 {
+        package_fee_rate(self.package_fee(), self.package_vsize())
+    }
+}
+
+/// 수수료율 (sat/vB)
+pub fn package_fee_rate(fee: Amount, vsize: u64) -> f64 {
+    fee.to_sat() as f64 / vsize as f64
+}
+
+/// 트랜잭션의 앵커 출력
+pub fn anchor_outpoint(tx: &Transaction) -> DeFiResult<(OutPoint, TxOut)> {
+    let anchor = anchor_output();
+    tx.output
+        .iter()
+        .position(|output| output.script_pubkey == anchor.script_pubkey)
+        .map(|vout| (OutPoint::new(tx.txid(), vout as u32), tx.output[vout].clone()))
+        .ok_or_else(|| DeFiHubError::BitcoinTransaction(format!("Transaction {} has no anchor output", tx.txid())))
+}
+
+/// 부모의 앵커를 지출해 패키지 수수료율을 `wallet.fee_rate`까지 올리는 CPFP 자식
+///
+/// `parent`는 서명이 끝난 트랜잭션이어야 크기가 정확하다. 자식 수수료는 패키지 목표를 채우는 데
+/// 필요한 금액과 자식 단독으로 목표 수수료율을 내는 금액 중 큰 쪽이다.
+/// 잔돈은 `wallet.change_script`로 돌려주며, 먼지면 잔돈 없이 전부 수수료로 쓴다.
+pub fn cpfp(parent: &Transaction, parent_fee: Amount, wallet: &FeeFunding) -> DeFiResult<CpfpPackage> {
+    if wallet.inputs.is_empty() {
+        return Err(DeFiHubError::BitcoinTransaction("CPFP requires a fee wallet UTXO".to_string()));
+    }
+    let (anchor, anchor_txout) = anchor_outpoint(parent)?;
+    let parent_vsize = parent.weight().to_vbytes_ceil();
+    let available = wallet.total_input() + anchor_txout.value;
+
+    let mut child = Transaction {
+        version: Version::TWO,
+        lock_time: LockTime::ZERO,
+        input: std::iter::once(anchor)
+            .chain(wallet.inputs.iter().map(|input| input.outpoint))
+            .map(|previous_output| TxIn {
+                previous_output,
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                witness: Witness::new(),
+            })
+            .collect(),
+        output: vec![TxOut {
+            value: available,
+            script_pubkey: wallet.change_script.clone(),
+        }],
+    };
+
+    let required = |child: &Transaction| -> DeFiResult<(Amount, u64)> {
+        let vsize = signed_vsize(child, wallet)?;
+        let package = (parent_vsize + vsize) * wallet.fee_rate;
+        let fee = package.saturating_sub(parent_fee.to_sat()).max(vsize * wallet.fee_rate);
+        Ok((Amount::from_sat(fee), vsize))
+    };
+
+    let (mut child_fee, mut child_vsize) = required(&child)?;
+    match available.checked_sub(child_fee) {
+        Some(change) if change >= DUST_AMOUNT => child.output[0].value = change,
+        _ => {
+            // 잔돈 없이 남는 금액을 모두 수수료로 쓴다 (출력이 하나는 있어야 하므로 빈 OP_RETURN을 둔다)
+            child.output[0] = TxOut {
+                value: Amount::ZERO,
+                script_pubkey: ScriptBuf::new_op_return([]),
+            };
+            (child_fee, child_vsize) = required(&child)?;
+            if available < child_fee {
+                return Err(DeFiHubError::InsufficientFunds {
+                    required: child_fee.to_sat(),
+                    available: available.to_sat(),
+                });
+            }
+            child_fee = available;
+        }
+    }
+
+    let mut psbt = Psbt::from_unsigned_tx(child)
+        .map_err(|e| DeFiHubError::BitcoinTransaction(format!("Failed to create PSBT: {}", e)))?;
+    psbt.inputs[0].witness_utxo = Some(anchor_txout);
+    psbt.inputs[0].final_script_witness = Some(Witness::new());
+    for (input, fee_input) in psbt.inputs[1..].iter_mut().zip(&wallet.inputs) {
+        input.witness_utxo = Some(fee_input.txout.clone());
+    }
+
+    Ok(CpfpPackage {
+        psbt,
+        parent_fee,
+        parent_vsize,
+        child_fee,
+        child_vsize,
+    })
+}
+
+/// 수수료 지갑 입력에 서명한 뒤의 자식 가상 크기 (앵커 입력의 증인은 비어 있다)
+fn signed_vsize(child: &Transaction, wallet: &FeeFunding) -> DeFiResult<u64> {
+    let mut sized = child.clone();
+    for (input, fee_input) in sized.input[1..].iter_mut().zip(&wallet.inputs) {
+        input.witness = dummy_witness(&fee_input.txout.script_pubkey)?;
+    }
+    Ok(sized.weight().to_vbytes_ceil())
+}
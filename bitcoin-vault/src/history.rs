@@ -0,0 +1,254 @@
+//! 금고 이벤트 이력 (해시 체인)
+//!
+//! 금고 상태 전환마다 이전 상태, 새 상태, 행위자, 블록 높이와 직전 항목의 해시를 담은 항목을
+//! 덧붙인다. 각 항목의 해시는 `SHA256("Purrfect/vault-event" ‖ JSON(해시를 뺀 항목))`이며
+//! 첫 항목의 직전 해시는 0이다. 중간 항목을 고치거나 지우면 해시 연결이 끊기고,
+//! 마지막 항목을 지우면 금고의 현재 상태와 어긋나므로 [`BitcoinVault::verify_history`]가
+//! 찾아낸다. 파일 전체를 바꿔치기하거나 예전 파일로 되돌린 경우는 금고 파일 밖에 보관한
+//! [`HistoryAnchor`](금고 저장소가 저장할 때마다 갱신한다)나 외부에 보관한 [`VaultHistory::head`]와
+//! 비교해 확인한다.
+//!
+//! [`BitcoinVault::verify_history`]: crate::vault::BitcoinVault::verify_history
+
+use shared::{DeFiHubError, DeFiResult, VaultState};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// 해시 도메인 구분 태그
+const EVENT_TAG: &[u8] = b"Purrfect/vault-event";
+
+/// 첫 항목의 직전 해시
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// 금고 이벤트 종류
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VaultAction {
+    /// 금고 생성
+    Created,
+    /// 이력이 없던 예전 금고 파일을 현재 상태로 가져옴
+    Imported,
+    /// `update_state`로 상태 직접 변경
+    StateUpdated,
+    /// 출금 트리거
+    WithdrawalTriggered,
+    /// 체인에서 발견한 트리거 반영
+    TriggerObserved,
+    /// 출금 취소
+    WithdrawalCancelled,
+    /// 출금 완료 (부분 출금이면 `Inactive`로 돌아간다)
+    WithdrawalCompleted,
+    /// 긴급 회수
+    Recovered,
+    /// 여러 UTXO 중 하나만 회수 (상태는 그대로다)
+    UtxoRecovered,
+    /// 롤업 연동
+    BridgedToRollup,
+    /// 롤업에서 복귀
+    UnbridgedFromRollup,
+    /// BitVMX 상태 루트 갱신
+    BitvmxStateUpdated,
+}
+
+/// 이력 항목
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct VaultEvent {
+    /// 0부터 시작하는 순번
+    pub sequence: u64,
+
+    /// 이벤트 종류
+    pub action: VaultAction,
+
+    /// 전환 전 상태
+    pub previous_state: VaultState,
+
+    /// 전환 후 상태
+    pub new_state: VaultState,
+
+    /// 행위자 (기본값: 금고 소유자)
+    pub actor: String,
+
+    /// 전환 시점의 블록 높이 (알 수 있을 때)
+    pub block_height: Option<u32>,
+
+    /// 기록 시간
+    pub timestamp: DateTime<Utc>,
+
+    /// 직전 항목의 해시 (hex)
+    pub prev_hash: String,
+
+    /// 이 항목의 해시 (hex)
+    pub hash: String,
+}
+
+/// 해시 계산에 쓰는 항목 내용 (`hash` 제외)
+#[derive(Serialize)]
+struct EventBody<'a> {
+    sequence: u64,
+    action: VaultAction,
+    previous_state: &'a VaultState,
+    new_state: &'a VaultState,
+    actor: &'a str,
+    block_height: Option<u32>,
+    timestamp: &'a DateTime<Utc>,
+    prev_hash: &'a str,
+}
+
+impl VaultEvent {
+    /// 항목 내용으로 계산한 해시
+    pub fn compute_hash(&self) -> String {
+        let body = EventBody {
+            sequence: self.sequence,
+            action: self.action,
+            previous_state: &self.previous_state,
+            new_state: &self.new_state,
+            actor: &self.actor,
+            block_height: self.block_height,
+            timestamp: &self.timestamp,
+            prev_hash: &self.prev_hash,
+        };
+        let mut hasher = Sha256::new();
+        hasher.update(EVENT_TAG);
+        hasher.update(serde_json::to_vec(&body).expect("vault event serializes"));
+        hex::encode(hasher.finalize())
+    }
+}
+
+/// 금고 파일 밖에 보관하는 이력 위치 - 이 순번의 항목 해시가 `head`여야 한다
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct HistoryAnchor {
+    /// 마지막으로 보관한 항목의 순번
+    pub sequence: u64,
+
+    /// 그 항목의 해시 (hex)
+    pub head: String,
+}
+
+/// 덧붙이기만 하는 금고 이력
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(transparent)]
+pub struct VaultHistory {
+    events: Vec<VaultEvent>,
+}
+
+impl VaultHistory {
+    /// 첫 항목 하나로 된 이력
+    pub fn genesis(action: VaultAction, state: VaultState, actor: String, block_height: Option<u32>) -> Self {
+        let mut history = Self::default();
+        history.record(action, state.clone(), state, actor, block_height);
+        history
+    }
+
+    /// 항목 덧붙이기
+    pub fn record(
+        &mut self,
+        action: VaultAction,
+        previous_state: VaultState,
+        new_state: VaultState,
+        actor: String,
+        block_height: Option<u32>,
+    ) -> &VaultEvent {
+        let mut event = VaultEvent {
+            sequence: self.events.len() as u64,
+            action,
+            previous_state,
+            new_state,
+            actor,
+            block_height,
+            timestamp: Utc::now(),
+            prev_hash: self.head().to_string(),
+            hash: String::new(),
+        };
+        event.hash = event.compute_hash();
+        self.events.push(event);
+        self.events.last().expect("event was just pushed")
+    }
+
+    /// 모든 항목 (오래된 순)
+    pub fn events(&self) -> &[VaultEvent] {
+        &self.events
+    }
+
+    /// 항목 수
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    /// 항목이 없는지 확인
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// 마지막 항목의 해시 (비어 있으면 [`GENESIS_HASH`])
+    pub fn head(&self) -> &str {
+        self.events.last().map_or(GENESIS_HASH, |event| event.hash.as_str())
+    }
+
+    /// 마지막 항목의 앵커 (비어 있으면 `None`)
+    pub fn anchor(&self) -> Option<HistoryAnchor> {
+        self.events.last().map(|event| HistoryAnchor {
+            sequence: event.sequence,
+            head: event.hash.clone(),
+        })
+    }
+
+    /// 해시 체인 검증
+    ///
+    /// 순번이 0부터 이어지는지, 각 항목의 해시와 직전 해시 연결이 맞는지, 이전 상태가 직전 항목의
+    /// 새 상태와 같은지 확인한다. 첫 실패 항목의 위치를 [`DeFiHubError::HistoryTampered`]로 알린다.
+    pub fn verify(&self) -> DeFiResult<()> {
+        let first = self.events.first().ok_or_else(|| tampered(0, "history is empty"))?;
+        if !matches!(first.action, VaultAction::Created | VaultAction::Imported) {
+            return Err(tampered(0, "history does not start with a genesis entry"));
+        }
+
+        let mut prev_hash = GENESIS_HASH;
+        let mut prev_state = &first.previous_state;
+        for (index, event) in self.events.iter().enumerate() {
+            if event.sequence != index as u64 {
+                return Err(tampered(index, format!("sequence {} where {} was expected", event.sequence, index)));
+            }
+            if event.prev_hash != prev_hash {
+                return Err(tampered(index, "previous hash does not link to the prior entry"));
+            }
+            if event.compute_hash() != event.hash {
+                return Err(tampered(index, "entry hash does not match its contents"));
+            }
+            if &event.previous_state != prev_state {
+                return Err(tampered(index, "previous state does not match the prior entry"));
+            }
+            prev_hash = &event.hash;
+            prev_state = &event.new_state;
+        }
+        Ok(())
+    }
+
+    /// 체인을 검증하고 마지막 해시가 외부에 보관한 값과 같은지 확인
+    pub fn verify_head(&self, head: &str) -> DeFiResult<()> {
+        self.verify()?;
+        if self.head() != head {
+            return Err(tampered(self.events.len(), format!("head {} does not match anchored {}", self.head(), head)));
+        }
+        Ok(())
+    }
+
+    /// 체인을 검증하고 앵커의 항목이 그대로 남아 있는지 확인
+    ///
+    /// 앵커 뒤에 덧붙은 항목은 허용한다 (앵커 갱신 전에 중단된 저장).
+    pub fn verify_anchor(&self, anchor: &HistoryAnchor) -> DeFiResult<()> {
+        self.verify()?;
+        let index = usize::try_from(anchor.sequence).unwrap_or(usize::MAX);
+        match self.events.get(index) {
+            Some(event) if event.hash == anchor.head => Ok(()),
+            Some(_) => Err(tampered(index, format!("entry does not match anchored head {}", anchor.head))),
+            None => Err(tampered(
+                self.events.len(),
+                format!("history ends before anchored entry {}", anchor.sequence),
+            )),
+        }
+    }
+}
+
+pub(crate) fn tampered(index: usize, reason: impl Into<String>) -> DeFiHubError {
+    DeFiHubError::HistoryTampered { index, reason: reason.into() }
+}
@@ -0,0 +1,924 @@
+//! 탭스크립트 인터프리터 (OP_CAT 포함)
+//!
+//! OP_CAT이 활성화된 노드 없이도 금고 트랜잭션의 지출을 단계별로 실행해
+//! 실행 추적이나 정확한 실패 원인을 돌려준다. BIP-341/342 합의 규칙을 따르되
+//! 표준성 규칙 중 최소 숫자 인코딩(MINIMALDATA)도 함께 검사한다.
+//! OP_SUCCESSx(OP_CAT 제외)는 합의상 무조건 성공이지만 검증 도구로서는 실패로 보고한다.
+
+use crate::covenant::SECP;
+use shared::DeFiHubError;
+use bitcoin::hashes::{hash160, ripemd160, sha1, sha256, sha256d, Hash};
+use bitcoin::opcodes::all::*;
+use bitcoin::opcodes::Opcode;
+use bitcoin::script::Instruction;
+use bitcoin::secp256k1::{schnorr, Message};
+use bitcoin::sighash::{Prevouts, SighashCache, TapSighashType};
+use bitcoin::taproot::{ControlBlock, LeafVersion, TapLeafHash, TAPROOT_ANNEX_PREFIX};
+use bitcoin::{Script, Transaction, TxOut, XOnlyPublicKey};
+use thiserror::Error;
+
+/// 스택 원소 최대 크기
+pub const MAX_ELEMENT_SIZE: usize = 520;
+
+/// 스택 + 알트스택 최대 원소 수
+pub const MAX_STACK_SIZE: usize = 1000;
+
+/// 서명 검사 1회당 검증 가중치 예산 소모량 (BIP-342)
+pub const VALIDATION_WEIGHT_PER_SIGOP: i64 = 50;
+
+/// 스크립트 실패 원인
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ScriptFailure {
+    #[error("input {0} does not exist")]
+    InputOutOfRange(usize),
+
+    #[error("prevouts count {prevouts} does not match input count {inputs}")]
+    PrevoutsMismatch { prevouts: usize, inputs: usize },
+
+    #[error("spent output is not a P2TR output")]
+    NotTaproot,
+
+    #[error("witness is empty")]
+    EmptyWitness,
+
+    #[error("annex is not supported")]
+    AnnexNotSupported,
+
+    #[error("invalid control block: {0}")]
+    InvalidControlBlock(String),
+
+    #[error("unsupported leaf version {0:#04x}")]
+    UnsupportedLeafVersion(u8),
+
+    #[error("control block does not commit to the script")]
+    CommitmentMismatch,
+
+    #[error("script decode error: {0}")]
+    ScriptDecode(String),
+
+    #[error("OP_SUCCESS opcode {0:#04x} makes the spend unconditionally valid")]
+    OpSuccess(u8),
+
+    #[error("disabled or unknown opcode {0}")]
+    BadOpcode(String),
+
+    #[error("{op} needs {needed} stack items, found {found}")]
+    StackUnderflow { op: String, needed: usize, found: usize },
+
+    #[error("altstack is empty")]
+    AltStackUnderflow,
+
+    #[error("stack element of {0} bytes exceeds 520")]
+    ElementTooLarge(usize),
+
+    #[error("stack size exceeds 1000")]
+    StackOverflow,
+
+    #[error("number of {0} bytes exceeds the allowed size")]
+    NumberOverflow(usize),
+
+    #[error("number is not minimally encoded")]
+    NonMinimalNumber,
+
+    #[error("OP_IF/OP_NOTIF argument must be empty or 0x01")]
+    MinimalIf,
+
+    #[error("unbalanced conditional")]
+    UnbalancedConditional,
+
+    #[error("{0} failed")]
+    VerifyFailed(String),
+
+    #[error("OP_RETURN executed")]
+    OpReturn,
+
+    #[error("empty public key")]
+    EmptyPublicKey,
+
+    #[error("invalid signature encoding of {0} bytes")]
+    InvalidSignatureEncoding(usize),
+
+    #[error("invalid sighash type {0:#04x}")]
+    InvalidSighashType(u8),
+
+    #[error("sighash computation failed: {0}")]
+    Sighash(String),
+
+    #[error("schnorr signature verification failed")]
+    InvalidSignature,
+
+    #[error("validation weight budget exceeded")]
+    SigopBudgetExceeded,
+
+    #[error("negative locktime")]
+    NegativeLocktime,
+
+    #[error("OP_CHECKSEQUENCEVERIFY not satisfied: {0}")]
+    CsvNotSatisfied(String),
+
+    #[error("OP_CHECKLOCKTIMEVERIFY not satisfied: {0}")]
+    CltvNotSatisfied(String),
+
+    #[error("final stack must contain exactly one true element, found {0} elements")]
+    CleanStack(usize),
+
+    #[error("final stack element is false")]
+    EvalFalse,
+}
+
+/// 실행 단계 기록
+#[derive(Clone, Debug)]
+pub struct TraceStep {
+    /// 스크립트 내 바이트 위치
+    pub position: usize,
+
+    /// 실행한 명령 (예: `OP_CAT`, `PUSH 0x...`)
+    pub instruction: String,
+
+    /// 실행 여부 (비활성 분기 안이면 false)
+    pub executed: bool,
+
+    /// 실행 후 메인 스택 (hex, 마지막이 top)
+    pub stack: Vec<String>,
+
+    /// 실행 후 알트스택 깊이
+    pub altstack_depth: usize,
+}
+
+/// 실행 추적
+#[derive(Clone, Debug, Default)]
+pub struct ExecutionTrace {
+    /// 초기 증인 스택 (hex)
+    pub initial_stack: Vec<String>,
+
+    /// 단계별 기록
+    pub steps: Vec<TraceStep>,
+}
+
+impl ExecutionTrace {
+    /// 사람이 읽기 쉬운 형태로 출력
+    pub fn render(&self) -> String {
+        let mut out = format!("initial: [{}]\n", self.initial_stack.join(", "));
+        for step in &self.steps {
+            let marker = if step.executed { ' ' } else { '-' };
+            out.push_str(&format!(
+                "{}{:>5}  {:<28} [{}] alt={}\n",
+                marker,
+                step.position,
+                step.instruction,
+                step.stack.join(", "),
+                step.altstack_depth
+            ));
+        }
+        out
+    }
+}
+
+/// 스크립트 실행 에러 - 실패 원인, 위치, 실패 직전까지의 추적
+#[derive(Error, Debug, Clone)]
+#[error("{reason}{}", .position.map(|p| format!(" at script position {}", p)).unwrap_or_default())]
+pub struct ScriptError {
+    pub reason: ScriptFailure,
+    pub position: Option<usize>,
+    pub trace: ExecutionTrace,
+}
+
+impl From<ScriptError> for DeFiHubError {
+    fn from(err: ScriptError) -> Self {
+        DeFiHubError::BitcoinTransaction(format!("Script verification failed: {}", err))
+    }
+}
+
+/// 트랜잭션 입력 하나를 검증하는 인터프리터
+pub struct TapscriptInterpreter<'a> {
+    tx: &'a Transaction,
+    input_index: usize,
+    prevouts: &'a [TxOut],
+}
+
+impl<'a> TapscriptInterpreter<'a> {
+    /// 새로운 인터프리터 생성 - `prevouts`는 모든 입력의 이전 출력 (입력 순서대로)
+    pub fn new(tx: &'a Transaction, input_index: usize, prevouts: &'a [TxOut]) -> Self {
+        Self {
+            tx,
+            input_index,
+            prevouts,
+        }
+    }
+
+    /// 입력 검증 (키 경로 또는 스크립트 경로)
+    pub fn verify(&self) -> Result<ExecutionTrace, ScriptError> {
+        let fail = |reason| ScriptError {
+            reason,
+            position: None,
+            trace: ExecutionTrace::default(),
+        };
+
+        let input = self
+            .tx
+            .input
+            .get(self.input_index)
+            .ok_or_else(|| fail(ScriptFailure::InputOutOfRange(self.input_index)))?;
+        if self.prevouts.len() != self.tx.input.len() {
+            return Err(fail(ScriptFailure::PrevoutsMismatch {
+                prevouts: self.prevouts.len(),
+                inputs: self.tx.input.len(),
+            }));
+        }
+
+        let spent_spk = &self.prevouts[self.input_index].script_pubkey;
+        if !spent_spk.is_p2tr() {
+            return Err(fail(ScriptFailure::NotTaproot));
+        }
+        let output_key = XOnlyPublicKey::from_slice(&spent_spk.as_bytes()[2..34])
+            .map_err(|_| fail(ScriptFailure::NotTaproot))?;
+
+        let mut witness: Vec<Vec<u8>> = input.witness.iter().map(|w| w.to_vec()).collect();
+        if witness.is_empty() {
+            return Err(fail(ScriptFailure::EmptyWitness));
+        }
+        if witness.len() >= 2 && witness.last().is_some_and(|w| w.first() == Some(&TAPROOT_ANNEX_PREFIX)) {
+            return Err(fail(ScriptFailure::AnnexNotSupported));
+        }
+
+        // 키 경로 지출
+        if witness.len() == 1 {
+            let sig = witness.pop().unwrap_or_default();
+            let sighash = self.signature_hash(&sig, None).map_err(fail)?;
+            self.verify_schnorr(&sig, &output_key, sighash).map_err(fail)?;
+            return Ok(ExecutionTrace::default());
+        }
+
+        // 스크립트 경로 지출
+        let control_block_bytes = witness.pop().unwrap_or_default();
+        let script_bytes = witness.pop().unwrap_or_default();
+        let control_block = ControlBlock::decode(&control_block_bytes)
+            .map_err(|e| fail(ScriptFailure::InvalidControlBlock(e.to_string())))?;
+        if control_block.leaf_version != LeafVersion::TapScript {
+            return Err(fail(ScriptFailure::UnsupportedLeafVersion(
+                control_block.leaf_version.to_consensus(),
+            )));
+        }
+
+        let script = Script::from_bytes(&script_bytes);
+        if !control_block.verify_taproot_commitment(&SECP, output_key, script) {
+            return Err(fail(ScriptFailure::CommitmentMismatch));
+        }
+
+        // BIP-342: 검증 가중치 예산 = 50 + 증인 직렬화 크기
+        let budget = VALIDATION_WEIGHT_PER_SIGOP + bitcoin::consensus::serialize(&input.witness).len() as i64;
+        let leaf_hash = TapLeafHash::from_script(script, LeafVersion::TapScript);
+
+        let machine = Machine::new(self, leaf_hash, budget, witness);
+        machine.run(script)
+    }
+
+    /// 서명과 sighash 타입으로 BIP-341 sighash 계산
+    fn signature_hash(&self, sig: &[u8], leaf_hash: Option<TapLeafHash>) -> Result<Message, ScriptFailure> {
+        let sighash_type = match sig.len() {
+            64 => TapSighashType::Default,
+            65 => {
+                if sig[64] == 0x00 {
+                    return Err(ScriptFailure::InvalidSighashType(0x00));
+                }
+                TapSighashType::from_consensus_u8(sig[64])
+                    .map_err(|_| ScriptFailure::InvalidSighashType(sig[64]))?
+            }
+            len => return Err(ScriptFailure::InvalidSignatureEncoding(len)),
+        };
+
+        let prevouts = Prevouts::All(self.prevouts);
+        let mut cache = SighashCache::new(self.tx);
+        let sighash = match leaf_hash {
+            Some(leaf_hash) => cache.taproot_script_spend_signature_hash(
+                self.input_index,
+                &prevouts,
+                leaf_hash,
+                sighash_type,
+            ),
+            None => cache.taproot_key_spend_signature_hash(self.input_index, &prevouts, sighash_type),
+        }
+        .map_err(|e| ScriptFailure::Sighash(e.to_string()))?;
+
+        Ok(Message::from_digest(sighash.to_byte_array()))
+    }
+
+    fn verify_schnorr(&self, sig: &[u8], key: &XOnlyPublicKey, msg: Message) -> Result<(), ScriptFailure> {
+        let signature = schnorr::Signature::from_slice(&sig[..64])
+            .map_err(|_| ScriptFailure::InvalidSignatureEncoding(sig.len()))?;
+        SECP.verify_schnorr(&signature, &msg, key)
+            .map_err(|_| ScriptFailure::InvalidSignature)
+    }
+}
+
+/// 스크립트 실행 상태
+struct Machine<'i, 'a> {
+    interpreter: &'i TapscriptInterpreter<'a>,
+    leaf_hash: TapLeafHash,
+    budget: i64,
+    stack: Vec<Vec<u8>>,
+    altstack: Vec<Vec<u8>>,
+    exec_stack: Vec<bool>,
+    trace: ExecutionTrace,
+}
+
+impl<'i, 'a> Machine<'i, 'a> {
+    fn new(
+        interpreter: &'i TapscriptInterpreter<'a>,
+        leaf_hash: TapLeafHash,
+        budget: i64,
+        stack: Vec<Vec<u8>>,
+    ) -> Self {
+        let trace = ExecutionTrace {
+            initial_stack: stack.iter().map(hex::encode).collect(),
+            steps: Vec::new(),
+        };
+        Self {
+            interpreter,
+            leaf_hash,
+            budget,
+            stack,
+            altstack: Vec::new(),
+            exec_stack: Vec::new(),
+            trace,
+        }
+    }
+
+    fn run(mut self, script: &Script) -> Result<ExecutionTrace, ScriptError> {
+        if let Some(element) = self.stack.iter().find(|e| e.len() > MAX_ELEMENT_SIZE) {
+            let reason = ScriptFailure::ElementTooLarge(element.len());
+            return Err(self.error(reason, None));
+        }
+
+        // BIP-342: OP_SUCCESSx는 실행 전에 검사한다
+        for item in script.instruction_indices() {
+            match item {
+                Ok((_, Instruction::Op(op))) if is_op_success(op) => {
+                    return Err(self.error(ScriptFailure::OpSuccess(op.to_u8()), None));
+                }
+                Ok(_) => {}
+                Err(e) => return Err(self.error(ScriptFailure::ScriptDecode(e.to_string()), None)),
+            }
+        }
+
+        for item in script.instruction_indices() {
+            let (position, instruction) = match item {
+                Ok(item) => item,
+                Err(e) => return Err(self.error(ScriptFailure::ScriptDecode(e.to_string()), None)),
+            };
+
+            let executing = self.exec_stack.iter().all(|branch| *branch);
+            let description = describe(&instruction);
+
+            if let Err(reason) = self.step(&instruction, executing) {
+                self.record(position, description, executing);
+                return Err(self.error(reason, Some(position)));
+            }
+            self.record(position, description, executing);
+
+            if self.stack.len() + self.altstack.len() > MAX_STACK_SIZE {
+                return Err(self.error(ScriptFailure::StackOverflow, Some(position)));
+            }
+        }
+
+        if !self.exec_stack.is_empty() {
+            return Err(self.error(ScriptFailure::UnbalancedConditional, None));
+        }
+        if self.stack.len() != 1 {
+            let depth = self.stack.len();
+            return Err(self.error(ScriptFailure::CleanStack(depth), None));
+        }
+        if !cast_to_bool(&self.stack[0]) {
+            return Err(self.error(ScriptFailure::EvalFalse, None));
+        }
+
+        Ok(self.trace)
+    }
+
+    fn record(&mut self, position: usize, instruction: String, executed: bool) {
+        self.trace.steps.push(TraceStep {
+            position,
+            instruction,
+            executed,
+            stack: self.stack.iter().map(hex::encode).collect(),
+            altstack_depth: self.altstack.len(),
+        });
+    }
+
+    fn error(&self, reason: ScriptFailure, position: Option<usize>) -> ScriptError {
+        ScriptError {
+            reason,
+            position,
+            trace: self.trace.clone(),
+        }
+    }
+
+    fn step(&mut self, instruction: &Instruction, executing: bool) -> Result<(), ScriptFailure> {
+        let op = match instruction {
+            Instruction::PushBytes(bytes) => {
+                if executing {
+                    self.push(bytes.as_bytes().to_vec())?;
+                }
+                return Ok(());
+            }
+            Instruction::Op(op) => *op,
+        };
+
+        // 조건문은 비활성 분기 안에서도 처리한다
+        match op {
+            OP_IF | OP_NOTIF => {
+                let mut value = false;
+                if executing {
+                    let condition = self.pop(op)?;
+                    if !condition.is_empty() && condition != [0x01] {
+                        return Err(ScriptFailure::MinimalIf);
+                    }
+                    value = condition == [0x01];
+                    if op == OP_NOTIF {
+                        value = !value;
+                    }
+                }
+                self.exec_stack.push(value);
+                return Ok(());
+            }
+            OP_ELSE => {
+                let top = self.exec_stack.last_mut().ok_or(ScriptFailure::UnbalancedConditional)?;
+                *top = !*top;
+                return Ok(());
+            }
+            OP_ENDIF => {
+                self.exec_stack.pop().ok_or(ScriptFailure::UnbalancedConditional)?;
+                return Ok(());
+            }
+            _ => {}
+        }
+
+        if !executing {
+            return Ok(());
+        }
+
+        match op {
+            OP_PUSHNUM_NEG1 => self.push_num(-1)?,
+            op if op.to_u8() >= OP_PUSHNUM_1.to_u8() && op.to_u8() <= OP_PUSHNUM_16.to_u8() => {
+                self.push_num((op.to_u8() - OP_PUSHNUM_1.to_u8() + 1) as i64)?
+            }
+            OP_NOP => {}
+            op if op.to_u8() == OP_NOP1.to_u8() || (op.to_u8() >= OP_NOP4.to_u8() && op.to_u8() <= OP_NOP10.to_u8()) => {}
+            OP_VERIFY => {
+                let top = self.pop(op)?;
+                if !cast_to_bool(&top) {
+                    return Err(ScriptFailure::VerifyFailed("OP_VERIFY".to_string()));
+                }
+            }
+            OP_RETURN => return Err(ScriptFailure::OpReturn),
+
+            // 스택 조작
+            OP_TOALTSTACK => {
+                let top = self.pop(op)?;
+                self.altstack.push(top);
+            }
+            OP_FROMALTSTACK => {
+                let top = self.altstack.pop().ok_or(ScriptFailure::AltStackUnderflow)?;
+                self.push(top)?;
+            }
+            OP_2DROP => {
+                self.require(op, 2)?;
+                self.stack.truncate(self.stack.len() - 2);
+            }
+            OP_2DUP => {
+                self.require(op, 2)?;
+                let n = self.stack.len();
+                let (a, b) = (self.stack[n - 2].clone(), self.stack[n - 1].clone());
+                self.push(a)?;
+                self.push(b)?;
+            }
+            OP_3DUP => {
+                self.require(op, 3)?;
+                let n = self.stack.len();
+                for i in 0..3 {
+                    let item = self.stack[n - 3 + i].clone();
+                    self.push(item)?;
+                }
+            }
+            OP_2OVER => {
+                self.require(op, 4)?;
+                let n = self.stack.len();
+                let (a, b) = (self.stack[n - 4].clone(), self.stack[n - 3].clone());
+                self.push(a)?;
+                self.push(b)?;
+            }
+            OP_2ROT => {
+                self.require(op, 6)?;
+                let n = self.stack.len();
+                let moved: Vec<Vec<u8>> = self.stack.drain(n - 6..n - 4).collect();
+                self.stack.extend(moved);
+            }
+            OP_2SWAP => {
+                self.require(op, 4)?;
+                let n = self.stack.len();
+                self.stack.swap(n - 4, n - 2);
+                self.stack.swap(n - 3, n - 1);
+            }
+            OP_IFDUP => {
+                let top = self.peek(op, 0)?.clone();
+                if cast_to_bool(&top) {
+                    self.push(top)?;
+                }
+            }
+            OP_DEPTH => self.push_num(self.stack.len() as i64)?,
+            OP_DROP => {
+                self.pop(op)?;
+            }
+            OP_DUP => {
+                let top = self.peek(op, 0)?.clone();
+                self.push(top)?;
+            }
+            OP_NIP => {
+                self.require(op, 2)?;
+                let n = self.stack.len();
+                self.stack.remove(n - 2);
+            }
+            OP_OVER => {
+                let item = self.peek(op, 1)?.clone();
+                self.push(item)?;
+            }
+            OP_PICK | OP_ROLL => {
+                let depth = self.pop_num(op, 4)?;
+                if depth < 0 {
+                    return Err(ScriptFailure::StackUnderflow {
+                        op: format!("{:?}", op),
+                        needed: 0,
+                        found: self.stack.len(),
+                    });
+                }
+                let item = self.peek(op, depth as usize)?.clone();
+                if op == OP_ROLL {
+                    let n = self.stack.len();
+                    self.stack.remove(n - 1 - depth as usize);
+                }
+                self.push(item)?;
+            }
+            OP_ROT => {
+                self.require(op, 3)?;
+                let n = self.stack.len();
+                let item = self.stack.remove(n - 3);
+                self.stack.push(item);
+            }
+            OP_SWAP => {
+                self.require(op, 2)?;
+                let n = self.stack.len();
+                self.stack.swap(n - 2, n - 1);
+            }
+            OP_TUCK => {
+                self.require(op, 2)?;
+                let n = self.stack.len();
+                let top = self.stack[n - 1].clone();
+                self.stack.insert(n - 2, top);
+            }
+
+            // 문자열 (BIP-347)
+            OP_CAT => {
+                let b = self.pop(op)?;
+                let mut a = self.pop(op)?;
+                a.extend_from_slice(&b);
+                self.push(a)?;
+            }
+            OP_SIZE => {
+                let size = self.peek(op, 0)?.len();
+                self.push_num(size as i64)?;
+            }
+
+            // 비트 논리
+            OP_EQUAL | OP_EQUALVERIFY => {
+                let b = self.pop(op)?;
+                let a = self.pop(op)?;
+                let equal = a == b;
+                if op == OP_EQUALVERIFY {
+                    if !equal {
+                        return Err(ScriptFailure::VerifyFailed(format!(
+                            "OP_EQUALVERIFY ({} != {})",
+                            hex::encode(&a),
+                            hex::encode(&b)
+                        )));
+                    }
+                } else {
+                    self.push_bool(equal)?;
+                }
+            }
+
+            // 산술 (단항)
+            OP_1ADD | OP_1SUB | OP_NEGATE | OP_ABS | OP_NOT | OP_0NOTEQUAL => {
+                let a = self.pop_num(op, 4)?;
+                let result = match op {
+                    OP_1ADD => a + 1,
+                    OP_1SUB => a - 1,
+                    OP_NEGATE => -a,
+                    OP_ABS => a.abs(),
+                    OP_NOT => (a == 0) as i64,
+                    _ => (a != 0) as i64,
+                };
+                self.push_num(result)?;
+            }
+
+            // 산술 (이항)
+            OP_ADD | OP_SUB | OP_BOOLAND | OP_BOOLOR | OP_NUMEQUAL | OP_NUMEQUALVERIFY
+            | OP_NUMNOTEQUAL | OP_LESSTHAN | OP_GREATERTHAN | OP_LESSTHANOREQUAL
+            | OP_GREATERTHANOREQUAL | OP_MIN | OP_MAX => {
+                let b = self.pop_num(op, 4)?;
+                let a = self.pop_num(op, 4)?;
+                let result = match op {
+                    OP_ADD => a + b,
+                    OP_SUB => a - b,
+                    OP_BOOLAND => (a != 0 && b != 0) as i64,
+                    OP_BOOLOR => (a != 0 || b != 0) as i64,
+                    OP_NUMEQUAL | OP_NUMEQUALVERIFY => (a == b) as i64,
+                    OP_NUMNOTEQUAL => (a != b) as i64,
+                    OP_LESSTHAN => (a < b) as i64,
+                    OP_GREATERTHAN => (a > b) as i64,
+                    OP_LESSTHANOREQUAL => (a <= b) as i64,
+                    OP_GREATERTHANOREQUAL => (a >= b) as i64,
+                    OP_MIN => a.min(b),
+                    _ => a.max(b),
+                };
+                if op == OP_NUMEQUALVERIFY {
+                    if result == 0 {
+                        return Err(ScriptFailure::VerifyFailed(format!("OP_NUMEQUALVERIFY ({} != {})", a, b)));
+                    }
+                } else {
+                    self.push_num(result)?;
+                }
+            }
+            OP_WITHIN => {
+                let max = self.pop_num(op, 4)?;
+                let min = self.pop_num(op, 4)?;
+                let x = self.pop_num(op, 4)?;
+                self.push_bool(min <= x && x < max)?;
+            }
+
+            // 암호
+            OP_RIPEMD160 => {
+                let a = self.pop(op)?;
+                self.push(ripemd160::Hash::hash(&a).to_byte_array().to_vec())?;
+            }
+            OP_SHA1 => {
+                let a = self.pop(op)?;
+                self.push(sha1::Hash::hash(&a).to_byte_array().to_vec())?;
+            }
+            OP_SHA256 => {
+                let a = self.pop(op)?;
+                self.push(sha256::Hash::hash(&a).to_byte_array().to_vec())?;
+            }
+            OP_HASH160 => {
+                let a = self.pop(op)?;
+                self.push(hash160::Hash::hash(&a).to_byte_array().to_vec())?;
+            }
+            OP_HASH256 => {
+                let a = self.pop(op)?;
+                self.push(sha256d::Hash::hash(&a).to_byte_array().to_vec())?;
+            }
+            OP_CHECKSIG | OP_CHECKSIGVERIFY => {
+                let pubkey = self.pop(op)?;
+                let sig = self.pop(op)?;
+                let valid = self.check_signature(&sig, &pubkey)?;
+                if op == OP_CHECKSIGVERIFY {
+                    if !valid {
+                        return Err(ScriptFailure::VerifyFailed("OP_CHECKSIGVERIFY".to_string()));
+                    }
+                } else {
+                    self.push_bool(valid)?;
+                }
+            }
+            OP_CHECKSIGADD => {
+                let pubkey = self.pop(op)?;
+                let n = self.pop_num(op, 4)?;
+                let sig = self.pop(op)?;
+                let valid = self.check_signature(&sig, &pubkey)?;
+                self.push_num(n + valid as i64)?;
+            }
+
+            // 타임락
+            OP_CLTV => {
+                let locktime = decode_num(self.peek(op, 0)?, 5)?;
+                self.check_locktime(locktime)?;
+            }
+            OP_CSV => {
+                let sequence = decode_num(self.peek(op, 0)?, 5)?;
+                self.check_sequence(sequence)?;
+            }
+
+            other => return Err(ScriptFailure::BadOpcode(format!("{:?}", other))),
+        }
+
+        Ok(())
+    }
+
+    /// BIP-342 서명 검사 - 빈 서명은 false, 잘못된 비어있지 않은 서명은 즉시 실패
+    fn check_signature(&mut self, sig: &[u8], pubkey: &[u8]) -> Result<bool, ScriptFailure> {
+        if pubkey.is_empty() {
+            return Err(ScriptFailure::EmptyPublicKey);
+        }
+        if sig.is_empty() {
+            return Ok(false);
+        }
+
+        self.budget -= VALIDATION_WEIGHT_PER_SIGOP;
+        if self.budget < 0 {
+            return Err(ScriptFailure::SigopBudgetExceeded);
+        }
+
+        // 알 수 없는 공개키 타입은 업그레이드 가능성을 위해 성공으로 취급
+        if pubkey.len() != 32 {
+            return Ok(true);
+        }
+
+        let key = XOnlyPublicKey::from_slice(pubkey).map_err(|_| ScriptFailure::InvalidSignature)?;
+        let msg = self.interpreter.signature_hash(sig, Some(self.leaf_hash))?;
+        self.interpreter.verify_schnorr(sig, &key, msg)?;
+        Ok(true)
+    }
+
+    /// BIP-65
+    fn check_locktime(&self, locktime: i64) -> Result<(), ScriptFailure> {
+        if locktime < 0 {
+            return Err(ScriptFailure::NegativeLocktime);
+        }
+
+        const THRESHOLD: i64 = 500_000_000;
+        let tx = self.interpreter.tx;
+        let tx_locktime = tx.lock_time.to_consensus_u32() as i64;
+        if (tx_locktime < THRESHOLD) != (locktime < THRESHOLD) {
+            return Err(ScriptFailure::CltvNotSatisfied("locktime type mismatch".to_string()));
+        }
+        if locktime > tx_locktime {
+            return Err(ScriptFailure::CltvNotSatisfied(format!(
+                "requires {}, transaction has {}",
+                locktime, tx_locktime
+            )));
+        }
+        if tx.input[self.interpreter.input_index].sequence.is_final() {
+            return Err(ScriptFailure::CltvNotSatisfied("input sequence is final".to_string()));
+        }
+        Ok(())
+    }
+
+    /// BIP-112
+    fn check_sequence(&self, sequence: i64) -> Result<(), ScriptFailure> {
+        if sequence < 0 {
+            return Err(ScriptFailure::NegativeLocktime);
+        }
+
+        const DISABLE_FLAG: i64 = 1 << 31;
+        const TYPE_FLAG: i64 = 1 << 22;
+        const MASK: i64 = TYPE_FLAG | 0xffff;
+
+        if sequence & DISABLE_FLAG != 0 {
+            return Ok(());
+        }
+
+        let tx = self.interpreter.tx;
+        if tx.version.0 < 2 {
+            return Err(ScriptFailure::CsvNotSatisfied("transaction version below 2".to_string()));
+        }
+        let tx_sequence = tx.input[self.interpreter.input_index].sequence.0 as i64;
+        if tx_sequence & DISABLE_FLAG != 0 {
+            return Err(ScriptFailure::CsvNotSatisfied("input disables relative locktime".to_string()));
+        }
+
+        let tx_masked = tx_sequence & MASK;
+        let required = sequence & MASK;
+        if (tx_masked < TYPE_FLAG) != (required < TYPE_FLAG) {
+            return Err(ScriptFailure::CsvNotSatisfied("locktime type mismatch".to_string()));
+        }
+        if required > tx_masked {
+            return Err(ScriptFailure::CsvNotSatisfied(format!(
+                "requires {}, input has {}",
+                required & 0xffff,
+                tx_masked & 0xffff
+            )));
+        }
+        Ok(())
+    }
+
+    fn require(&self, op: Opcode, needed: usize) -> Result<(), ScriptFailure> {
+        if self.stack.len() < needed {
+            return Err(ScriptFailure::StackUnderflow {
+                op: format!("{:?}", op),
+                needed,
+                found: self.stack.len(),
+            });
+        }
+        Ok(())
+    }
+
+    fn peek(&self, op: Opcode, depth: usize) -> Result<&Vec<u8>, ScriptFailure> {
+        self.require(op, depth + 1)?;
+        Ok(&self.stack[self.stack.len() - 1 - depth])
+    }
+
+    fn pop(&mut self, op: Opcode) -> Result<Vec<u8>, ScriptFailure> {
+        self.require(op, 1)?;
+        Ok(self.stack.pop().unwrap_or_default())
+    }
+
+    fn pop_num(&mut self, op: Opcode, max_len: usize) -> Result<i64, ScriptFailure> {
+        let top = self.pop(op)?;
+        decode_num(&top, max_len)
+    }
+
+    fn push(&mut self, item: Vec<u8>) -> Result<(), ScriptFailure> {
+        if item.len() > MAX_ELEMENT_SIZE {
+            return Err(ScriptFailure::ElementTooLarge(item.len()));
+        }
+        self.stack.push(item);
+        Ok(())
+    }
+
+    fn push_num(&mut self, value: i64) -> Result<(), ScriptFailure> {
+        self.push(encode_num(value))
+    }
+
+    fn push_bool(&mut self, value: bool) -> Result<(), ScriptFailure> {
+        self.push(if value { vec![0x01] } else { Vec::new() })
+    }
+}
+
+/// 탭스크립트의 OP_SUCCESSx 여부 (OP_CAT은 BIP-347로 활성화)
+fn is_op_success(op: Opcode) -> bool {
+    let code = op.to_u8();
+    if code == OP_CAT.to_u8() {
+        return false;
+    }
+    code == 80
+        || code == 98
+        || (126..=129).contains(&code)
+        || (131..=134).contains(&code)
+        || (137..=138).contains(&code)
+        || (141..=142).contains(&code)
+        || (149..=153).contains(&code)
+        || (187..=254).contains(&code)
+}
+
+fn describe(instruction: &Instruction) -> String {
+    match instruction {
+        Instruction::PushBytes(bytes) if bytes.is_empty() => "OP_0".to_string(),
+        Instruction::PushBytes(bytes) => format!("PUSH 0x{}", hex::encode(bytes.as_bytes())),
+        Instruction::Op(op) => format!("{:?}", op),
+    }
+}
+
+/// 스택 원소의 불리언 해석 (음의 0 포함 0은 false)
+pub fn cast_to_bool(bytes: &[u8]) -> bool {
+    for (i, byte) in bytes.iter().enumerate() {
+        if *byte != 0 {
+            return !(i == bytes.len() - 1 && *byte == 0x80);
+        }
+    }
+    false
+}
+
+/// 스크립트 숫자 디코딩 (최소 인코딩 강제)
+pub fn decode_num(bytes: &[u8], max_len: usize) -> Result<i64, ScriptFailure> {
+    if bytes.len() > max_len {
+        return Err(ScriptFailure::NumberOverflow(bytes.len()));
+    }
+    let last = match bytes.last() {
+        Some(last) => *last,
+        None => return Ok(0),
+    };
+    if last & 0x7f == 0 && (bytes.len() == 1 || bytes[bytes.len() - 2] & 0x80 == 0) {
+        return Err(ScriptFailure::NonMinimalNumber);
+    }
+
+    let mut value: i64 = 0;
+    for (i, byte) in bytes.iter().enumerate() {
+        value |= (*byte as i64) << (8 * i);
+    }
+    if last & 0x80 != 0 {
+        let sign_bit = 0x80i64 << (8 * (bytes.len() - 1));
+        Ok(-(value & !sign_bit))
+    } else {
+        Ok(value)
+    }
+}
+
+/// 스크립트 숫자 인코딩 (최소 인코딩)
+pub fn encode_num(value: i64) -> Vec<u8> {
+    if value == 0 {
+        return Vec::new();
+    }
+    let negative = value < 0;
+    let mut abs = value.unsigned_abs();
+    let mut bytes = Vec::new();
+    while abs > 0 {
+        bytes.push((abs & 0xff) as u8);
+        abs >>= 8;
+    }
+    if bytes.last().is_some_and(|b| b & 0x80 != 0) {
+        bytes.push(if negative { 0x80 } else { 0x00 });
+    } else if negative {
+        if let Some(last) = bytes.last_mut() {
+            *last |= 0x80;
+        }
+    }
+    bytes
+}
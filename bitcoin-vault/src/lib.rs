@@ -2,8 +2,28 @@ pub mod covenant;
 pub mod vault;
 pub mod script;
 pub mod signature;
+pub mod psbt;
+pub mod timelock;
 pub mod manager;
+pub mod interpreter;
+pub mod watchtower;
+pub mod fee_bump;
+pub mod history;
+pub mod musig;
+pub mod descriptor;
+pub mod lifecycle;
+pub mod rate_limit;
 
 pub use vault::*;
-pub use manager::VaultManager;
-pub use covenant::*;
\ No newline at end of file
+pub use manager::{VaultFilter, VaultManager, HISTORY_ANCHORS_FILE};
+pub use covenant::*;
+pub use psbt::*;
+pub use timelock::*;
+pub use interpreter::{ExecutionTrace, ScriptError, ScriptFailure, TapscriptInterpreter};
+pub use watchtower::{ChainSource, MemoryChain, ResponseSigner, WatchEvent, WatchPolicy, WatchResponse, Watchtower};
+pub use fee_bump::{anchor_outpoint, cpfp, CpfpPackage};
+pub use history::{HistoryAnchor, VaultAction, VaultEvent, VaultHistory};
+pub use musig::{MusigSession, NonceShare, PartialSignatureShare, SecretNonce};
+pub use descriptor::{descriptor_checksum, parse_descriptor};
+pub use lifecycle::{FinalizedRootSource, FinalizedRoots, PegOutSettlement, VaultTransition};
+pub use rate_limit::{RateLimit, WithdrawalWindow};
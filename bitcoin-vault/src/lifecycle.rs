@@ -0,0 +1,171 @@
+//! 금고 생명주기 전환표
+//!
+//! 모든 금고 상태 전환이 어느 상태에서 허용되는지 한곳에 정리한다. 롤업 연동(peg-in)은
+//! `Inactive`에서만 가능하고, `Bridged`에서 빠져나오는 길(peg-out)은 신뢰하는 출처([`FinalizedRootSource`])가
+//! 확정한 상태 루트와, 그 루트에서 소유자의 롤업 잔액이 비어 있다는 증명([`PegOutSettlement`])이 있어야 한다.
+//! `Bridged` 금고는 L1에서 출금·회수할 수 없다.
+//!
+//! ```text
+//! Inactive ──Trigger/ObserveTrigger──▶ Triggered ──Complete──▶ Completed (부분 출금이면 Inactive)
+//!    ▲  │                                 │  │
+//!    │  └──PegIn──▶ Bridged               │  └──Recover──▶ Recovered
+//!    └────PegOut────┘    Inactive ◀──Cancel┘
+//! ```
+
+use shared::merkle::{MerkleProof, EMPTY_HASH};
+use shared::state::RollupState;
+use shared::{account_address, DeFiHubError, DeFiResult, StateRoot, TokenType, VaultState};
+use bitcoin::secp256k1::XOnlyPublicKey;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// 금고 상태 전환 종류
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VaultTransition {
+    /// UTXO 예치 기록
+    Deposit,
+    /// 금고 UTXO 병합
+    Consolidate,
+    /// 출금 트리거
+    Trigger,
+    /// 체인에서 발견한 트리거 반영
+    ObserveTrigger,
+    /// 타임락 이후 출금 완료
+    Complete,
+    /// 트리거 취소
+    Cancel,
+    /// 회수 키로 긴급 회수
+    Recover,
+    /// 롤업 연동 (peg-in)
+    PegIn,
+    /// 롤업에서 복귀 (peg-out)
+    PegOut,
+}
+
+impl VaultTransition {
+    /// 모든 전환
+    pub const ALL: [VaultTransition; 9] = [
+        VaultTransition::Deposit,
+        VaultTransition::Consolidate,
+        VaultTransition::Trigger,
+        VaultTransition::ObserveTrigger,
+        VaultTransition::Complete,
+        VaultTransition::Cancel,
+        VaultTransition::Recover,
+        VaultTransition::PegIn,
+        VaultTransition::PegOut,
+    ];
+
+    /// 전환이 허용되는 출발 상태 이름
+    pub fn allowed_from(self) -> &'static [&'static str] {
+        match self {
+            VaultTransition::Deposit
+            | VaultTransition::Consolidate
+            | VaultTransition::Trigger
+            | VaultTransition::ObserveTrigger
+            | VaultTransition::PegIn => &["Inactive"],
+            VaultTransition::Complete | VaultTransition::Cancel => &["Triggered"],
+            VaultTransition::Recover => &["Inactive", "Triggered"],
+            VaultTransition::PegOut => &["Bridged"],
+        }
+    }
+
+    /// 현재 상태에서 허용되는지 확인
+    pub fn is_allowed(self, state: &VaultState) -> bool {
+        self.allowed_from().contains(&state.name())
+    }
+
+    /// 허용되지 않으면 [`DeFiHubError::InvalidVaultState`]
+    pub fn check(self, state: &VaultState) -> DeFiResult<()> {
+        if self.is_allowed(state) {
+            return Ok(());
+        }
+        Err(DeFiHubError::InvalidVaultState {
+            current: format!("{:?}", state),
+            expected: self.allowed_from().join(" or "),
+        })
+    }
+}
+
+/// 확정된 롤업 상태 루트의 출처 (BitVMX 챌린지 기간을 지켜보는 검증 노드 등)
+///
+/// 정산을 내미는 쪽과 독립된 출처여야 한다. 정산 안의 값으로 확정 여부를 정하지 않는다.
+pub trait FinalizedRootSource {
+    /// `height`에서 챌린지 기간을 통과한 상태 루트 해시 (아직 확정되지 않았으면 `None`)
+    fn finalized_root(&self, height: u64) -> DeFiResult<Option<[u8; 32]>>;
+}
+
+/// 미리 알고 있는 확정 상태 루트 (오프라인 검증용)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FinalizedRoots(pub BTreeMap<u64, [u8; 32]>);
+
+impl FinalizedRoots {
+    /// 확정된 상태 루트 추가
+    pub fn finalize(&mut self, root: &StateRoot) {
+        self.0.insert(root.height, root.hash);
+    }
+}
+
+impl FinalizedRootSource for FinalizedRoots {
+    fn finalized_root(&self, height: u64) -> DeFiResult<Option<[u8; 32]>> {
+        Ok(self.0.get(&height).copied())
+    }
+}
+
+/// 롤업 출금 정산 - 금고 소유자의 롤업 WBTC 잔액이 비어 있음을 보이는 상태 루트와 증명
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PegOutSettlement {
+    /// 소각이 반영된 롤업 상태 루트
+    pub state_root: StateRoot,
+
+    /// 상태 루트에서 소유자 계정의 WBTC 잔액 리프가 비어 있다는 증명
+    pub balance_proof: MerkleProof,
+}
+
+impl PegOutSettlement {
+    /// 롤업 상태에서 금고 소유자의 잔액 증명으로 정산 생성
+    pub fn from_state(state: &RollupState, owner_key: &XOnlyPublicKey) -> Self {
+        Self {
+            state_root: state.current_state_root.clone(),
+            balance_proof: state.prove_balance(&account_address(owner_key), &TokenType::WBTC),
+        }
+    }
+
+    /// 연동 시점의 상태 루트 이후에 확정되었고 그 루트에서 소유자의 롤업 잔액이 0인지 확인
+    pub fn verify<F: FinalizedRootSource + ?Sized>(
+        &self,
+        owner_key: &XOnlyPublicKey,
+        bridged_root: &StateRoot,
+        finality: &F,
+    ) -> DeFiResult<()> {
+        if self.state_root.height <= bridged_root.height {
+            return Err(DeFiHubError::InvalidStateRoot {
+                expected: format!("height above {}", bridged_root.height),
+                actual: format!("height {}", self.state_root.height),
+            });
+        }
+        match finality.finalized_root(self.state_root.height)? {
+            None => {
+                return Err(DeFiHubError::BridgeVerification(format!(
+                    "Rollup state root at height {} is not finalized",
+                    self.state_root.height
+                )));
+            }
+            Some(finalized) if finalized != self.state_root.hash => {
+                return Err(DeFiHubError::InvalidStateRoot {
+                    expected: hex::encode(finalized),
+                    actual: hex::encode(self.state_root.hash),
+                });
+            }
+            Some(_) => {}
+        }
+        let key = RollupState::balance_leaf_key(&account_address(owner_key), &TokenType::WBTC);
+        if !self.balance_proof.verify(&self.state_root.hash, &key, &EMPTY_HASH) {
+            return Err(DeFiHubError::BridgeVerification(format!(
+                "State root at height {} does not prove an empty rollup balance for the vault owner",
+                self.state_root.height
+            )));
+        }
+        Ok(())
+    }
+}
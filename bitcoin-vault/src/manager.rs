@@ -0,0 +1,336 @@
+//! 여러 금고의 영구 저장소
+//!
+//! 금고마다 `<data_dir>/vaults/<txid>_<vout>.json` 파일 하나를 두고 OutPoint로 찾는다.
+//! CLI와 데몬이 같은 디렉토리를 동시에 쓸 수 있도록 모든 접근은 디렉토리의 잠금 파일
+//! (읽기는 공유, 쓰기는 배타)을 잡은 상태에서 이루어지며, 파일은 [`shared::persistence`]로
+//! 원자적으로 교체하므로 중간에 중단되어도 반쯤 쓰인 파일이 남지 않는다.
+//! 패스프레이즈를 주고 열면 금고 파일을 암호화해 저장한다.
+//!
+//! 금고를 저장할 때마다 이력의 마지막 항목을 금고 파일 밖의 `<data_dir>/history_anchors.json`에
+//! 앵커로 남겨, 금고 파일만 바꿔치기하거나 예전 파일로 되돌려도 [`VaultManager::verify_history`]가 찾아낸다.
+
+use crate::history::HistoryAnchor;
+use crate::vault::BitcoinVault;
+use shared::encryption::Passphrase;
+use shared::persistence::{self, Versioned};
+use shared::state::GlobalState;
+use shared::{DeFiHubError, DeFiResult};
+use bitcoin::{OutPoint, ScriptBuf};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+/// 데이터 디렉토리 아래 금고 파일 디렉토리
+pub const VAULTS_DIR: &str = "vaults";
+
+/// 데이터 디렉토리 아래 이력 앵커 파일
+pub const HISTORY_ANCHORS_FILE: &str = "history_anchors.json";
+
+/// 프로세스 간 잠금 파일
+const LOCK_FILE: &str = ".lock";
+
+/// 금고별 이력 앵커 (금고 ID → 마지막으로 저장한 이력 항목)
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct HistoryAnchors {
+    anchors: HashMap<OutPoint, HistoryAnchor>,
+}
+
+impl Versioned for HistoryAnchors {
+    const SCHEMA_VERSION: u32 = 1;
+    const KIND: &'static str = "history anchors";
+}
+
+/// 금고 목록 필터
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VaultFilter {
+    /// 소유자
+    pub owner: Option<String>,
+
+    /// 상태 이름 (대소문자 무시, 예: "triggered")
+    pub state: Option<String>,
+
+    /// 금고 주소의 스크립트 (같은 커버넌트 금고 찾기)
+    pub script_pubkey: Option<ScriptBuf>,
+}
+
+impl VaultFilter {
+    /// 소유자 조건 추가
+    pub fn owner(mut self, owner: impl Into<String>) -> Self {
+        self.owner = Some(owner.into());
+        self
+    }
+
+    /// 상태 조건 추가
+    pub fn state(mut self, state: impl Into<String>) -> Self {
+        self.state = Some(state.into());
+        self
+    }
+
+    /// 금고 주소 스크립트 조건 추가
+    pub fn script_pubkey(mut self, script_pubkey: ScriptBuf) -> Self {
+        self.script_pubkey = Some(script_pubkey);
+        self
+    }
+
+    /// 금고가 조건을 만족하는지 확인
+    pub fn matches(&self, vault: &BitcoinVault) -> bool {
+        let owner_matches = self.owner.as_ref().is_none_or(|owner| &vault.owner == owner);
+        let state_matches = self
+            .state
+            .as_ref()
+            .is_none_or(|state| vault.state.name().eq_ignore_ascii_case(state));
+        let script_matches = self
+            .script_pubkey
+            .as_ref()
+            .is_none_or(|script| &vault.address.script_pubkey() == script);
+        owner_matches && state_matches && script_matches
+    }
+}
+
+/// 금고 관리자 - 금고 파일과 글로벌 상태의 `vaults`를 함께 관리한다
+#[derive(Clone, Debug)]
+pub struct VaultManager {
+    /// 금고 파일 디렉토리
+    dir: PathBuf,
+
+    /// 이력 앵커 파일
+    anchors: PathBuf,
+
+    /// 동기화할 글로벌 상태
+    global_state: Arc<RwLock<GlobalState>>,
+
+    /// 금고 파일 암호화 패스프레이즈
+    passphrase: Option<Passphrase>,
+}
+
+impl VaultManager {
+    /// 데이터 디렉토리의 금고 저장소 열기
+    pub fn open<P: AsRef<Path>>(data_dir: P) -> DeFiResult<Self> {
+        Self::with_global_state(data_dir, Arc::new(RwLock::new(GlobalState::new())))
+    }
+
+    /// 암호화된 금고 저장소 열기 - 쓰는 파일은 모두 암호화되고, 평문 파일도 읽을 수 있다
+    pub fn open_with_passphrase<P: AsRef<Path>>(data_dir: P, passphrase: Option<Passphrase>) -> DeFiResult<Self> {
+        Self::build(data_dir, Arc::new(RwLock::new(GlobalState::new())), passphrase)
+    }
+
+    /// 기존 글로벌 상태를 공유하며 열기 (열 때 디스크 내용으로 동기화)
+    pub fn with_global_state<P: AsRef<Path>>(
+        data_dir: P,
+        global_state: Arc<RwLock<GlobalState>>,
+    ) -> DeFiResult<Self> {
+        Self::build(data_dir, global_state, None)
+    }
+
+    fn build<P: AsRef<Path>>(
+        data_dir: P,
+        global_state: Arc<RwLock<GlobalState>>,
+        passphrase: Option<Passphrase>,
+    ) -> DeFiResult<Self> {
+        let dir = data_dir.as_ref().join(VAULTS_DIR);
+        let anchors = data_dir.as_ref().join(HISTORY_ANCHORS_FILE);
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| DeFiHubError::Configuration(format!("Failed to create vault directory: {}", e)))?;
+
+        let manager = Self { dir, anchors, global_state, passphrase };
+        manager.sync()?;
+        Ok(manager)
+    }
+
+    /// 공유 글로벌 상태
+    pub fn global_state(&self) -> Arc<RwLock<GlobalState>> {
+        Arc::clone(&self.global_state)
+    }
+
+    /// 예치된 금고 등록
+    pub fn create(&self, vault: &BitcoinVault) -> DeFiResult<()> {
+        if vault.id == OutPoint::null() {
+            return Err(DeFiHubError::BitcoinTransaction("Vault has no funded UTXO".to_string()));
+        }
+
+        let _lock = self.lock(true)?;
+        let path = self.path(&vault.id);
+        if path.exists() {
+            return Err(DeFiHubError::VaultAlreadyExists(vault.id.to_string()));
+        }
+        self.write(&path, vault)?;
+        self.update_anchors(|anchors| anchor(anchors, vault))?;
+
+        self.with_global(|state| state.add_vault(vault.info()))
+    }
+
+    /// 금고 조회
+    pub fn load(&self, id: &OutPoint) -> DeFiResult<BitcoinVault> {
+        let _lock = self.lock(false)?;
+        self.read(id)
+    }
+
+    /// 조건에 맞는 금고 목록 (생성 시간순)
+    pub fn list(&self, filter: &VaultFilter) -> DeFiResult<Vec<BitcoinVault>> {
+        let _lock = self.lock(false)?;
+        let mut vaults: Vec<_> = self.read_all()?.into_iter().filter(|vault| filter.matches(vault)).collect();
+        vaults.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)));
+        Ok(vaults)
+    }
+
+    /// 금고 갱신 - 배타 잠금 안에서 읽기 → 수정 → 쓰기
+    ///
+    /// `f`가 에러를 반환하면 아무것도 저장하지 않는다. 트리거/취소처럼 금고의 OutPoint가
+    /// 바뀌면 새 키로 옮긴다.
+    pub fn update<T, F>(&self, id: &OutPoint, f: F) -> DeFiResult<T>
+    where
+        F: FnOnce(&mut BitcoinVault) -> DeFiResult<T>,
+    {
+        let _lock = self.lock(true)?;
+        let mut vault = self.read(id)?;
+        let result = f(&mut vault)?;
+
+        let path = self.path(&vault.id);
+        if vault.id != *id && path.exists() {
+            return Err(DeFiHubError::VaultAlreadyExists(vault.id.to_string()));
+        }
+        self.write(&path, &vault)?;
+        if vault.id != *id {
+            self.delete(id)?;
+        }
+        self.update_anchors(|anchors| {
+            anchors.remove(id);
+            anchor(anchors, &vault);
+        })?;
+
+        self.with_global(|state| {
+            state.vaults.remove(id);
+            state.add_vault(vault.info());
+        })?;
+        Ok(result)
+    }
+
+    /// 금고 삭제
+    pub fn remove(&self, id: &OutPoint) -> DeFiResult<BitcoinVault> {
+        let _lock = self.lock(true)?;
+        let vault = self.read(id)?;
+        self.delete(id)?;
+        self.update_anchors(|anchors| {
+            anchors.remove(id);
+        })?;
+
+        self.with_global(|state| {
+            state.vaults.remove(id);
+        })?;
+        Ok(vault)
+    }
+
+    /// 금고 이력을 검증하고 저장소가 보관한 앵커와 대조
+    ///
+    /// 대조한 앵커를 돌려준다 (앵커를 남기기 전에 만든 금고면 `None`).
+    pub fn verify_history(&self, id: &OutPoint) -> DeFiResult<Option<HistoryAnchor>> {
+        let _lock = self.lock(false)?;
+        let vault = self.read(id)?;
+        vault.verify_history()?;
+        let anchor = self.read_anchors()?.anchors.remove(id);
+        if let Some(anchor) = &anchor {
+            vault.history.verify_anchor(anchor)?;
+        }
+        Ok(anchor)
+    }
+
+    /// 디스크의 금고 파일로 글로벌 상태의 `vaults`를 다시 만든다
+    ///
+    /// 다른 프로세스가 바꾼 내용을 반영할 때 호출한다.
+    pub fn sync(&self) -> DeFiResult<()> {
+        let vaults = {
+            let _lock = self.lock(false)?;
+            self.read_all()?
+        };
+
+        self.with_global(|state| {
+            state.vaults = vaults.iter().map(|vault| (vault.id, vault.info())).collect();
+            state.last_updated = chrono::Utc::now();
+        })
+    }
+
+    /// 잠금 파일 잠그기 - 반환된 파일이 drop되면 풀린다
+    fn lock(&self, exclusive: bool) -> DeFiResult<File> {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.dir.join(LOCK_FILE))
+            .map_err(|e| DeFiHubError::Configuration(format!("Failed to open vault lock: {}", e)))?;
+
+        let locked = if exclusive { file.lock() } else { file.lock_shared() };
+        locked.map_err(|e| DeFiHubError::Configuration(format!("Failed to lock vault directory: {}", e)))?;
+        Ok(file)
+    }
+
+    fn path(&self, id: &OutPoint) -> PathBuf {
+        self.dir.join(format!("{}_{}.json", id.txid, id.vout))
+    }
+
+    fn read(&self, id: &OutPoint) -> DeFiResult<BitcoinVault> {
+        let path = self.path(id);
+        if !path.exists() {
+            return Err(DeFiHubError::VaultNotFound);
+        }
+        BitcoinVault::load_from_file_with(path, self.passphrase.as_ref())
+    }
+
+    fn read_all(&self) -> DeFiResult<Vec<BitcoinVault>> {
+        let entries = std::fs::read_dir(&self.dir)
+            .map_err(|e| DeFiHubError::Configuration(format!("Failed to read vault directory: {}", e)))?;
+
+        let mut vaults = Vec::new();
+        for entry in entries {
+            let path = entry
+                .map_err(|e| DeFiHubError::Configuration(format!("Failed to read vault directory: {}", e)))?
+                .path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                vaults.push(BitcoinVault::load_from_file_with(&path, self.passphrase.as_ref())?);
+            }
+        }
+        Ok(vaults)
+    }
+
+    /// 임시 파일에 쓴 뒤 원자적으로 교체 (이전 파일은 백업으로 남는다)
+    fn write(&self, path: &Path, vault: &BitcoinVault) -> DeFiResult<()> {
+        vault.save_to_file_with(path, self.passphrase.as_ref())
+    }
+
+    /// 금고 파일과 그 백업 삭제
+    fn delete(&self, id: &OutPoint) -> DeFiResult<()> {
+        persistence::remove(&self.path(id))
+            .map_err(|e| DeFiHubError::Configuration(format!("Failed to remove vault file: {}", e)))
+    }
+
+    fn read_anchors(&self) -> DeFiResult<HistoryAnchors> {
+        if !self.anchors.exists() {
+            return Ok(HistoryAnchors::default());
+        }
+        persistence::load_with(&self.anchors, self.passphrase.as_ref())
+    }
+
+    /// 배타 잠금 안에서 앵커 파일 갱신
+    fn update_anchors<F: FnOnce(&mut HashMap<OutPoint, HistoryAnchor>)>(&self, f: F) -> DeFiResult<()> {
+        let mut anchors = self.read_anchors()?;
+        f(&mut anchors.anchors);
+        persistence::save_with(&anchors, &self.anchors, self.passphrase.as_ref())
+    }
+
+    fn with_global<F: FnOnce(&mut GlobalState)>(&self, f: F) -> DeFiResult<()> {
+        let mut state = self
+            .global_state
+            .write()
+            .map_err(|_| DeFiHubError::Internal("Global state lock poisoned".to_string()))?;
+        f(&mut state);
+        Ok(())
+    }
+}
+
+/// 금고 이력의 마지막 항목을 앵커로 남긴다
+fn anchor(anchors: &mut HashMap<OutPoint, HistoryAnchor>, vault: &BitcoinVault) {
+    if let Some(anchor) = vault.history.anchor() {
+        anchors.insert(vault.id, anchor);
+    }
+}
@@ -0,0 +1,422 @@
+//! MuSig2 공동 소유 금고
+//!
+//! 여러 소유자의 x-only 키를 `schnorr_fun`의 MuSig2로 집계한 n-of-n 키를 금고 소유자 키로 쓴다.
+//! 집계 키는 트리거/취소/부분 출금/병합 리프의 `OP_CHECKSIG` 키가 되고, 탭루트 내부키는 NUMS로 둔다.
+//! 집계 키를 내부키에 넣으면 소유자들이 키 경로로 타임락과 취소 경로를 건너뛸 수 있기 때문이다.
+//!
+//! 서명은 파일을 주고받으며 진행하므로 에어갭 기기에서도 쓸 수 있다.
+//! 1. 코디네이터가 금고 PSBT로 [`MusigSession`]을 만들어 모든 소유자에게 전달한다.
+//! 2. 소유자마다 [`MusigSession::generate_nonce`]로 공개 논스([`NonceShare`])를 내보내고
+//!    비밀 논스([`SecretNonce`])는 자기 기기에만 보관한다.
+//! 3. 공개 논스를 모두 모으면 [`MusigSession::partial_sign`]으로 부분 서명([`PartialSignatureShare`])을
+//!    만든다. 비밀 논스는 이때 소비되어 같은 논스로 두 번 서명할 수 없다.
+//! 4. 코디네이터가 [`MusigSession::combine`]으로 부분 서명을 검증해 합치고 PSBT의 `tap_script_sigs`에
+//!    넣으면 [`finalize_vault_input`](crate::psbt::finalize_vault_input)으로 완성할 수 있다.
+
+use crate::covenant::SECP;
+use crate::psbt::{PSBT_OWNER_SIG_PLACEHOLDER, PSBT_PROPRIETARY_PREFIX};
+use shared::encryption::random_bytes;
+use shared::{DeFiHubError, DeFiResult};
+use bitcoin::hashes::Hash;
+use bitcoin::psbt::Psbt;
+use bitcoin::secp256k1::{schnorr, Keypair, Message as SighashMessage};
+use bitcoin::sighash::{Prevouts, SighashCache, TapSighashType};
+use bitcoin::taproot::{self, TapLeafHash};
+use bitcoin::{TxOut, XOnlyPublicKey};
+use schnorr_fun::binonce::{Nonce, NonceKeyPair};
+use schnorr_fun::fun::marker::{EvenY, Normal, Public, Secret, Zero};
+use schnorr_fun::fun::nonce::NoNonces;
+use schnorr_fun::fun::{KeyPair, Point, Scalar};
+use schnorr_fun::musig::{self, AggKey, MuSig};
+use schnorr_fun::Message;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::path::Path;
+
+/// 비밀 논스 유도 태그
+const NONCE_TAG: &[u8] = b"Purrfect/musig-nonce";
+
+/// n-of-n 집계 소유자 키
+///
+/// 키 순서에 상관없이 같은 결과가 나오도록 정렬한 뒤 집계한다.
+pub fn aggregate_key(cosigners: &[XOnlyPublicKey]) -> DeFiResult<XOnlyPublicKey> {
+    let cosigners = sorted_cosigners(cosigners)?;
+    let agg_key = agg_key(&cosigners)?;
+    xonly(agg_key.agg_public_key())
+}
+
+/// 공동 소유자 키 정렬 및 검사 (2명 이상, 중복 없음)
+pub fn sorted_cosigners(cosigners: &[XOnlyPublicKey]) -> DeFiResult<Vec<XOnlyPublicKey>> {
+    let mut sorted = cosigners.to_vec();
+    sorted.sort();
+    sorted.dedup();
+    if sorted.len() != cosigners.len() {
+        return Err(musig_error("duplicate cosigner key"));
+    }
+    if sorted.len() < 2 {
+        return Err(musig_error(format!("at least 2 cosigners are required, got {}", sorted.len())));
+    }
+    Ok(sorted)
+}
+
+/// 서명 세션 - 코디네이터가 만들어 모든 공동 소유자에게 전달한다
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct MusigSession {
+    /// 세션 ID (무작위 32바이트 hex)
+    pub id: String,
+
+    /// 정렬된 공동 소유자 키
+    pub cosigners: Vec<XOnlyPublicKey>,
+
+    /// 서명할 커버넌트 입력
+    pub input_index: usize,
+
+    /// 서명할 리프 해시
+    pub leaf_hash: TapLeafHash,
+
+    /// 서명할 PSBT (BIP-174 바이너리 hex)
+    pub psbt: String,
+}
+
+/// 공개 논스 - 모든 참여자에게 공유한다
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct NonceShare {
+    /// 세션 ID
+    pub session_id: String,
+
+    /// 논스를 만든 공동 소유자
+    pub signer: XOnlyPublicKey,
+
+    /// 공개 논스 쌍 (66바이트 hex)
+    pub nonce: String,
+}
+
+/// 비밀 논스 - 만든 기기 밖으로 내보내지 않고, 부분 서명에 한 번만 쓴다
+#[derive(Serialize, Deserialize)]
+pub struct SecretNonce {
+    /// 세션 ID
+    pub session_id: String,
+
+    /// 논스를 만든 공동 소유자
+    pub signer: XOnlyPublicKey,
+
+    /// 비밀 논스 쌍 (64바이트 hex)
+    secret: String,
+}
+
+impl fmt::Debug for SecretNonce {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecretNonce")
+            .field("session_id", &self.session_id)
+            .field("signer", &self.signer)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SecretNonce {
+    /// 파일에서 비밀 논스를 읽고 파일을 지운다 (재사용 방지)
+    pub fn take(path: impl AsRef<Path>) -> DeFiResult<Self> {
+        let nonce = load_json(&path)?;
+        std::fs::remove_file(&path).map_err(|e| DeFiHubError::Internal(e.to_string()))?;
+        Ok(nonce)
+    }
+}
+
+/// 부분 서명 - 코디네이터에게 보낸다
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PartialSignatureShare {
+    /// 세션 ID
+    pub session_id: String,
+
+    /// 서명한 공동 소유자
+    pub signer: XOnlyPublicKey,
+
+    /// 부분 서명 스칼라 (32바이트 hex)
+    pub partial_signature: String,
+}
+
+impl MusigSession {
+    /// 금고 PSBT의 소유자 서명 세션 생성
+    ///
+    /// 입력의 서명 자리 표시자 키가 공동 소유자들의 집계 키와 같아야 한다.
+    pub fn new(psbt: &Psbt, input_index: usize, cosigners: &[XOnlyPublicKey]) -> DeFiResult<Self> {
+        let cosigners = sorted_cosigners(cosigners)?;
+        let aggregate = aggregate_key(&cosigners)?;
+        let input = psbt
+            .inputs
+            .get(input_index)
+            .ok_or_else(|| DeFiHubError::BitcoinTransaction(format!("PSBT input {} does not exist", input_index)))?;
+
+        let signer = input
+            .proprietary
+            .keys()
+            .find(|key| key.prefix == PSBT_PROPRIETARY_PREFIX && key.subtype == PSBT_OWNER_SIG_PLACEHOLDER)
+            .ok_or_else(|| musig_error(format!("PSBT input {} needs no owner signature", input_index)))?;
+        if signer.key != aggregate.serialize() {
+            return Err(musig_error("PSBT owner key is not the cosigners' aggregate key"));
+        }
+        let (_, (leaf_script, leaf_version)) = input
+            .tap_scripts
+            .iter()
+            .next()
+            .ok_or_else(|| DeFiHubError::BitcoinTransaction("PSBT input has no vault leaf".to_string()))?;
+
+        let mut id = [0u8; 32];
+        random_bytes(&mut id)?;
+        let session = Self {
+            id: hex::encode(id),
+            cosigners,
+            input_index,
+            leaf_hash: TapLeafHash::from_script(leaf_script, *leaf_version),
+            psbt: hex::encode(psbt.serialize()),
+        };
+        session.sighash()?;
+        Ok(session)
+    }
+
+    /// 세션의 PSBT
+    pub fn psbt(&self) -> DeFiResult<Psbt> {
+        let bytes = hex::decode(&self.psbt).map_err(|e| DeFiHubError::Serialization(e.to_string()))?;
+        Psbt::deserialize(&bytes).map_err(|e| DeFiHubError::Serialization(e.to_string()))
+    }
+
+    /// 집계 소유자 키
+    pub fn aggregate_key(&self) -> DeFiResult<XOnlyPublicKey> {
+        aggregate_key(&self.cosigners)
+    }
+
+    /// 서명할 BIP-341 스크립트 경로 sighash (`SIGHASH_DEFAULT`)
+    ///
+    /// 각 서명자는 전달받은 값을 믿지 않고 PSBT에서 직접 계산한다.
+    pub fn sighash(&self) -> DeFiResult<[u8; 32]> {
+        let psbt = self.psbt()?;
+        let prevouts = psbt
+            .inputs
+            .iter()
+            .enumerate()
+            .map(|(index, input)| {
+                input
+                    .witness_utxo
+                    .clone()
+                    .ok_or_else(|| DeFiHubError::BitcoinTransaction(format!("PSBT input {} has no witness UTXO", index)))
+            })
+            .collect::<DeFiResult<Vec<TxOut>>>()?;
+        let sighash = SighashCache::new(&psbt.unsigned_tx)
+            .taproot_script_spend_signature_hash(
+                self.input_index,
+                &Prevouts::All(&prevouts),
+                self.leaf_hash,
+                TapSighashType::Default,
+            )
+            .map_err(|e| DeFiHubError::BitcoinTransaction(e.to_string()))?;
+        Ok(sighash.to_byte_array())
+    }
+
+    /// 1단계: 공개 논스 생성
+    ///
+    /// 비밀 논스는 OS 난수에 비밀키, 집계 키, 세션 ID와 sighash를 섞어 만든다.
+    pub fn generate_nonce(&self, keypair: &Keypair) -> DeFiResult<(NonceShare, SecretNonce)> {
+        let signer = self.signer(keypair)?;
+        let sighash = self.sighash()?;
+        let mut entropy = [0u8; 32];
+        random_bytes(&mut entropy)?;
+
+        let mut secret = [0u8; 64];
+        for (index, chunk) in secret.chunks_mut(32).enumerate() {
+            let mut hasher = Sha256::new();
+            hasher.update(NONCE_TAG);
+            hasher.update(entropy);
+            hasher.update(keypair.secret_bytes());
+            hasher.update(self.aggregate_key()?.serialize());
+            hasher.update(self.id.as_bytes());
+            hasher.update(sighash);
+            hasher.update([index as u8]);
+            chunk.copy_from_slice(&hasher.finalize());
+        }
+        let nonce = NonceKeyPair::from_bytes(secret).ok_or_else(|| musig_error("derived nonce is invalid"))?;
+
+        let share = NonceShare {
+            session_id: self.id.clone(),
+            signer,
+            nonce: hex::encode(nonce.public().to_bytes()),
+        };
+        let secret = SecretNonce {
+            session_id: self.id.clone(),
+            signer,
+            secret: hex::encode(nonce.to_bytes()),
+        };
+        Ok((share, secret))
+    }
+
+    /// 2단계: 부분 서명 (모든 공동 소유자의 공개 논스 필요, 비밀 논스는 소비된다)
+    pub fn partial_sign(
+        &self,
+        keypair: &Keypair,
+        secret: SecretNonce,
+        nonces: &[NonceShare],
+    ) -> DeFiResult<PartialSignatureShare> {
+        let signer = self.signer(keypair)?;
+        if secret.session_id != self.id || secret.signer != signer {
+            return Err(musig_error("secret nonce belongs to another session or signer"));
+        }
+        let secret_bytes: [u8; 64] = hex::decode(&secret.secret)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| musig_error("malformed secret nonce"))?;
+        let local_nonce = NonceKeyPair::from_bytes(secret_bytes).ok_or_else(|| musig_error("malformed secret nonce"))?;
+
+        let public_nonces = self.public_nonces(nonces)?;
+        let index = self.signer_index(&signer)?;
+        if public_nonces[index] != local_nonce.public() {
+            return Err(musig_error(format!("published nonce of {} does not match the secret nonce", signer)));
+        }
+
+        let musig = musig_context();
+        let agg_key = agg_key(&self.cosigners)?;
+        let sighash = self.sighash()?;
+        let session = musig.start_sign_session(&agg_key, public_nonces, Message::<Public>::raw(&sighash));
+        let partial = musig.sign(&agg_key, &session, index, &schnorr_keypair(keypair)?, local_nonce);
+
+        Ok(PartialSignatureShare {
+            session_id: self.id.clone(),
+            signer,
+            partial_signature: hex::encode(partial.to_bytes()),
+        })
+    }
+
+    /// 3단계: 부분 서명을 검증해 합치고 PSBT에 집계 서명 추가
+    pub fn combine(&self, nonces: &[NonceShare], partials: &[PartialSignatureShare]) -> DeFiResult<Psbt> {
+        let public_nonces = self.public_nonces(nonces)?;
+        let musig = musig_context();
+        let agg_key = agg_key(&self.cosigners)?;
+        let sighash = self.sighash()?;
+        let session = musig.start_sign_session(&agg_key, public_nonces, Message::<Public>::raw(&sighash));
+
+        let mut scalars = Vec::with_capacity(self.cosigners.len());
+        for (index, share) in self.ordered(partials, |share| (&share.session_id, share.signer))?.into_iter().enumerate() {
+            let scalar: Scalar<Public, Zero> = hex::decode(&share.partial_signature)
+                .ok()
+                .and_then(|bytes| bytes.try_into().ok())
+                .and_then(Scalar::<Public, Zero>::from_bytes)
+                .ok_or_else(|| musig_error(format!("malformed partial signature from {}", share.signer)))?;
+            if !musig.verify_partial_signature(&agg_key, &session, index, scalar) {
+                return Err(musig_error(format!("invalid partial signature from {}", share.signer)));
+            }
+            scalars.push(scalar);
+        }
+
+        let signature = musig.combine_partial_signatures(&agg_key, &session, scalars);
+        let sig = schnorr::Signature::from_slice(&signature.to_bytes())
+            .map_err(|e| musig_error(format!("aggregate signature is malformed: {}", e)))?;
+        let aggregate = self.aggregate_key()?;
+        let message = SighashMessage::from_digest(sighash);
+        SECP.verify_schnorr(&sig, &message, &aggregate)
+            .map_err(|_| musig_error("aggregate signature does not verify"))?;
+
+        let mut psbt = self.psbt()?;
+        psbt.inputs[self.input_index].tap_script_sigs.insert(
+            (aggregate, self.leaf_hash),
+            taproot::Signature { sig, hash_ty: TapSighashType::Default },
+        );
+        Ok(psbt)
+    }
+
+    /// 공개 논스를 공동 소유자 순서로 정렬
+    fn public_nonces(&self, nonces: &[NonceShare]) -> DeFiResult<Vec<Nonce>> {
+        self.ordered(nonces, |share| (&share.session_id, share.signer))?
+            .into_iter()
+            .map(|share| {
+                hex::decode(&share.nonce)
+                    .ok()
+                    .and_then(|bytes| bytes.try_into().ok())
+                    .and_then(Nonce::from_bytes)
+                    .ok_or_else(|| musig_error(format!("malformed nonce from {}", share.signer)))
+            })
+            .collect()
+    }
+
+    /// 공동 소유자마다 정확히 하나씩, 이 세션의 항목인지 확인하고 키 순서로 정렬
+    fn ordered<'a, T>(&self, items: &'a [T], header: impl Fn(&T) -> (&String, XOnlyPublicKey)) -> DeFiResult<Vec<&'a T>> {
+        let mut ordered = Vec::with_capacity(self.cosigners.len());
+        for cosigner in &self.cosigners {
+            let mut matching = items.iter().filter(|item| header(item).1 == *cosigner);
+            let item = matching
+                .next()
+                .ok_or_else(|| musig_error(format!("missing share from {}", cosigner)))?;
+            if matching.next().is_some() {
+                return Err(musig_error(format!("duplicate share from {}", cosigner)));
+            }
+            if header(item).0 != &self.id {
+                return Err(musig_error(format!("share from {} belongs to another session", cosigner)));
+            }
+            ordered.push(item);
+        }
+        if let Some(stranger) = items.iter().find(|item| !self.cosigners.contains(&header(item).1)) {
+            return Err(musig_error(format!("{} is not a cosigner", header(stranger).1)));
+        }
+        Ok(ordered)
+    }
+
+    fn signer(&self, keypair: &Keypair) -> DeFiResult<XOnlyPublicKey> {
+        let signer = keypair.x_only_public_key().0;
+        self.signer_index(&signer)?;
+        Ok(signer)
+    }
+
+    fn signer_index(&self, signer: &XOnlyPublicKey) -> DeFiResult<usize> {
+        self.cosigners
+            .iter()
+            .position(|cosigner| cosigner == signer)
+            .ok_or_else(|| musig_error(format!("{} is not a cosigner", signer)))
+    }
+}
+
+/// 세션 파일 저장 (JSON)
+pub fn save_json<T: Serialize>(path: impl AsRef<Path>, value: &T) -> DeFiResult<()> {
+    let content = serde_json::to_string_pretty(value)?;
+    std::fs::write(path.as_ref(), content)
+        .map_err(|e| DeFiHubError::Internal(format!("Failed to write {}: {}", path.as_ref().display(), e)))
+}
+
+/// 세션 파일 읽기 (JSON)
+pub fn load_json<T: DeserializeOwned>(path: impl AsRef<Path>) -> DeFiResult<T> {
+    let content = std::fs::read_to_string(path.as_ref())
+        .map_err(|e| DeFiHubError::Internal(format!("Failed to read {}: {}", path.as_ref().display(), e)))?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+fn musig_context() -> MuSig<Sha256, NoNonces> {
+    musig::new_without_nonce_generation::<Sha256>()
+}
+
+/// x-only 키는 짝수 y 점으로 해석한다
+fn agg_key(cosigners: &[XOnlyPublicKey]) -> DeFiResult<AggKey<EvenY>> {
+    let points = cosigners
+        .iter()
+        .map(|key| {
+            Point::<EvenY>::from_xonly_bytes(key.serialize())
+                .map(|point| point.normalize())
+                .ok_or_else(|| musig_error(format!("{} is not a valid point", key)))
+        })
+        .collect::<DeFiResult<Vec<Point>>>()?;
+    Ok(musig_context().new_agg_key(points).into_xonly_key())
+}
+
+/// 비밀키를 짝수 y 공개키에 맞춰 부호를 고친 `schnorr_fun` 키쌍
+fn schnorr_keypair(keypair: &Keypair) -> DeFiResult<KeyPair<Normal>> {
+    let secret = Scalar::<Secret, Zero>::from_bytes(keypair.secret_bytes())
+        .and_then(|scalar| scalar.non_zero())
+        .ok_or_else(|| musig_error("invalid secret key"))?;
+    let even = KeyPair::<EvenY>::new(secret);
+    Ok(KeyPair::<Normal>::new(even.secret_key().clone()))
+}
+
+fn xonly(point: Point<EvenY>) -> DeFiResult<XOnlyPublicKey> {
+    XOnlyPublicKey::from_slice(&point.to_xonly_bytes()).map_err(|e| musig_error(e.to_string()))
+}
+
+fn musig_error(reason: impl Into<String>) -> DeFiHubError {
+    DeFiHubError::Musig(reason.into())
+}
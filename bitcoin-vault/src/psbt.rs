@@ -0,0 +1,807 @@
+//! 금고 지출 PSBT 빌더
+//!
+//! 트리거/완료/취소/회수 트랜잭션(과 부분 출금 트리거/완료)을 PSBT로 만든다. 커버넌트 입력은 항상 0번이며,
+//! 수수료는 ANYONECANPAY 덕분에 별도의 수수료 입력과 잔돈 출력으로 충당한다.
+//! 병합(및 병합 트리거) 트랜잭션은 금고 UTXO들만 입력으로 쓰고 수수료는 금고 금액에서 낸다.
+//! 모든 트랜잭션은 커버넌트 출력 바로 뒤에 P2A 앵커 출력을 두어 나중에 CPFP로 수수료를 올릴 수 있다
+//! ([`crate::fee_bump`]).
+//! 커버넌트 증인은 서명을 제외한 나머지를 독점(proprietary) 필드에 미리 채워 두고,
+//! 외부 서명자가 소유자 서명(`tap_script_sigs`)과 수수료 입력 서명을 추가하면
+//! [`finalize_vault_input`]으로 완성한다.
+
+use crate::covenant::{nums_internal_key, VaultCovenant, VaultLeaf};
+use crate::script::{
+    amount_parts, ANCHOR_AMOUNT, ANCHOR_SPK, MAX_MERGE_FEE, MAX_PARTIAL_VAULT_AMOUNT, MAX_TARGET_SPK_LEN, MERGE_INPUTS, VAULT_TX_VERSION,
+};
+use crate::signature::{grind_merge, grind_sequence, sha_prevouts, sha_sequences, SEQUENCE_DISABLE_FLAG};
+use shared::constants::{DEFAULT_FEE_RATE, DUST_AMOUNT};
+use shared::{DeFiHubError, DeFiResult};
+use bitcoin::absolute::LockTime;
+use bitcoin::bip32::KeySource;
+use bitcoin::consensus::{serialize, Encodable};
+use bitcoin::hashes::Hash;
+use bitcoin::opcodes::all::OP_RETURN;
+use bitcoin::psbt::raw::ProprietaryKey;
+use bitcoin::psbt::{Input, Psbt};
+use bitcoin::script::{Builder, PushBytesBuf};
+use bitcoin::taproot::{LeafVersion, TapLeafHash};
+use bitcoin::transaction::Version;
+use bitcoin::{Amount, OutPoint, ScriptBuf, Sequence, Transaction, TxIn, TxOut, VarInt, Witness};
+
+/// PSBT 독점 키 접두사
+pub const PSBT_PROPRIETARY_PREFIX: &[u8] = b"purrfect";
+
+/// 독점 키 서브타입: 커버넌트 증인 원소 (키 = 원소 인덱스)
+pub const PSBT_WITNESS_ELEMENT: u8 = 0x00;
+
+/// 독점 키 서브타입: 서명 자리 표시자 (키 = 소유자 또는 회수 키)
+pub const PSBT_OWNER_SIG_PLACEHOLDER: u8 = 0x01;
+
+/// CPFP 앵커 출력 (누구나 지출할 수 있는 P2A)
+pub fn anchor_output() -> TxOut {
+    TxOut {
+        value: Amount::from_sat(ANCHOR_AMOUNT),
+        script_pubkey: ScriptBuf::from_bytes(ANCHOR_SPK.to_vec()),
+    }
+}
+
+/// 수수료 입력 하나
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FeeInput {
+    /// 수수료 UTXO
+    pub outpoint: OutPoint,
+
+    /// UTXO 출력 (금액과 scriptPubKey)
+    pub txout: TxOut,
+}
+
+/// 금고 트랜잭션 수수료 설정
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FeeFunding {
+    /// 수수료 입력들 (P2WPKH 또는 P2TR)
+    pub inputs: Vec<FeeInput>,
+
+    /// 잔돈 scriptPubKey
+    pub change_script: ScriptBuf,
+
+    /// 수수료율 (sat/vB)
+    pub fee_rate: u64,
+}
+
+impl FeeFunding {
+    /// 기본 수수료율로 생성
+    pub fn new(inputs: Vec<FeeInput>, change_script: ScriptBuf) -> Self {
+        Self {
+            inputs,
+            change_script,
+            fee_rate: DEFAULT_FEE_RATE,
+        }
+    }
+
+    /// 수수료율 지정
+    pub fn with_fee_rate(mut self, fee_rate: u64) -> Self {
+        self.fee_rate = fee_rate;
+        self
+    }
+
+    /// 수수료 입력 총액
+    pub fn total_input(&self) -> Amount {
+        self.inputs.iter().map(|input| input.txout.value).sum()
+    }
+}
+
+impl VaultCovenant {
+    /// 트리거 PSBT - 금고 UTXO를 같은 금고에 재잠그고 출금 대상을 OP_RETURN 마커로 기록
+    pub fn trigger_psbt(
+        &self,
+        vault_outpoint: OutPoint,
+        amount: Amount,
+        target: &ScriptBuf,
+        funding: &FeeFunding,
+    ) -> DeFiResult<Psbt> {
+        let vault_output = TxOut {
+            value: amount,
+            script_pubkey: self.script_pubkey()?,
+        };
+        let outputs = vec![
+            vault_output.clone(),
+            TxOut {
+                value: Amount::ZERO,
+                script_pubkey: target_marker(target)?,
+            },
+        ];
+
+        let target = target.clone();
+        let draft = SpendDraft {
+            leaf: VaultLeaf::Trigger,
+            outpoint: vault_outpoint,
+            prevout: vault_output,
+            outputs,
+            base_sequence: Sequence(SEQUENCE_DISABLE_FLAG),
+            lock_time: LockTime::ZERO,
+        };
+        self.build_psbt(draft, funding, move |tx, prevout, extras| {
+            vec![
+                serialize(&tx.input[0].previous_output),
+                extras,
+                target.to_bytes(),
+                prevout.script_pubkey.to_bytes(),
+                serialize(&prevout.value.to_sat()),
+            ]
+        })
+    }
+
+    /// 부분 출금 트리거 PSBT - 금고 전액을 재잠그고 출금 대상과 금액을 마커에 기록
+    pub fn partial_trigger_psbt(
+        &self,
+        vault_outpoint: OutPoint,
+        amount: Amount,
+        withdrawal: Amount,
+        target: &ScriptBuf,
+        funding: &FeeFunding,
+    ) -> DeFiResult<Psbt> {
+        check_partial_amounts(amount, withdrawal)?;
+
+        let vault_output = TxOut {
+            value: amount,
+            script_pubkey: self.script_pubkey()?,
+        };
+        let outputs = vec![
+            vault_output.clone(),
+            TxOut {
+                value: Amount::ZERO,
+                script_pubkey: partial_target_marker(target, withdrawal)?,
+            },
+        ];
+
+        let target = target.clone();
+        let draft = SpendDraft {
+            leaf: VaultLeaf::PartialTrigger,
+            outpoint: vault_outpoint,
+            prevout: vault_output,
+            outputs,
+            base_sequence: Sequence(SEQUENCE_DISABLE_FLAG),
+            lock_time: LockTime::ZERO,
+        };
+        self.build_psbt(draft, funding, move |tx, prevout, extras| {
+            vec![
+                serialize(&tx.input[0].previous_output),
+                extras,
+                target.to_bytes(),
+                serialize(&withdrawal.to_sat()),
+                prevout.script_pubkey.to_bytes(),
+                serialize(&prevout.value.to_sat()),
+            ]
+        })
+    }
+
+    /// 완료 PSBT - 트리거된 금액을 출금 대상에게 보낸다 (타임락 이후)
+    ///
+    /// 부분 출금 트리거였다면 마커의 금액만 대상에게 보내고 나머지는 출력 1로 금고에 다시 잠근다.
+    pub fn complete_psbt(&self, trigger_tx: &Transaction, funding: &FeeFunding) -> DeFiResult<Psbt> {
+        let vault_output = trigger_tx
+            .output
+            .first()
+            .cloned()
+            .ok_or_else(|| DeFiHubError::BitcoinTransaction("Trigger transaction has no outputs".to_string()))?;
+        if vault_output.script_pubkey != self.script_pubkey()? {
+            return Err(DeFiHubError::BitcoinTransaction(
+                "Trigger output is not locked to this vault".to_string(),
+            ));
+        }
+        let (target, withdrawal) = parse_marker(trigger_tx)?;
+
+        // 트리거 트랜잭션 재조립용 조각: version || 입력들 || 출력 개수
+        let mut trigger_prefix = serialize(&trigger_tx.version);
+        trigger_prefix.extend(serialize(&trigger_tx.input));
+        trigger_prefix.extend(serialize(&VarInt(trigger_tx.output.len() as u64)));
+        let trigger_extras = serialize_outputs(&trigger_tx.output[2..]);
+        let trigger_locktime = serialize(&trigger_tx.lock_time);
+
+        // 상대 타임락은 nSequence, 절대 타임락은 nLockTime으로 만족시킨다
+        let timelock = self.complete_timelock();
+        let base_sequence = timelock.relative_sequence().unwrap_or(Sequence(SEQUENCE_DISABLE_FLAG));
+        let lock_time = timelock.lock_time();
+
+        let outpoint = OutPoint::new(trigger_tx.txid(), 0);
+        let Some(withdrawal) = withdrawal else {
+            let draft = SpendDraft {
+                leaf: VaultLeaf::Complete,
+                outpoint,
+                prevout: vault_output.clone(),
+                outputs: vec![TxOut {
+                    value: vault_output.value,
+                    script_pubkey: target.clone(),
+                }],
+                base_sequence,
+                lock_time,
+            };
+            return self.build_psbt(draft, funding, move |_, prevout, extras| {
+                vec![
+                    trigger_prefix.clone(),
+                    trigger_extras.clone(),
+                    trigger_locktime.clone(),
+                    extras,
+                    target.to_bytes(),
+                    prevout.script_pubkey.to_bytes(),
+                    serialize(&prevout.value.to_sat()),
+                ]
+            });
+        };
+
+        check_partial_amounts(vault_output.value, withdrawal)?;
+        let change = vault_output.value - withdrawal;
+        let draft = SpendDraft {
+            leaf: VaultLeaf::PartialComplete,
+            outpoint,
+            prevout: vault_output.clone(),
+            outputs: vec![
+                TxOut {
+                    value: withdrawal,
+                    script_pubkey: target.clone(),
+                },
+                TxOut {
+                    value: change,
+                    script_pubkey: vault_output.script_pubkey.clone(),
+                },
+            ],
+            base_sequence,
+            lock_time,
+        };
+        self.build_psbt(draft, funding, move |_, prevout, extras| {
+            let (change_num, change_pad) = amount_parts(change.to_sat());
+            let (withdraw_num, withdraw_pad) = amount_parts(withdrawal.to_sat());
+            let (amount_num, amount_pad) = amount_parts(prevout.value.to_sat());
+            vec![
+                trigger_prefix.clone(),
+                trigger_extras.clone(),
+                trigger_locktime.clone(),
+                extras,
+                target.to_bytes(),
+                prevout.script_pubkey.to_bytes(),
+                change_num,
+                change_pad,
+                withdraw_num,
+                withdraw_pad,
+                amount_num,
+                amount_pad,
+            ]
+        })
+    }
+
+    /// 취소 PSBT - 트리거된 UTXO를 같은 금고에 그대로 재잠금
+    pub fn cancel_psbt(
+        &self,
+        triggered_outpoint: OutPoint,
+        amount: Amount,
+        funding: &FeeFunding,
+    ) -> DeFiResult<Psbt> {
+        let vault_output = TxOut {
+            value: amount,
+            script_pubkey: self.script_pubkey()?,
+        };
+
+        let draft = SpendDraft {
+            leaf: VaultLeaf::Cancel,
+            outpoint: triggered_outpoint,
+            prevout: vault_output.clone(),
+            outputs: vec![vault_output],
+            base_sequence: Sequence(SEQUENCE_DISABLE_FLAG),
+            lock_time: LockTime::ZERO,
+        };
+        self.build_psbt(draft, funding, |tx, prevout, extras| {
+            vec![
+                serialize(&tx.input[0].previous_output),
+                extras,
+                prevout.script_pubkey.to_bytes(),
+                serialize(&prevout.value.to_sat()),
+            ]
+        })
+    }
+
+    /// 회수 PSBT - 금고 또는 트리거된 UTXO 전액을 콜드 주소로 보낸다 (회수 키 서명 필요)
+    pub fn recover_psbt(&self, outpoint: OutPoint, amount: Amount, funding: &FeeFunding) -> DeFiResult<Psbt> {
+        let recovery = self
+            .recovery
+            .as_ref()
+            .ok_or_else(|| DeFiHubError::Configuration("Vault has no recovery path".to_string()))?;
+
+        let draft = SpendDraft {
+            leaf: VaultLeaf::Recover,
+            outpoint,
+            prevout: TxOut {
+                value: amount,
+                script_pubkey: self.script_pubkey()?,
+            },
+            outputs: vec![TxOut {
+                value: amount,
+                script_pubkey: recovery.cold_script.clone(),
+            }],
+            base_sequence: Sequence(SEQUENCE_DISABLE_FLAG),
+            lock_time: LockTime::ZERO,
+        };
+        self.build_psbt(draft, funding, |tx, prevout, extras| {
+            vec![
+                serialize(&tx.input[0].previous_output),
+                extras,
+                prevout.script_pubkey.to_bytes(),
+                serialize(&prevout.value.to_sat()),
+            ]
+        })
+    }
+
+    /// 병합 PSBT - 금고 UTXO들을 하나로 합쳐 같은 금고에 재잠금 (수수료는 금고 금액에서 낸다)
+    pub fn consolidate_psbt(&self, utxos: &[(OutPoint, Amount)], fee_rate: u64) -> DeFiResult<Psbt> {
+        self.merge_psbt(utxos, None, fee_rate)
+    }
+
+    /// 병합 트리거 PSBT - 금고 UTXO들을 합쳐 재잠그면서 출금 대상을 마커로 기록
+    ///
+    /// 결과 트랜잭션은 일반 트리거와 같은 모양이므로 [`Self::complete_psbt`]로 완료할 수 있다.
+    pub fn merge_trigger_psbt(
+        &self,
+        utxos: &[(OutPoint, Amount)],
+        target: &ScriptBuf,
+        fee_rate: u64,
+    ) -> DeFiResult<Psbt> {
+        self.merge_psbt(utxos, Some(target), fee_rate)
+    }
+
+    fn merge_psbt(&self, utxos: &[(OutPoint, Amount)], target: Option<&ScriptBuf>, fee_rate: u64) -> DeFiResult<Psbt> {
+        if utxos.len() != MERGE_INPUTS {
+            return Err(DeFiHubError::BitcoinTransaction(format!(
+                "Merging requires exactly {} vault UTXOs, got {}",
+                MERGE_INPUTS,
+                utxos.len()
+            )));
+        }
+        let total: Amount = utxos.iter().map(|(_, amount)| *amount).sum();
+        if total.to_sat() > MAX_PARTIAL_VAULT_AMOUNT {
+            return Err(DeFiHubError::BitcoinTransaction(format!(
+                "Merging requires vault UTXOs totalling at most {} sats",
+                MAX_PARTIAL_VAULT_AMOUNT
+            )));
+        }
+
+        let leaf = match target {
+            Some(_) => VaultLeaf::MergeTrigger,
+            None => VaultLeaf::Consolidate,
+        };
+        let vault_spk = self.script_pubkey()?;
+        let prevouts: Vec<TxOut> = utxos
+            .iter()
+            .map(|(_, amount)| TxOut {
+                value: *amount,
+                script_pubkey: vault_spk.clone(),
+            })
+            .collect();
+
+        let mut output = vec![TxOut {
+            value: total,
+            script_pubkey: vault_spk.clone(),
+        }];
+        if let Some(target) = target {
+            output.push(TxOut {
+                value: Amount::ZERO,
+                script_pubkey: target_marker(target)?,
+            });
+        }
+        output.push(anchor_output());
+        let base_sequence = Sequence(SEQUENCE_DISABLE_FLAG);
+        let mut tx = Transaction {
+            version: Version(VAULT_TX_VERSION),
+            lock_time: LockTime::ZERO,
+            input: utxos
+                .iter()
+                .map(|(previous_output, _)| TxIn {
+                    previous_output: *previous_output,
+                    script_sig: ScriptBuf::new(),
+                    sequence: base_sequence,
+                    witness: Witness::new(),
+                })
+                .collect(),
+            output,
+        };
+
+        let leaf_script = self.leaf_script(leaf);
+        let control_block = self.control_block(leaf)?;
+        let leaf_hash = TapLeafHash::from_script(&leaf_script, LeafVersion::TapScript);
+
+        let template = |tx: &Transaction, index: usize, e_prefix: &[u8]| -> DeFiResult<Vec<Vec<u8>>> {
+            let mut elements = vec![
+                e_prefix.to_vec(),
+                leaf_hash.to_byte_array().to_vec(),
+                serialize(&tx.lock_time),
+                sha_prevouts(tx)?.to_vec(),
+                sha_sequences(tx)?.to_vec(),
+                serialize(&(index as u32)),
+            ];
+            if let Some(target) = target {
+                elements.push(target.to_bytes());
+            }
+            elements.push(vault_spk.to_bytes());
+            let (out_num, out_pad) = amount_parts(tx.output[0].value.to_sat());
+            elements.extend([out_num, out_pad]);
+            for prevout in prevouts.iter().rev() {
+                let (num, pad) = amount_parts(prevout.value.to_sat());
+                elements.extend([num, pad]);
+            }
+            Ok(elements)
+        };
+
+        // 수수료: 모든 금고 입력의 완성된 증인 크기로 계산
+        let mut sized = tx.clone();
+        for index in 0..sized.input.len() {
+            let mut witness = template(&tx, index, &[0u8; 31])?;
+            witness.extend([vec![0u8; 64], leaf_script.to_bytes(), control_block.serialize()]);
+            sized.input[index].witness = Witness::from_slice(&witness);
+        }
+        let fee = Amount::from_sat(sized.weight().to_vbytes_ceil() * fee_rate);
+        if fee.to_sat() + ANCHOR_AMOUNT > MAX_MERGE_FEE {
+            return Err(DeFiHubError::BitcoinTransaction(format!(
+                "Merge fee of {} plus the anchor exceeds the covenant limit of {} sats",
+                fee, MAX_MERGE_FEE
+            )));
+        }
+        let fee = fee + Amount::from_sat(ANCHOR_AMOUNT);
+        match total.checked_sub(fee) {
+            Some(merged) if merged >= DUST_AMOUNT => tx.output[0].value = merged,
+            _ => {
+                return Err(DeFiHubError::InsufficientFunds {
+                    required: (fee + DUST_AMOUNT).to_sat(),
+                    available: total.to_sat(),
+                })
+            }
+        }
+
+        let grinds = grind_merge(&mut tx, &prevouts, leaf_hash, base_sequence)?;
+        let mut psbt = Psbt::from_unsigned_tx(tx)
+            .map_err(|e| DeFiHubError::BitcoinTransaction(format!("Failed to create PSBT: {}", e)))?;
+
+        for (index, grind) in grinds.iter().enumerate() {
+            let elements = template(&psbt.unsigned_tx, index, &grind.e_prefix)?;
+            self.fill_vault_input(&mut psbt.inputs[index], leaf, prevouts[index].clone(), elements)?;
+        }
+        self.annotate_vault_outputs(&mut psbt)?;
+        Ok(psbt)
+    }
+
+    /// 수수료 계산 → 그라인딩 → PSBT 필드 채우기
+    ///
+    /// `leaf_elements`는 공통 하단부와 소유자 서명 사이의 리프별 증인 원소를 만든다.
+    fn build_psbt<F>(&self, draft: SpendDraft, funding: &FeeFunding, leaf_elements: F) -> DeFiResult<Psbt>
+    where
+        F: Fn(&Transaction, &TxOut, Vec<u8>) -> Vec<Vec<u8>>,
+    {
+        let covenant_outputs = draft.outputs.len();
+        let mut outputs = draft.outputs;
+        outputs.push(anchor_output());
+        let mut tx = Transaction {
+            version: Version(VAULT_TX_VERSION),
+            lock_time: draft.lock_time,
+            input: std::iter::once(draft.outpoint)
+                .chain(funding.inputs.iter().map(|input| input.outpoint))
+                .map(|previous_output| TxIn {
+                    previous_output,
+                    script_sig: ScriptBuf::new(),
+                    sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                    witness: Witness::new(),
+                })
+                .collect(),
+            output: outputs,
+        };
+        tx.input[0].sequence = draft.base_sequence;
+
+        let leaf_script = self.leaf_script(draft.leaf);
+        let control_block = self.control_block(draft.leaf)?;
+        let leaf_hash = TapLeafHash::from_script(&leaf_script, LeafVersion::TapScript);
+        let signer = self.signing_key(draft.leaf);
+
+        let template = |tx: &Transaction, e_prefix: &[u8]| -> Vec<Vec<u8>> {
+            let mut elements = vec![
+                e_prefix.to_vec(),
+                leaf_hash.to_byte_array().to_vec(),
+                serialize(&tx.input[0].sequence),
+                serialize(&tx.lock_time),
+            ];
+            let extras = serialize_outputs(&tx.output[covenant_outputs..]);
+            elements.extend(leaf_elements(tx, &draft.prevout, extras));
+            elements
+        };
+
+        // 수수료: 잔돈 출력을 포함한 크기로 먼저 계산하고, 먼지면 잔돈 없이 다시 계산
+        // (앵커 금액도 수수료 입력에서 낸다)
+        let anchor = Amount::from_sat(ANCHOR_AMOUNT);
+        let available = funding.total_input();
+        let mut with_change = tx.clone();
+        with_change.output.push(TxOut {
+            value: available,
+            script_pubkey: funding.change_script.clone(),
+        });
+        let fee = estimate_fee(&with_change, funding, &template, signer.is_some(), &leaf_script, &control_block)?;
+
+        match available.checked_sub(fee + anchor) {
+            Some(change) if change >= DUST_AMOUNT => {
+                with_change.output.last_mut().expect("change output was pushed").value = change;
+                tx = with_change;
+            }
+            _ => {
+                let fee = estimate_fee(&tx, funding, &template, signer.is_some(), &leaf_script, &control_block)?;
+                if available < fee + anchor {
+                    return Err(DeFiHubError::InsufficientFunds {
+                        required: (fee + anchor).to_sat(),
+                        available: available.to_sat(),
+                    });
+                }
+            }
+        }
+
+        let grind = grind_sequence(&mut tx, 0, &draft.prevout, leaf_hash, draft.base_sequence)?;
+        let elements = template(&tx, &grind.e_prefix);
+
+        let mut psbt = Psbt::from_unsigned_tx(tx)
+            .map_err(|e| DeFiHubError::BitcoinTransaction(format!("Failed to create PSBT: {}", e)))?;
+
+        self.fill_vault_input(&mut psbt.inputs[0], draft.leaf, draft.prevout, elements)?;
+        for (input, fee_input) in psbt.inputs[1..].iter_mut().zip(&funding.inputs) {
+            input.witness_utxo = Some(fee_input.txout.clone());
+        }
+        self.annotate_vault_outputs(&mut psbt)?;
+
+        Ok(psbt)
+    }
+
+    /// 커버넌트 입력의 PSBT 필드 채우기 (리프, 증인 원소, 서명 자리 표시자)
+    fn fill_vault_input(&self, input: &mut Input, leaf: VaultLeaf, prevout: TxOut, elements: Vec<Vec<u8>>) -> DeFiResult<()> {
+        let leaf_script = self.leaf_script(leaf);
+        let leaf_hash = TapLeafHash::from_script(&leaf_script, LeafVersion::TapScript);
+
+        input.witness_utxo = Some(prevout);
+        input.tap_internal_key = Some(nums_internal_key());
+        input.tap_merkle_root = self.spend_info()?.merkle_root();
+        input
+            .tap_scripts
+            .insert(self.control_block(leaf)?, (leaf_script, LeafVersion::TapScript));
+        for (index, element) in elements.into_iter().enumerate() {
+            input
+                .proprietary
+                .insert(proprietary_key(PSBT_WITNESS_ELEMENT, vec![index as u8]), element);
+        }
+        if let Some(signer) = self.signing_key(leaf) {
+            input
+                .tap_key_origins
+                .insert(signer, (vec![leaf_hash], KeySource::default()));
+            input.proprietary.insert(
+                proprietary_key(PSBT_OWNER_SIG_PLACEHOLDER, signer.serialize().to_vec()),
+                Vec::new(),
+            );
+        }
+        Ok(())
+    }
+
+    /// 금고로 재잠그는 출력에는 탭트리 정보를 남겨 서명자가 확인할 수 있게 한다
+    fn annotate_vault_outputs(&self, psbt: &mut Psbt) -> DeFiResult<()> {
+        let vault_spk = self.script_pubkey()?;
+        for (index, output) in psbt.unsigned_tx.output.iter().enumerate() {
+            if output.script_pubkey == vault_spk {
+                psbt.outputs[index].tap_internal_key = Some(nums_internal_key());
+                psbt.outputs[index].tap_tree = Some(self.tap_tree()?);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 커버넌트 입력 완성
+///
+/// 독점 필드의 증인 원소에 소유자 또는 회수 키 서명(필요한 경우), 리프 스크립트, 컨트롤 블록을 붙여
+/// `final_script_witness`를 채우고 BIP-174에 따라 나머지 서명 필드를 지운다.
+pub fn finalize_vault_input(psbt: &mut Psbt, input_index: usize) -> DeFiResult<()> {
+    let input = psbt
+        .inputs
+        .get_mut(input_index)
+        .ok_or_else(|| DeFiHubError::BitcoinTransaction(format!("PSBT input {} does not exist", input_index)))?;
+
+    let (control_block, (leaf_script, leaf_version)) = input
+        .tap_scripts
+        .iter()
+        .next()
+        .map(|(cb, script)| (cb.clone(), script.clone()))
+        .ok_or_else(|| DeFiHubError::BitcoinTransaction("PSBT input has no vault leaf".to_string()))?;
+    let leaf_hash = TapLeafHash::from_script(&leaf_script, leaf_version);
+
+    let mut elements: Vec<(u8, Vec<u8>)> = input
+        .proprietary
+        .iter()
+        .filter(|(key, _)| key.prefix == PSBT_PROPRIETARY_PREFIX && key.subtype == PSBT_WITNESS_ELEMENT)
+        .filter_map(|(key, value)| key.key.first().map(|index| (*index, value.clone())))
+        .collect();
+    if elements.is_empty() {
+        return Err(DeFiHubError::BitcoinTransaction(
+            "PSBT input has no covenant witness".to_string(),
+        ));
+    }
+    elements.sort_by_key(|(index, _)| *index);
+
+    let mut witness: Vec<Vec<u8>> = elements.into_iter().map(|(_, element)| element).collect();
+
+    let owner_placeholder = input
+        .proprietary
+        .keys()
+        .find(|key| key.prefix == PSBT_PROPRIETARY_PREFIX && key.subtype == PSBT_OWNER_SIG_PLACEHOLDER)
+        .cloned();
+    if let Some(placeholder) = owner_placeholder {
+        let signature = input
+            .tap_script_sigs
+            .iter()
+            .find(|((key, hash), _)| key.serialize().as_slice() == placeholder.key.as_slice() && *hash == leaf_hash)
+            .map(|(_, signature)| signature.to_vec())
+            .ok_or_else(|| DeFiHubError::BitcoinTransaction("Missing vault leaf signature".to_string()))?;
+        witness.push(signature);
+    }
+
+    witness.push(leaf_script.to_bytes());
+    witness.push(control_block.serialize());
+
+    input.final_script_witness = Some(Witness::from_slice(&witness));
+    input.tap_scripts.clear();
+    input.tap_script_sigs.clear();
+    input.tap_key_origins.clear();
+    input.tap_internal_key = None;
+    input.tap_merkle_root = None;
+    input.proprietary.retain(|key, _| key.prefix != PSBT_PROPRIETARY_PREFIX);
+    Ok(())
+}
+
+/// 출금 대상 마커 출력 스크립트 `OP_RETURN <target_spk>`
+pub fn target_marker(target: &ScriptBuf) -> DeFiResult<ScriptBuf> {
+    if target.is_empty() || target.len() > MAX_TARGET_SPK_LEN as usize {
+        return Err(DeFiHubError::InvalidAddress(format!(
+            "Withdrawal scriptPubKey must be 1-{} bytes, got {}",
+            MAX_TARGET_SPK_LEN,
+            target.len()
+        )));
+    }
+
+    let push = PushBytesBuf::try_from(target.to_bytes())
+        .map_err(|e| DeFiHubError::InvalidAddress(e.to_string()))?;
+    Ok(Builder::new().push_opcode(OP_RETURN).push_slice(push).into_script())
+}
+
+/// 부분 출금 마커 출력 스크립트 `OP_RETURN <target_spk> <withdraw_amount(8)>`
+pub fn partial_target_marker(target: &ScriptBuf, withdrawal: Amount) -> DeFiResult<ScriptBuf> {
+    let mut marker = target_marker(target)?.into_bytes();
+    marker.push(8);
+    marker.extend_from_slice(&withdrawal.to_sat().to_le_bytes());
+    Ok(ScriptBuf::from_bytes(marker))
+}
+
+/// 트리거 트랜잭션의 마커 출력에서 출금 대상 scriptPubKey 추출
+pub fn trigger_target(trigger_tx: &Transaction) -> DeFiResult<ScriptBuf> {
+    parse_marker(trigger_tx).map(|(target, _)| target)
+}
+
+/// 트리거 트랜잭션의 부분 출금 금액 (전액 출금이면 `None`)
+pub fn trigger_withdrawal_amount(trigger_tx: &Transaction) -> DeFiResult<Option<Amount>> {
+    parse_marker(trigger_tx).map(|(_, withdrawal)| withdrawal)
+}
+
+/// 마커 출력 해석 - `OP_RETURN <target>` 또는 `OP_RETURN <target> <amount(8)>`
+fn parse_marker(trigger_tx: &Transaction) -> DeFiResult<(ScriptBuf, Option<Amount>)> {
+    let marker = trigger_tx
+        .output
+        .get(1)
+        .ok_or_else(|| DeFiHubError::BitcoinTransaction("Trigger transaction has no marker output".to_string()))?;
+
+    let bytes = marker.script_pubkey.as_bytes();
+    let malformed = || DeFiHubError::BitcoinTransaction("Trigger transaction has a malformed marker output".to_string());
+    if marker.value != Amount::ZERO || bytes.len() < 3 || bytes[0] != OP_RETURN.to_u8() {
+        return Err(malformed());
+    }
+
+    let len = bytes[1] as usize;
+    if len == 0 || len as i64 > MAX_TARGET_SPK_LEN {
+        return Err(malformed());
+    }
+    let target = ScriptBuf::from_bytes(bytes[2..].iter().take(len).copied().collect());
+    match bytes.len() - 2 {
+        rest if rest == len => Ok((target, None)),
+        rest if rest == len + 9 && bytes[len + 2] == 8 => {
+            let amount: [u8; 8] = bytes[len + 3..].try_into().expect("length checked above");
+            Ok((target, Some(Amount::from_sat(u64::from_le_bytes(amount)))))
+        }
+        _ => Err(malformed()),
+    }
+}
+
+/// 부분 출금 금액 검사 - 출금액과 잔돈 모두 먼지 이상이고 금고 금액이 스크립트 숫자 범위 안이어야 한다
+fn check_partial_amounts(amount: Amount, withdrawal: Amount) -> DeFiResult<()> {
+    if amount.to_sat() > MAX_PARTIAL_VAULT_AMOUNT {
+        return Err(DeFiHubError::BitcoinTransaction(format!(
+            "Partial withdrawals require a vault of at most {} sats",
+            MAX_PARTIAL_VAULT_AMOUNT
+        )));
+    }
+    let change = amount.checked_sub(withdrawal).unwrap_or(Amount::ZERO);
+    if withdrawal < DUST_AMOUNT || change < DUST_AMOUNT {
+        return Err(DeFiHubError::BitcoinTransaction(format!(
+            "Partial withdrawal of {} from {} leaves a dust output",
+            withdrawal, amount
+        )));
+    }
+    Ok(())
+}
+
+/// 금고 입력 하나를 지출하는 트랜잭션 초안
+struct SpendDraft {
+    leaf: VaultLeaf,
+    outpoint: OutPoint,
+    prevout: TxOut,
+    outputs: Vec<TxOut>,
+    base_sequence: Sequence,
+    lock_time: LockTime,
+}
+
+/// 완성된 증인 크기로 수수료 추정
+fn estimate_fee<F>(
+    tx: &Transaction,
+    funding: &FeeFunding,
+    template: &F,
+    needs_signature: bool,
+    leaf_script: &ScriptBuf,
+    control_block: &bitcoin::taproot::ControlBlock,
+) -> DeFiResult<Amount>
+where
+    F: Fn(&Transaction, &[u8]) -> Vec<Vec<u8>>,
+{
+    let mut sized = tx.clone();
+
+    let mut vault_witness = template(tx, &[0u8; 31]);
+    if needs_signature {
+        vault_witness.push(vec![0u8; 64]);
+    }
+    vault_witness.push(leaf_script.to_bytes());
+    vault_witness.push(control_block.serialize());
+    sized.input[0].witness = Witness::from_slice(&vault_witness);
+
+    for (input, fee_input) in sized.input[1..].iter_mut().zip(&funding.inputs) {
+        input.witness = dummy_witness(&fee_input.txout.script_pubkey)?;
+    }
+
+    let vsize = sized.weight().to_vbytes_ceil();
+    Ok(Amount::from_sat(vsize * funding.fee_rate))
+}
+
+/// 수수료 입력의 서명 크기만큼의 더미 증인
+///
+/// 트리거 txid가 서명 전에 확정되어야 하므로 세그윗 입력만 허용한다.
+pub(crate) fn dummy_witness(script_pubkey: &ScriptBuf) -> DeFiResult<Witness> {
+    if script_pubkey.is_p2tr() {
+        Ok(Witness::from_slice(&[vec![0u8; 64]]))
+    } else if script_pubkey.is_p2wpkh() {
+        Ok(Witness::from_slice(&[vec![0u8; 72], vec![0u8; 33]]))
+    } else {
+        Err(DeFiHubError::BitcoinTransaction(format!(
+            "Unsupported fee input script: {}",
+            script_pubkey
+        )))
+    }
+}
+
+fn serialize_outputs(outputs: &[TxOut]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for output in outputs {
+        output
+            .consensus_encode(&mut bytes)
+            .expect("writing to a Vec never fails");
+    }
+    bytes
+}
+
+fn proprietary_key(subtype: u8, key: Vec<u8>) -> ProprietaryKey {
+    ProprietaryKey {
+        prefix: PSBT_PROPRIETARY_PREFIX.to_vec(),
+        subtype,
+        key,
+    }
+}
@@ -0,0 +1,110 @@
+//! 기간별 출금 한도 (예: 144 블록마다 최대 0.1 BTC)
+//!
+//! 한도가 있는 금고는 부분 출금 경로만 쓴다. 전액 트리거/완료와 병합 트리거 리프를 빼고,
+//! 부분 완료 리프가 출금 금액이 한도([`RateLimit::cap`]) 이하인지 스크립트로 검사한 뒤
+//! 잔돈을 같은 금고에 다시 잠근다. 완료 리프의 CSV는 `max(타임락, 기간)`이므로
+//! 한 번 출금한 뒤 다시 잠긴 금고에서 다음 출금을 완료하려면 적어도 한 기간을 기다려야 한다.
+//! 따라서 같은 금고 UTXO 계보에서는 기간(정렬된 `period_blocks` 구간)마다 최대 한 번,
+//! `cap` 이하만 빠져나갈 수 있다. 취소 트랜잭션에 가짜 마커를 붙여도 같은 리프를 거치므로 우회할 수 없다.
+//!
+//! 스크립트는 기간 안의 누적액을 볼 수 없으므로, 기간 카운터([`WithdrawalWindow`])는 금고가 보관하고
+//! [`BitcoinVault::trigger_withdrawal`](crate::BitcoinVault::trigger_withdrawal)이 남은 한도를 넘는 요청을 거부한다.
+//! 한도는 UTXO 계보마다 걸리므로 한도가 있는 금고는 UTXO가 하나일 때만 트리거한다.
+//! 추가 예치로 UTXO가 둘이 되면 병합하기 전까지 출금도 다음 예치도 받지 않아 UTXO가 늘어나지 않는다.
+
+use crate::script::MAX_PARTIAL_VAULT_AMOUNT;
+use crate::timelock::Timelock;
+use shared::{DeFiHubError, DeFiResult};
+use bitcoin::Amount;
+use serde::{Deserialize, Serialize};
+
+/// 기간별 출금 한도
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimit {
+    /// 기간마다 출금할 수 있는 최대 금액
+    pub cap: Amount,
+
+    /// 기간 길이 (블록)
+    pub period_blocks: u16,
+}
+
+impl RateLimit {
+    /// 한도 생성
+    pub fn new(cap: Amount, period_blocks: u16) -> DeFiResult<Self> {
+        let limit = Self { cap, period_blocks };
+        limit.validate()?;
+        Ok(limit)
+    }
+
+    /// 한도 값 검증 (부분 완료 리프의 스크립트 숫자 범위)
+    pub fn validate(&self) -> DeFiResult<()> {
+        if self.cap == Amount::ZERO || self.cap.to_sat() > MAX_PARTIAL_VAULT_AMOUNT {
+            return Err(DeFiHubError::Configuration(format!(
+                "Rate limit cap must be between 1 and {} sats",
+                MAX_PARTIAL_VAULT_AMOUNT
+            )));
+        }
+        if self.period_blocks == 0 {
+            return Err(DeFiHubError::Configuration("Rate limit period must be at least one block".to_string()));
+        }
+        Ok(())
+    }
+
+    /// 한도가 있는 금고의 완료 리프 타임락 - 상대 타임락과 기간 중 긴 쪽
+    pub fn complete_timelock(&self, timelock: Timelock) -> DeFiResult<Timelock> {
+        match timelock {
+            Timelock::Relative { blocks } => Ok(Timelock::Relative { blocks: blocks.max(self.period_blocks) }),
+            // 기간은 블록 단위이므로 블록 기반 상대 타임락만 쓸 수 있다
+            _ => Err(DeFiHubError::Configuration(
+                "Rate-limited vaults need a relative block timelock".to_string(),
+            )),
+        }
+    }
+
+    /// 블록 높이가 속한 기간 번호
+    pub fn period_of(&self, height: u32) -> u32 {
+        height / self.period_blocks as u32
+    }
+
+    /// 기간이 끝나는 (다음 기간이 시작하는) 블록 높이
+    pub fn period_end(&self, period: u32) -> u32 {
+        period.saturating_add(1).saturating_mul(self.period_blocks as u32)
+    }
+}
+
+/// 현재 기간의 출금 누적 (기간 카운터)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WithdrawalWindow {
+    /// 기간 번호 (`height / period_blocks`)
+    pub period: u32,
+
+    /// 이 기간에 트리거한 출금 합계
+    pub withdrawn: Amount,
+}
+
+impl WithdrawalWindow {
+    /// `height`에서 남은 한도
+    pub fn remaining(&self, limit: &RateLimit, height: u32) -> Amount {
+        if self.period == limit.period_of(height) {
+            limit.cap.checked_sub(self.withdrawn).unwrap_or(Amount::ZERO)
+        } else {
+            limit.cap
+        }
+    }
+
+    /// `height`의 기간에 출금을 더한다 (새 기간이면 카운터를 다시 시작)
+    pub fn spend(&mut self, limit: &RateLimit, height: u32, amount: Amount) {
+        let period = limit.period_of(height);
+        if self.period != period {
+            *self = Self { period, withdrawn: Amount::ZERO };
+        }
+        self.withdrawn += amount;
+    }
+
+    /// 취소된 출금을 트리거했던 기간의 한도로 돌려준다
+    pub fn refund(&mut self, limit: &RateLimit, trigger_height: u32, amount: Amount) {
+        if self.period == limit.period_of(trigger_height) {
+            self.withdrawn = self.withdrawn.checked_sub(amount).unwrap_or(Amount::ZERO);
+        }
+    }
+}
@@ -0,0 +1,681 @@
+//! OP_CAT (BIP-347) 커버넌트 탭스크립트 빌더
+//!
+//! 모든 커버넌트 리프는 "Schnorr 트릭"으로 지출 트랜잭션을 검사한다:
+//! 공개키와 논스를 모두 생성자 G로 고정하면 서명이 `G.x || (e + 1)`이 되므로,
+//! 스크립트가 OP_CAT으로 BIP-341 sighash 프리이미지를 직접 조립하고
+//! 그 챌린지 `e`로 만든 서명을 `G`에 대해 OP_CHECKSIG 하면
+//! 프리이미지의 각 필드가 실제 트랜잭션과 일치함이 보장된다.
+//!
+//! 증인 스택의 공통 하단부 (아래 → 위):
+//! `[e_prefix(31), tapleaf_hash(32), nSequence(4), nLockTime(4), ...]`
+//! `e_prefix`는 마지막 바이트가 0x00인 챌린지의 앞 31바이트다 (그라인딩 필요).
+//!
+//! 여러 금고 UTXO를 합치는 병합 리프만은 모든 입력을 보아야 하므로 `SIGHASH_DEFAULT`
+//! 프리이미지를 쓰며, 공통 하단부가
+//! `[e_prefix(31), tapleaf_hash(32), nLockTime(4), sha_prevouts(32), sha_sequences(32), input_index(4), ...]`이다.
+
+use crate::timelock::Timelock;
+use bitcoin::opcodes::all::*;
+use bitcoin::script::{Builder, PushBytesBuf};
+use bitcoin::{ScriptBuf, XOnlyPublicKey};
+use sha2::{Digest, Sha256};
+
+/// secp256k1 생성자 G의 x좌표 (Schnorr 트릭의 공개키이자 논스)
+pub const G_X: [u8; 32] = [
+    0x79, 0xbe, 0x66, 0x7e, 0xf9, 0xdc, 0xbb, 0xac, 0x55, 0xa0, 0x62, 0x95, 0xce, 0x87, 0x0b, 0x07,
+    0x02, 0x9b, 0xfc, 0xdb, 0x2d, 0xce, 0x28, 0xd9, 0x59, 0xf2, 0x81, 0x5b, 0x16, 0xf8, 0x17, 0x98,
+];
+
+/// BIP-341 NUMS 내부키 - 키 경로 지출을 불가능하게 만든다
+pub const NUMS_INTERNAL_KEY: [u8; 32] = [
+    0x50, 0x92, 0x9b, 0x74, 0xc1, 0xa0, 0x49, 0x54, 0xb7, 0x8b, 0x4b, 0x60, 0x35, 0xe9, 0x7a, 0x5e,
+    0x07, 0x8a, 0x5a, 0x0f, 0x28, 0xec, 0x96, 0xd5, 0x47, 0xbf, 0xee, 0x9a, 0xce, 0x80, 0x3a, 0xc0,
+];
+
+/// 커버넌트 서명의 sighash 타입 (SIGHASH_ALL | SIGHASH_ANYONECANPAY)
+///
+/// ANYONECANPAY 덕분에 수수료 입력을 자유롭게 추가할 수 있고,
+/// 프리이미지에 현재 입력의 금액과 scriptPubKey가 직접 들어간다.
+pub const COVENANT_SIGHASH_TYPE: u8 = 0x81;
+
+/// 커버넌트 트랜잭션 버전 (BIP-68 상대 타임락을 위해 2 고정)
+pub const VAULT_TX_VERSION: i32 = 2;
+
+/// 금고 scriptPubKey 길이 (OP_1 <32바이트 출력키>)
+pub const VAULT_SPK_LEN: i64 = 34;
+
+/// 출금 대상 scriptPubKey 최대 길이 (OP_RETURN 마커에 단일 푸시로 들어가야 함)
+pub const MAX_TARGET_SPK_LEN: i64 = 75;
+
+/// 부분 출금이 가능한 최대 금고 금액 (사토시)
+///
+/// 부분 완료 리프는 금액을 4바이트 스크립트 숫자로 더하므로 `2^31 - 1`을 넘을 수 없다.
+pub const MAX_PARTIAL_VAULT_AMOUNT: u64 = i32::MAX as u64;
+
+/// 병합 리프가 한 번에 합치는 금고 UTXO 수
+pub const MERGE_INPUTS: usize = 2;
+
+/// 병합 트랜잭션이 금고 자금에서 낼 수 있는 최대 수수료 (사토시)
+///
+/// 병합 트랜잭션은 수수료 입력 없이 금고 금액에서 수수료를 빼므로 상한을 둔다.
+pub const MAX_MERGE_FEE: u64 = 50_000;
+
+/// 앵커 출력 scriptPubKey - 누구나 지출할 수 있는 P2A (`OP_1 <0x4e73>`)
+pub const ANCHOR_SPK: [u8; 4] = [0x51, 0x02, 0x4e, 0x73];
+
+/// 앵커 출력 금액 (사토시, P2A 먼지 한도)
+pub const ANCHOR_AMOUNT: u64 = 240;
+
+/// 직렬화된 앵커 출력 `amount(8) || 0x04 || ANCHOR_SPK`
+pub fn anchor_output_bytes() -> Vec<u8> {
+    let mut bytes = ANCHOR_AMOUNT.to_le_bytes().to_vec();
+    bytes.push(ANCHOR_SPK.len() as u8);
+    bytes.extend_from_slice(&ANCHOR_SPK);
+    bytes
+}
+
+/// BIP-340 태그 해시 접두사 `SHA256(tag) || SHA256(tag)`
+pub fn tagged_hash_prefix(tag: &str) -> [u8; 64] {
+    let tag_hash: [u8; 32] = Sha256::digest(tag.as_bytes()).into();
+    let mut prefix = [0u8; 64];
+    prefix[..32].copy_from_slice(&tag_hash);
+    prefix[32..].copy_from_slice(&tag_hash);
+    prefix
+}
+
+/// 트리거 리프 - 소유자 서명으로 출금을 시작한다
+///
+/// 출력 0은 같은 금고 스크립트에 같은 금액을 그대로 다시 잠그고,
+/// 출력 1은 출금 대상 scriptPubKey를 담은 0 사토시 OP_RETURN 마커다.
+///
+/// 증인: `[공통, outpoint(36), extra_outputs, target_spk, vault_spk, amount, owner_sig]`
+pub fn trigger_script(owner_key: &XOnlyPublicKey) -> ScriptBuf {
+    let builder = Builder::new()
+        .push_x_only_key(owner_key)
+        .push_opcode(OP_CHECKSIGVERIFY);
+    let builder = push_self_output_prelude(builder)
+        // [.., extras, target, out0]
+        .push_opcode(OP_SWAP);
+    let builder = push_target_marker(builder)
+        // [.., extras, out0, marker]
+        .push_opcode(OP_CAT)
+        .push_opcode(OP_SWAP)
+        .push_opcode(OP_CAT)
+        .push_opcode(OP_SHA256)
+        .push_opcode(OP_TOALTSTACK);
+    push_sigmsg_and_verify(builder).into_script()
+}
+
+/// 부분 출금 트리거 리프 - 트리거 리프와 같지만 마커에 출금 금액도 기록한다
+///
+/// 출력 0은 금고 전액을 그대로 다시 잠그고, 출력 1은
+/// `OP_RETURN <target_spk> <withdraw_amount(8)>` 마커다.
+///
+/// 증인: `[공통, outpoint(36), extra_outputs, target_spk, withdraw_amount, vault_spk, amount, owner_sig]`
+pub fn partial_trigger_script(owner_key: &XOnlyPublicKey) -> ScriptBuf {
+    let builder = Builder::new()
+        .push_x_only_key(owner_key)
+        .push_opcode(OP_CHECKSIGVERIFY);
+    let builder = push_self_output_prelude(builder)
+        // [.., extras, target, withdraw, out0]
+        .push_opcode(OP_ROT)
+        .push_opcode(OP_ROT)
+        .push_opcode(OP_SIZE)
+        .push_int(8)
+        .push_opcode(OP_EQUALVERIFY);
+    let builder = push_partial_marker(builder)
+        // [.., extras, out0, marker]
+        .push_opcode(OP_CAT)
+        .push_opcode(OP_SWAP)
+        .push_opcode(OP_CAT)
+        .push_opcode(OP_SHA256)
+        .push_opcode(OP_TOALTSTACK);
+    push_sigmsg_and_verify(builder).into_script()
+}
+
+/// 완료 리프 - 타임락 이후 누구나 트리거된 금액을 대상 주소로 보낼 수 있다
+///
+/// 상대 타임락은 `<blocks> OP_CSV`, 절대 타임락은 `<height> OP_CLTV`로 시작한다.
+///
+/// 지출 대상 UTXO가 트리거 트랜잭션의 출력 0임을 확인하기 위해
+/// 트리거 트랜잭션을 증인에서 재조립해 txid를 계산한다.
+///
+/// 증인: `[공통, trigger_prefix, trigger_extras, trigger_locktime, extra_outputs, target_spk, vault_spk, amount]`
+/// `trigger_prefix`는 트리거 트랜잭션의 `version || 입력들 || 출력 개수`다.
+pub fn complete_script(timelock: Timelock) -> ScriptBuf {
+    let builder = push_timelock_check(Builder::new(), timelock);
+    let builder = push_amount_and_spk_checks(builder)
+        .push_opcode(OP_2DUP)
+        .push_opcode(OP_SWAP)
+        .push_opcode(OP_TOALTSTACK)
+        .push_opcode(OP_TOALTSTACK)
+        // 완료 트랜잭션 출력: amount || len || target || extras
+        .push_opcode(OP_DUP)
+        .push_int(3)
+        .push_opcode(OP_PICK);
+    let builder = push_target_spk_len_check(builder)
+        .push_opcode(OP_SWAP)
+        .push_opcode(OP_CAT)
+        .push_opcode(OP_CAT)
+        .push_int(4)
+        .push_opcode(OP_ROLL)
+        .push_opcode(OP_CAT)
+        .push_opcode(OP_SHA256)
+        .push_opcode(OP_TOALTSTACK)
+        // [.., trigger_prefix, trigger_extras, trigger_locktime, target, spk, amount]
+        .push_opcode(OP_SWAP)
+        .push_slice([0x22])
+        .push_opcode(OP_SWAP)
+        .push_opcode(OP_CAT)
+        .push_opcode(OP_CAT)
+        .push_opcode(OP_SWAP);
+    let builder = push_target_marker(builder);
+    let builder = push_trigger_outpoint(builder);
+    push_sigmsg_and_verify(builder).into_script()
+}
+
+/// 부분 출금 완료 리프 - 타임락 이후 마커의 금액만 대상에게 보내고 나머지는 금고에 다시 잠근다
+///
+/// 완료 트랜잭션의 출력 0은 `withdraw || len || target_spk`, 출력 1은 `change || 0x22 || vault_spk`다.
+/// 세 금액은 `(스크립트 숫자, 0 패딩)` 쌍으로 받아 8바이트 필드를 만들고
+/// `withdraw + change == amount`를 확인하므로 어느 출력도 금액을 바꿀 수 없다.
+///
+/// 증인: `[공통, trigger_prefix, trigger_extras, trigger_locktime, extra_outputs, target_spk, vault_spk,
+/// change_num, change_pad, withdraw_num, withdraw_pad, amount_num, amount_pad]`
+pub fn partial_complete_script(timelock: Timelock) -> ScriptBuf {
+    build_partial_complete(timelock, None)
+}
+
+/// 한도가 있는 부분 출금 완료 리프 - 부분 완료 리프에 `withdraw ≤ cap` 검사를 더한다
+///
+/// `timelock`은 기간 이상의 상대 타임락이어야 기간마다 한 번만 완료할 수 있다
+/// ([`RateLimit::complete_timelock`](crate::rate_limit::RateLimit::complete_timelock)).
+/// 증인은 부분 완료 리프와 같다.
+pub fn rate_limited_complete_script(timelock: Timelock, cap: u64) -> ScriptBuf {
+    build_partial_complete(timelock, Some(cap))
+}
+
+fn build_partial_complete(timelock: Timelock, cap: Option<u64>) -> ScriptBuf {
+    let builder = push_timelock_check(Builder::new(), timelock);
+    let builder = push_amount_from_parts(builder).push_opcode(OP_2ROT);
+    let builder = push_amount_from_parts(builder).push_opcode(OP_2ROT);
+    let builder = push_amount_from_parts(builder)
+        // [.., amount_num, amount, change_num, change, withdraw_num, withdraw]
+        .push_opcode(OP_OVER)
+        .push_int(0)
+        .push_opcode(OP_GREATERTHAN)
+        .push_opcode(OP_VERIFY);
+    let builder = match cap {
+        Some(cap) => builder
+            .push_opcode(OP_OVER)
+            .push_int(cap as i64)
+            .push_opcode(OP_LESSTHANOREQUAL)
+            .push_opcode(OP_VERIFY),
+        None => builder,
+    };
+    let builder = builder
+        .push_int(3)
+        .push_opcode(OP_PICK)
+        .push_int(0)
+        .push_opcode(OP_GREATERTHAN)
+        .push_opcode(OP_VERIFY)
+        .push_opcode(OP_OVER)
+        .push_int(4)
+        .push_opcode(OP_PICK)
+        .push_opcode(OP_ADD)
+        .push_int(6)
+        .push_opcode(OP_PICK)
+        .push_opcode(OP_NUMEQUALVERIFY)
+        .push_opcode(OP_NIP)
+        .push_int(2)
+        .push_opcode(OP_ROLL)
+        .push_opcode(OP_DROP)
+        .push_int(3)
+        .push_opcode(OP_ROLL)
+        .push_opcode(OP_DROP)
+        // [.., extras, target, spk, amount, change, withdraw]
+        .push_int(3)
+        .push_opcode(OP_PICK)
+        .push_opcode(OP_SIZE)
+        .push_int(VAULT_SPK_LEN)
+        .push_opcode(OP_EQUALVERIFY)
+        .push_opcode(OP_TOALTSTACK)
+        .push_int(2)
+        .push_opcode(OP_PICK)
+        .push_opcode(OP_TOALTSTACK)
+        // 완료 트랜잭션 출력: withdraw || len || target || change || 0x22 || spk || extras
+        .push_opcode(OP_DUP)
+        .push_int(5)
+        .push_opcode(OP_PICK);
+    let builder = push_target_spk_len_check(builder)
+        .push_opcode(OP_SWAP)
+        .push_opcode(OP_CAT)
+        .push_opcode(OP_CAT)
+        .push_int(2)
+        .push_opcode(OP_PICK)
+        .push_slice([0x22])
+        .push_opcode(OP_CAT)
+        .push_int(5)
+        .push_opcode(OP_PICK)
+        .push_opcode(OP_CAT)
+        .push_opcode(OP_CAT)
+        .push_int(6)
+        .push_opcode(OP_ROLL)
+        .push_opcode(OP_CAT)
+        .push_opcode(OP_SHA256)
+        .push_opcode(OP_TOALTSTACK)
+        // [.., trigger_prefix, trigger_extras, trigger_locktime, target, spk, amount, change, withdraw]
+        .push_opcode(OP_NIP)
+        .push_opcode(OP_ROT)
+        .push_opcode(OP_ROT)
+        .push_opcode(OP_SWAP)
+        .push_slice([0x22])
+        .push_opcode(OP_SWAP)
+        .push_opcode(OP_CAT)
+        .push_opcode(OP_CAT)
+        .push_opcode(OP_ROT)
+        .push_opcode(OP_ROT);
+    let builder = push_partial_marker(builder);
+    let builder = push_trigger_outpoint(builder);
+    push_sigmsg_and_verify(builder).into_script()
+}
+
+/// 취소 리프 - 소유자 서명으로 트리거된 금액을 같은 금고에 다시 잠근다
+///
+/// 증인: `[공통, outpoint(36), extra_outputs, vault_spk, amount, owner_sig]`
+pub fn cancel_script(owner_key: &XOnlyPublicKey) -> ScriptBuf {
+    let builder = Builder::new()
+        .push_x_only_key(owner_key)
+        .push_opcode(OP_CHECKSIGVERIFY);
+    let builder = push_self_output_prelude(builder)
+        // [.., extras, out0]
+        .push_opcode(OP_SWAP)
+        .push_opcode(OP_CAT)
+        .push_opcode(OP_SHA256)
+        .push_opcode(OP_TOALTSTACK);
+    push_sigmsg_and_verify(builder).into_script()
+}
+
+/// 회수 리프 - 회수 키 서명으로 언제든 전액을 미리 정한 콜드 주소로 보낸다
+///
+/// 트리거 전후 모두 쓸 수 있으며, 출력 0은 `amount || len || cold_spk`로 고정된다.
+///
+/// 증인: `[공통, outpoint(36), extra_outputs, vault_spk, amount, recovery_sig]`
+pub fn recover_script(recovery_key: &XOnlyPublicKey, cold_script: &ScriptBuf) -> ScriptBuf {
+    let mut cold_output = vec![cold_script.len() as u8];
+    cold_output.extend_from_slice(cold_script.as_bytes());
+
+    let builder = Builder::new()
+        .push_x_only_key(recovery_key)
+        .push_opcode(OP_CHECKSIGVERIFY);
+    let builder = push_amount_and_spk_checks(builder)
+        .push_opcode(OP_2DUP)
+        .push_opcode(OP_SWAP)
+        .push_opcode(OP_TOALTSTACK)
+        .push_opcode(OP_TOALTSTACK)
+        // [.., extras, vault_spk, amount] → [.., extras, amount || len || cold_spk]
+        .push_opcode(OP_NIP)
+        .push_slice(push_bytes(cold_output))
+        .push_opcode(OP_CAT)
+        .push_opcode(OP_SWAP)
+        .push_opcode(OP_CAT)
+        .push_opcode(OP_SHA256)
+        .push_opcode(OP_TOALTSTACK);
+    push_sigmsg_and_verify(builder).into_script()
+}
+
+/// 병합 리프 - 소유자 서명으로 금고 UTXO [`MERGE_INPUTS`]개를 하나로 합친다
+///
+/// 트랜잭션의 입력은 모두 같은 금고 스크립트의 UTXO여야 하고 (`sha_scriptpubkeys`를 스크립트가 만든다),
+/// 출력 0은 같은 금고에 `입력 합계 - 수수료 - 앵커`를 잠그고 (수수료 + 앵커 ≤ [`MAX_MERGE_FEE`]),
+/// 마지막 출력은 CPFP용 앵커다. `with_marker`이면 출력 1에 출금 대상 마커를 붙인 병합 트리거 리프가 되며,
+/// 그 출력은 일반 트리거 출력처럼 완료/취소 리프로 지출한다.
+///
+/// 금액 산술은 부분 완료 리프와 같은 `(스크립트 숫자, 0 패딩)` 쌍을 쓴다.
+///
+/// 증인: `[병합 공통, (target_spk), vault_spk, out_num, out_pad, a_{n-1}_num, a_{n-1}_pad, .., a_0_num, a_0_pad, owner_sig]`
+pub fn merge_script(owner_key: &XOnlyPublicKey, with_marker: bool) -> ScriptBuf {
+    let builder = Builder::new()
+        .push_x_only_key(owner_key)
+        .push_opcode(OP_CHECKSIGVERIFY);
+
+    // 입력 금액들: [.., sum, a_0 || a_1 || ..]
+    let mut builder = push_amount_from_parts(builder)
+        .push_opcode(OP_OVER)
+        .push_int(0)
+        .push_opcode(OP_GREATERTHAN)
+        .push_opcode(OP_VERIFY);
+    for _ in 1..MERGE_INPUTS {
+        builder = push_amount_from_parts(builder.push_opcode(OP_2SWAP))
+            .push_opcode(OP_OVER)
+            .push_int(0)
+            .push_opcode(OP_GREATERTHAN)
+            .push_opcode(OP_VERIFY)
+            .push_opcode(OP_ROT)
+            .push_opcode(OP_SWAP)
+            .push_opcode(OP_CAT)
+            .push_opcode(OP_ROT)
+            .push_opcode(OP_ROT)
+            .push_opcode(OP_ADD)
+            .push_opcode(OP_SWAP);
+    }
+    let builder = builder
+        .push_opcode(OP_SHA256)
+        .push_opcode(OP_TOALTSTACK)
+        // [.., vault_spk, out_num, out_pad, sum] → 0 ≤ sum - out ≤ MAX_MERGE_FEE
+        .push_opcode(OP_ROT)
+        .push_opcode(OP_ROT);
+    let builder = push_amount_from_parts(builder)
+        .push_opcode(OP_OVER)
+        .push_int(0)
+        .push_opcode(OP_GREATERTHAN)
+        .push_opcode(OP_VERIFY)
+        .push_opcode(OP_ROT)
+        .push_opcode(OP_ROT)
+        .push_opcode(OP_SUB)
+        .push_int(0)
+        .push_int(MAX_MERGE_FEE as i64 + 1)
+        .push_opcode(OP_WITHIN)
+        .push_opcode(OP_VERIFY)
+        // [.., vault_spk, out] → [.., 0x22 || vault_spk, out0]
+        .push_opcode(OP_SWAP)
+        .push_opcode(OP_SIZE)
+        .push_int(VAULT_SPK_LEN)
+        .push_opcode(OP_EQUALVERIFY)
+        .push_slice([0x22])
+        .push_opcode(OP_SWAP)
+        .push_opcode(OP_CAT)
+        .push_opcode(OP_TUCK)
+        .push_opcode(OP_CAT);
+    let builder = if with_marker {
+        push_target_marker(builder.push_opcode(OP_ROT)).push_opcode(OP_CAT)
+    } else {
+        builder
+    };
+    // 마지막 출력은 고정된 앵커
+    let builder = builder.push_slice(push_bytes(anchor_output_bytes())).push_opcode(OP_CAT);
+
+    // sha_outputs, sha_scriptpubkeys (모든 입력이 같은 금고 스크립트)
+    let mut builder = builder.push_opcode(OP_SHA256).push_opcode(OP_SWAP);
+    for _ in 1..MERGE_INPUTS {
+        builder = builder.push_opcode(OP_DUP);
+    }
+    for _ in 1..MERGE_INPUTS {
+        builder = builder.push_opcode(OP_CAT);
+    }
+    let builder = builder.push_opcode(OP_SHA256);
+    push_merge_sigmsg_and_verify(builder).into_script()
+}
+
+/// `amount`(8바이트)와 `vault_spk`(34바이트)의 길이 검사
+///
+/// 필드 경계를 옮겨 같은 바이트열을 다르게 해석하는 공격을 막는다.
+fn push_amount_and_spk_checks(builder: Builder) -> Builder {
+    builder
+        .push_opcode(OP_SIZE)
+        .push_int(8)
+        .push_opcode(OP_EQUALVERIFY)
+        .push_opcode(OP_SWAP)
+        .push_opcode(OP_SIZE)
+        .push_int(VAULT_SPK_LEN)
+        .push_opcode(OP_EQUALVERIFY)
+        .push_opcode(OP_SWAP)
+}
+
+/// `[.., vault_spk, amount]` → `[.., out0]`, 알트스택에 `[vault_spk, amount]` 보관
+///
+/// out0 = `amount || 0x22 || vault_spk` - 현재 입력을 같은 금고에 같은 금액으로 재잠금
+fn push_self_output_prelude(builder: Builder) -> Builder {
+    push_amount_and_spk_checks(builder)
+        .push_opcode(OP_2DUP)
+        .push_opcode(OP_SWAP)
+        .push_opcode(OP_TOALTSTACK)
+        .push_opcode(OP_TOALTSTACK)
+        .push_opcode(OP_SWAP)
+        .push_slice([0x22])
+        .push_opcode(OP_SWAP)
+        .push_opcode(OP_CAT)
+        .push_opcode(OP_CAT)
+}
+
+/// `[.., target_spk]` → `[.., target_spk, len]` (1 ≤ len ≤ 75 검사)
+///
+/// 길이를 OP_SIZE로 직접 계산하므로 증인이 compact size를 조작할 수 없다.
+fn push_target_spk_len_check(builder: Builder) -> Builder {
+    builder
+        .push_opcode(OP_SIZE)
+        .push_opcode(OP_DUP)
+        .push_int(1)
+        .push_int(MAX_TARGET_SPK_LEN + 1)
+        .push_opcode(OP_WITHIN)
+        .push_opcode(OP_VERIFY)
+}
+
+/// `[.., target_spk]` → `[.., marker]`
+///
+/// marker = `0u64 || (len + 2) || OP_RETURN || len || target_spk`
+fn push_target_marker(builder: Builder) -> Builder {
+    push_target_spk_len_check(builder)
+        .push_opcode(OP_DUP)
+        .push_int(2)
+        .push_opcode(OP_ADD)
+        .push_slice([OP_RETURN.to_u8()])
+        .push_opcode(OP_CAT)
+        .push_opcode(OP_SWAP)
+        .push_opcode(OP_CAT)
+        .push_opcode(OP_SWAP)
+        .push_opcode(OP_CAT)
+        .push_slice([0u8; 8])
+        .push_opcode(OP_SWAP)
+        .push_opcode(OP_CAT)
+}
+
+/// `[.., target_spk, withdraw]` → `[.., marker]`
+///
+/// marker = `0u64 || (len + 11) || OP_RETURN || len || target_spk || 0x08 || withdraw`
+fn push_partial_marker(builder: Builder) -> Builder {
+    let builder = builder.push_opcode(OP_SWAP);
+    push_target_spk_len_check(builder)
+        .push_opcode(OP_DUP)
+        .push_int(11)
+        .push_opcode(OP_ADD)
+        .push_slice([OP_RETURN.to_u8()])
+        .push_opcode(OP_CAT)
+        .push_opcode(OP_SWAP)
+        .push_opcode(OP_CAT)
+        .push_opcode(OP_SWAP)
+        .push_opcode(OP_CAT)
+        .push_slice([0x08])
+        .push_opcode(OP_CAT)
+        .push_opcode(OP_SWAP)
+        .push_opcode(OP_CAT)
+        .push_slice([0u8; 8])
+        .push_opcode(OP_SWAP)
+        .push_opcode(OP_CAT)
+}
+
+/// 완료 리프의 타임락 검사: `<sequence> OP_CSV` 또는 `<height|timestamp> OP_CLTV`
+fn push_timelock_check(builder: Builder, timelock: Timelock) -> Builder {
+    let builder = builder.push_int(timelock.script_operand());
+    if timelock.is_relative() {
+        builder.push_opcode(OP_CSV)
+    } else {
+        builder.push_opcode(OP_CLTV)
+    }
+    .push_opcode(OP_DROP)
+}
+
+/// `[.., num, pad]` → `[.., num, amount(8)]`
+///
+/// `pad`는 4~7바이트의 0이어야 하고 `num || pad`는 정확히 8바이트여야 한다.
+/// 따라서 `num`은 4바이트 이하의 스크립트 숫자이며, 양수라면 8바이트 금액 필드와 같은 값이다.
+fn push_amount_from_parts(builder: Builder) -> Builder {
+    builder
+        .push_opcode(OP_DUP)
+        .push_slice([0u8; 4])
+        .push_opcode(OP_EQUAL)
+        .push_opcode(OP_OVER)
+        .push_slice([0u8; 5])
+        .push_opcode(OP_EQUAL)
+        .push_opcode(OP_BOOLOR)
+        .push_opcode(OP_OVER)
+        .push_slice([0u8; 6])
+        .push_opcode(OP_EQUAL)
+        .push_opcode(OP_BOOLOR)
+        .push_opcode(OP_OVER)
+        .push_slice([0u8; 7])
+        .push_opcode(OP_EQUAL)
+        .push_opcode(OP_BOOLOR)
+        .push_opcode(OP_VERIFY)
+        .push_opcode(OP_OVER)
+        .push_opcode(OP_SWAP)
+        .push_opcode(OP_CAT)
+        .push_opcode(OP_SIZE)
+        .push_int(8)
+        .push_opcode(OP_EQUALVERIFY)
+}
+
+/// `[.., trigger_prefix, trigger_extras, trigger_locktime, out0, marker]` → `[.., trigger_outpoint]`
+///
+/// 트리거 트랜잭션을 재조립해 txid를 계산하고 vout 0을 붙인다.
+fn push_trigger_outpoint(builder: Builder) -> Builder {
+    builder
+        .push_opcode(OP_CAT)
+        .push_opcode(OP_ROT)
+        .push_opcode(OP_CAT)
+        .push_opcode(OP_SWAP)
+        .push_opcode(OP_CAT)
+        .push_opcode(OP_CAT)
+        .push_opcode(OP_HASH256)
+        .push_slice([0u8; 4])
+        .push_opcode(OP_CAT)
+}
+
+/// 부분 완료 증인의 금액 조각 `(스크립트 숫자, 0 패딩)`
+///
+/// 1 ≤ `sats` ≤ [`MAX_PARTIAL_VAULT_AMOUNT`]일 때만 리프가 받아들인다.
+pub fn amount_parts(sats: u64) -> (Vec<u8>, Vec<u8>) {
+    let mut num = sats.to_le_bytes().to_vec();
+    while num.last() == Some(&0) {
+        num.pop();
+    }
+    // 최상위 비트가 1이면 부호 바이트를 붙인다
+    if num.last().is_some_and(|byte| byte & 0x80 != 0) {
+        num.push(0x00);
+    }
+    let pad = vec![0u8; 8usize.saturating_sub(num.len())];
+    (num, pad)
+}
+
+/// 공통 꼬리: sighash 프리이미지 조립 → 챌린지 검증 → G에 대한 OP_CHECKSIG
+///
+/// 스택 `[e_prefix, tapleaf_hash, nSequence, nLockTime, outpoint]`,
+/// 알트스택 `[vault_spk, amount, sha_outputs]`에서 시작한다.
+fn push_sigmsg_and_verify(builder: Builder) -> Builder {
+    // epoch || hash_type || nVersion
+    let mut header = vec![0x00, COVENANT_SIGHASH_TYPE];
+    header.extend_from_slice(&VAULT_TX_VERSION.to_le_bytes());
+
+    let builder = builder
+        .push_opcode(OP_SWAP)
+        .push_slice(push_bytes(header))
+        .push_opcode(OP_SWAP)
+        .push_opcode(OP_CAT)
+        .push_opcode(OP_FROMALTSTACK)
+        .push_opcode(OP_CAT)
+        // spend_type: 스크립트 경로, annex 없음
+        .push_int(2)
+        .push_opcode(OP_CAT)
+        .push_opcode(OP_SWAP)
+        .push_opcode(OP_CAT)
+        .push_opcode(OP_FROMALTSTACK)
+        .push_opcode(OP_CAT)
+        .push_slice([0x22])
+        .push_opcode(OP_CAT)
+        .push_opcode(OP_FROMALTSTACK)
+        .push_opcode(OP_CAT)
+        .push_opcode(OP_SWAP)
+        .push_opcode(OP_CAT)
+        .push_opcode(OP_SWAP)
+        .push_opcode(OP_CAT)
+        // key_version || codesep_pos
+        .push_slice([0x00, 0xff, 0xff, 0xff, 0xff])
+        .push_opcode(OP_CAT);
+    push_challenge_and_verify(builder, &[0x01, COVENANT_SIGHASH_TYPE])
+}
+
+/// `[e_prefix, sigmsg]` → 챌린지 검증 후 G에 대한 OP_CHECKSIG
+///
+/// sig = `G.x || e_prefix || sig_suffix` (`0x01`, 기본 sighash가 아니면 뒤에 hash_type)
+fn push_challenge_and_verify(builder: Builder, sig_suffix: &[u8]) -> Builder {
+    let sighash_prefix = tagged_hash_prefix("TapSighash");
+    let mut challenge_prefix = tagged_hash_prefix("BIP0340/challenge").to_vec();
+    challenge_prefix.extend_from_slice(&G_X);
+    challenge_prefix.extend_from_slice(&G_X);
+
+    builder
+        .push_slice(push_bytes(sighash_prefix.to_vec()))
+        .push_opcode(OP_SWAP)
+        .push_opcode(OP_CAT)
+        .push_opcode(OP_SHA256)
+        .push_slice(push_bytes(challenge_prefix))
+        .push_opcode(OP_SWAP)
+        .push_opcode(OP_CAT)
+        .push_opcode(OP_SHA256)
+        // e == e_prefix || 0x00
+        .push_opcode(OP_OVER)
+        .push_slice([0x00])
+        .push_opcode(OP_CAT)
+        .push_opcode(OP_EQUALVERIFY)
+        .push_slice(G_X)
+        .push_opcode(OP_SWAP)
+        .push_opcode(OP_CAT)
+        .push_slice(push_bytes(sig_suffix.to_vec()))
+        .push_opcode(OP_CAT)
+        .push_slice(G_X)
+        .push_opcode(OP_CHECKSIG)
+}
+
+/// 병합 리프 꼬리: `SIGHASH_DEFAULT` 프리이미지 조립 → 챌린지 검증 → G에 대한 OP_CHECKSIG
+///
+/// 스택 `[e_prefix, tapleaf_hash, nLockTime, sha_prevouts, sha_sequences, input_index, sha_outputs, sha_scriptpubkeys]`,
+/// 알트스택 `[sha_amounts]`에서 시작한다.
+fn push_merge_sigmsg_and_verify(builder: Builder) -> Builder {
+    // epoch || hash_type || nVersion
+    let mut header = vec![0x00, 0x00];
+    header.extend_from_slice(&VAULT_TX_VERSION.to_le_bytes());
+
+    let builder = builder
+        // sha_amounts || sha_scriptpubkeys || sha_sequences || sha_outputs || spend_type || input_index
+        .push_opcode(OP_FROMALTSTACK)
+        .push_opcode(OP_SWAP)
+        .push_opcode(OP_CAT)
+        .push_int(3)
+        .push_opcode(OP_ROLL)
+        .push_opcode(OP_CAT)
+        .push_opcode(OP_SWAP)
+        .push_opcode(OP_CAT)
+        .push_int(2)
+        .push_opcode(OP_CAT)
+        .push_opcode(OP_SWAP)
+        .push_opcode(OP_CAT)
+        // header || nLockTime || sha_prevouts || ..
+        .push_opcode(OP_ROT)
+        .push_opcode(OP_ROT)
+        .push_opcode(OP_CAT)
+        .push_slice(push_bytes(header))
+        .push_opcode(OP_SWAP)
+        .push_opcode(OP_CAT)
+        .push_opcode(OP_SWAP)
+        .push_opcode(OP_CAT)
+        // .. || tapleaf_hash || key_version || codesep_pos
+        .push_opcode(OP_SWAP)
+        .push_opcode(OP_CAT)
+        .push_slice([0x00, 0xff, 0xff, 0xff, 0xff])
+        .push_opcode(OP_CAT);
+    push_challenge_and_verify(builder, &[0x01])
+}
+
+fn push_bytes(bytes: Vec<u8>) -> PushBytesBuf {
+    PushBytesBuf::try_from(bytes).expect("constant push fits in a script element")
+}
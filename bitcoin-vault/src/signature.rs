@@ -0,0 +1,336 @@
+//! 커버넌트 서명 (Schnorr 트릭)
+//!
+//! [`taproot_sigmsg`]는 트랜잭션 조각으로 BIP-341 서명 메시지를 조립하고,
+//! [`fixed_nonce_signature`]는 비밀키와 논스가 모두 1인 서명을 만든다.
+//!
+//! 커버넌트 리프는 `SIGHASH_ALL | SIGHASH_ANYONECANPAY` 프리이미지를 스크립트에서 조립하고
+//! 공개키·논스가 모두 G인 서명을 OP_CHECKSIG로 검사한다. 따라서 지출 트랜잭션은
+//! 챌린지 `e`의 마지막 바이트가 0x00이 될 때까지 nSequence를 바꿔 가며 그라인딩해야 한다.
+//!
+//! 병합 리프는 `SIGHASH_DEFAULT` 프리이미지를 쓰므로 모든 금고 입력의 챌린지가
+//! 동시에 조건을 만족할 때까지 입력들의 nSequence를 함께 그라인딩한다.
+
+use crate::script::{tagged_hash_prefix, G_X};
+use shared::{DeFiHubError, DeFiResult};
+use bitcoin::consensus::Encodable;
+use bitcoin::hashes::Hash;
+use bitcoin::sighash::{Prevouts, TapSighashType};
+use bitcoin::taproot::TapLeafHash;
+use bitcoin::{Sequence, Transaction, TxOut};
+use secp256kfun::marker::{NonZero, Public};
+use secp256kfun::{op, Scalar};
+use sha2::{Digest, Sha256};
+use std::borrow::Borrow;
+
+/// BIP-68이 해석하지 않는 nSequence 비트 (16-21, 23-30) - 그라인딩에 사용한다
+pub const SEQUENCE_GRIND_MASK: u32 = 0x7fbf_0000;
+
+/// 상대 타임락 비활성화 플래그 (BIP-68)
+pub const SEQUENCE_DISABLE_FLAG: u32 = 1 << 31;
+
+/// 출력 해시 종류 (hash_type 하위 2비트)
+const SIGHASH_NONE: u8 = 0x02;
+const SIGHASH_SINGLE: u8 = 0x03;
+
+/// 그라인딩 결과
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GrindResult {
+    /// 커버넌트 입력에 사용할 nSequence
+    pub sequence: Sequence,
+
+    /// 챌린지의 앞 31바이트 (증인 스택 최하단)
+    pub e_prefix: [u8; 31],
+}
+
+/// BIP-341 서명 메시지 (epoch 포함)
+///
+/// 트랜잭션 조각(버전, nLockTime, 입력/출력 해시, 현재 입력 정보)으로 `SigMsg`를 조립한다.
+/// `leaf_hash`가 있으면 스크립트 경로(`ext_flag = 1`, key_version 0, 코드 구분자 없음), 없으면 키 경로다.
+/// annex는 지원하지 않는다. ANYONECANPAY면 `prevouts`에 현재 입력의 출력 하나만 있어도 된다.
+pub fn taproot_sigmsg<T: Borrow<TxOut>>(
+    tx: &Transaction,
+    input_index: usize,
+    prevouts: &Prevouts<T>,
+    hash_type: TapSighashType,
+    leaf_hash: Option<TapLeafHash>,
+) -> DeFiResult<Vec<u8>> {
+    let input = tx
+        .input
+        .get(input_index)
+        .ok_or_else(|| DeFiHubError::BitcoinTransaction(format!("Input {} does not exist", input_index)))?;
+    let anyone_can_pay = matches!(
+        hash_type,
+        TapSighashType::AllPlusAnyoneCanPay
+            | TapSighashType::NonePlusAnyoneCanPay
+            | TapSighashType::SinglePlusAnyoneCanPay
+    );
+    let output_type = hash_type as u8 & 0x03;
+
+    let mut msg = vec![0x00, hash_type as u8];
+    tx.version.consensus_encode(&mut msg).map_err(io_error)?;
+    tx.lock_time.consensus_encode(&mut msg).map_err(io_error)?;
+    if !anyone_can_pay {
+        let all = match prevouts {
+            Prevouts::All(all) if all.len() == tx.input.len() => all,
+            _ => {
+                return Err(DeFiHubError::BitcoinTransaction(
+                    "Sighash type commits to every spent output".to_string(),
+                ))
+            }
+        };
+        msg.extend_from_slice(&sha_prevouts(tx)?);
+        msg.extend_from_slice(&sha_amounts(all)?);
+        msg.extend_from_slice(&sha_scriptpubkeys(all)?);
+        msg.extend_from_slice(&sha_sequences(tx)?);
+    }
+    if output_type != SIGHASH_NONE && output_type != SIGHASH_SINGLE {
+        msg.extend_from_slice(&sha_outputs(&tx.output)?);
+    }
+
+    msg.push(if leaf_hash.is_some() { 0x02 } else { 0x00 });
+    if anyone_can_pay {
+        let prevout = match prevouts {
+            Prevouts::One(index, prevout) if *index == input_index => prevout.borrow(),
+            Prevouts::All(all) if all.len() == tx.input.len() => all[input_index].borrow(),
+            _ => {
+                return Err(DeFiHubError::BitcoinTransaction(format!(
+                    "Missing spent output for input {}",
+                    input_index
+                )))
+            }
+        };
+        input.previous_output.consensus_encode(&mut msg).map_err(io_error)?;
+        prevout.value.to_sat().consensus_encode(&mut msg).map_err(io_error)?;
+        prevout.script_pubkey.consensus_encode(&mut msg).map_err(io_error)?;
+        input.sequence.consensus_encode(&mut msg).map_err(io_error)?;
+    } else {
+        (input_index as u32).consensus_encode(&mut msg).map_err(io_error)?;
+    }
+    if output_type == SIGHASH_SINGLE {
+        let output = tx.output.get(input_index).ok_or_else(|| {
+            DeFiHubError::BitcoinTransaction(format!("SIGHASH_SINGLE input {} has no matching output", input_index))
+        })?;
+        msg.extend_from_slice(&sha_outputs(std::slice::from_ref(output))?);
+    }
+
+    if let Some(leaf_hash) = leaf_hash {
+        msg.extend_from_slice(&leaf_hash.to_byte_array());
+        msg.extend_from_slice(&[0x00, 0xff, 0xff, 0xff, 0xff]);
+    }
+    Ok(msg)
+}
+
+/// 커버넌트 입력의 BIP-341 서명 메시지 (epoch 포함)
+///
+/// `hash_type = 0x81`, 스크립트 경로, annex 없음, 코드 구분자 없음.
+pub fn covenant_sigmsg(
+    tx: &Transaction,
+    input_index: usize,
+    prevout: &TxOut,
+    leaf_hash: TapLeafHash,
+) -> DeFiResult<Vec<u8>> {
+    taproot_sigmsg(
+        tx,
+        input_index,
+        &Prevouts::One(input_index, prevout),
+        TapSighashType::AllPlusAnyoneCanPay,
+        Some(leaf_hash),
+    )
+}
+
+/// 병합 서명 메시지에서 sha_sequences의 위치 (epoch, hash_type, version, locktime, 해시 3개 다음)
+const MERGE_SHA_SEQUENCES_OFFSET: usize = 2 + 4 + 4 + 32 * 3;
+
+/// 병합 리프 입력의 BIP-341 서명 메시지 (epoch 포함)
+///
+/// `hash_type = 0x00` (SIGHASH_DEFAULT), 스크립트 경로, annex 없음, 코드 구분자 없음.
+pub fn merge_sigmsg(
+    tx: &Transaction,
+    input_index: usize,
+    prevouts: &[TxOut],
+    leaf_hash: TapLeafHash,
+) -> DeFiResult<Vec<u8>> {
+    taproot_sigmsg(tx, input_index, &Prevouts::All(prevouts), TapSighashType::Default, Some(leaf_hash))
+}
+
+/// 모든 입력 outpoint의 SHA256
+pub fn sha_prevouts(tx: &Transaction) -> DeFiResult<[u8; 32]> {
+    let mut buf = Vec::new();
+    for input in &tx.input {
+        input.previous_output.consensus_encode(&mut buf).map_err(io_error)?;
+    }
+    Ok(Sha256::digest(&buf).into())
+}
+
+/// 모든 입력 nSequence의 SHA256
+pub fn sha_sequences(tx: &Transaction) -> DeFiResult<[u8; 32]> {
+    let mut buf = Vec::new();
+    for input in &tx.input {
+        input.sequence.consensus_encode(&mut buf).map_err(io_error)?;
+    }
+    Ok(Sha256::digest(&buf).into())
+}
+
+/// 지출되는 모든 출력 금액의 SHA256
+pub fn sha_amounts<T: Borrow<TxOut>>(prevouts: &[T]) -> DeFiResult<[u8; 32]> {
+    let mut buf = Vec::new();
+    for prevout in prevouts {
+        prevout.borrow().value.to_sat().consensus_encode(&mut buf).map_err(io_error)?;
+    }
+    Ok(Sha256::digest(&buf).into())
+}
+
+/// 지출되는 모든 출력 scriptPubKey의 SHA256
+pub fn sha_scriptpubkeys<T: Borrow<TxOut>>(prevouts: &[T]) -> DeFiResult<[u8; 32]> {
+    let mut buf = Vec::new();
+    for prevout in prevouts {
+        prevout.borrow().script_pubkey.consensus_encode(&mut buf).map_err(io_error)?;
+    }
+    Ok(Sha256::digest(&buf).into())
+}
+
+/// 직렬화된 출력들의 SHA256
+pub fn sha_outputs(outputs: &[TxOut]) -> DeFiResult<[u8; 32]> {
+    let mut buf = Vec::new();
+    for output in outputs {
+        output.consensus_encode(&mut buf).map_err(io_error)?;
+    }
+    Ok(Sha256::digest(&buf).into())
+}
+
+/// 서명 메시지 → TapSighash
+pub fn tap_sighash(sigmsg: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(tagged_hash_prefix("TapSighash"));
+    hasher.update(sigmsg);
+    hasher.finalize().into()
+}
+
+/// P = R = G 일 때의 BIP-340 챌린지
+pub fn covenant_challenge(sighash: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(tagged_hash_prefix("BIP0340/challenge"));
+    hasher.update(G_X);
+    hasher.update(G_X);
+    hasher.update(sighash);
+    hasher.finalize().into()
+}
+
+/// 비밀키와 논스가 모두 1인 BIP-340 서명 (P = R = G)
+///
+/// `s = 1 + e`이므로 서명은 `G_x || (1 + e)`다. 챌린지가 0x00으로 끝나도록 그라인딩된 트랜잭션이면
+/// `s = e[..31] || 0x01`이 되어 스크립트가 OP_CAT만으로 같은 서명을 만들 수 있다.
+/// `hash_type`이 `SIGHASH_DEFAULT`가 아니면 65바이트 서명이 된다.
+pub fn fixed_nonce_signature(sighash: &[u8; 32], hash_type: TapSighashType) -> Vec<u8> {
+    let e = Scalar::<Public, _>::from_bytes_mod_order(covenant_challenge(sighash));
+    let s = op::scalar_add(&Scalar::<Public, NonZero>::one(), &e);
+
+    let mut signature = G_X.to_vec();
+    signature.extend_from_slice(&s.to_bytes());
+    if hash_type != TapSighashType::Default {
+        signature.push(hash_type as u8);
+    }
+    signature
+}
+
+/// 커버넌트 입력의 nSequence를 그라인딩
+///
+/// `base`의 BIP-68 의미 비트(비활성화 플래그, 타입 플래그, 하위 16비트)는 그대로 두고
+/// [`SEQUENCE_GRIND_MASK`] 비트만 바꾼다. 성공하면 트랜잭션의 nSequence가 갱신된다.
+pub fn grind_sequence(
+    tx: &mut Transaction,
+    input_index: usize,
+    prevout: &TxOut,
+    leaf_hash: TapLeafHash,
+    base: Sequence,
+) -> DeFiResult<GrindResult> {
+    let fixed = base.0 & !SEQUENCE_GRIND_MASK;
+    let attempts = 1u32 << SEQUENCE_GRIND_MASK.count_ones();
+
+    for counter in 0..attempts {
+        let sequence = Sequence(fixed | spread_bits(counter, SEQUENCE_GRIND_MASK));
+        if sequence.0 >= Sequence::ENABLE_LOCKTIME_NO_RBF.0 {
+            continue;
+        }
+
+        tx.input[input_index].sequence = sequence;
+        let sigmsg = covenant_sigmsg(tx, input_index, prevout, leaf_hash)?;
+        let challenge = covenant_challenge(&tap_sighash(&sigmsg));
+
+        if challenge[31] == 0x00 {
+            let mut e_prefix = [0u8; 31];
+            e_prefix.copy_from_slice(&challenge[..31]);
+            return Ok(GrindResult { sequence, e_prefix });
+        }
+    }
+
+    Err(DeFiHubError::BitcoinTransaction(
+        "Failed to grind covenant signature".to_string(),
+    ))
+}
+
+/// 병합 트랜잭션의 모든 입력 nSequence를 함께 그라인딩
+///
+/// 한 입력의 nSequence가 바뀌면 `sha_sequences`를 통해 모든 입력의 챌린지가 바뀌므로,
+/// 첫 두 입력의 [`SEQUENCE_GRIND_MASK`] 비트를 묶어 전체 입력이 동시에 조건을 만족하는 값을 찾는다.
+/// 반환값은 입력 순서대로의 그라인딩 결과다.
+pub fn grind_merge(
+    tx: &mut Transaction,
+    prevouts: &[TxOut],
+    leaf_hash: TapLeafHash,
+    base: Sequence,
+) -> DeFiResult<Vec<GrindResult>> {
+    let fixed = base.0 & !SEQUENCE_GRIND_MASK;
+    let bits = SEQUENCE_GRIND_MASK.count_ones();
+    let attempts = 1u64 << (bits * tx.input.len().min(2) as u32);
+
+    // 입력별 서명 메시지는 sha_sequences 필드만 바뀐다
+    let mut sigmsgs = (0..tx.input.len())
+        .map(|index| merge_sigmsg(tx, index, prevouts, leaf_hash))
+        .collect::<DeFiResult<Vec<_>>>()?;
+
+    'search: for counter in 0..attempts {
+        for (index, input) in tx.input.iter_mut().enumerate() {
+            let chunk = if index < 2 { (counter >> (bits * index as u32)) as u32 & ((1 << bits) - 1) } else { 0 };
+            input.sequence = Sequence(fixed | spread_bits(chunk, SEQUENCE_GRIND_MASK));
+        }
+        let sequences = sha_sequences(tx)?;
+
+        let mut results = Vec::with_capacity(tx.input.len());
+        for (index, sigmsg) in sigmsgs.iter_mut().enumerate() {
+            sigmsg[MERGE_SHA_SEQUENCES_OFFSET..MERGE_SHA_SEQUENCES_OFFSET + 32].copy_from_slice(&sequences);
+            let challenge = covenant_challenge(&tap_sighash(sigmsg));
+            if challenge[31] != 0x00 {
+                continue 'search;
+            }
+
+            let mut e_prefix = [0u8; 31];
+            e_prefix.copy_from_slice(&challenge[..31]);
+            results.push(GrindResult {
+                sequence: tx.input[index].sequence,
+                e_prefix,
+            });
+        }
+        return Ok(results);
+    }
+
+    Err(DeFiHubError::BitcoinTransaction(
+        "Failed to grind covenant signature".to_string(),
+    ))
+}
+
+/// `counter`의 하위 비트를 `mask`의 1 비트 위치에 차례로 배치
+fn spread_bits(mut counter: u32, mask: u32) -> u32 {
+    let mut result = 0;
+    for bit in 0..32 {
+        if mask & (1 << bit) != 0 {
+            result |= (counter & 1) << bit;
+            counter >>= 1;
+        }
+    }
+    result
+}
+
+fn io_error(err: std::io::Error) -> DeFiHubError {
+    DeFiHubError::BitcoinTransaction(err.to_string())
+}
@@ -0,0 +1,281 @@
+use shared::{ChainNetwork, DeFiHubError, DeFiResult};
+use bitcoin::absolute::{LockTime, LOCK_TIME_THRESHOLD};
+use bitcoin::Sequence;
+use bitcoincore_rpc::{Client, RpcApi};
+use serde::{Deserialize, Serialize};
+
+/// 시간 기반 상대 타임락의 단위 (BIP-68, 512초)
+pub const SEQUENCE_INTERVAL_SECS: u64 = 512;
+
+/// 완료 리프의 타임락
+///
+/// 시간 기반 타임락은 블록 시간이 아니라 median-time-past(BIP-113, 직전 11블록 시간의 중앙값)로 판단한다.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Timelock {
+    /// 트리거 트랜잭션 확정 후 n 블록 (BIP-112 OP_CHECKSEQUENCEVERIFY)
+    Relative { blocks: u16 },
+
+    /// 트리거 트랜잭션 확정 후 n × 512초 (OP_CHECKSEQUENCEVERIFY, 시간 타입 플래그)
+    RelativeTime { intervals: u16 },
+
+    /// 지정한 블록 높이 이후 (BIP-65 OP_CHECKLOCKTIMEVERIFY)
+    Absolute { height: u32 },
+
+    /// 지정한 유닉스 시각 이후 (OP_CHECKLOCKTIMEVERIFY)
+    AbsoluteTime { timestamp: u32 },
+}
+
+impl Timelock {
+    /// `secs`초 이상 기다리는 시간 기반 상대 타임락 (512초 단위로 올림)
+    pub fn relative_time(secs: u64) -> DeFiResult<Self> {
+        let intervals = secs.div_ceil(SEQUENCE_INTERVAL_SECS);
+        let intervals = u16::try_from(intervals).map_err(|_| {
+            DeFiHubError::Configuration(format!(
+                "Relative time lock of {} seconds exceeds {} seconds",
+                secs,
+                u16::MAX as u64 * SEQUENCE_INTERVAL_SECS
+            ))
+        })?;
+        Ok(Timelock::RelativeTime { intervals })
+    }
+
+    /// `secs`초를 네트워크 블록 간격으로 환산한 블록 기반 상대 타임락
+    pub fn relative_blocks(chain: ChainNetwork, secs: u64) -> DeFiResult<Self> {
+        let blocks = chain.blocks_for(secs);
+        let blocks = u16::try_from(blocks).map_err(|_| {
+            DeFiHubError::Configuration(format!("Relative lock of {} blocks on {} exceeds {}", blocks, chain, u16::MAX))
+        })?;
+        Ok(Timelock::Relative { blocks })
+    }
+
+    /// 타임락 값 검증
+    pub fn validate(&self) -> DeFiResult<()> {
+        match self {
+            // 지연이 0이면 트리거와 같은 블록에서 완료할 수 있어 감시탑이 대응할 틈이 없다
+            Timelock::Relative { blocks: 0 } => {
+                Err(DeFiHubError::Configuration("Relative timelock must be at least one block".to_string()))
+            },
+            Timelock::RelativeTime { intervals: 0 } => Err(DeFiHubError::Configuration(format!(
+                "Relative time lock must be at least {} seconds",
+                SEQUENCE_INTERVAL_SECS
+            ))),
+            Timelock::Relative { .. } | Timelock::RelativeTime { .. } => Ok(()),
+            Timelock::Absolute { height } if *height < LOCK_TIME_THRESHOLD => Ok(()),
+            Timelock::Absolute { height } => Err(DeFiHubError::Configuration(format!(
+                "Absolute timelock height {} must be below {}",
+                height, LOCK_TIME_THRESHOLD
+            ))),
+            Timelock::AbsoluteTime { timestamp } if *timestamp >= LOCK_TIME_THRESHOLD => Ok(()),
+            Timelock::AbsoluteTime { timestamp } => Err(DeFiHubError::Configuration(format!(
+                "Absolute timelock timestamp {} must be at least {}",
+                timestamp, LOCK_TIME_THRESHOLD
+            ))),
+        }
+    }
+
+    /// 상대 타임락(OP_CSV)인지
+    pub fn is_relative(&self) -> bool {
+        matches!(self, Timelock::Relative { .. } | Timelock::RelativeTime { .. })
+    }
+
+    /// median-time-past로 판단하는 타임락인지
+    pub fn is_time_based(&self) -> bool {
+        matches!(self, Timelock::RelativeTime { .. } | Timelock::AbsoluteTime { .. })
+    }
+
+    /// 스크립트에서 OP_CSV/OP_CLTV 앞에 넣는 값
+    ///
+    /// 상대 타임락은 nSequence와 같은 인코딩(시간 타입이면 1 << 22 플래그)을 쓴다.
+    pub fn script_operand(&self) -> i64 {
+        match self {
+            Timelock::Relative { blocks } => *blocks as i64,
+            Timelock::RelativeTime { intervals } => Sequence::from_512_second_intervals(*intervals).to_consensus_u32() as i64,
+            Timelock::Absolute { height } => *height as i64,
+            Timelock::AbsoluteTime { timestamp } => *timestamp as i64,
+        }
+    }
+
+    /// 완료 입력의 nSequence (절대 타임락이면 `None`)
+    pub fn relative_sequence(&self) -> Option<Sequence> {
+        match self {
+            Timelock::Relative { blocks } => Some(Sequence::from_height(*blocks)),
+            Timelock::RelativeTime { intervals } => Some(Sequence::from_512_second_intervals(*intervals)),
+            Timelock::Absolute { .. } | Timelock::AbsoluteTime { .. } => None,
+        }
+    }
+
+    /// 완료 트랜잭션의 nLockTime (상대 타임락이면 0)
+    pub fn lock_time(&self) -> LockTime {
+        match self {
+            Timelock::Relative { .. } | Timelock::RelativeTime { .. } => LockTime::ZERO,
+            Timelock::Absolute { height } => LockTime::from_consensus(*height),
+            Timelock::AbsoluteTime { timestamp } => LockTime::from_consensus(*timestamp),
+        }
+    }
+
+    /// 완료 트랜잭션을 브로드캐스트할 수 있는 최소 블록 높이
+    ///
+    /// 트리거가 `trigger_height` 다음 블록에 확정된다고 가정한다. 이때 상대 타임락은
+    /// `trigger_height + blocks + 1` 높이 블록부터, 절대 타임락(nLockTime = height)은
+    /// `height + 1` 높이 블록부터 완료 트랜잭션을 포함할 수 있으므로
+    /// 현재 높이가 각각 `trigger_height + blocks`, `height` 이상이면 브로드캐스트할 수 있다.
+    /// 시간 기반 타임락에는 높이 조건이 없으므로 `trigger_height`다 ([`Timelock::unlock_time`] 참고).
+    pub fn unlock_height(&self, trigger_height: u32) -> u32 {
+        match self {
+            Timelock::Relative { blocks } => trigger_height.saturating_add(*blocks as u32),
+            Timelock::Absolute { height } => *height,
+            Timelock::RelativeTime { .. } | Timelock::AbsoluteTime { .. } => trigger_height,
+        }
+    }
+
+    /// 남은 블록 수 (0이면 높이 조건은 만족)
+    pub fn blocks_remaining(&self, trigger_height: u32, current_height: u32) -> u32 {
+        self.unlock_height(trigger_height).saturating_sub(current_height)
+    }
+
+    /// 완료 트랜잭션을 브로드캐스트하려면 최신 블록의 median-time-past가 도달해야 하는 시각
+    ///
+    /// `trigger_median_time`은 트리거가 확정된 블록의 직전 블록(높이 `trigger_height`)의 median-time-past다.
+    /// BIP-68 상대 타임락은 그 값에 `intervals × 512`초를 더한 시각부터, BIP-113 절대 타임락은
+    /// nLockTime보다 median-time-past가 커야 풀린다. 블록 기반 타임락이면 `None`.
+    pub fn unlock_time(&self, trigger_median_time: u32) -> Option<u32> {
+        match self {
+            Timelock::RelativeTime { intervals } => {
+                Some(trigger_median_time.saturating_add((*intervals as u64 * SEQUENCE_INTERVAL_SECS) as u32))
+            }
+            Timelock::AbsoluteTime { timestamp } => Some(timestamp.saturating_add(1)),
+            Timelock::Relative { .. } | Timelock::Absolute { .. } => None,
+        }
+    }
+
+    /// 남은 시간 (초, 0이면 시간 조건은 만족)
+    pub fn seconds_remaining(&self, trigger_median_time: u32, current_median_time: u32) -> u32 {
+        self.unlock_time(trigger_median_time)
+            .map_or(0, |unlock| unlock.saturating_sub(current_median_time))
+    }
+}
+
+/// 사람이 읽는 기간(`90s`, `30m`, `2h`, `1d12h`, `2w`)을 초로 변환 (단위가 없으면 초)
+pub fn parse_duration(input: &str) -> DeFiResult<u64> {
+    let invalid = || DeFiHubError::Configuration(format!("Invalid duration: {}", input));
+    let input = input.trim();
+    if input.is_empty() {
+        return Err(invalid());
+    }
+    if let Ok(secs) = input.parse::<u64>() {
+        return Ok(secs);
+    }
+
+    let mut total: u64 = 0;
+    let mut digits = String::new();
+    for c in input.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 24 * 60 * 60,
+            'w' => 7 * 24 * 60 * 60,
+            _ => return Err(invalid()),
+        };
+        let value: u64 = digits.parse().map_err(|_| invalid())?;
+        total = value
+            .checked_mul(unit)
+            .and_then(|secs| total.checked_add(secs))
+            .ok_or_else(invalid)?;
+        digits.clear();
+    }
+    if !digits.is_empty() {
+        return Err(invalid());
+    }
+    Ok(total)
+}
+
+/// 절대 타임락 시각(유닉스 초 또는 RFC 3339, 예: `2025-01-01T00:00:00Z`)을 유닉스 초로 변환
+pub fn parse_timestamp(input: &str) -> DeFiResult<u32> {
+    let input = input.trim();
+    let secs = match input.parse::<i64>() {
+        Ok(secs) => secs,
+        Err(_) => chrono::DateTime::parse_from_rfc3339(input)
+            .map_err(|e| DeFiHubError::Configuration(format!("Invalid timestamp {}: {}", input, e)))?
+            .timestamp(),
+    };
+    u32::try_from(secs).map_err(|_| DeFiHubError::Configuration(format!("Timestamp out of range: {}", input)))
+}
+
+/// 현재 블록 높이 조회
+///
+/// 실제 노드는 [`Client`](bitcoincore_rpc::Client)를, 테스트는 모의 구현을 주입한다.
+pub trait ChainHeightSource {
+    /// 최신 블록 높이
+    fn current_height(&self) -> DeFiResult<u32>;
+
+    /// `height` 블록의 median-time-past (시간 기반 타임락에만 쓴다)
+    fn median_time_past(&self, height: u32) -> DeFiResult<u32> {
+        Err(DeFiHubError::Configuration(format!(
+            "Median time past of block {} is not available from this chain source",
+            height
+        )))
+    }
+}
+
+impl ChainHeightSource for Client {
+    fn current_height(&self) -> DeFiResult<u32> {
+        let height = self
+            .get_block_count()
+            .map_err(|e| DeFiHubError::RpcConnection(e.to_string()))?;
+        u32::try_from(height).map_err(|_| DeFiHubError::RpcConnection(format!("Invalid block height: {}", height)))
+    }
+
+    fn median_time_past(&self, height: u32) -> DeFiResult<u32> {
+        let hash = self
+            .get_block_hash(height as u64)
+            .map_err(|e| DeFiHubError::RpcConnection(e.to_string()))?;
+        let header = self
+            .get_block_header_info(&hash)
+            .map_err(|e| DeFiHubError::RpcConnection(e.to_string()))?;
+        header
+            .median_time
+            .and_then(|time| u32::try_from(time).ok())
+            .ok_or_else(|| DeFiHubError::RpcConnection(format!("Block {} has no median time", height)))
+    }
+}
+
+/// 고정된 블록 높이 (오프라인 PSBT 생성용)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FixedHeight(pub u32);
+
+impl ChainHeightSource for FixedHeight {
+    fn current_height(&self) -> DeFiResult<u32> {
+        Ok(self.0)
+    }
+}
+
+/// 고정된 블록 높이와 median-time-past (시간 기반 타임락의 오프라인 PSBT 생성용)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FixedTip {
+    /// 블록 높이
+    pub height: u32,
+
+    /// 그 블록의 median-time-past
+    pub median_time: u32,
+}
+
+impl ChainHeightSource for FixedTip {
+    fn current_height(&self) -> DeFiResult<u32> {
+        Ok(self.height)
+    }
+
+    fn median_time_past(&self, height: u32) -> DeFiResult<u32> {
+        if height != self.height {
+            return Err(DeFiHubError::Configuration(format!(
+                "Median time past is only known for block {}, not {}",
+                self.height, height
+            )));
+        }
+        Ok(self.median_time)
+    }
+}
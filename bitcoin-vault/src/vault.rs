@@ -1,30 +1,91 @@
-use shared::{VaultState, StateRoot, DeFiResult, DeFiHubError};
-use bitcoin::{Address, Amount, OutPoint, Transaction, Network};
-use serde::{Deserialize, Serialize};
+use crate::covenant::{owner_key_from_str, RecoveryPath, VaultCovenant};
+use crate::history::{tampered, VaultAction, VaultHistory};
+use crate::musig::{self, MusigSession};
+use crate::script::{MAX_TARGET_SPK_LEN, MERGE_INPUTS};
+use crate::rate_limit::{RateLimit, WithdrawalWindow};
+use crate::psbt::{trigger_target, trigger_withdrawal_amount, FeeFunding};
+use crate::timelock::{ChainHeightSource, Timelock};
+use crate::lifecycle::{FinalizedRootSource, PegOutSettlement, VaultTransition};
+use shared::{ChainNetwork, VaultState, StateRoot, DeFiResult, DeFiHubError};
+use shared::encryption::Passphrase;
+use shared::persistence::{self, Versioned};
+use shared::state::{VaultInfo, VaultUtxo};
+use bitcoin::address::NetworkUnchecked;
+use bitcoin::psbt::Psbt;
+use bitcoin::{Address, Amount, OutPoint, Transaction, Network, XOnlyPublicKey};
+use serde::{Deserialize, Deserializer, Serialize};
 use chrono::{DateTime, Utc};
 use std::path::Path;
 
 /// Bitcoin 금고 구조체 - DeFi 허브의 핵심 컴포넌트
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct BitcoinVault {
-    /// 금고 ID (OutPoint)
+    /// 금고 ID (OutPoint) - 첫 번째 금고 UTXO
     pub id: OutPoint,
     
     /// 현재 금고 상태
     pub state: VaultState,
     
-    /// 금고 주소
+    /// 금고 주소 (커버넌트 P2TR)
+    #[serde(deserialize_with = "deserialize_address")]
     pub address: Address,
     
-    /// 금고 잔액
+    /// 비트코인 네트워크 (주소 인코딩)
+    pub network: Network,
+    
+    /// 금고가 있는 체인 네트워크 (Fractal 포함)
+    pub chain: ChainNetwork,
+    
+    /// 금고 잔액 (금고 UTXO 합계)
     pub amount: Amount,
     
-    /// 타임락 블록 수
-    pub timelock_blocks: u16,
+    /// 금고 UTXO들 (입금마다 하나씩, 병합하면 하나로 합쳐진다)
+    #[serde(default)]
+    pub utxos: Vec<VaultUtxo>,
+    
+    /// 금고 지출 PSBT를 만들면서 교체한 이전 금고 UTXO들
+    ///
+    /// PSBT를 아직 브로드캐스트하지 않았거나 다른 트랜잭션에 밀리면 체인의 금고 UTXO는 여전히 이 중 하나다.
+    #[serde(default)]
+    pub previous_utxos: Vec<VaultUtxo>,
+    
+    /// 완료 리프 타임락
+    pub timelock: Timelock,
     
     /// 소유자
     pub owner: String,
     
+    /// 소유자 x-only 키 (트리거/취소 서명)
+    pub owner_key: XOnlyPublicKey,
+    
+    /// 공동 소유자 키 (비어 있지 않으면 `owner_key`는 이들의 MuSig2 집계 키)
+    #[serde(default)]
+    pub cosigners: Vec<XOnlyPublicKey>,
+    
+    /// 회수 키 (회수 리프 서명)
+    #[serde(default)]
+    pub recovery_key: Option<XOnlyPublicKey>,
+    
+    /// 회수 자금을 받는 콜드 주소
+    #[serde(default, deserialize_with = "deserialize_optional_address")]
+    pub recovery_address: Option<Address>,
+    
+    /// 기간별 출금 한도
+    #[serde(default)]
+    pub rate_limit: Option<RateLimit>,
+    
+    /// 현재 기간의 출금 누적 (한도가 있을 때만 쓴다)
+    #[serde(default)]
+    pub withdrawal_window: WithdrawalWindow,
+    
+    /// 진행 중인 출금의 트리거 트랜잭션 (완료 증인에 필요)
+    #[serde(default)]
+    pub trigger_tx: Option<Transaction>,
+    
+    /// 트리거 기준 블록의 median-time-past (시간 기반 타임락일 때만 기록)
+    #[serde(default)]
+    pub trigger_median_time: Option<u32>,
+    
     /// 생성 시간
     pub created_at: DateTime<Utc>,
     
@@ -33,6 +94,14 @@ pub struct BitcoinVault {
     
     /// BitVMX 연동 설정
     pub bitvmx_config: Option<BitVMXConfig>,
+    
+    /// 상태 전환 이력 (해시 체인)
+    #[serde(default)]
+    pub history: VaultHistory,
+    
+    /// 이력에 남길 행위자 (저장하지 않음, 기본값: 소유자)
+    #[serde(skip)]
+    actor: Option<String>,
 }
 
 /// BitVMX 설정
@@ -52,30 +121,138 @@ pub struct BitVMXConfig {
 }
 
 impl BitcoinVault {
-    /// 새로운 금고 생성
+    /// 새로운 금고 생성 (상대 타임락)
     pub fn new(
-        network: Network,
+        chain: impl Into<ChainNetwork>,
         timelock_blocks: u16,
         owner: String,
     ) -> DeFiResult<Self> {
+        Self::with_timelock(chain, Timelock::Relative { blocks: timelock_blocks }, owner)
+    }
+    
+    /// 타임락 방식을 지정해 금고 생성
+    pub fn with_timelock(
+        chain: impl Into<ChainNetwork>,
+        timelock: Timelock,
+        owner: String,
+    ) -> DeFiResult<Self> {
+        timelock.validate()?;
+        let chain = chain.into();
+        let network = chain.bitcoin_network();
         let now = Utc::now();
         
-        // 임시 주소 생성 (실제로는 커버넌트 스크립트에서 생성)
-        let address = Self::generate_vault_address(network)?;
+        let owner_key = owner_key_from_str(&owner);
+        let address = VaultCovenant::with_timelock(owner_key, timelock).address(network)?;
+        let history = VaultHistory::genesis(VaultAction::Created, VaultState::Inactive, owner.clone(), None);
         
         Ok(Self {
             id: OutPoint::null(), // 실제 UTXO가 생성되면 업데이트
             state: VaultState::Inactive,
             address,
+            network,
+            chain,
             amount: Amount::ZERO,
-            timelock_blocks,
+            utxos: Vec::new(),
+            previous_utxos: Vec::new(),
+            timelock,
             owner,
+            owner_key,
+            cosigners: Vec::new(),
+            recovery_key: None,
+            recovery_address: None,
+            rate_limit: None,
+            withdrawal_window: WithdrawalWindow::default(),
+            trigger_tx: None,
+            trigger_median_time: None,
             created_at: now,
             updated_at: now,
             bitvmx_config: None,
+            history,
+            actor: None,
         })
     }
     
+    /// 소유자 공개키 지정 (예: 키 저장소에서 유도한 키) - 금고 주소가 바뀌므로 예치 전에만 가능하다
+    pub fn with_owner_key(mut self, owner_key: XOnlyPublicKey) -> DeFiResult<Self> {
+        self.require_unfunded()?;
+        self.owner_key = owner_key;
+        self.cosigners.clear();
+        self.address = self.covenant().address(self.network)?;
+        self.updated_at = Utc::now();
+        Ok(self)
+    }
+    
+    /// n-of-n MuSig2 공동 소유 - 공동 소유자 키들의 집계 키를 소유자 키로 쓴다 (예치 전에만 가능)
+    pub fn with_cosigners(self, cosigners: &[XOnlyPublicKey]) -> DeFiResult<Self> {
+        let cosigners = musig::sorted_cosigners(cosigners)?;
+        let mut vault = self.with_owner_key(musig::aggregate_key(&cosigners)?)?;
+        vault.cosigners = cosigners;
+        Ok(vault)
+    }
+    
+    /// 금고 PSBT의 커버넌트 입력(0번)에 대한 공동 소유자 서명 세션
+    pub fn musig_session(&self, psbt: &Psbt) -> DeFiResult<MusigSession> {
+        if self.cosigners.is_empty() {
+            return Err(DeFiHubError::Musig("vault has a single owner key".to_string()));
+        }
+        MusigSession::new(psbt, 0, &self.cosigners)
+    }
+    
+    /// 회수 경로 설정 - 금고 주소가 바뀌므로 예치 전에만 가능하다
+    pub fn with_recovery(mut self, recovery_key: XOnlyPublicKey, recovery_address: Address) -> DeFiResult<Self> {
+        self.require_unfunded()?;
+        if !recovery_address.as_unchecked().is_valid_for_network(self.network) {
+            return Err(DeFiHubError::InvalidAddress(format!(
+                "Recovery address {} is not valid for {}",
+                recovery_address, self.network
+            )));
+        }
+        if recovery_address.script_pubkey().len() > MAX_TARGET_SPK_LEN as usize {
+            return Err(DeFiHubError::InvalidAddress(format!(
+                "Recovery scriptPubKey must be at most {} bytes",
+                MAX_TARGET_SPK_LEN
+            )));
+        }
+        
+        self.recovery_key = Some(recovery_key);
+        self.recovery_address = Some(recovery_address);
+        self.address = self.covenant().address(self.network)?;
+        self.updated_at = Utc::now();
+        Ok(self)
+    }
+    
+    /// 기간별 출금 한도 설정 - 금고 주소가 바뀌므로 예치 전에만 가능하다
+    ///
+    /// 이후 출금은 모두 부분 출금이며 한 번에 `cap` 이하, 완료까지 `max(타임락, period_blocks)` 블록이 걸린다.
+    /// 한도는 금고 UTXO마다 걸리므로 추가 예치로 UTXO가 둘이 되면 [`consolidate`](Self::consolidate)로
+    /// 병합하기 전까지 트리거와 다음 예치를 거부한다.
+    pub fn with_rate_limit(mut self, cap: Amount, period_blocks: u16) -> DeFiResult<Self> {
+        self.require_unfunded()?;
+        let rate_limit = RateLimit::new(cap, period_blocks)?;
+        rate_limit.complete_timelock(self.timelock)?;
+        
+        self.rate_limit = Some(rate_limit);
+        self.withdrawal_window = WithdrawalWindow::default();
+        self.address = self.covenant().address(self.network)?;
+        self.updated_at = Utc::now();
+        Ok(self)
+    }
+    
+    /// `height`에서 남은 출금 한도 (한도가 없으면 `None`)
+    pub fn remaining_allowance(&self, height: u32) -> Option<Amount> {
+        self.rate_limit.map(|limit| self.withdrawal_window.remaining(&limit, height))
+    }
+    
+    fn require_unfunded(&self) -> DeFiResult<()> {
+        if self.id != OutPoint::null() {
+            return Err(DeFiHubError::InvalidVaultState {
+                current: format!("funded at {}", self.id),
+                expected: "unfunded".to_string(),
+            });
+        }
+        Ok(())
+    }
+    
     /// BitVMX와 연동
     pub fn enable_bitvmx(&mut self, elf_path: String, min_verifiers: usize) {
         self.bitvmx_config = Some(BitVMXConfig {
@@ -89,32 +266,232 @@ impl BitcoinVault {
     
     /// 금고 상태 업데이트
     pub fn update_state(&mut self, new_state: VaultState) {
-        self.state = new_state;
+        let previous = std::mem::replace(&mut self.state, new_state);
+        self.updated_at = Utc::now();
+        self.record(VaultAction::StateUpdated, previous, None);
+    }
+    
+    /// 이후 이력 항목에 남길 행위자 지정 (예: "watchtower")
+    pub fn set_actor(&mut self, actor: impl Into<String>) {
+        self.actor = Some(actor.into());
+    }
+    
+    /// 이력 검증 - 해시 체인과 함께 마지막 항목이 현재 상태와 같은지 확인한다
+    pub fn verify_history(&self) -> DeFiResult<()> {
+        self.history.verify()?;
+        let last = &self.history.events()[self.history.len() - 1];
+        if last.new_state != self.state {
+            return Err(tampered(
+                self.history.len(),
+                format!("last entry ends in {} but the vault is {}", last.new_state.name(), self.state.name()),
+            ));
+        }
+        Ok(())
+    }
+    
+    /// UTXO 예치 기록 - 금고 UTXO 목록에 추가한다 (첫 예치가 금고 ID가 된다)
+    pub fn record_deposit(&mut self, outpoint: OutPoint, amount: Amount) -> DeFiResult<()> {
+        match &self.state {
+            VaultState::Inactive => {
+                if self.utxos.iter().any(|utxo| utxo.outpoint == outpoint) {
+                    return Err(DeFiHubError::BitcoinTransaction(format!(
+                        "Deposit {} is already recorded",
+                        outpoint
+                    )));
+                }
+                // 커버넌트는 UTXO마다 한도를 걸므로 병합하지 않은 추가 예치가 있으면 더 받지 않는다
+                if self.rate_limit.is_some() && self.utxos.len() >= MERGE_INPUTS {
+                    return Err(DeFiHubError::BitcoinTransaction(format!(
+                        "Rate-limited vault already holds {} UTXOs, each capped separately; consolidate them before depositing again",
+                        self.utxos.len()
+                    )));
+                }
+                self.utxos.push(VaultUtxo::new(outpoint, amount));
+                self.sync_utxos();
+                Ok(())
+            },
+            _ => Err(DeFiHubError::InvalidVaultState {
+                current: format!("{:?}", self.state),
+                expected: "Inactive".to_string(),
+            }),
+        }
+    }
+    
+    /// 예치 UTXO가 블록에 포함된 높이 기록
+    pub fn confirm_deposit<H: ChainHeightSource + ?Sized>(
+        &mut self,
+        outpoint: OutPoint,
+        height: u32,
+        heights: &H,
+    ) -> DeFiResult<()> {
+        let current_height = heights.current_height()?;
+        let utxo = self
+            .utxos
+            .iter_mut()
+            .find(|utxo| utxo.outpoint == outpoint)
+            .ok_or_else(|| DeFiHubError::BitcoinTransaction(format!("Vault has no UTXO {}", outpoint)))?;
+        utxo.height = Some(height);
+        utxo.refresh_confirmations(current_height);
         self.updated_at = Utc::now();
+        Ok(())
     }
     
-    /// 출금 트리거
-    pub fn trigger_withdrawal(
+    /// 현재 체인 높이로 모든 금고 UTXO의 확인 수 갱신
+    pub fn refresh_confirmations<H: ChainHeightSource + ?Sized>(&mut self, heights: &H) -> DeFiResult<()> {
+        let current_height = heights.current_height()?;
+        for utxo in &mut self.utxos {
+            utxo.refresh_confirmations(current_height);
+        }
+        self.updated_at = Utc::now();
+        Ok(())
+    }
+    
+    /// 네트워크 기본 확인 수 이상 확인된 UTXO 합계
+    pub fn confirmed_amount(&self) -> Amount {
+        let required = self.chain.params().default_confirmations;
+        self.utxos.iter().filter(|utxo| utxo.confirmations >= required).map(|utxo| utxo.amount).sum()
+    }
+    
+    /// 금고 UTXO 병합 - 앞의 두 UTXO를 하나로 합치는 병합 PSBT 생성
+    ///
+    /// 수수료는 금고 금액에서 내며, 병합된 UTXO가 새 금고 ID가 된다.
+    pub fn consolidate(&mut self, fee_rate: u64) -> DeFiResult<Psbt> {
+        match &self.state {
+            VaultState::Inactive => {
+                if self.utxos.len() < MERGE_INPUTS {
+                    return Err(DeFiHubError::BitcoinTransaction(format!(
+                        "Vault has {} UTXO(s), nothing to consolidate",
+                        self.utxos.len()
+                    )));
+                }
+                let psbt = self.covenant().consolidate_psbt(&self.merge_inputs(), fee_rate)?;
+                
+                let merged = VaultUtxo::new(OutPoint::new(psbt.unsigned_tx.txid(), 0), psbt.unsigned_tx.output[0].value);
+                self.utxos.splice(..MERGE_INPUTS, [merged]);
+                self.sync_utxos();
+                Ok(psbt)
+            },
+            _ => Err(DeFiHubError::InvalidVaultState {
+                current: format!("{:?}", self.state),
+                expected: "Inactive".to_string(),
+            }),
+        }
+    }
+    
+    /// 출금 트리거 - 트리거 PSBT를 만들고 금고 UTXO를 트리거 출력으로 옮긴다
+    ///
+    /// 트리거 시점의 블록 높이를 기록해 완료 시 타임락 계산에 사용한다.
+    /// 금고 잔액보다 적은 금액이면 부분 출금 트리거를 만든다.
+    /// UTXO가 두 개인 금고의 전액 출금은 병합 트리거로 두 UTXO를 함께 지출하며,
+    /// 병합 수수료를 뺀 금액이 출금된다 (`funding`의 수수료율만 쓴다).
+    pub fn trigger_withdrawal<H: ChainHeightSource + ?Sized>(
         &mut self, 
         withdrawal_address: Address, 
-        amount: Amount
-    ) -> DeFiResult<()> {
+        amount: Amount,
+        funding: &FeeFunding,
+        heights: &H,
+    ) -> DeFiResult<Psbt> {
         match &self.state {
             VaultState::Inactive => {
+                // 0 sat 부분 출금은 완료 리프(출금액 > 0)를 통과할 수 없어 취소만 남는다
+                if amount == Amount::ZERO {
+                    return Err(DeFiHubError::InvalidAmount("Withdrawal amount cannot be zero".to_string()));
+                }
                 if amount > self.amount {
                     return Err(DeFiHubError::InsufficientFunds {
                         required: amount.to_sat(),
                         available: self.amount.to_sat(),
                     });
                 }
+                self.ensure_funded()?;
+                let trigger_height = heights.current_height()?;
+                self.check_allowance(amount, trigger_height)?;
+                let trigger_median_time = self.trigger_median_time_at(trigger_height, heights)?;
+                
+                let target = withdrawal_address.script_pubkey();
+                let (psbt, amount) = match self.utxos.len() {
+                    1 if amount < self.amount => {
+                        (self.covenant().partial_trigger_psbt(self.id, self.amount, amount, &target, funding)?, amount)
+                    },
+                    1 => (self.covenant().trigger_psbt(self.id, self.amount, &target, funding)?, amount),
+                    MERGE_INPUTS if amount == self.amount => {
+                        let psbt = self.covenant().merge_trigger_psbt(&self.merge_inputs(), &target, funding.fee_rate)?;
+                        let merged = psbt.unsigned_tx.output[0].value;
+                        (psbt, merged)
+                    },
+                    count => {
+                        return Err(DeFiHubError::BitcoinTransaction(format!(
+                            "Vault has {} UTXOs; consolidate before this withdrawal",
+                            count
+                        )));
+                    },
+                };
                 
-                self.state = VaultState::Triggered {
-                    withdrawal_address,
+                self.enter_triggered(
+                    psbt.unsigned_tx.clone(),
+                    withdrawal_address.to_string(),
                     amount,
-                    trigger_time: Utc::now(),
-                    timelock_blocks: self.timelock_blocks,
+                    trigger_height,
+                    trigger_median_time,
+                );
+                self.spend_allowance(amount, trigger_height);
+                self.record(VaultAction::WithdrawalTriggered, VaultState::Inactive, Some(trigger_height));
+                Ok(psbt)
+            },
+            _ => Err(DeFiHubError::InvalidVaultState {
+                current: format!("{:?}", self.state),
+                expected: "Inactive".to_string(),
+            }),
+        }
+    }
+    
+    /// 체인에서 발견한 트리거 트랜잭션 반영
+    ///
+    /// 다른 곳(예: 탈취된 키)에서 만든 트리거도 금고에 기록해 취소/회수/완료 PSBT를 만들 수 있게 한다.
+    /// `trigger_height`는 트리거가 확정된 블록의 직전 높이다. 이미 기록된 트리거면 아무것도 하지 않는다.
+    /// 시간 기반 타임락이면 `heights`에서 그 블록의 median-time-past를 읽는다.
+    pub fn observe_trigger<H: ChainHeightSource + ?Sized>(
+        &mut self,
+        trigger_tx: &Transaction,
+        trigger_height: u32,
+        heights: &H,
+    ) -> DeFiResult<()> {
+        if self.trigger_tx.as_ref().is_some_and(|tx| tx.txid() == trigger_tx.txid()) {
+            return Ok(());
+        }
+        let previous_state = self.rewind_to_spent_utxos(trigger_tx);
+        match &self.state {
+            VaultState::Inactive => {
+                let spends_vault = trigger_tx
+                    .input
+                    .iter()
+                    .any(|input| self.utxos.iter().any(|utxo| utxo.outpoint == input.previous_output));
+                let vault_output = trigger_tx
+                    .output
+                    .first()
+                    .filter(|output| output.script_pubkey == self.address.script_pubkey());
+                let vault_output = match vault_output {
+                    Some(output) if spends_vault => output.value,
+                    _ => {
+                        return Err(DeFiHubError::BitcoinTransaction(format!(
+                            "Transaction {} is not a trigger of this vault",
+                            trigger_tx.txid()
+                        )));
+                    },
                 };
-                self.updated_at = Utc::now();
+                
+                let target = trigger_target(trigger_tx)?;
+                let amount = trigger_withdrawal_amount(trigger_tx)?.unwrap_or(vault_output);
+                let withdrawal_address = Address::from_script(&target, self.network)
+                    .map(|address| address.to_string())
+                    .unwrap_or_else(|_| target.to_hex_string());
+                let trigger_median_time = self.trigger_median_time_at(trigger_height, heights)?;
+                self.enter_triggered(trigger_tx.clone(), withdrawal_address, amount, trigger_height, trigger_median_time);
+                // 체인에서 지출이 확정된 UTXO는 되돌릴 대상이 아니다
+                self.previous_utxos
+                    .retain(|utxo| trigger_tx.input.iter().all(|input| input.previous_output != utxo.outpoint));
+                self.spend_allowance(amount, trigger_height);
+                self.record(VaultAction::TriggerObserved, previous_state, Some(trigger_height));
                 Ok(())
             },
             _ => Err(DeFiHubError::InvalidVaultState {
@@ -124,14 +501,35 @@ impl BitcoinVault {
         }
     }
     
-    /// 출금 완료
-    pub fn complete_withdrawal(&mut self) -> DeFiResult<()> {
+    /// 출금 완료 - 타임락 이후 출금 대상에게 보내는 완료 PSBT 생성
+    ///
+    /// 부분 출금이면 잔돈 출력(vout 1)이 새 금고 UTXO가 되고 금고는 `Inactive`로 돌아간다.
+    pub fn complete_withdrawal<H: ChainHeightSource + ?Sized>(
+        &mut self,
+        heights: &H,
+        funding: &FeeFunding,
+    ) -> DeFiResult<Psbt> {
         match &self.state {
-            VaultState::Triggered { .. } => {
-                // TODO: 타임락 확인
-                self.state = VaultState::Completed;
+            VaultState::Triggered { trigger_height, amount, .. } => {
+                let current_height = heights.current_height()?;
+                self.check_timelock(*trigger_height, current_height, heights)?;
+                
+                let withdrawn = *amount;
+                let trigger_tx = self.pending_trigger()?;
+                let psbt = self.covenant().complete_psbt(trigger_tx, funding)?;
+                
+                self.trigger_tx = None;
+                self.trigger_median_time = None;
+                let new_state = if withdrawn < self.amount {
+                    self.set_single_utxo(OutPoint::new(psbt.unsigned_tx.txid(), 1), self.amount - withdrawn);
+                    VaultState::Inactive
+                } else {
+                    VaultState::Completed
+                };
+                let previous = std::mem::replace(&mut self.state, new_state);
                 self.updated_at = Utc::now();
-                Ok(())
+                self.record(VaultAction::WithdrawalCompleted, previous, Some(current_height));
+                Ok(psbt)
             },
             _ => Err(DeFiHubError::InvalidVaultState {
                 current: format!("{:?}", self.state),
@@ -140,13 +538,31 @@ impl BitcoinVault {
         }
     }
     
-    /// 출금 취소
-    pub fn cancel_withdrawal(&mut self) -> DeFiResult<()> {
+    /// 출금 취소 - 트리거 출력을 금고에 재잠그는 취소 PSBT 생성
+    ///
+    /// 취소 시점의 블록 높이를 이력에 남긴다.
+    pub fn cancel_withdrawal<H: ChainHeightSource + ?Sized>(
+        &mut self,
+        heights: &H,
+        funding: &FeeFunding,
+    ) -> DeFiResult<Psbt> {
         match &self.state {
-            VaultState::Triggered { .. } => {
-                self.state = VaultState::Inactive;
+            VaultState::Triggered { trigger_height, amount, .. } => {
+                let (trigger_height, withdrawn) = (*trigger_height, *amount);
+                self.pending_trigger()?;
+                let current_height = heights.current_height()?;
+                let psbt = self.covenant().cancel_psbt(self.id, self.amount, funding)?;
+                
+                if let Some(limit) = self.rate_limit {
+                    self.withdrawal_window.refund(&limit, trigger_height, withdrawn);
+                }
+                self.set_single_utxo(OutPoint::new(psbt.unsigned_tx.txid(), 0), self.amount);
+                self.trigger_tx = None;
+                self.trigger_median_time = None;
+                let previous = std::mem::replace(&mut self.state, VaultState::Inactive);
                 self.updated_at = Utc::now();
-                Ok(())
+                self.record(VaultAction::WithdrawalCancelled, previous, Some(current_height));
+                Ok(psbt)
             },
             _ => Err(DeFiHubError::InvalidVaultState {
                 current: format!("{:?}", self.state),
@@ -155,41 +571,296 @@ impl BitcoinVault {
         }
     }
     
+    /// 긴급 회수 - 금고 또는 트리거된 UTXO 전액을 콜드 주소로 보내는 회수 PSBT 생성
+    ///
+    /// 타임락과 무관하게 언제든 쓸 수 있으며 회수 키 서명이 필요하다.
+    /// 금고 UTXO가 여러 개면 한 번에 하나씩 회수하며, 마지막 UTXO를 회수할 때 `Recovered`가 된다.
+    /// 회수할 때마다 그 시점의 블록 높이와 함께 이력에 남긴다.
+    pub fn recover<H: ChainHeightSource + ?Sized>(&mut self, heights: &H, funding: &FeeFunding) -> DeFiResult<Psbt> {
+        match &self.state {
+            VaultState::Inactive | VaultState::Triggered { .. } => {
+                self.ensure_funded()?;
+                let current_height = heights.current_height()?;
+                let recovery_address = self
+                    .recovery_address
+                    .clone()
+                    .ok_or_else(|| DeFiHubError::Configuration("Vault has no recovery path".to_string()))?;
+                let swept = self.utxos[0].clone();
+                let psbt = self.covenant().recover_psbt(swept.outpoint, swept.amount, funding)?;
+                
+                if self.utxos.len() > 1 {
+                    let swept = self.utxos.remove(0);
+                    self.retire_utxos(vec![swept]);
+                    self.sync_utxos();
+                    self.updated_at = Utc::now();
+                    self.record(VaultAction::UtxoRecovered, self.state.clone(), Some(current_height));
+                    return Ok(psbt);
+                }
+                self.trigger_tx = None;
+                self.trigger_median_time = None;
+                let recovered = VaultState::Recovered {
+                    recovery_address: recovery_address.to_string(),
+                    amount: swept.amount,
+                    recovery_time: Utc::now(),
+                };
+                let previous = std::mem::replace(&mut self.state, recovered);
+                self.updated_at = Utc::now();
+                self.record(VaultAction::Recovered, previous, Some(current_height));
+                Ok(psbt)
+            },
+            _ => Err(DeFiHubError::InvalidVaultState {
+                current: format!("{:?}", self.state),
+                expected: "Inactive or Triggered".to_string(),
+            }),
+        }
+    }
+    
     /// 롤업과 연동
     pub fn bridge_to_rollup(&mut self, state_root: StateRoot) -> DeFiResult<()> {
-        self.state = VaultState::Bridged {
+        VaultTransition::PegIn.check(&self.state)?;
+        self.ensure_funded()?;
+        let bridged = VaultState::Bridged {
             rollup_state_root: state_root,
             last_sync: Utc::now(),
         };
+        let previous = std::mem::replace(&mut self.state, bridged);
         self.updated_at = Utc::now();
+        self.record(VaultAction::BridgedToRollup, previous, None);
         Ok(())
     }
     
-    /// 파일에서 로드
+    /// 롤업에서 복귀 (`finality`가 확정한 상태 루트에서 소유자의 롤업 잔액이 비어 있어야 한다)
+    pub fn unbridge_from_rollup<F: FinalizedRootSource + ?Sized>(
+        &mut self,
+        settlement: &PegOutSettlement,
+        finality: &F,
+    ) -> DeFiResult<()> {
+        VaultTransition::PegOut.check(&self.state)?;
+        if let VaultState::Bridged { rollup_state_root, .. } = &self.state {
+            settlement.verify(&self.owner_key, rollup_state_root, finality)?;
+        }
+        let previous = std::mem::replace(&mut self.state, VaultState::Inactive);
+        self.updated_at = Utc::now();
+        self.record(VaultAction::UnbridgedFromRollup, previous, None);
+        Ok(())
+    }
+    
+    /// 파일에서 로드 (깨진 파일은 최근 백업으로 대신한다)
     pub fn load_from_file<P: AsRef<Path>>(path: P) -> DeFiResult<Self> {
-        let content = std::fs::read_to_string(path)
-            .map_err(|e| DeFiHubError::Configuration(format!("Failed to read vault file: {}", e)))?;
-        
-        let vault: BitcoinVault = serde_json::from_str(&content)?;
-        Ok(vault)
+        persistence::load(path.as_ref())
     }
     
-    /// 파일에 저장
+    /// 파일에 원자적으로 저장
     pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> DeFiResult<()> {
-        let content = serde_json::to_string_pretty(self)?;
-        std::fs::write(path, content)
-            .map_err(|e| DeFiHubError::Configuration(format!("Failed to write vault file: {}", e)))?;
+        persistence::save(self, path.as_ref())
+    }
+    
+    /// 암호화되었을 수 있는 금고 파일 로드
+    pub fn load_from_file_with<P: AsRef<Path>>(path: P, passphrase: Option<&Passphrase>) -> DeFiResult<Self> {
+        persistence::load_with(path.as_ref(), passphrase)
+    }
+    
+    /// 패스프레이즈가 있으면 암호화해 저장
+    pub fn save_to_file_with<P: AsRef<Path>>(&self, path: P, passphrase: Option<&Passphrase>) -> DeFiResult<()> {
+        persistence::save_with(self, path.as_ref(), passphrase)
+    }
+    
+    /// 금고 커버넌트
+    pub fn covenant(&self) -> VaultCovenant {
+        let mut covenant = VaultCovenant::with_timelock(self.owner_key, self.timelock);
+        if let (Some(recovery_key), Some(address)) = (self.recovery_key, &self.recovery_address) {
+            covenant = covenant.with_recovery(RecoveryPath {
+                recovery_key,
+                cold_script: address.script_pubkey(),
+            });
+        }
+        // 한도는 설정할 때 검증했다
+        covenant.rate_limit = self.rate_limit;
+        covenant
+    }
+    
+    /// 완료까지 남은 블록 수
+    ///
+    /// 시간 기반 타임락은 남은 시간을 네트워크 블록 간격으로 환산한 예상치다.
+    pub fn blocks_remaining(&self, trigger_height: u32, current_height: u32) -> u32 {
+        let timelock = self.covenant().complete_timelock();
+        match self.trigger_median_time {
+            Some(trigger_time) if timelock.is_time_based() => {
+                let seconds = timelock.seconds_remaining(trigger_time, trigger_time);
+                let blocks = self.chain.blocks_for(seconds as u64).min(u32::MAX as u64) as u32;
+                blocks.saturating_sub(current_height.saturating_sub(trigger_height))
+            }
+            _ => timelock.blocks_remaining(trigger_height, current_height),
+        }
+    }
+    
+    /// 높이와 median-time-past 모두 완료 타임락을 만족하는지 확인
+    fn check_timelock<H: ChainHeightSource + ?Sized>(
+        &self,
+        trigger_height: u32,
+        current_height: u32,
+        heights: &H,
+    ) -> DeFiResult<()> {
+        let timelock = self.covenant().complete_timelock();
+        let blocks_remaining = timelock.blocks_remaining(trigger_height, current_height);
+        if blocks_remaining > 0 {
+            return Err(DeFiHubError::TimelockNotExpired { blocks_remaining });
+        }
+        if timelock.is_time_based() {
+            let trigger_median_time = self
+                .trigger_median_time
+                .ok_or_else(|| DeFiHubError::BitcoinTransaction("Missing trigger median time".to_string()))?;
+            let seconds_remaining = timelock.seconds_remaining(trigger_median_time, heights.median_time_past(current_height)?);
+            if seconds_remaining > 0 {
+                return Err(DeFiHubError::MedianTimeNotReached { seconds_remaining });
+            }
+        }
         Ok(())
     }
     
-    /// 금고 주소 생성 (임시 구현)
-    fn generate_vault_address(network: Network) -> DeFiResult<Address> {
-        // 임시로 더미 주소 생성
-        let dummy_pubkey_bytes = [2u8; 33]; // 압축 공개키
-        let pubkey = bitcoin::PublicKey::from_slice(&dummy_pubkey_bytes)
-            .map_err(|e| DeFiHubError::BitcoinTransaction(e.to_string()))?;
-        
-        Ok(Address::p2pkh(&pubkey, network))
+    /// 시간 기반 타임락이면 트리거 기준 블록의 median-time-past
+    fn trigger_median_time_at<H: ChainHeightSource + ?Sized>(&self, trigger_height: u32, heights: &H) -> DeFiResult<Option<u32>> {
+        if !self.covenant().complete_timelock().is_time_based() {
+            return Ok(None);
+        }
+        heights.median_time_past(trigger_height).map(Some)
+    }
+    
+    /// 한도가 있으면 UTXO 하나에서 부분 출금만 가능하고 남은 한도 이하여야 한다
+    ///
+    /// 커버넌트는 UTXO마다 따로 한도를 걸므로, UTXO가 여러 개면 먼저 하나로 병합해야 한다.
+    fn check_allowance(&self, amount: Amount, height: u32) -> DeFiResult<()> {
+        let Some(limit) = self.rate_limit else {
+            return Ok(());
+        };
+        if self.utxos.len() > 1 {
+            return Err(DeFiHubError::BitcoinTransaction(format!(
+                "Rate-limited vault has {} UTXOs, each capped separately; consolidate them into one before withdrawing",
+                self.utxos.len()
+            )));
+        }
+        if amount >= self.amount {
+            return Err(DeFiHubError::BitcoinTransaction(
+                "Rate-limited vaults keep change locked; withdraw less than the vault balance".to_string(),
+            ));
+        }
+        let remaining = self.withdrawal_window.remaining(&limit, height);
+        if amount > remaining {
+            return Err(DeFiHubError::RateLimitExceeded {
+                requested: amount.to_sat(),
+                remaining: remaining.to_sat(),
+                resets_at: limit.period_end(limit.period_of(height)),
+            });
+        }
+        Ok(())
+    }
+    
+    /// 트리거한 금액을 기간 카운터에 더한다
+    fn spend_allowance(&mut self, amount: Amount, height: u32) {
+        if let Some(limit) = self.rate_limit {
+            self.withdrawal_window.spend(&limit, height, amount);
+        }
+    }
+    
+    /// 예치된 UTXO가 있는지 확인
+    fn ensure_funded(&self) -> DeFiResult<()> {
+        if self.utxos.is_empty() || self.amount == Amount::ZERO {
+            return Err(DeFiHubError::BitcoinTransaction("Vault has no funded UTXO".to_string()));
+        }
+        Ok(())
+    }
+    
+    /// 병합 트랜잭션 입력 (앞의 `MERGE_INPUTS`개 UTXO)
+    fn merge_inputs(&self) -> Vec<(OutPoint, Amount)> {
+        self.utxos
+            .iter()
+            .take(MERGE_INPUTS)
+            .map(|utxo| (utxo.outpoint, utxo.amount))
+            .collect()
+    }
+    
+    /// 금고 지출 후 남은 하나의 금고 UTXO로 교체
+    fn set_single_utxo(&mut self, outpoint: OutPoint, amount: Amount) {
+        let replaced = std::mem::replace(&mut self.utxos, vec![VaultUtxo::new(outpoint, amount)]);
+        self.retire_utxos(replaced);
+        self.sync_utxos();
+    }
+    
+    /// 교체된 금고 UTXO를 이전 UTXO 목록에 남긴다
+    fn retire_utxos(&mut self, utxos: Vec<VaultUtxo>) {
+        for utxo in utxos {
+            if !self.previous_utxos.iter().any(|previous| previous.outpoint == utxo.outpoint) {
+                self.previous_utxos.push(utxo);
+            }
+        }
+    }
+    
+    /// 트랜잭션이 현재 UTXO가 아닌 이전 UTXO를 지출하면, 그 뒤에 로컬에서 만든 PSBT는 체인에 오르지 못한 것이다
+    ///
+    /// 금고를 지출된 이전 UTXO의 `Inactive` 상태로 되돌리고 (진행 중이던 트리거의 한도는 돌려준다)
+    /// 되돌리기 전 상태를 반환한다.
+    fn rewind_to_spent_utxos(&mut self, tx: &Transaction) -> VaultState {
+        let spent = |utxo: &VaultUtxo| tx.input.iter().any(|input| input.previous_output == utxo.outpoint);
+        if self.utxos.iter().any(spent) {
+            return self.state.clone();
+        }
+        let stale: Vec<VaultUtxo> = self.previous_utxos.iter().filter(|utxo| spent(utxo)).cloned().collect();
+        if stale.is_empty() {
+            return self.state.clone();
+        }
+        if let (VaultState::Triggered { trigger_height, amount, .. }, Some(limit)) = (&self.state, self.rate_limit) {
+            self.withdrawal_window.refund(&limit, *trigger_height, *amount);
+        }
+        let unconfirmed = std::mem::replace(&mut self.utxos, stale);
+        self.previous_utxos.retain(|utxo| !spent(utxo));
+        self.retire_utxos(unconfirmed);
+        self.sync_utxos();
+        self.trigger_tx = None;
+        self.trigger_median_time = None;
+        std::mem::replace(&mut self.state, VaultState::Inactive)
+    }
+    
+    /// UTXO 목록에서 금고 ID와 잔액 다시 계산
+    fn sync_utxos(&mut self) {
+        self.id = self.utxos.first().map_or(OutPoint::null(), |utxo| utxo.outpoint);
+        self.amount = self.utxos.iter().map(|utxo| utxo.amount).sum();
+        self.updated_at = Utc::now();
+    }
+    
+    /// 트리거 출력(vout 0)을 금고 UTXO로 삼고 `Triggered` 상태로 전환
+    fn enter_triggered(
+        &mut self,
+        trigger_tx: Transaction,
+        withdrawal_address: String,
+        amount: Amount,
+        trigger_height: u32,
+        trigger_median_time: Option<u32>,
+    ) {
+        self.set_single_utxo(OutPoint::new(trigger_tx.txid(), 0), trigger_tx.output[0].value);
+        self.trigger_tx = Some(trigger_tx);
+        self.trigger_median_time = trigger_median_time;
+        self.state = VaultState::Triggered {
+            withdrawal_address,
+            amount,
+            trigger_time: Utc::now(),
+            trigger_height,
+            timelock_blocks: self.blocks_remaining(trigger_height, trigger_height)
+                .min(u16::MAX as u32) as u16,
+        };
+        self.updated_at = Utc::now();
+    }
+    
+    /// 진행 중인 트리거 트랜잭션
+    fn pending_trigger(&self) -> DeFiResult<&Transaction> {
+        self.trigger_tx
+            .as_ref()
+            .ok_or_else(|| DeFiHubError::BitcoinTransaction("Missing trigger transaction".to_string()))
+    }
+    
+    /// 현재 상태로의 전환을 이력에 기록
+    fn record(&mut self, action: VaultAction, previous_state: VaultState, block_height: Option<u32>) {
+        let actor = self.actor.clone().unwrap_or_else(|| self.owner.clone());
+        self.history.record(action, previous_state, self.state.clone(), actor, block_height);
     }
     
     /// BitVMX 상태 루트 업데이트
@@ -199,19 +870,83 @@ impl BitcoinVault {
                 config.current_state_root = Some(state_root);
                 config.last_sync = Utc::now();
                 self.updated_at = Utc::now();
+                self.record(VaultAction::BitvmxStateUpdated, self.state.clone(), None);
                 Ok(())
             },
             None => Err(DeFiHubError::Configuration("BitVMX not enabled".to_string())),
         }
     }
     
+    /// 글로벌 상태용 요약 정보
+    pub fn info(&self) -> VaultInfo {
+        VaultInfo {
+            outpoint: self.id,
+            amount: self.amount,
+            state: self.state.clone(),
+            owner: self.owner.clone(),
+            created_at: self.created_at,
+            utxos: self.utxos.clone(),
+            previous_outpoints: self.previous_utxos.iter().map(|utxo| utxo.outpoint).collect(),
+        }
+    }
+    
     /// 금고가 활성 상태인지 확인
     pub fn is_active(&self) -> bool {
-        !matches!(self.state, VaultState::Completed)
+        !matches!(self.state, VaultState::Completed | VaultState::Recovered { .. })
     }
     
     /// 출금 가능 여부 확인
     pub fn can_withdraw(&self) -> bool {
-        matches!(self.state, VaultState::Inactive | VaultState::Bridged { .. })
+        VaultTransition::Trigger.is_allowed(&self.state)
     }
+}
+
+impl Versioned for BitcoinVault {
+    /// 1: `schema_version` 필드 도입, UTXO 목록 필수
+    /// 2: 상태 전환 이력 (`history`) 필수
+    /// 3: 체인 네트워크 (`chain`) 필수
+    const SCHEMA_VERSION: u32 = 3;
+    const KIND: &'static str = "vault";
+
+    fn migrate(from: u32, value: &mut serde_json::Value) -> DeFiResult<()> {
+        match from {
+            0 => {
+                // UTXO 목록이 없던 예전 파일은 단일 UTXO 금고로 읽는다
+                let has_utxos = value.get("utxos").and_then(|utxos| utxos.as_array()).is_some_and(|utxos| !utxos.is_empty());
+                let id: OutPoint = serde_json::from_value(value["id"].clone())?;
+                if !has_utxos && id != OutPoint::null() {
+                    let amount: Amount = serde_json::from_value(value["amount"].clone())?;
+                    value["utxos"] = serde_json::to_value(vec![VaultUtxo::new(id, amount)])?;
+                }
+            },
+            1 => {
+                // 이력이 없던 금고는 현재 상태를 첫 항목으로 삼는다
+                let has_history = value.get("history").and_then(|history| history.as_array()).is_some_and(|history| !history.is_empty());
+                if !has_history {
+                    let state: VaultState = serde_json::from_value(value["state"].clone())?;
+                    let owner: String = serde_json::from_value(value["owner"].clone())?;
+                    let history = VaultHistory::genesis(VaultAction::Imported, state, owner, None);
+                    value["history"] = serde_json::to_value(history)?;
+                }
+            },
+            // Fractal 이전 금고는 주소 네트워크가 곧 체인 네트워크다
+            2 if value.get("chain").is_none() => {
+                let network: Network = serde_json::from_value(value["network"].clone())?;
+                value["chain"] = serde_json::to_value(ChainNetwork::from(network))?;
+            },
+            _ => {},
+        }
+        Ok(())
+    }
+}
+
+/// 주소 역직렬화 (bitcoin 0.31은 `NetworkUnchecked` 주소만 역직렬화할 수 있다)
+fn deserialize_address<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Address, D::Error> {
+    let address = Address::<NetworkUnchecked>::deserialize(deserializer)?;
+    Ok(address.assume_checked())
+}
+
+fn deserialize_optional_address<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Address>, D::Error> {
+    let address = Option::<Address<NetworkUnchecked>>::deserialize(deserializer)?;
+    Ok(address.map(Address::assume_checked))
 }
\ No newline at end of file
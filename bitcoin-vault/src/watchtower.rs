@@ -0,0 +1,431 @@
+//! 워치타워 - 허용되지 않은 출금 트리거를 감지해 자동으로 취소/회수
+//!
+//! 소유자 키가 탈취되면 공격자는 승인하지 않은 주소로 출금을 트리거할 수 있고,
+//! 타임락은 누군가 체인을 지켜볼 때만 의미가 있다. 워치타워는 체인 소스에서 새 블록을 따라가며
+//! 글로벌 상태에 등록된 금고 UTXO를 지출하는 트리거를 찾고, 출금 대상이 허용 목록에 없으면
+//! 타임락이 풀리기 전에 취소 또는 회수 트랜잭션을 브로드캐스트한다.
+//!
+//! 대응 트랜잭션은 트리거 출력(공격자가 만든 txid)을 지출하므로 트리거를 본 뒤에야 완성할 수 있다.
+//! 그래서 서명은 미리 위임받은 [`ResponseSigner`]가 맡는다.
+
+use crate::manager::VaultManager;
+use crate::psbt::{trigger_target, FeeFunding};
+use crate::timelock::ChainHeightSource;
+use crate::vault::BitcoinVault;
+use shared::{DeFiHubError, DeFiResult};
+use bitcoin::psbt::Psbt;
+use bitcoin::{Address, OutPoint, ScriptBuf, Transaction, Txid};
+use bitcoincore_rpc::{Client, RpcApi};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tracing::warn;
+
+/// 감시탑이 남기는 이력 항목의 행위자
+pub const WATCHTOWER_ACTOR: &str = "watchtower";
+
+/// 블록과 브로드캐스트를 제공하는 체인 소스
+///
+/// 실제 노드는 [`Client`](bitcoincore_rpc::Client)를, 테스트는 [`MemoryChain`]을 주입한다.
+pub trait ChainSource: ChainHeightSource {
+    /// 해당 높이 블록의 트랜잭션들
+    fn block_transactions(&self, height: u32) -> DeFiResult<Vec<Transaction>>;
+
+    /// 트랜잭션 브로드캐스트
+    fn broadcast(&self, tx: &Transaction) -> DeFiResult<Txid>;
+}
+
+impl ChainSource for Client {
+    fn block_transactions(&self, height: u32) -> DeFiResult<Vec<Transaction>> {
+        let hash = self
+            .get_block_hash(height as u64)
+            .map_err(|e| DeFiHubError::RpcConnection(e.to_string()))?;
+        let block = self
+            .get_block(&hash)
+            .map_err(|e| DeFiHubError::RpcConnection(e.to_string()))?;
+        Ok(block.txdata)
+    }
+
+    fn broadcast(&self, tx: &Transaction) -> DeFiResult<Txid> {
+        self.send_raw_transaction(tx)
+            .map_err(|e| DeFiHubError::RpcConnection(e.to_string()))
+    }
+}
+
+/// 메모리 체인 첫 블록의 시각 (유닉스 초)
+pub const MEMORY_CHAIN_GENESIS_TIME: u32 = 1_700_000_000;
+
+/// 메모리 체인 - 블록을 직접 쌓아 시나리오를 재현한다
+///
+/// 브로드캐스트한 트랜잭션은 멤풀에 들어가고 다음에 채굴하는 블록에 포함된다.
+/// 블록 시각은 [`MEMORY_CHAIN_GENESIS_TIME`]부터 블록 간격(기본 600초)씩 늘어난다.
+#[derive(Debug)]
+pub struct MemoryChain {
+    inner: Mutex<MemoryChainState>,
+}
+
+#[derive(Debug)]
+struct MemoryChainState {
+    /// 첫 블록의 높이
+    base_height: u32,
+
+    /// 높이순 블록들
+    blocks: Vec<Vec<Transaction>>,
+
+    /// 높이순 블록 시각
+    times: Vec<u32>,
+
+    /// 새 블록의 시각 간격 (초)
+    block_interval: u32,
+
+    /// 아직 채굴되지 않은 트랜잭션
+    mempool: Vec<Transaction>,
+}
+
+impl MemoryChain {
+    /// `base_height` 높이의 빈 블록 하나로 시작
+    pub fn new(base_height: u32) -> Self {
+        Self {
+            inner: Mutex::new(MemoryChainState {
+                base_height,
+                blocks: vec![Vec::new()],
+                times: vec![MEMORY_CHAIN_GENESIS_TIME],
+                block_interval: 600,
+                mempool: Vec::new(),
+            }),
+        }
+    }
+
+    /// 새 블록의 시각 간격 지정 (예: Fractal 30초)
+    pub fn with_block_interval(self, secs: u32) -> Self {
+        self.state().block_interval = secs;
+        self
+    }
+
+    /// 멤풀과 주어진 트랜잭션으로 블록 하나를 채굴하고 그 높이를 반환
+    pub fn mine_block(&self, txs: Vec<Transaction>) -> u32 {
+        let mut state = self.state();
+        let mut block = std::mem::take(&mut state.mempool);
+        block.extend(txs);
+        state.blocks.push(block);
+        let time = state.times.last().copied().unwrap_or(MEMORY_CHAIN_GENESIS_TIME) + state.block_interval;
+        state.times.push(time);
+        state.tip()
+    }
+
+    /// 빈 블록 `count`개 채굴 (멤풀은 첫 블록에 들어간다)
+    pub fn mine_empty(&self, count: u32) -> u32 {
+        let mut tip = self.state().tip();
+        for _ in 0..count {
+            tip = self.mine_block(Vec::new());
+        }
+        tip
+    }
+
+    /// 멤풀의 트랜잭션들
+    pub fn mempool(&self) -> Vec<Transaction> {
+        self.state().mempool.clone()
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, MemoryChainState> {
+        self.inner.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl MemoryChainState {
+    fn tip(&self) -> u32 {
+        self.base_height + self.blocks.len() as u32 - 1
+    }
+}
+
+impl ChainHeightSource for MemoryChain {
+    fn current_height(&self) -> DeFiResult<u32> {
+        Ok(self.state().tip())
+    }
+
+    /// 직전 11블록(있는 만큼) 시각의 중앙값
+    fn median_time_past(&self, height: u32) -> DeFiResult<u32> {
+        let state = self.state();
+        let index = height
+            .checked_sub(state.base_height)
+            .map(|index| index as usize)
+            .filter(|index| *index < state.times.len())
+            .ok_or_else(|| DeFiHubError::RpcConnection(format!("Block {} is not available", height)))?;
+        let mut times = state.times[index.saturating_sub(10)..=index].to_vec();
+        times.sort_unstable();
+        Ok(times[times.len() / 2])
+    }
+}
+
+impl ChainSource for MemoryChain {
+    fn block_transactions(&self, height: u32) -> DeFiResult<Vec<Transaction>> {
+        let state = self.state();
+        height
+            .checked_sub(state.base_height)
+            .and_then(|index| state.blocks.get(index as usize))
+            .cloned()
+            .ok_or_else(|| DeFiHubError::RpcConnection(format!("Block {} is not available", height)))
+    }
+
+    fn broadcast(&self, tx: &Transaction) -> DeFiResult<Txid> {
+        let mut state = self.state();
+        let spent = |other: &Transaction| {
+            other
+                .input
+                .iter()
+                .any(|a| tx.input.iter().any(|b| a.previous_output == b.previous_output))
+        };
+        if state.mempool.iter().any(spent) {
+            return Err(DeFiHubError::BitcoinTransaction(format!(
+                "Transaction {} conflicts with the mempool",
+                tx.txid()
+            )));
+        }
+        state.mempool.push(tx.clone());
+        Ok(tx.txid())
+    }
+}
+
+/// 대응 PSBT 서명자
+///
+/// 취소 리프는 소유자 키, 회수 리프는 회수 키 서명이 필요하며 수수료 입력도 함께 서명해야 한다.
+pub trait ResponseSigner {
+    /// 대응 PSBT에 서명하고 완성된 트랜잭션을 반환
+    fn sign(&self, vault: &BitcoinVault, psbt: Psbt) -> DeFiResult<Transaction>;
+}
+
+impl<F> ResponseSigner for F
+where
+    F: Fn(&BitcoinVault, Psbt) -> DeFiResult<Transaction>,
+{
+    fn sign(&self, vault: &BitcoinVault, psbt: Psbt) -> DeFiResult<Transaction> {
+        self(vault, psbt)
+    }
+}
+
+/// 허용되지 않은 트리거에 대한 대응
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WatchResponse {
+    /// 트리거 출력을 금고에 재잠금
+    Cancel,
+    /// 회수 키로 콜드 주소에 회수
+    Recover,
+}
+
+/// 금고 감시 정책
+#[derive(Clone, Debug)]
+pub struct WatchPolicy {
+    /// 출금을 허용하는 대상 scriptPubKey들
+    pub allowlist: Vec<ScriptBuf>,
+
+    /// 허용되지 않은 트리거에 대한 대응
+    pub response: WatchResponse,
+
+    /// 대응 트랜잭션 수수료 입력
+    pub funding: FeeFunding,
+}
+
+impl WatchPolicy {
+    /// 빈 허용 목록으로 정책 생성 (모든 트리거에 대응한다)
+    pub fn new(response: WatchResponse, funding: FeeFunding) -> Self {
+        Self {
+            allowlist: Vec::new(),
+            response,
+            funding,
+        }
+    }
+
+    /// 허용 주소 추가
+    pub fn allow(mut self, address: &Address) -> Self {
+        self.allowlist.push(address.script_pubkey());
+        self
+    }
+
+    /// 출금 대상이 허용 목록에 있는지 확인
+    pub fn allows(&self, target: &ScriptBuf) -> bool {
+        self.allowlist.contains(target)
+    }
+}
+
+/// 워치타워가 처리한 사건
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WatchEvent {
+    /// 허용 목록의 대상으로 가는 트리거
+    AuthorizedTrigger {
+        vault: OutPoint,
+        trigger_txid: Txid,
+        destination: ScriptBuf,
+    },
+
+    /// 허용되지 않은 트리거에 대응 트랜잭션을 브로드캐스트함
+    Responded {
+        vault: OutPoint,
+        trigger_txid: Txid,
+        destination: ScriptBuf,
+        response: WatchResponse,
+        response_txid: Txid,
+        /// 브로드캐스트 시점에 타임락이 풀리기까지 남은 블록 수 (0이면 완료와 경쟁 중)
+        blocks_remaining: u32,
+    },
+
+    /// 금고 UTXO를 지출하는 트랜잭션을 처리하지 못함 (상태 불일치, 서명/브로드캐스트 실패 등)
+    ///
+    /// 다른 금고와 이후 블록은 계속 감시하므로 운영자가 직접 대응해야 한다.
+    Failed {
+        vault: OutPoint,
+        txid: Txid,
+        error: String,
+    },
+}
+
+/// 워치타워
+///
+/// 정책은 금고 scriptPubKey에 붙으므로 취소/병합으로 금고 OutPoint가 바뀌어도 계속 감시한다.
+pub struct Watchtower<C: ChainSource, S: ResponseSigner> {
+    /// 체인 소스
+    chain: C,
+
+    /// 대응 PSBT 서명자
+    signer: S,
+
+    /// 감시하는 금고 저장소
+    manager: VaultManager,
+
+    /// 금고 scriptPubKey별 감시 정책
+    policies: HashMap<ScriptBuf, WatchPolicy>,
+
+    /// 다음에 처리할 블록 높이
+    next_height: u32,
+}
+
+impl<C: ChainSource, S: ResponseSigner> Watchtower<C, S> {
+    /// `start_height` 블록부터 감시하는 워치타워 생성
+    pub fn new(chain: C, manager: VaultManager, signer: S, start_height: u32) -> Self {
+        Self {
+            chain,
+            signer,
+            manager,
+            policies: HashMap::new(),
+            next_height: start_height,
+        }
+    }
+
+    /// 금고 감시 등록
+    pub fn watch(&mut self, vault: &BitcoinVault, policy: WatchPolicy) {
+        self.policies.insert(vault.address.script_pubkey(), policy);
+    }
+
+    /// 체인 소스
+    pub fn chain(&self) -> &C {
+        &self.chain
+    }
+
+    /// 다음에 처리할 블록 높이
+    pub fn next_height(&self) -> u32 {
+        self.next_height
+    }
+
+    /// 새 블록들을 처리하고 발생한 사건을 반환
+    ///
+    /// 체인 소스 에러가 나면 그 블록부터 다시 처리할 수 있도록 높이를 올리지 않는다.
+    /// 금고 하나의 처리 실패는 [`WatchEvent::Failed`]로 남기고 계속 진행한다.
+    pub fn poll(&mut self) -> DeFiResult<Vec<WatchEvent>> {
+        self.manager.sync()?;
+        let tip = self.chain.current_height()?;
+
+        let mut events = Vec::new();
+        while self.next_height <= tip {
+            for tx in self.chain.block_transactions(self.next_height)? {
+                let Some(vault_id) = self.spent_vault(&tx)? else {
+                    continue;
+                };
+                match self.inspect(vault_id, &tx, self.next_height, tip) {
+                    Ok(Some(event)) => events.push(event),
+                    Ok(None) => {},
+                    Err(err) => {
+                        warn!("Watchtower failed to handle {} spending vault {}: {}", tx.txid(), vault_id, err);
+                        events.push(WatchEvent::Failed {
+                            vault: vault_id,
+                            txid: tx.txid(),
+                            error: err.to_string(),
+                        });
+                    },
+                }
+            }
+            self.next_height += 1;
+        }
+        Ok(events)
+    }
+
+    /// `height` 블록의 트랜잭션이 감시 중인 금고의 트리거인지 확인하고 대응
+    fn inspect(&self, vault_id: OutPoint, tx: &Transaction, height: u32, tip: u32) -> DeFiResult<Option<WatchEvent>> {
+        let vault = self.manager.load(&vault_id)?;
+        let Some(policy) = self.policies.get(&vault.address.script_pubkey()) else {
+            return Ok(None);
+        };
+        let is_trigger = tx
+            .output
+            .first()
+            .is_some_and(|output| output.script_pubkey == vault.address.script_pubkey());
+        let destination = match trigger_target(tx) {
+            Ok(destination) if is_trigger => destination,
+            _ => return Ok(None),
+        };
+
+        // 트리거는 `height` 블록에 확정됐으므로 타임락 기준 높이는 그 직전이다
+        let trigger_height = height.saturating_sub(1);
+        let trigger_txid = tx.txid();
+        if policy.allows(&destination) {
+            let vault = self.manager.update(&vault_id, |vault| {
+                vault.set_actor(WATCHTOWER_ACTOR);
+                vault.observe_trigger(tx, trigger_height, &self.chain)?;
+                Ok(vault.id)
+            })?;
+            return Ok(Some(WatchEvent::AuthorizedTrigger {
+                vault,
+                trigger_txid,
+                destination,
+            }));
+        }
+
+        let blocks_remaining = vault.blocks_remaining(trigger_height, tip);
+        let (vault, response_txid) = self.manager.update(&vault_id, |vault| {
+            vault.set_actor(WATCHTOWER_ACTOR);
+            vault.observe_trigger(tx, trigger_height, &self.chain)?;
+            let psbt = match policy.response {
+                WatchResponse::Cancel => vault.cancel_withdrawal(&self.chain, &policy.funding)?,
+                WatchResponse::Recover => vault.recover(&self.chain, &policy.funding)?,
+            };
+            let response = self.signer.sign(vault, psbt)?;
+            Ok((vault.id, self.chain.broadcast(&response)?))
+        })?;
+        Ok(Some(WatchEvent::Responded {
+            vault,
+            trigger_txid,
+            destination,
+            response: policy.response,
+            response_txid,
+            blocks_remaining,
+        }))
+    }
+
+    /// 트랜잭션이 지출하는 금고 UTXO의 금고 ID
+    ///
+    /// 로컬 PSBT가 금고 UTXO를 옮겨 둬도 체인에는 이전 UTXO가 남아 있을 수 있으므로 이전 UTXO도 함께 본다.
+    fn spent_vault(&self, tx: &Transaction) -> DeFiResult<Option<OutPoint>> {
+        let state = self.manager.global_state();
+        let state = state
+            .read()
+            .map_err(|_| DeFiHubError::Internal("Global state lock poisoned".to_string()))?;
+        Ok(state
+            .vaults
+            .values()
+            .find(|info| {
+                info.utxos
+                    .iter()
+                    .map(|utxo| &utxo.outpoint)
+                    .chain(&info.previous_outpoints)
+                    .any(|outpoint| tx.input.iter().any(|input| input.previous_output == *outpoint))
+            })
+            .map(|info| info.outpoint))
+    }
+}
@@ -0,0 +1,174 @@
+use bitcoin::hashes::Hash;
+use bitcoin::{AddressType, Network, XOnlyPublicKey};
+use bitcoin_vault::{owner_key_from_str, BitcoinVault, VaultCovenant, VaultLeaf};
+use std::str::FromStr;
+
+/// BIP-340 테스트 벡터 1의 공개키
+const OWNER_KEY: &str = "f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9";
+
+/// 공통 꼬리: sighash 프리이미지 조립 → 챌린지 검증 → G에 대한 OP_CHECKSIG
+const SIGMSG_TAIL: &str = concat!(
+    "7c060081020000007c7e6c7e527e7c7e6c7e01227e6c7e7c7e7c7e0500ffffffff7e",
+    "40f40a48df4b2a70c8b4924bf2654661ed3d95fd66a313eb87237597c628e4a031",
+    "f40a48df4b2a70c8b4924bf2654661ed3d95fd66a313eb87237597c628e4a0317c7ea8",
+    "4c807bb52d7a9fef58323eb1bf7a407db382d2f3f2d81bb1224f49fe518f6d48d37c",
+    "7bb52d7a9fef58323eb1bf7a407db382d2f3f2d81bb1224f49fe518f6d48d37c",
+    "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+    "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f817987c7ea8",
+    "7801007e88",
+    "2079be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f817987c7e0201817e",
+    "2079be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798ac",
+);
+
+/// `[vault_spk, amount]` 길이 검사 후 out0 조립
+const SELF_OUTPUT_PRELUDE: &str = "8258887c820122887c6e7c6b6b7c01227c7e7e";
+
+/// 출금 대상 OP_RETURN 마커 조립
+const TARGET_MARKER: &str = "7c827651014ca569765293016a7e7c7e7c7e0800000000000000007c7e";
+
+fn covenant() -> VaultCovenant {
+    VaultCovenant::new(XOnlyPublicKey::from_str(OWNER_KEY).unwrap(), 144)
+}
+
+#[test]
+fn trigger_leaf_is_pinned() {
+    let expected = format!(
+        "20{}ad{}{}7e7c7ea86b{}",
+        OWNER_KEY, SELF_OUTPUT_PRELUDE, TARGET_MARKER, SIGMSG_TAIL
+    );
+    assert_eq!(covenant().leaf_script(VaultLeaf::Trigger).to_hex_string(), expected);
+}
+
+#[test]
+fn complete_leaf_is_pinned() {
+    let expected = format!(
+        "029000b275{}{}{}{}7e7b7e7c7e7eaa04000000007e{}",
+        "8258887c820122887c6e7c6b6b",
+        "765379827651014ca5697c7e7e547a7ea86b",
+        "7c01227c7e7e",
+        TARGET_MARKER,
+        SIGMSG_TAIL,
+    );
+    assert_eq!(covenant().leaf_script(VaultLeaf::Complete).to_hex_string(), expected);
+}
+
+#[test]
+fn cancel_leaf_is_pinned() {
+    let expected = format!("20{}ad{}7c7ea86b{}", OWNER_KEY, SELF_OUTPUT_PRELUDE, SIGMSG_TAIL);
+    assert_eq!(covenant().leaf_script(VaultLeaf::Cancel).to_hex_string(), expected);
+}
+
+#[test]
+fn complete_leaf_starts_with_csv() {
+    let asm = covenant().leaf_script(VaultLeaf::Complete).to_asm_string();
+    assert!(asm.starts_with("OP_PUSHBYTES_2 9000 OP_CSV OP_DROP"));
+
+    let short = VaultCovenant::new(XOnlyPublicKey::from_str(OWNER_KEY).unwrap(), 6);
+    let asm = short.leaf_script(VaultLeaf::Complete).to_asm_string();
+    assert!(asm.starts_with("OP_PUSHNUM_6 OP_CSV OP_DROP"));
+}
+
+#[test]
+fn leaf_hashes_are_pinned() {
+    let covenant = covenant();
+    let hashes: Vec<String> = [VaultLeaf::Trigger, VaultLeaf::Complete, VaultLeaf::Cancel]
+        .iter()
+        .map(|leaf| covenant.leaf_hash(*leaf).to_string())
+        .collect();
+
+    assert_eq!(
+        hashes,
+        vec![
+            "92a37afc9f870a627b68f9c3b3ae33c30dd0277f06eb6cd17223c2948c614663",
+            "d7a4289ae470922e0aa676d9845270dc1891ac3ff4007730214bb7d925802209",
+            "acaa0af75cac29c773afbbb8b5de6134e97cd5585a5f3ea087cc6ec79c4e2f6a",
+        ]
+    );
+}
+
+#[test]
+fn address_is_pinned() {
+    let covenant = covenant();
+    assert_eq!(
+        covenant.address(Network::Bitcoin).unwrap().to_string(),
+        "bc1pdmmyhxcyc2ljm43hjlj9v7rh3nr8cac7kmeh4dd3mclq9glt57vqjdw2q9"
+    );
+    assert_eq!(
+        covenant.address(Network::Regtest).unwrap().to_string(),
+        "bcrt1pdmmyhxcyc2ljm43hjlj9v7rh3nr8cac7kmeh4dd3mclq9glt57vqgujr0s"
+    );
+}
+
+#[test]
+fn control_blocks_commit_to_leaves() {
+    let covenant = covenant();
+    let spend_info = covenant.spend_info().unwrap();
+    let output_key = spend_info.output_key().to_inner();
+
+    for leaf in [
+        VaultLeaf::Trigger,
+        VaultLeaf::Complete,
+        VaultLeaf::Cancel,
+        VaultLeaf::PartialTrigger,
+        VaultLeaf::PartialComplete,
+        VaultLeaf::Consolidate,
+        VaultLeaf::MergeTrigger,
+    ] {
+        let control_block = covenant.control_block(leaf).unwrap();
+        let script = covenant.leaf_script(leaf);
+        assert!(control_block.verify_taproot_commitment(&bitcoin_vault::SECP, output_key, &script));
+    }
+
+    // 키 경로는 NUMS 내부키로 막혀 있다
+    assert_eq!(spend_info.internal_key(), bitcoin_vault::nums_internal_key());
+    assert_eq!(
+        covenant.script_pubkey().unwrap().as_bytes()[2..],
+        output_key.serialize()[..]
+    );
+    assert!(spend_info.merkle_root().is_some_and(|root| root.to_byte_array() != [0u8; 32]));
+}
+
+#[test]
+fn vault_address_is_p2tr_and_reproducible() {
+    let first = BitcoinVault::new(Network::Regtest, 144, "alice".to_string()).unwrap();
+    let second = BitcoinVault::new(Network::Regtest, 144, "alice".to_string()).unwrap();
+
+    assert_eq!(first.address.address_type(), Some(AddressType::P2tr));
+    assert_eq!(first.address, second.address);
+    assert_eq!(first.owner_key, owner_key_from_str("alice"));
+    assert_eq!(first.address, first.covenant().address(Network::Regtest).unwrap());
+}
+
+#[test]
+fn timelock_and_owner_change_the_address() {
+    let base = BitcoinVault::new(Network::Regtest, 144, "alice".to_string()).unwrap();
+    let other_timelock = BitcoinVault::new(Network::Regtest, 145, "alice".to_string()).unwrap();
+    let other_owner = BitcoinVault::new(Network::Regtest, 144, "bob".to_string()).unwrap();
+
+    assert_ne!(base.address, other_timelock.address);
+    assert_ne!(base.address, other_owner.address);
+    assert_ne!(other_timelock.address, other_owner.address);
+}
+
+#[test]
+fn hex_owner_is_used_as_key() {
+    let vault = BitcoinVault::new(Network::Bitcoin, 144, OWNER_KEY.to_string()).unwrap();
+    assert_eq!(vault.owner_key.to_string(), OWNER_KEY);
+    assert_eq!(
+        vault.address.to_string(),
+        "bc1pdmmyhxcyc2ljm43hjlj9v7rh3nr8cac7kmeh4dd3mclq9glt57vqjdw2q9"
+    );
+}
+
+#[test]
+fn vault_round_trips_through_json() {
+    let vault = BitcoinVault::new(Network::Regtest, 144, "alice".to_string()).unwrap();
+    let file = tempfile::NamedTempFile::new().unwrap();
+
+    vault.save_to_file(file.path()).unwrap();
+    let loaded = BitcoinVault::load_from_file(file.path()).unwrap();
+
+    assert_eq!(loaded.address, vault.address);
+    assert_eq!(loaded.owner_key, vault.owner_key);
+    assert_eq!(loaded.network, Network::Regtest);
+}
@@ -0,0 +1,254 @@
+use super::psbt::{destination, funded_vault, funding, owner, prevouts, TRIGGER_HEIGHT, VAULT_AMOUNT};
+use super::recovery::{cold_address, recovery, sign_recovery};
+use bitcoin::hashes::Hash;
+use bitcoin::psbt::Psbt;
+use bitcoin::secp256k1::Message;
+use bitcoin::sighash::{Prevouts, SighashCache, TapSighashType};
+use bitcoin::taproot::{self, LeafVersion, TapLeafHash};
+use bitcoin::{Amount, Network, OutPoint, Txid};
+use bitcoin_vault::script::{ANCHOR_AMOUNT, MAX_MERGE_FEE};
+use bitcoin_vault::signature::{covenant_challenge, merge_sigmsg, tap_sighash};
+use bitcoin_vault::*;
+use shared::{DeFiHubError, VaultState};
+
+const SECOND_DEPOSIT: u64 = 50_000;
+
+fn second_outpoint() -> OutPoint {
+    OutPoint::new(Txid::from_byte_array([5; 32]), 0)
+}
+
+fn two_deposit_vault() -> BitcoinVault {
+    let mut vault = funded_vault();
+    vault
+        .record_deposit(second_outpoint(), Amount::from_sat(SECOND_DEPOSIT))
+        .unwrap();
+    vault
+}
+
+/// 모든 금고 입력의 그라인딩 결과가 챌린지 조건을 만족하는지 확인
+fn assert_merge_ground(psbt: &Psbt) {
+    let prevouts = prevouts(psbt);
+    for (index, input) in psbt.inputs.iter().enumerate() {
+        let (_, (script, _)) = input.tap_scripts.iter().next().unwrap();
+        let leaf_hash = TapLeafHash::from_script(script, LeafVersion::TapScript);
+
+        let sigmsg = merge_sigmsg(&psbt.unsigned_tx, index, &prevouts, leaf_hash).unwrap();
+        let challenge = covenant_challenge(&tap_sighash(&sigmsg));
+        assert_eq!(challenge[31], 0x00);
+
+        let e_prefix = input
+            .proprietary
+            .iter()
+            .find(|(key, _)| key.subtype == PSBT_WITNESS_ELEMENT && key.key == [0])
+            .map(|(_, value)| value.clone())
+            .unwrap();
+        assert_eq!(e_prefix, challenge[..31].to_vec());
+    }
+}
+
+/// 모든 금고 입력에 소유자 서명 후 완성
+fn sign_and_finalize_all(psbt: &mut Psbt) {
+    let prevouts = prevouts(psbt);
+    let mut cache = SighashCache::new(psbt.unsigned_tx.clone());
+    for index in 0..psbt.inputs.len() {
+        let (_, (script, _)) = psbt.inputs[index].tap_scripts.iter().next().unwrap();
+        let leaf_hash = TapLeafHash::from_script(script, LeafVersion::TapScript);
+        let sighash = cache
+            .taproot_script_spend_signature_hash(index, &Prevouts::All(&prevouts), leaf_hash, TapSighashType::Default)
+            .unwrap();
+        let sig = SECP.sign_schnorr_no_aux_rand(&Message::from_digest(sighash.to_byte_array()), &owner());
+        psbt.inputs[index].tap_script_sigs.insert(
+            (owner().x_only_public_key().0, leaf_hash),
+            taproot::Signature { sig, hash_ty: TapSighashType::Default },
+        );
+        finalize_vault_input(psbt, index).unwrap();
+    }
+}
+
+#[test]
+fn deposits_accumulate_into_utxo_set() {
+    let mut vault = two_deposit_vault();
+    let first = vault.utxos[0].outpoint;
+
+    assert_eq!(vault.id, first);
+    assert_eq!(vault.amount, Amount::from_sat(VAULT_AMOUNT + SECOND_DEPOSIT));
+    assert_eq!(vault.utxos.len(), 2);
+    assert_eq!(vault.utxos[1].outpoint, second_outpoint());
+    assert!(vault.utxos.iter().all(|utxo| utxo.height.is_none() && utxo.confirmations == 0));
+
+    // 같은 UTXO는 두 번 기록할 수 없다
+    let duplicate = vault.record_deposit(second_outpoint(), Amount::from_sat(SECOND_DEPOSIT));
+    assert!(matches!(duplicate, Err(DeFiHubError::BitcoinTransaction(_))));
+    assert_eq!(vault.utxos.len(), 2);
+
+    let info = vault.info();
+    assert_eq!(info.outpoint, first);
+    assert_eq!(info.utxos, vault.utxos);
+}
+
+#[test]
+fn confirmations_follow_chain_height() {
+    let mut vault = two_deposit_vault();
+    vault.confirm_deposit(second_outpoint(), 100, &FixedHeight(105)).unwrap();
+
+    let confirmed = &vault.utxos[1];
+    assert_eq!(confirmed.height, Some(100));
+    assert_eq!(confirmed.confirmations, 6);
+
+    vault.refresh_confirmations(&FixedHeight(110)).unwrap();
+    assert_eq!(vault.utxos[1].confirmations, 11);
+    assert_eq!(vault.utxos[0].confirmations, 0);
+
+    let unknown = OutPoint::new(Txid::from_byte_array([6; 32]), 0);
+    assert!(vault.confirm_deposit(unknown, 100, &FixedHeight(105)).is_err());
+}
+
+#[test]
+fn consolidate_merges_utxos_under_covenant() {
+    let mut vault = two_deposit_vault();
+    let inputs: Vec<OutPoint> = vault.utxos.iter().map(|utxo| utxo.outpoint).collect();
+    let vault_spk = vault.address.script_pubkey();
+
+    let mut psbt = vault.consolidate(2).unwrap();
+    let tx = psbt.unsigned_tx.clone();
+
+    // 금고 UTXO만 입력으로 쓰고 수수료는 금고 금액에서 낸다
+    let spent: Vec<OutPoint> = tx.input.iter().map(|input| input.previous_output).collect();
+    assert_eq!(spent, inputs);
+    assert_eq!(tx.output.len(), 2);
+    assert_eq!(tx.output[0].script_pubkey, vault_spk);
+    assert_eq!(tx.output[1], anchor_output());
+    let fee = psbt.fee().unwrap();
+    assert!(fee.to_sat() + ANCHOR_AMOUNT <= MAX_MERGE_FEE);
+    assert_eq!(tx.output[0].value + fee + tx.output[1].value, Amount::from_sat(VAULT_AMOUNT + SECOND_DEPOSIT));
+    assert!(psbt.outputs[0].tap_tree.is_some());
+    assert_merge_ground(&psbt);
+
+    assert_eq!(vault.utxos.len(), 1);
+    assert_eq!(vault.id, OutPoint::new(tx.txid(), 0));
+    assert_eq!(vault.amount, tx.output[0].value);
+    assert_eq!(vault.state, VaultState::Inactive);
+
+    // 두 입력 모두 소유자 서명이 필요하다
+    assert!(finalize_vault_input(&mut psbt.clone(), 1).is_err());
+    sign_and_finalize_all(&mut psbt);
+    for input in &psbt.inputs {
+        assert_eq!(input.final_script_witness.as_ref().unwrap().len(), 16);
+    }
+
+    // 앵커를 포함한 출력으로 모든 금고 입력이 로컬 인터프리터를 통과한다
+    let prevouts = prevouts(&psbt);
+    let signed = psbt.extract_tx_unchecked_fee_rate();
+    for index in 0..signed.input.len() {
+        TapscriptInterpreter::new(&signed, index, &prevouts).verify().unwrap();
+    }
+}
+
+#[test]
+fn merge_trigger_spends_all_utxos_and_completes() {
+    let mut vault = two_deposit_vault();
+    let total = Amount::from_sat(VAULT_AMOUNT + SECOND_DEPOSIT);
+
+    let psbt = vault
+        .trigger_withdrawal(destination(), total, &funding(2, 20_000), &FixedHeight(TRIGGER_HEIGHT))
+        .unwrap();
+    let tx = psbt.unsigned_tx.clone();
+
+    assert_eq!(tx.input.len(), 2);
+    assert_eq!(trigger_target(&tx).unwrap(), destination().script_pubkey());
+    assert_eq!(trigger_withdrawal_amount(&tx).unwrap(), None);
+    assert_merge_ground(&psbt);
+
+    let merged = tx.output[0].value;
+    assert!(merged < total);
+    assert!(matches!(vault.state, VaultState::Triggered { amount, .. } if amount == merged));
+    assert_eq!(vault.utxos.len(), 1);
+    assert_eq!(vault.id, OutPoint::new(tx.txid(), 0));
+
+    let complete = vault
+        .complete_withdrawal(&FixedHeight(TRIGGER_HEIGHT + 10), &funding(3, 20_000))
+        .unwrap();
+    assert_eq!(complete.unsigned_tx.input[0].previous_output, OutPoint::new(tx.txid(), 0));
+    assert_eq!(complete.unsigned_tx.output[0].value, merged);
+    assert_eq!(complete.unsigned_tx.output[0].script_pubkey, destination().script_pubkey());
+    assert_eq!(vault.state, VaultState::Completed);
+}
+
+#[test]
+fn multi_utxo_withdrawals_require_consolidation() {
+    let mut vault = two_deposit_vault();
+
+    // 부분 출금은 병합 후에만 가능하다
+    let partial = vault.trigger_withdrawal(destination(), Amount::from_sat(30_000), &funding(2, 20_000), &FixedHeight(TRIGGER_HEIGHT));
+    assert!(matches!(partial, Err(DeFiHubError::BitcoinTransaction(_))));
+
+    vault
+        .record_deposit(OutPoint::new(Txid::from_byte_array([6; 32]), 0), Amount::from_sat(SECOND_DEPOSIT))
+        .unwrap();
+    let full = vault.amount;
+    let three = vault.trigger_withdrawal(destination(), full, &funding(2, 20_000), &FixedHeight(TRIGGER_HEIGHT));
+    assert!(matches!(three, Err(DeFiHubError::BitcoinTransaction(_))));
+    assert_eq!(vault.state, VaultState::Inactive);
+    assert_eq!(vault.utxos.len(), 3);
+
+    // 단일 UTXO 금고는 병합할 것이 없다
+    assert!(funded_vault().consolidate(2).is_err());
+}
+
+#[test]
+fn recover_sweeps_utxos_one_at_a_time() {
+    let owner_key = owner().x_only_public_key().0.to_string();
+    let mut vault = BitcoinVault::new(Network::Regtest, 10, owner_key)
+        .unwrap()
+        .with_recovery(recovery().x_only_public_key().0, cold_address())
+        .unwrap();
+    vault
+        .record_deposit(OutPoint::new(Txid::from_byte_array([1; 32]), 1), Amount::from_sat(VAULT_AMOUNT))
+        .unwrap();
+    vault
+        .record_deposit(second_outpoint(), Amount::from_sat(SECOND_DEPOSIT))
+        .unwrap();
+
+    let mut first = vault.recover(&FixedHeight(TRIGGER_HEIGHT + 1), &funding(2, 20_000)).unwrap();
+    assert_eq!(first.unsigned_tx.output[0].value, Amount::from_sat(VAULT_AMOUNT));
+    assert_eq!(vault.state, VaultState::Inactive);
+    assert_eq!(vault.id, second_outpoint());
+    assert_eq!(vault.amount, Amount::from_sat(SECOND_DEPOSIT));
+    sign_recovery(&mut first);
+    finalize_vault_input(&mut first, 0).unwrap();
+
+    let second = vault.recover(&FixedHeight(TRIGGER_HEIGHT + 1), &funding(3, 20_000)).unwrap();
+    assert_eq!(second.unsigned_tx.input[0].previous_output, second_outpoint());
+    assert!(matches!(vault.state, VaultState::Recovered { .. }));
+
+    // 스윕마다 높이와 함께 이력에 남는다
+    let events = vault.history.events();
+    let sweeps: Vec<_> = events[events.len() - 2..]
+        .iter()
+        .map(|event| (event.action, event.block_height))
+        .collect();
+    assert_eq!(
+        sweeps,
+        vec![
+            (VaultAction::UtxoRecovered, Some(TRIGGER_HEIGHT + 1)),
+            (VaultAction::Recovered, Some(TRIGGER_HEIGHT + 1)),
+        ]
+    );
+    vault.verify_history().unwrap();
+}
+
+#[test]
+fn legacy_vault_file_loads_as_single_utxo() {
+    let vault = funded_vault();
+    let mut json = serde_json::to_value(&vault).unwrap();
+    json.as_object_mut().unwrap().remove("utxos");
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("vault.json");
+    std::fs::write(&path, json.to_string()).unwrap();
+
+    let restored = BitcoinVault::load_from_file(&path).unwrap();
+    assert_eq!(restored.utxos.len(), 1);
+    assert_eq!(restored.utxos[0].outpoint, vault.id);
+    assert_eq!(restored.utxos[0].amount, Amount::from_sat(VAULT_AMOUNT));
+}
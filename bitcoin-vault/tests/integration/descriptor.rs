@@ -0,0 +1,66 @@
+use super::psbt::{destination, funded_vault, owner};
+use bitcoin::{Address, Network};
+use bitcoin_vault::*;
+use shared::DeFiHubError;
+
+/// 디스크립터를 다시 주소로 바꿔 금고 주소와 비교
+fn assert_round_trips(vault: &BitcoinVault) {
+    let covenant = vault.covenant();
+    for descriptor in [covenant.descriptor().unwrap(), covenant.tree_descriptor().unwrap()] {
+        let script = parse_descriptor(&descriptor).unwrap();
+        let address = Address::from_script(&script, vault.network).unwrap();
+        assert_eq!(address, vault.address, "{}", descriptor);
+    }
+}
+
+#[test]
+fn checksum_matches_bip380_vectors() {
+    assert_eq!(descriptor_checksum("raw(deadbeef)").unwrap(), "89f8spxm");
+    assert!(parse_descriptor("rawtr(00)#89f8spxm").is_err());
+    assert!(descriptor_checksum("raw(dead\u{e9}beef)").is_err());
+}
+
+#[test]
+fn vault_descriptors_round_trip_to_address() {
+    let vault = funded_vault();
+    assert_round_trips(&vault);
+
+    let descriptor = vault.covenant().descriptor().unwrap();
+    assert!(descriptor.starts_with("rawtr("));
+    let tree = vault.covenant().tree_descriptor().unwrap();
+    assert!(tree.starts_with(&format!("tr({},{{", nums_internal_key())));
+    assert_eq!(tree.matches("rawleaf(").count(), 7);
+
+    // 회수 경로가 있으면 리프가 하나 늘고 주소도 달라진다
+    let recovery = owner().x_only_public_key().0;
+    let with_recovery = BitcoinVault::new(Network::Regtest, 10, "alice".to_string())
+        .unwrap()
+        .with_recovery(recovery, destination())
+        .unwrap();
+    assert_round_trips(&with_recovery);
+    assert_eq!(with_recovery.covenant().tree_descriptor().unwrap().matches("rawleaf(").count(), 8);
+    assert_ne!(with_recovery.covenant().descriptor().unwrap(), descriptor);
+}
+
+#[test]
+fn tampered_descriptors_are_rejected() {
+    let vault = funded_vault();
+    let tree = vault.covenant().tree_descriptor().unwrap();
+    let (body, checksum) = tree.split_once('#').unwrap();
+
+    // 체크섬이 틀리면 거부
+    let mut wrong = checksum.to_string();
+    wrong.replace_range(0..1, if checksum.starts_with('q') { "p" } else { "q" });
+    let err = parse_descriptor(&format!("{}#{}", body, wrong)).unwrap_err();
+    assert!(matches!(err, DeFiHubError::InvalidAddress(ref reason) if reason.contains("checksum")));
+
+    // 체크섬 없이 리프를 바꾸면 다른 주소가 된다
+    let altered = body.replacen("rawleaf(", "rawleaf(51", 1);
+    let script = parse_descriptor(&altered).unwrap();
+    assert_ne!(script, vault.address.script_pubkey());
+
+    // 체크섬 없는 원본은 그대로 읽힌다
+    assert_eq!(parse_descriptor(body).unwrap(), vault.address.script_pubkey());
+    assert!(parse_descriptor("wpkh(02aa)").is_err());
+    assert!(parse_descriptor(&format!("tr({},{{rawleaf(51)}})", nums_internal_key())).is_err());
+}
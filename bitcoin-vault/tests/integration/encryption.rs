@@ -0,0 +1,152 @@
+use super::psbt::funded_vault;
+use bitcoin_vault::manager::VAULTS_DIR;
+use bitcoin_vault::*;
+use shared::encryption::{EncryptedContainer, KdfParams, Passphrase, CONTAINER_FORMAT};
+use shared::persistence::{backup_path, is_encrypted, BACKUP_COUNT};
+use shared::state::GlobalState;
+use shared::DeFiHubError;
+use std::path::Path;
+
+/// 테스트용으로 가벼운 scrypt 매개변수
+pub(super) fn passphrase(secret: &str) -> Passphrase {
+    Passphrase::new(secret).with_kdf(KdfParams { log_n: 10, r: 8, p: 1 })
+}
+
+fn container(path: &Path) -> EncryptedContainer {
+    serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+}
+
+#[test]
+fn encrypted_vault_round_trips() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("vault.json");
+    let vault = funded_vault();
+    let unlock = passphrase("correct horse");
+    vault.save_to_file_with(&path, Some(&unlock)).unwrap();
+
+    // 평문 내용은 파일에 남지 않는다
+    let content = std::fs::read_to_string(&path).unwrap();
+    assert!(is_encrypted(&path));
+    assert!(!content.contains(&vault.owner));
+    assert!(!content.contains(&vault.address.to_string()));
+    assert_eq!(container(&path).format, CONTAINER_FORMAT);
+
+    // 새 Passphrase 값(빈 키 캐시)으로도 열린다
+    let loaded = BitcoinVault::load_from_file_with(&path, Some(&passphrase("correct horse"))).unwrap();
+    assert_eq!(loaded.id, vault.id);
+    assert_eq!(loaded.address, vault.address);
+    assert_eq!(loaded.utxos, vault.utxos);
+
+    // 같은 패스프레이즈로 다시 저장해도 논스는 매번 다르다
+    vault.save_to_file_with(&path, Some(&unlock)).unwrap();
+    let first = container(&backup_path(&path, 1));
+    let second = container(&path);
+    assert_eq!(first.salt, second.salt);
+    assert_ne!(first.nonce, second.nonce);
+}
+
+#[test]
+fn wrong_or_missing_passphrase_is_rejected() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("vault.json");
+    funded_vault().save_to_file_with(&path, Some(&passphrase("correct horse"))).unwrap();
+
+    let wrong = BitcoinVault::load_from_file_with(&path, Some(&passphrase("battery staple")));
+    assert!(matches!(wrong, Err(DeFiHubError::DecryptionFailed)));
+    let missing = BitcoinVault::load_from_file(&path);
+    assert!(matches!(missing, Err(DeFiHubError::PassphraseRequired(ref what)) if what.contains("vault.json")));
+
+    // 헤더나 암호문을 바꾸면 인증에 실패한다
+    let mut tampered = container(&path);
+    tampered.kdf.log_n = 11;
+    std::fs::write(&path, serde_json::to_string(&tampered).unwrap()).unwrap();
+    let err = BitcoinVault::load_from_file_with(&path, Some(&passphrase("correct horse")));
+    assert!(matches!(err, Err(DeFiHubError::DecryptionFailed)));
+
+    let mut tampered = container(&path);
+    tampered.kdf.log_n = 10;
+    let mut ciphertext = hex::decode(&tampered.ciphertext).unwrap();
+    ciphertext[0] ^= 1;
+    tampered.ciphertext = hex::encode(ciphertext);
+    std::fs::write(&path, serde_json::to_string(&tampered).unwrap()).unwrap();
+    let err = BitcoinVault::load_from_file_with(&path, Some(&passphrase("correct horse")));
+    assert!(matches!(err, Err(DeFiHubError::DecryptionFailed)));
+
+    // 평문 백업이 있어도 패스프레이즈 에러를 백업으로 덮지 않는다
+    funded_vault().save_to_file_with(&path, Some(&passphrase("correct horse"))).unwrap();
+    funded_vault().save_to_file(backup_path(&path, 1)).unwrap();
+    assert!(BitcoinVault::load_from_file(backup_path(&path, 1)).is_ok());
+    let missing = BitcoinVault::load_from_file(&path);
+    assert!(matches!(missing, Err(DeFiHubError::PassphraseRequired(_))));
+    let wrong = BitcoinVault::load_from_file_with(&path, Some(&passphrase("battery staple")));
+    assert!(matches!(wrong, Err(DeFiHubError::DecryptionFailed)));
+
+    // 빈 패스프레이즈로는 암호화하지 않는다
+    assert!(funded_vault().save_to_file_with(&path, Some(&passphrase(""))).is_err());
+}
+
+#[test]
+fn plaintext_files_upgrade_to_encrypted() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("state.json");
+    let path = path.to_str().unwrap();
+    GlobalState::new().save_to_file(path).unwrap();
+
+    GlobalState::new().save_to_file(path).unwrap();
+    assert!(backup_path(Path::new(path), 1).exists());
+
+    // 평문 파일은 패스프레이즈가 있어도 그대로 읽히고, 다시 저장하면 암호화된다
+    let unlock = passphrase("correct horse");
+    let state = GlobalState::load_from_file_with(path, Some(&unlock)).unwrap();
+    state.save_to_file_with(path, Some(&unlock)).unwrap();
+    assert!(is_encrypted(Path::new(path)));
+
+    // 평문 백업은 남기지 않는다
+    for n in 1..=BACKUP_COUNT {
+        assert!(!backup_path(Path::new(path), n).exists(), "{}", n);
+    }
+    state.save_to_file_with(path, Some(&unlock)).unwrap();
+    assert!(is_encrypted(&backup_path(Path::new(path), 1)));
+    GlobalState::load_from_file_with(path, Some(&unlock)).unwrap();
+
+    // 깨진 암호화 파일은 같은 패스프레이즈로 백업에서 복구한다
+    std::fs::write(path, b"{\"format\":").unwrap();
+    GlobalState::load_from_file_with(path, Some(&unlock)).unwrap();
+}
+
+#[test]
+fn encrypted_manager_writes_only_ciphertext() {
+    let dir = tempfile::tempdir().unwrap();
+    let unlock = passphrase("correct horse");
+    let manager = VaultManager::open_with_passphrase(dir.path(), Some(unlock.clone())).unwrap();
+    let vault = funded_vault();
+    manager.create(&vault).unwrap();
+    manager
+        .update(&vault.id, |vault| {
+            vault.owner = "bob".to_string();
+            Ok(())
+        })
+        .unwrap();
+
+    let path = dir
+        .path()
+        .join(VAULTS_DIR)
+        .join(format!("{}_{}.json", vault.id.txid, vault.id.vout));
+    assert!(is_encrypted(&path));
+    assert!(is_encrypted(&backup_path(&path, 1)));
+
+    let reopened = VaultManager::open_with_passphrase(dir.path(), Some(passphrase("correct horse"))).unwrap();
+    assert_eq!(reopened.load(&vault.id).unwrap().owner, "bob");
+    assert!(matches!(VaultManager::open(dir.path()), Err(DeFiHubError::PassphraseRequired(_))));
+}
+
+#[test]
+fn default_kdf_parameters_work() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("vault.json");
+    let unlock = Passphrase::new("correct horse");
+    funded_vault().save_to_file_with(&path, Some(&unlock)).unwrap();
+
+    assert_eq!(container(&path).kdf, KdfParams::default());
+    BitcoinVault::load_from_file_with(&path, Some(&unlock)).unwrap();
+}
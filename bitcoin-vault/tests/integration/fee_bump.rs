@@ -0,0 +1,130 @@
+use super::interpreter::sign_fee_inputs;
+use super::psbt::{destination, funded_vault, funding, sign_owner, TRIGGER_HEIGHT, VAULT_AMOUNT};
+use bitcoin::psbt::Psbt;
+use bitcoin::{Amount, Transaction};
+use bitcoin_vault::fee_bump::package_fee_rate;
+use bitcoin_vault::*;
+use shared::constants::{DEFAULT_FEE_RATE, DUST_AMOUNT};
+use shared::DeFiHubError;
+
+/// 1 sat/vB로 미리 서명된 트리거 (부모)와 그 수수료
+fn stuck_trigger() -> (Transaction, Amount) {
+    let mut vault = funded_vault();
+    let mut psbt = vault
+        .trigger_withdrawal(destination(), Amount::from_sat(VAULT_AMOUNT), &funding(2, 20_000).with_fee_rate(1), &FixedHeight(TRIGGER_HEIGHT))
+        .unwrap();
+    let fee = psbt.fee().unwrap();
+    sign_owner(&mut psbt);
+    sign_fee_inputs(&mut psbt);
+    finalize_vault_input(&mut psbt, 0).unwrap();
+    (psbt.extract_tx_unchecked_fee_rate(), fee)
+}
+
+fn signed_child(mut psbt: Psbt) -> Transaction {
+    sign_fee_inputs(&mut psbt);
+    psbt.extract_tx_unchecked_fee_rate()
+}
+
+#[test]
+fn every_template_carries_an_anchor() {
+    let mut vault = funded_vault();
+    let trigger = vault
+        .trigger_withdrawal(destination(), Amount::from_sat(VAULT_AMOUNT), &funding(2, 20_000), &FixedHeight(TRIGGER_HEIGHT))
+        .unwrap();
+    let cancel = vault.clone().cancel_withdrawal(&FixedHeight(TRIGGER_HEIGHT + 1), &funding(3, 20_000)).unwrap();
+    let complete = vault
+        .complete_withdrawal(&FixedHeight(TRIGGER_HEIGHT + 10), &funding(3, 20_000))
+        .unwrap();
+
+    let mut partial = funded_vault();
+    let partial_trigger = partial
+        .trigger_withdrawal(destination(), Amount::from_sat(30_000), &funding(2, 20_000), &FixedHeight(TRIGGER_HEIGHT))
+        .unwrap();
+    let partial_complete = partial
+        .complete_withdrawal(&FixedHeight(TRIGGER_HEIGHT + 10), &funding(3, 20_000))
+        .unwrap();
+
+    for psbt in [trigger, cancel, complete, partial_trigger, partial_complete] {
+        let tx = &psbt.unsigned_tx;
+        let (outpoint, anchor) = anchor_outpoint(tx).unwrap();
+        assert_eq!(anchor, anchor_output());
+        assert_eq!(tx.output.iter().filter(|output| **output == anchor_output()).count(), 1);
+        assert_eq!(outpoint.txid, tx.txid());
+
+        // 앵커 금액은 수수료 입력에서 나온다
+        let inputs: Amount = psbt.inputs.iter().map(|input| input.witness_utxo.as_ref().unwrap().value).sum();
+        let outputs: Amount = tx.output.iter().map(|output| output.value).sum();
+        assert_eq!(inputs - outputs, psbt.fee().unwrap());
+        assert!(psbt.fee().unwrap() >= Amount::from_sat(tx.vsize() as u64 * DEFAULT_FEE_RATE));
+    }
+}
+
+#[test]
+fn cpfp_reaches_default_package_fee_rate() {
+    let (parent, parent_fee) = stuck_trigger();
+    assert!(package_fee_rate(parent_fee, parent.vsize() as u64) < 2.0);
+
+    let wallet = funding(6, 50_000);
+    assert_eq!(wallet.fee_rate, DEFAULT_FEE_RATE);
+    let package = cpfp(&parent, parent_fee, &wallet).unwrap();
+
+    // 자식 수수료 = 목표 × (부모 + 자식 크기) - 부모 수수료
+    let expected = DEFAULT_FEE_RATE * package.package_vsize() - parent_fee.to_sat();
+    assert_eq!(package.parent_vsize, parent.vsize() as u64);
+    assert_eq!(package.child_fee, Amount::from_sat(expected));
+    assert_eq!(package.package_fee(), Amount::from_sat(DEFAULT_FEE_RATE * package.package_vsize()));
+    assert_eq!(package.package_fee_rate(), DEFAULT_FEE_RATE as f64);
+
+    let child = &package.psbt.unsigned_tx;
+    assert_eq!(child.input[0].previous_output, anchor_outpoint(&parent).unwrap().0);
+    assert_eq!(child.input[1].previous_output, wallet.inputs[0].outpoint);
+    assert_eq!(child.output.len(), 1);
+    assert_eq!(child.output[0].script_pubkey, wallet.change_script);
+    assert_eq!(child.output[0].value + package.child_fee, Amount::from_sat(50_000) + anchor_output().value);
+    assert_eq!(package.psbt.fee().unwrap(), package.child_fee);
+
+    // 서명 후 실제 크기가 추정과 같다
+    let signed = signed_child(package.psbt.clone());
+    assert_eq!(signed.vsize() as u64, package.child_vsize);
+    assert!(signed.input[0].witness.is_empty());
+}
+
+#[test]
+fn well_funded_parent_only_needs_child_rate() {
+    let (parent, _) = stuck_trigger();
+    let generous = Amount::from_sat(parent.vsize() as u64 * 50);
+
+    let package = cpfp(&parent, generous, &funding(6, 50_000)).unwrap();
+    assert_eq!(package.child_fee, Amount::from_sat(package.child_vsize * DEFAULT_FEE_RATE));
+    assert!(package.package_fee_rate() > DEFAULT_FEE_RATE as f64);
+
+    // 목표 수수료율을 올리면 자식이 더 낸다
+    let (parent, parent_fee) = stuck_trigger();
+    let urgent = cpfp(&parent, parent_fee, &funding(6, 50_000).with_fee_rate(40)).unwrap();
+    assert_eq!(urgent.package_fee(), Amount::from_sat(40 * urgent.package_vsize()));
+}
+
+#[test]
+fn small_wallets_drop_change_or_fail() {
+    let (parent, parent_fee) = stuck_trigger();
+    let needed = cpfp(&parent, parent_fee, &funding(6, 50_000)).unwrap().child_fee;
+
+    // 잔돈이 먼지면 지갑 전체가 수수료가 된다
+    let tight = needed + DUST_AMOUNT - anchor_output().value - Amount::from_sat(1);
+    let package = cpfp(&parent, parent_fee, &funding(6, tight.to_sat())).unwrap();
+    let child = &package.psbt.unsigned_tx;
+    assert_eq!(child.output.len(), 1);
+    assert_eq!(child.output[0].value, Amount::ZERO);
+    assert!(child.output[0].script_pubkey.is_op_return());
+    assert_eq!(package.child_fee, tight + anchor_output().value);
+    assert!(package.package_fee_rate() >= DEFAULT_FEE_RATE as f64);
+
+    let broke = cpfp(&parent, parent_fee, &funding(6, 500));
+    assert!(matches!(broke, Err(DeFiHubError::InsufficientFunds { available: 740, .. })));
+
+    // 앵커가 없는 트랜잭션은 올릴 수 없다
+    let mut bare = parent.clone();
+    bare.output.retain(|output| *output != anchor_output());
+    assert!(cpfp(&bare, parent_fee, &funding(6, 50_000)).is_err());
+    assert!(anchor_outpoint(&bare).is_err());
+}
@@ -0,0 +1,236 @@
+use super::psbt::{destination, funded_vault, funding, TRIGGER_HEIGHT, VAULT_AMOUNT};
+use bitcoin::Amount;
+use bitcoin_vault::history::GENESIS_HASH;
+use bitcoin_vault::manager::VAULTS_DIR;
+use bitcoin_vault::*;
+use chrono::Utc;
+use shared::{DeFiHubError, StateRoot, VaultState};
+
+/// 높이로 정한 테스트용 롤업 상태 루트
+pub(super) fn state_root(height: u64) -> StateRoot {
+    StateRoot {
+        hash: [height as u8; 32],
+        height,
+        timestamp: Utc::now(),
+    }
+}
+
+/// 트리거 → 취소 → 부분 출금 트리거 → 완료를 거친 금고
+fn busy_vault() -> BitcoinVault {
+    let mut vault = funded_vault();
+    vault
+        .trigger_withdrawal(destination(), Amount::from_sat(VAULT_AMOUNT), &funding(2, 20_000), &FixedHeight(TRIGGER_HEIGHT))
+        .unwrap();
+    vault.set_actor("alice-laptop");
+    vault.cancel_withdrawal(&FixedHeight(TRIGGER_HEIGHT + 1), &funding(3, 20_000)).unwrap();
+    vault
+        .trigger_withdrawal(destination(), Amount::from_sat(30_000), &funding(4, 20_000), &FixedHeight(TRIGGER_HEIGHT + 5))
+        .unwrap();
+    vault
+        .complete_withdrawal(&FixedHeight(TRIGGER_HEIGHT + 15), &funding(5, 20_000))
+        .unwrap();
+    vault
+}
+
+fn assert_tampered(result: shared::DeFiResult<()>, at: usize) {
+    match result {
+        Err(DeFiHubError::HistoryTampered { index, .. }) => assert_eq!(index, at),
+        other => panic!("expected tampering at {}, got {:?}", at, other),
+    }
+}
+
+#[test]
+fn lifecycle_transitions_are_recorded() {
+    let vault = busy_vault();
+    let events = vault.history.events();
+    let actions: Vec<_> = events.iter().map(|event| event.action).collect();
+    assert_eq!(
+        actions,
+        vec![
+            VaultAction::Created,
+            VaultAction::WithdrawalTriggered,
+            VaultAction::WithdrawalCancelled,
+            VaultAction::WithdrawalTriggered,
+            VaultAction::WithdrawalCompleted,
+        ]
+    );
+
+    assert_eq!(events[0].prev_hash, GENESIS_HASH);
+    assert_eq!(events[1].block_height, Some(TRIGGER_HEIGHT));
+    assert!(matches!(events[1].new_state, VaultState::Triggered { .. }));
+    assert_eq!(events[2].previous_state, events[1].new_state);
+    assert_eq!(events[2].new_state, VaultState::Inactive);
+    assert_eq!(events[2].block_height, Some(TRIGGER_HEIGHT + 1));
+    assert_eq!(events[4].block_height, Some(TRIGGER_HEIGHT + 15));
+    assert_eq!(events[4].new_state, VaultState::Inactive);
+
+    // 행위자는 기본적으로 소유자이고, 지정하면 그 뒤 항목부터 바뀐다
+    assert_eq!(events[1].actor, vault.owner);
+    assert!(events[2..].iter().all(|event| event.actor == "alice-laptop"));
+    for pair in events.windows(2) {
+        assert_eq!(pair[1].prev_hash, pair[0].hash);
+    }
+    vault.verify_history().unwrap();
+
+    // 실패한 전환은 기록되지 않는다
+    let mut vault = vault;
+    assert!(vault.cancel_withdrawal(&FixedHeight(TRIGGER_HEIGHT + 1), &funding(6, 20_000)).is_err());
+    assert_eq!(vault.history.len(), 5);
+}
+
+#[test]
+fn bridge_bitvmx_and_manual_updates_are_recorded() {
+    let mut vault = funded_vault();
+    vault.enable_bitvmx("vault.elf".to_string(), 1);
+    vault.update_bitvmx_state(state_root(1)).unwrap();
+    vault.bridge_to_rollup(state_root(2)).unwrap();
+    vault.update_state(VaultState::Completed);
+
+    let actions: Vec<_> = vault.history.events().iter().map(|event| event.action).collect();
+    assert_eq!(
+        actions,
+        vec![
+            VaultAction::Created,
+            VaultAction::BitvmxStateUpdated,
+            VaultAction::BridgedToRollup,
+            VaultAction::StateUpdated,
+        ]
+    );
+    let bitvmx = &vault.history.events()[1];
+    assert_eq!(bitvmx.previous_state, bitvmx.new_state);
+    assert!(matches!(vault.history.events()[3].previous_state, VaultState::Bridged { .. }));
+    vault.verify_history().unwrap();
+
+    // 파일로 저장해도 그대로 검증된다
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("vault.json");
+    vault.save_to_file(&path).unwrap();
+    let loaded = BitcoinVault::load_from_file(&path).unwrap();
+    assert_eq!(loaded.history, vault.history);
+    loaded.verify_history().unwrap();
+}
+
+#[test]
+fn edited_entries_are_detected() {
+    let vault = busy_vault();
+    let mut json = serde_json::to_value(&vault).unwrap();
+    json["history"][2]["actor"] = "mallory".into();
+    let edited: BitcoinVault = serde_json::from_value(json).unwrap();
+    assert_tampered(edited.verify_history(), 2);
+
+    // 해시까지 다시 계산해도 다음 항목과의 연결이 끊긴다
+    let mut json = serde_json::to_value(&vault).unwrap();
+    json["history"][2]["actor"] = "mallory".into();
+    let mut forged: BitcoinVault = serde_json::from_value(json).unwrap();
+    let recomputed = forged.history.events()[2].compute_hash();
+    let mut json = serde_json::to_value(&forged).unwrap();
+    json["history"][2]["hash"] = recomputed.into();
+    forged = serde_json::from_value(json).unwrap();
+    assert_tampered(forged.verify_history(), 3);
+
+    // 높이를 바꿔도 마찬가지
+    let mut json = serde_json::to_value(&vault).unwrap();
+    json["history"][1]["block_height"] = (TRIGGER_HEIGHT + 100).into();
+    let edited: BitcoinVault = serde_json::from_value(json).unwrap();
+    assert_tampered(edited.verify_history(), 1);
+}
+
+#[test]
+fn removed_entries_are_detected() {
+    let vault = busy_vault();
+
+    // 중간 항목 삭제
+    let mut json = serde_json::to_value(&vault).unwrap();
+    json["history"].as_array_mut().unwrap().remove(2);
+    let removed: BitcoinVault = serde_json::from_value(json).unwrap();
+    assert_tampered(removed.verify_history(), 2);
+
+    // 마지막 항목 삭제 - 체인은 맞지만 현재 상태와 어긋난다
+    let mut json = serde_json::to_value(&vault).unwrap();
+    json["history"].as_array_mut().unwrap().pop();
+    let truncated: BitcoinVault = serde_json::from_value(json).unwrap();
+    truncated.history.verify().unwrap();
+    assert_tampered(truncated.verify_history(), 4);
+
+    // 첫 항목 삭제
+    let mut json = serde_json::to_value(&vault).unwrap();
+    json["history"].as_array_mut().unwrap().remove(0);
+    let headless: BitcoinVault = serde_json::from_value(json).unwrap();
+    assert_tampered(headless.verify_history(), 0);
+
+    // 외부에 보관한 head와 비교하면 이력 전체를 갈아 끼운 경우도 찾는다
+    let anchored = vault.history.head().to_string();
+    vault.history.verify_head(&anchored).unwrap();
+    let mut replaced = funded_vault();
+    replaced.update_state(VaultState::Inactive);
+    assert!(replaced.history.verify_head(&anchored).is_err());
+}
+
+#[test]
+fn anchors_catch_rollback_and_rewritten_history() {
+    let vault = busy_vault();
+    let anchor = HistoryAnchor { sequence: 2, head: vault.history.events()[2].hash.clone() };
+
+    // 앵커 뒤에 덧붙은 항목은 허용한다
+    vault.history.verify_anchor(&anchor).unwrap();
+    vault.history.verify_anchor(&vault.history.anchor().unwrap()).unwrap();
+
+    // 앵커보다 짧아진 이력
+    let mut json = serde_json::to_value(&vault).unwrap();
+    json["history"].as_array_mut().unwrap().truncate(2);
+    let truncated: BitcoinVault = serde_json::from_value(json).unwrap();
+    assert_tampered(truncated.history.verify_anchor(&anchor), 2);
+
+    // 체인은 맞지만 앵커한 항목과 다른 이력
+    let mut other = funded_vault();
+    other.update_state(VaultState::Completed);
+    other.update_state(VaultState::Inactive);
+    assert_tampered(other.history.verify_anchor(&anchor), 2);
+}
+
+#[test]
+fn manager_verifies_history_against_stored_anchor() {
+    let dir = tempfile::tempdir().unwrap();
+    let manager = VaultManager::open(dir.path()).unwrap();
+    let vault = funded_vault();
+    let id = vault.id;
+    manager.create(&vault).unwrap();
+    let path = dir.path().join(VAULTS_DIR).join(format!("{}_{}.json", id.txid, id.vout));
+    let original = std::fs::read(&path).unwrap();
+
+    manager
+        .update(&id, |vault| {
+            vault.update_state(VaultState::Completed);
+            Ok(())
+        })
+        .unwrap();
+    let anchor = manager.verify_history(&id).unwrap().unwrap();
+    assert_eq!(anchor.sequence, 1);
+
+    // 예전 파일로 되돌리면 금고 파일만으로는 정상이지만 앵커와 어긋난다
+    std::fs::write(&path, original).unwrap();
+    manager.load(&id).unwrap().verify_history().unwrap();
+    assert!(matches!(manager.verify_history(&id), Err(DeFiHubError::HistoryTampered { .. })));
+}
+
+#[test]
+fn legacy_vault_files_get_an_imported_genesis() {
+    let mut vault = funded_vault();
+    vault
+        .trigger_withdrawal(destination(), Amount::from_sat(VAULT_AMOUNT), &funding(2, 20_000), &FixedHeight(TRIGGER_HEIGHT))
+        .unwrap();
+    let mut json = serde_json::to_value(&vault).unwrap();
+    json.as_object_mut().unwrap().remove("history");
+    json["schema_version"] = 1.into();
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("vault.json");
+    std::fs::write(&path, json.to_string()).unwrap();
+    let loaded = BitcoinVault::load_from_file(&path).unwrap();
+
+    let events = loaded.history.events();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].action, VaultAction::Imported);
+    assert_eq!(events[0].new_state, vault.state);
+    loaded.verify_history().unwrap();
+}
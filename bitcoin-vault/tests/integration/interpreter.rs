@@ -0,0 +1,202 @@
+use super::psbt::{destination, funded_vault, funding, owner, prevouts, sign_owner, TRIGGER_HEIGHT, VAULT_AMOUNT};
+use bitcoin::absolute::LockTime;
+use bitcoin::hashes::Hash;
+use bitcoin::key::TapTweak;
+use bitcoin::psbt::Psbt;
+use bitcoin::secp256k1::{Keypair, Message, SecretKey};
+use bitcoin::sighash::{Prevouts, SighashCache, TapSighashType};
+use bitcoin::taproot::{LeafVersion, TaprootBuilder};
+use bitcoin::transaction::Version;
+use bitcoin::{Amount, OutPoint, ScriptBuf, Sequence, Transaction, TxIn, TxOut, Txid, Witness};
+use bitcoin_vault::interpreter::{decode_num, encode_num};
+use bitcoin_vault::*;
+
+/// 수수료 입력(키 경로)에 서명
+pub(super) fn sign_fee_inputs(psbt: &mut Psbt) {
+    let fee_key = Keypair::from_secret_key(&SECP, &SecretKey::from_slice(&[9u8; 32]).unwrap());
+    let tweaked = fee_key.tap_tweak(&SECP, None).to_inner();
+    let prevouts = prevouts(psbt);
+    let mut cache = SighashCache::new(psbt.unsigned_tx.clone());
+
+    for index in 0..psbt.inputs.len() {
+        if !psbt.inputs[index].tap_scripts.is_empty() || psbt.inputs[index].final_script_witness.is_some() {
+            continue;
+        }
+        let sighash = cache
+            .taproot_key_spend_signature_hash(index, &Prevouts::All(&prevouts), TapSighashType::Default)
+            .unwrap();
+        let sig = SECP.sign_schnorr_no_aux_rand(&Message::from_digest(sighash.to_byte_array()), &tweaked);
+        psbt.inputs[index].final_script_witness = Some(Witness::from_slice(&[sig.as_ref().to_vec()]));
+    }
+}
+
+/// 금고 입력(0번)과 수수료 입력에 서명하고 완성된 트랜잭션을 추출
+pub(super) fn signed(mut psbt: Psbt, needs_owner: bool) -> (Transaction, Vec<TxOut>) {
+    if needs_owner {
+        sign_owner(&mut psbt);
+    }
+    sign_fee_inputs(&mut psbt);
+    finalize_vault_input(&mut psbt, 0).unwrap();
+    let prevouts = prevouts(&psbt);
+    (psbt.extract_tx_unchecked_fee_rate(), prevouts)
+}
+
+/// NUMS 내부키와 리프 하나로 된 P2TR 출력을 지출하는 트랜잭션
+fn single_leaf_spend(script: &ScriptBuf, stack: &[&[u8]]) -> (Transaction, Vec<TxOut>) {
+    let spend_info = TaprootBuilder::new()
+        .add_leaf(0, script.clone())
+        .unwrap()
+        .finalize(&SECP, nums_internal_key())
+        .unwrap();
+    let control_block = spend_info.control_block(&(script.clone(), LeafVersion::TapScript)).unwrap();
+    let prevout = TxOut {
+        value: Amount::from_sat(10_000),
+        script_pubkey: ScriptBuf::new_p2tr_tweaked(spend_info.output_key()),
+    };
+
+    let mut witness: Vec<Vec<u8>> = stack.iter().map(|item| item.to_vec()).collect();
+    witness.push(script.to_bytes());
+    witness.push(control_block.serialize());
+    let tx = Transaction {
+        version: Version::TWO,
+        lock_time: LockTime::ZERO,
+        input: vec![TxIn {
+            previous_output: OutPoint::new(Txid::from_byte_array([3; 32]), 0),
+            script_sig: ScriptBuf::new(),
+            sequence: Sequence::MAX,
+            witness: Witness::from_slice(&witness),
+        }],
+        output: vec![TxOut {
+            value: Amount::from_sat(9_000),
+            script_pubkey: destination().script_pubkey(),
+        }],
+    };
+    (tx, vec![prevout])
+}
+
+#[test]
+fn signed_trigger_and_complete_verify() {
+    let mut vault = funded_vault();
+    let trigger = vault
+        .trigger_withdrawal(destination(), Amount::from_sat(VAULT_AMOUNT), &funding(2, 20_000), &FixedHeight(TRIGGER_HEIGHT))
+        .unwrap();
+    let (tx, prevouts) = signed(trigger, true);
+
+    for index in 0..tx.input.len() {
+        TapscriptInterpreter::new(&tx, index, &prevouts).verify().unwrap();
+    }
+    let trace = TapscriptInterpreter::new(&tx, 0, &prevouts).verify().unwrap();
+    assert!(trace.steps.iter().any(|step| step.instruction == "OP_CAT"));
+    assert_eq!(trace.steps.last().unwrap().instruction, "OP_CHECKSIG");
+    assert_eq!(trace.steps.last().unwrap().stack, vec!["01".to_string()]);
+
+    let complete = vault
+        .complete_withdrawal(&FixedHeight(TRIGGER_HEIGHT + 10), &funding(3, 20_000))
+        .unwrap();
+    let (tx, prevouts) = signed(complete, false);
+    TapscriptInterpreter::new(&tx, 0, &prevouts).verify().unwrap();
+}
+
+#[test]
+fn early_complete_fails_csv() {
+    let mut vault = funded_vault();
+    vault
+        .trigger_withdrawal(destination(), Amount::from_sat(VAULT_AMOUNT), &funding(2, 20_000), &FixedHeight(TRIGGER_HEIGHT))
+        .unwrap();
+    let complete = vault
+        .complete_withdrawal(&FixedHeight(TRIGGER_HEIGHT + 10), &funding(3, 20_000))
+        .unwrap();
+    let (mut tx, prevouts) = signed(complete, false);
+
+    // 그라인딩 비트는 그대로 두고 상대 타임락만 9블록으로 줄인다
+    tx.input[0].sequence = Sequence((tx.input[0].sequence.0 & !0xffff) | 9);
+    let err = TapscriptInterpreter::new(&tx, 0, &prevouts).verify().unwrap_err();
+    assert!(matches!(err.reason, ScriptFailure::CsvNotSatisfied(_)), "{}", err);
+    assert!(err.position.is_some());
+}
+
+#[test]
+fn tampered_trigger_reports_precise_failure() {
+    let mut vault = funded_vault();
+    let trigger = vault
+        .trigger_withdrawal(destination(), Amount::from_sat(VAULT_AMOUNT), &funding(2, 20_000), &FixedHeight(TRIGGER_HEIGHT))
+        .unwrap();
+
+    // 다른 키의 서명은 소유자 서명 검사에서 실패한다
+    let mut forged = trigger.clone();
+    let other = Keypair::from_secret_key(&SECP, &SecretKey::from_slice(&[8u8; 32]).unwrap());
+    let (_, (script, _)) = forged.inputs[0].tap_scripts.iter().next().unwrap();
+    let leaf_hash = bitcoin::taproot::TapLeafHash::from_script(script, LeafVersion::TapScript);
+    let sighash = SighashCache::new(&forged.unsigned_tx)
+        .taproot_script_spend_signature_hash(0, &Prevouts::All(&prevouts(&forged)), leaf_hash, TapSighashType::Default)
+        .unwrap();
+    let sig = SECP.sign_schnorr_no_aux_rand(&Message::from_digest(sighash.to_byte_array()), &other);
+    forged.inputs[0].tap_script_sigs.insert(
+        (owner().x_only_public_key().0, leaf_hash),
+        bitcoin::taproot::Signature { sig, hash_ty: TapSighashType::Default },
+    );
+    sign_fee_inputs(&mut forged);
+    finalize_vault_input(&mut forged, 0).unwrap();
+    let prevouts_forged = prevouts(&forged);
+    let tx = forged.extract_tx_unchecked_fee_rate();
+    let err = TapscriptInterpreter::new(&tx, 0, &prevouts_forged).verify().unwrap_err();
+    assert_eq!(err.reason, ScriptFailure::InvalidSignature);
+
+    // 서명 후 트리거 출력을 바꾸면 실패 위치와 그 직전까지의 추적이 남는다
+    let (mut tx, prevouts) = signed(trigger, true);
+    tx.output[0].value -= Amount::from_sat(1);
+    let err = TapscriptInterpreter::new(&tx, 0, &prevouts).verify().unwrap_err();
+    assert!(err.position.is_some());
+    assert!(!err.trace.initial_stack.is_empty());
+    assert!(err.to_string().contains("at script position"));
+}
+
+#[test]
+fn op_cat_leaf_executes_with_trace() {
+    let script = bitcoin::script::Builder::new()
+        .push_opcode(bitcoin::opcodes::all::OP_CAT)
+        .push_slice(b"ab")
+        .push_opcode(bitcoin::opcodes::all::OP_EQUAL)
+        .into_script();
+
+    let (tx, prevouts) = single_leaf_spend(&script, &[b"a", b"b"]);
+    let trace = TapscriptInterpreter::new(&tx, 0, &prevouts).verify().unwrap();
+    assert_eq!(trace.initial_stack, vec!["61".to_string(), "62".to_string()]);
+    assert_eq!(trace.steps.len(), 3);
+    assert_eq!(trace.steps[0].instruction, "OP_CAT");
+    assert_eq!(trace.steps[0].stack, vec!["6162".to_string()]);
+    assert!(trace.render().contains("OP_EQUAL"));
+
+    let (tx, prevouts) = single_leaf_spend(&script, &[b"a", b"c"]);
+    let err = TapscriptInterpreter::new(&tx, 0, &prevouts).verify().unwrap_err();
+    assert_eq!(err.reason, ScriptFailure::EvalFalse);
+
+    // OP_SUCCESSx는 실행 전에 거부한다
+    let success = ScriptBuf::from_bytes(vec![0x50]);
+    let (tx, prevouts) = single_leaf_spend(&success, &[]);
+    let err = TapscriptInterpreter::new(&tx, 0, &prevouts).verify().unwrap_err();
+    assert_eq!(err.reason, ScriptFailure::OpSuccess(0x50));
+
+    // 다른 스크립트로 바꾸면 컨트롤 블록 검사에서 실패한다
+    let (mut tx, prevouts) = single_leaf_spend(&script, &[b"a", b"b"]);
+    let mut witness: Vec<Vec<u8>> = tx.input[0].witness.to_vec();
+    witness[2] = ScriptBuf::from_bytes(vec![0x51]).to_bytes();
+    tx.input[0].witness = Witness::from_slice(&witness);
+    let err = TapscriptInterpreter::new(&tx, 0, &prevouts).verify().unwrap_err();
+    assert_eq!(err.reason, ScriptFailure::CommitmentMismatch);
+}
+
+#[test]
+fn script_numbers_are_minimal() {
+    for value in [0i64, 1, -1, 127, 128, -128, 255, 256, 32_767, 32_768, i32::MAX as i64, -(i32::MAX as i64)] {
+        assert_eq!(decode_num(&encode_num(value), 4).unwrap(), value);
+    }
+    assert_eq!(encode_num(-1), vec![0x81]);
+    assert_eq!(encode_num(128), vec![0x80, 0x00]);
+
+    assert_eq!(decode_num(&[0x00], 4), Err(ScriptFailure::NonMinimalNumber));
+    assert_eq!(decode_num(&[0x01, 0x00], 4), Err(ScriptFailure::NonMinimalNumber));
+    assert_eq!(decode_num(&[0x01, 0x02, 0x03, 0x04, 0x05], 4), Err(ScriptFailure::NumberOverflow(5)));
+    assert!(!interpreter::cast_to_bool(&[0x00, 0x80]));
+    assert!(interpreter::cast_to_bool(&[0x00, 0x01]));
+}
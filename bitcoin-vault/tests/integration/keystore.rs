@@ -0,0 +1,124 @@
+use super::encryption::passphrase;
+use super::psbt::destination;
+use bitcoin::bip32::DerivationPath;
+use bitcoin::{Address, Network};
+use bitcoin_vault::*;
+use shared::keystore::{KeyRole, Keystore};
+use shared::persistence::backup_path;
+use shared::DeFiHubError;
+use std::str::FromStr;
+
+/// BIP39 "abandon abandon … about" (빈 패스프레이즈)의 시드
+const BIP39_SEED: &str = "5eb00bbddcf069084889a8ab9155568165f5c453ccb85e70811aaed6f6da5fc19a5ac40b389cd370d086206dec8aa6c43daea6690f20ad3d8d48b2d2ce9e38e4";
+
+fn keystore(network: Network) -> Keystore {
+    Keystore::from_seed(network, &hex::decode(BIP39_SEED).unwrap()).unwrap()
+}
+
+#[test]
+fn owner_keys_match_bip86_vectors() {
+    let keystore = keystore(Network::Bitcoin);
+    assert_eq!(
+        keystore.derivation_path(KeyRole::Owner, 0).unwrap(),
+        DerivationPath::from_str("m/86'/0'/0'/0/0").unwrap()
+    );
+    assert_eq!(
+        keystore.public_key(KeyRole::Owner, 0).unwrap().to_string(),
+        "cc8a4bc64d897bddc5fbc2f670f7a8ba0b386779106cf1223c6fc5d7cd6fc115"
+    );
+    assert_eq!(
+        keystore.account_xpub(KeyRole::Owner).unwrap().to_string(),
+        "xpub6BgBgsespWvERF3LHQu6CnqdvfEvtMcQjYrcRzx53QJjSxarj2afYWcLteoGVky7D3UKDP9QyrLprQ3VCECoY49yfdDEHGCtMMj92pReUsQ"
+    );
+
+    let owner = keystore.owner_key(1).unwrap().x_only_public_key().0;
+    let address = Address::p2tr(&SECP, owner, None, Network::Bitcoin);
+    assert_eq!(address.to_string(), "bc1p4qhjn9zdvkux4e44uhx8tc55attvtyu358kutcqkudyccelu0was9fqzwh");
+}
+
+#[test]
+fn roles_and_networks_use_separate_accounts() {
+    let mainnet = keystore(Network::Bitcoin);
+    let regtest = keystore(Network::Regtest);
+    assert_eq!(
+        regtest.derivation_path(KeyRole::Recovery, 3).unwrap(),
+        DerivationPath::from_str("m/86'/1'/1'/0/3").unwrap()
+    );
+    assert_eq!(
+        regtest.derivation_path(KeyRole::Rollup, 0).unwrap(),
+        DerivationPath::from_str("m/86'/1'/2'/0/0").unwrap()
+    );
+
+    let mut keys = Vec::new();
+    for keystore in [&mainnet, &regtest] {
+        for role in KeyRole::ALL {
+            keys.push(keystore.public_key(role, 0).unwrap());
+        }
+    }
+    keys.sort();
+    keys.dedup();
+    assert_eq!(keys.len(), 6);
+
+    assert_eq!(KeyRole::from_str("Recovery").unwrap(), KeyRole::Recovery);
+    assert!(KeyRole::from_str("cold").is_err());
+    assert!(regtest.derivation_path(KeyRole::Owner, 1 << 31).is_err());
+    assert!(Keystore::from_seed(Network::Regtest, &[1u8; 8]).is_err());
+}
+
+#[test]
+fn encrypted_keystore_round_trips() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("keystore.json");
+    let generated = Keystore::generate(Network::Regtest).unwrap();
+    let unlock = passphrase("correct horse");
+    generated.save_to_file(&path, Some(&unlock)).unwrap();
+
+    let content = std::fs::read_to_string(&path).unwrap();
+    assert!(!content.contains("tprv"));
+    assert!(matches!(Keystore::load_from_file(&path, None), Err(DeFiHubError::PassphraseRequired(_))));
+
+    let loaded = Keystore::load_from_file(&path, Some(&unlock)).unwrap();
+    assert_eq!(loaded.fingerprint(), generated.fingerprint());
+    assert_eq!(loaded.public_key(KeyRole::Owner, 0).unwrap(), generated.public_key(KeyRole::Owner, 0).unwrap());
+    assert!(!format!("{:?}", loaded).contains("tprv"));
+    assert_ne!(Keystore::generate(Network::Regtest).unwrap().fingerprint(), generated.fingerprint());
+}
+
+#[test]
+fn encrypting_a_plaintext_keystore_leaves_no_plaintext_backup() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("keystore.json");
+    let generated = Keystore::generate(Network::Regtest).unwrap();
+    generated.save_to_file(&path, None).unwrap();
+    generated.save_to_file(&path, None).unwrap();
+    assert!(std::fs::read_to_string(backup_path(&path, 1)).unwrap().contains("tprv"));
+
+    generated.save_to_file(&path, Some(&passphrase("correct horse"))).unwrap();
+    for entry in std::fs::read_dir(dir.path()).unwrap() {
+        let file = entry.unwrap().path();
+        assert!(!std::fs::read_to_string(&file).unwrap().contains("tprv"), "{}", file.display());
+    }
+}
+
+#[test]
+fn vaults_use_keystore_owner_and_recovery_keys() {
+    let keystore = keystore(Network::Regtest);
+    let owner = keystore.public_key(KeyRole::Owner, 0).unwrap();
+    let recovery = keystore.public_key(KeyRole::Recovery, 0).unwrap();
+
+    let vault = BitcoinVault::new(Network::Regtest, 10, "alice".to_string())
+        .unwrap()
+        .with_owner_key(owner)
+        .unwrap()
+        .with_recovery(recovery, destination())
+        .unwrap();
+    assert_eq!(vault.owner, "alice");
+    assert_eq!(vault.owner_key, owner);
+    assert_eq!(vault.address, vault.covenant().address(Network::Regtest).unwrap());
+    assert_ne!(vault.address, BitcoinVault::new(Network::Regtest, 10, "alice".to_string()).unwrap().address);
+
+    // 예치 후에는 소유자 키를 바꿀 수 없다
+    let mut funded = vault.clone();
+    funded.record_deposit(bitcoin::OutPoint::new(bitcoin::Txid::from_str(&"11".repeat(32)).unwrap(), 0), bitcoin::Amount::from_sat(50_000)).unwrap();
+    assert!(matches!(funded.with_owner_key(recovery), Err(DeFiHubError::InvalidVaultState { .. })));
+}
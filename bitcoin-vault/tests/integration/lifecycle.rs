@@ -0,0 +1,252 @@
+use super::psbt::{destination, funded_vault, funding, TRIGGER_HEIGHT};
+use super::history::state_root;
+use super::recovery::recoverable_vault;
+use bitcoin::hashes::Hash;
+use bitcoin::{Amount, OutPoint, Txid};
+use bitcoin_vault::*;
+use chrono::Utc;
+use proptest::prelude::*;
+use shared::state::RollupState;
+use shared::{account_address, DeFiHubError, DeFiResult, StateRoot, TokenType, VaultState};
+
+/// `height`의 롤업 상태 - 금고 소유자의 WBTC 잔액이 `balance`이고 다른 계정에도 잔액이 있다
+fn rollup(vault: &BitcoinVault, height: u64, balance: u64) -> RollupState {
+    let mut state = RollupState::new();
+    state.set_balance("bob".to_string(), TokenType::WBTC, 25_000);
+    state.set_balance(account_address(&vault.owner_key), TokenType::WBTC, balance);
+    state.set_balance(account_address(&vault.owner_key), TokenType::USDC, 1_200);
+    state.current_state_root = StateRoot {
+        hash: state.state_hash(),
+        height,
+        timestamp: Utc::now(),
+    };
+    state
+}
+
+/// 소유자 잔액이 소각된 `height`의 정산과 그 루트를 확정한 출처
+fn burned(vault: &BitcoinVault, height: u64) -> (PegOutSettlement, FinalizedRoots) {
+    let settlement = PegOutSettlement::from_state(&rollup(vault, height, 0), &vault.owner_key);
+    let mut finality = FinalizedRoots::default();
+    finality.finalize(&settlement.state_root);
+    (settlement, finality)
+}
+
+/// 무작위 순서로 적용할 금고 조작 (전환 + 유효한 인자)
+#[derive(Clone, Debug)]
+enum Op {
+    Deposit,
+    Trigger { partial: bool },
+    Complete,
+    Cancel,
+    Recover,
+    PegIn,
+    PegOut,
+}
+
+impl Op {
+    fn transition(&self) -> VaultTransition {
+        match self {
+            Op::Deposit => VaultTransition::Deposit,
+            Op::Trigger { .. } => VaultTransition::Trigger,
+            Op::Complete => VaultTransition::Complete,
+            Op::Cancel => VaultTransition::Cancel,
+            Op::Recover => VaultTransition::Recover,
+            Op::PegIn => VaultTransition::PegIn,
+            Op::PegOut => VaultTransition::PegOut,
+        }
+    }
+
+    fn apply(&self, vault: &mut BitcoinVault, step: u8) -> DeFiResult<()> {
+        let fee = funding(100 + step, 20_000);
+        match self {
+            Op::Deposit => vault.record_deposit(OutPoint::new(Txid::from_byte_array([step; 32]), 0), Amount::from_sat(50_000)),
+            Op::Trigger { partial } => {
+                let amount = if *partial { vault.amount / 2 } else { vault.amount };
+                vault.trigger_withdrawal(destination(), amount, &fee, &FixedHeight(TRIGGER_HEIGHT)).map(|_| ())
+            }
+            Op::Complete => vault.complete_withdrawal(&FixedHeight(TRIGGER_HEIGHT + 10), &fee).map(|_| ()),
+            Op::Cancel => vault.cancel_withdrawal(&FixedHeight(TRIGGER_HEIGHT + 1), &fee).map(|_| ()),
+            Op::Recover => vault.recover(&FixedHeight(TRIGGER_HEIGHT + 1), &fee).map(|_| ()),
+            Op::PegIn => vault.bridge_to_rollup(state_root(step as u64)),
+            Op::PegOut => {
+                let (settlement, finality) = burned(vault, u64::from(step) + 1_000);
+                vault.unbridge_from_rollup(&settlement, &finality)
+            }
+        }
+    }
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        Just(Op::Deposit),
+        any::<bool>().prop_map(|partial| Op::Trigger { partial }),
+        Just(Op::Complete),
+        Just(Op::Cancel),
+        Just(Op::Recover),
+        Just(Op::PegIn),
+        Just(Op::PegOut),
+    ]
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    /// 전환표가 막은 조작은 InvalidVaultState로 거부되고 금고를 건드리지 않으며,
+    /// 허용한 조작은 상태 오류로 실패하지 않는다
+    #[test]
+    fn transitions_follow_the_table(ops in prop::collection::vec(op(), 1..24)) {
+        let mut vault = recoverable_vault();
+        for (step, op) in ops.iter().enumerate() {
+            let step = step as u8 + 2;
+            let before = vault.state.clone();
+            let history_len = vault.history.len();
+            let result = op.apply(&mut vault, step);
+
+            if op.transition().is_allowed(&before) {
+                // 허용된 조작은 상태 때문에 실패하지 않는다 (UTXO가 여럿이면 병합 요구 등은 가능)
+                prop_assert!(
+                    !matches!(result, Err(DeFiHubError::InvalidVaultState { .. })),
+                    "{:?} from {} failed: {:?}", op, before.name(), result.map(|_| ())
+                );
+                if result.is_err() {
+                    prop_assert_eq!(&vault.state, &before);
+                }
+            } else {
+                prop_assert!(
+                    matches!(result, Err(DeFiHubError::InvalidVaultState { .. })),
+                    "{:?} from {} should be rejected, got {:?}", op, before.name(), result.map(|_| ())
+                );
+                prop_assert_eq!(&vault.state, &before);
+                prop_assert_eq!(vault.history.len(), history_len);
+            }
+            prop_assert_eq!(vault.can_withdraw(), vault.state == VaultState::Inactive);
+        }
+        vault.verify_history().unwrap();
+    }
+
+    /// 복귀는 신뢰하는 출처가 확정한 더 새로운 상태 루트에서 소유자 잔액이 0일 때만 성공한다
+    #[test]
+    fn peg_out_requires_finalized_burn(
+        bridged_height in 0u64..1_000,
+        settled_height in 0u64..1_000,
+        finality in 0u8..3,
+        balance in 0u64..3,
+    ) {
+        let mut vault = funded_vault();
+        vault.bridge_to_rollup(state_root(bridged_height)).unwrap();
+        let bridged = vault.state.clone();
+        let settlement = PegOutSettlement::from_state(&rollup(&vault, settled_height, balance), &vault.owner_key);
+
+        // 0: 확정되지 않음, 1: 같은 높이에 다른 루트가 확정됨, 2: 정산의 루트가 확정됨
+        let mut finalized = FinalizedRoots::default();
+        match finality {
+            0 => {}
+            1 => finalized.finalize(&state_root(settled_height)),
+            _ => finalized.finalize(&settlement.state_root),
+        }
+
+        let result = vault.unbridge_from_rollup(&settlement, &finalized);
+        if finality == 2 && settled_height > bridged_height && balance == 0 {
+            prop_assert!(result.is_ok());
+            prop_assert_eq!(&vault.state, &VaultState::Inactive);
+            prop_assert_eq!(vault.history.events().last().unwrap().action, VaultAction::UnbridgedFromRollup);
+        } else {
+            let rejected = matches!(
+                result,
+                Err(DeFiHubError::BridgeVerification(_)) | Err(DeFiHubError::InvalidStateRoot { .. })
+            );
+            prop_assert!(rejected);
+            prop_assert_eq!(&vault.state, &bridged);
+        }
+    }
+}
+
+#[test]
+fn peg_out_rejects_proofs_that_do_not_match_the_finalized_root() {
+    let mut vault = funded_vault();
+    vault.bridge_to_rollup(state_root(1)).unwrap();
+    let (burned_settlement, _) = burned(&vault, 5);
+
+    // 잔액이 남은 루트가 확정되었으면 다른 상태의 소각 증명을 붙여도 거부한다
+    let unburned = rollup(&vault, 5, 50_000);
+    let mut finality = FinalizedRoots::default();
+    finality.finalize(&unburned.current_state_root);
+    let forged = PegOutSettlement {
+        state_root: unburned.current_state_root.clone(),
+        balance_proof: burned_settlement.balance_proof.clone(),
+    };
+    let err = vault.unbridge_from_rollup(&forged, &finality).unwrap_err();
+    assert!(matches!(err, DeFiHubError::BridgeVerification(ref reason) if reason.contains("empty rollup balance")), "{:?}", err);
+
+    // 잔액이 남은 상태에서 만든 증명은 빈 잔액을 보이지 못한다
+    let forged = PegOutSettlement {
+        state_root: unburned.current_state_root.clone(),
+        balance_proof: PegOutSettlement::from_state(&unburned, &vault.owner_key).balance_proof,
+    };
+    assert!(vault.unbridge_from_rollup(&forged, &finality).is_err());
+
+    // 다른 소유자의 소각 증명은 이 금고에 쓸 수 없다
+    let mut other = funded_vault();
+    other.owner_key = recoverable_vault().recovery_key.unwrap();
+    assert_ne!(other.owner_key, vault.owner_key);
+    let mut state = rollup(&other, 6, 0);
+    state.set_balance(account_address(&vault.owner_key), TokenType::WBTC, 50_000);
+    state.current_state_root.hash = state.state_hash();
+    let foreign = PegOutSettlement::from_state(&state, &other.owner_key);
+    let mut finality = FinalizedRoots::default();
+    finality.finalize(&foreign.state_root);
+    assert!(vault.unbridge_from_rollup(&foreign, &finality).is_err());
+    assert!(matches!(vault.state, VaultState::Bridged { .. }));
+
+    // 직렬화한 정산도 같은 출처로 검증된다
+    let (settlement, finality) = burned(&vault, 7);
+    let decoded: PegOutSettlement = serde_json::from_str(&serde_json::to_string(&settlement).unwrap()).unwrap();
+    vault.unbridge_from_rollup(&decoded, &finality).unwrap();
+    assert_eq!(vault.state, VaultState::Inactive);
+}
+
+#[test]
+fn every_transition_is_checked_from_every_state() {
+    let states = [
+        VaultState::Inactive,
+        VaultState::Triggered {
+            withdrawal_address: destination().to_string(),
+            amount: Amount::from_sat(1_000),
+            trigger_time: Utc::now(),
+            trigger_height: TRIGGER_HEIGHT,
+            timelock_blocks: 10,
+        },
+        VaultState::Completed,
+        VaultState::Recovered {
+            recovery_address: destination().to_string(),
+            amount: Amount::from_sat(1_000),
+            recovery_time: Utc::now(),
+        },
+        VaultState::Bridged {
+            rollup_state_root: state_root(1),
+            last_sync: Utc::now(),
+        },
+    ];
+    for state in &states {
+        for transition in VaultTransition::ALL {
+            match transition.check(state) {
+                Ok(()) => assert!(transition.allowed_from().contains(&state.name())),
+                Err(DeFiHubError::InvalidVaultState { current, expected }) => {
+                    assert!(current.starts_with(state.name()));
+                    assert_eq!(expected, transition.allowed_from().join(" or "));
+                }
+                Err(other) => panic!("unexpected error {:?}", other),
+            }
+        }
+    }
+
+    // 롤업 연동은 Inactive에서만, 연동 중에는 L1 출금·회수 불가
+    let mut vault = recoverable_vault();
+    vault.trigger_withdrawal(destination(), vault.amount, &funding(2, 20_000), &FixedHeight(TRIGGER_HEIGHT)).unwrap();
+    assert!(matches!(vault.bridge_to_rollup(state_root(1)), Err(DeFiHubError::InvalidVaultState { .. })));
+    vault.cancel_withdrawal(&FixedHeight(TRIGGER_HEIGHT + 1), &funding(3, 20_000)).unwrap();
+    vault.bridge_to_rollup(state_root(1)).unwrap();
+    assert!(!vault.can_withdraw());
+    assert!(matches!(vault.recover(&FixedHeight(TRIGGER_HEIGHT + 1), &funding(4, 20_000)), Err(DeFiHubError::InvalidVaultState { .. })));
+    assert!(matches!(vault.bridge_to_rollup(state_root(2)), Err(DeFiHubError::InvalidVaultState { .. })));
+}
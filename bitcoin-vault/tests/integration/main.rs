@@ -0,0 +1,24 @@
+//! bitcoin-vault 통합 테스트
+//!
+//! 실행: `cargo test -p bitcoin-vault --test integration`
+
+mod covenant;
+mod deposits;
+mod descriptor;
+mod encryption;
+mod fee_bump;
+mod history;
+mod interpreter;
+mod keystore;
+mod lifecycle;
+mod manager;
+mod musig;
+mod network;
+mod partial;
+mod persistence;
+mod psbt;
+mod rate_limit;
+mod recovery;
+mod signature;
+mod timelock;
+mod watchtower;
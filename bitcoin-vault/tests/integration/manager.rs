@@ -0,0 +1,168 @@
+use super::psbt::{destination, funding, owner, TRIGGER_HEIGHT, VAULT_AMOUNT};
+use bitcoin::hashes::Hash;
+use bitcoin::{Amount, Network, OutPoint, Txid};
+use bitcoin_vault::manager::VAULTS_DIR;
+use bitcoin_vault::*;
+use shared::persistence::BACKUP_COUNT;
+use shared::{DeFiHubError, VaultState};
+use std::thread;
+
+fn outpoint(tag: u8) -> OutPoint {
+    OutPoint::new(Txid::from_byte_array([tag; 32]), 0)
+}
+
+fn deposited(owner: &str, tag: u8) -> BitcoinVault {
+    let mut vault = BitcoinVault::new(Network::Regtest, 10, owner.to_string()).unwrap();
+    vault.record_deposit(outpoint(tag), Amount::from_sat(VAULT_AMOUNT)).unwrap();
+    vault
+}
+
+fn owner_hex() -> String {
+    owner().x_only_public_key().0.to_string()
+}
+
+#[test]
+fn create_load_and_filter() {
+    let dir = tempfile::tempdir().unwrap();
+    let manager = VaultManager::open(dir.path()).unwrap();
+
+    manager.create(&deposited("alice", 1)).unwrap();
+    manager.create(&deposited("alice", 2)).unwrap();
+    manager.create(&deposited("bob", 3)).unwrap();
+    manager
+        .update(&outpoint(3), |vault| {
+            vault.update_state(VaultState::Completed);
+            Ok(())
+        })
+        .unwrap();
+
+    assert_eq!(manager.load(&outpoint(2)).unwrap().owner, "alice");
+    assert_eq!(manager.list(&VaultFilter::default()).unwrap().len(), 3);
+
+    let alice = manager.list(&VaultFilter::default().owner("alice")).unwrap();
+    assert_eq!(alice.iter().map(|vault| vault.id).collect::<Vec<_>>(), vec![outpoint(1), outpoint(2)]);
+
+    let script = manager.load(&outpoint(3)).unwrap().address.script_pubkey();
+    let same_covenant = manager.list(&VaultFilter::default().script_pubkey(script)).unwrap();
+    assert_eq!(same_covenant.iter().map(|vault| vault.id).collect::<Vec<_>>(), vec![outpoint(3)]);
+
+    let completed = manager.list(&VaultFilter::default().state("completed")).unwrap();
+    assert_eq!(completed.len(), 1);
+    assert_eq!(completed[0].id, outpoint(3));
+    assert!(manager.list(&VaultFilter::default().owner("bob").state("Inactive")).unwrap().is_empty());
+
+    let state = manager.global_state();
+    let state = state.read().unwrap();
+    assert_eq!(state.vaults.len(), 3);
+    assert_eq!(state.vaults[&outpoint(3)].state, VaultState::Completed);
+}
+
+#[test]
+fn duplicate_and_unfunded_vaults_are_rejected() {
+    let dir = tempfile::tempdir().unwrap();
+    let manager = VaultManager::open(dir.path()).unwrap();
+
+    manager.create(&deposited("alice", 1)).unwrap();
+    assert!(matches!(
+        manager.create(&deposited("alice", 1)),
+        Err(DeFiHubError::VaultAlreadyExists(_))
+    ));
+
+    let unfunded = BitcoinVault::new(Network::Regtest, 10, "alice".to_string()).unwrap();
+    assert!(manager.create(&unfunded).is_err());
+    assert!(matches!(manager.load(&outpoint(9)), Err(DeFiHubError::VaultNotFound)));
+}
+
+#[test]
+fn trigger_moves_vault_to_new_outpoint() {
+    let dir = tempfile::tempdir().unwrap();
+    let manager = VaultManager::open(dir.path()).unwrap();
+    manager.create(&deposited(&owner_hex(), 1)).unwrap();
+
+    let psbt = manager
+        .update(&outpoint(1), |vault| {
+            vault.trigger_withdrawal(
+                destination(),
+                Amount::from_sat(VAULT_AMOUNT),
+                &funding(2, 20_000),
+                &FixedHeight(TRIGGER_HEIGHT),
+            )
+        })
+        .unwrap();
+    let new_id = OutPoint::new(psbt.unsigned_tx.txid(), 0);
+
+    assert!(matches!(manager.load(&outpoint(1)), Err(DeFiHubError::VaultNotFound)));
+    let vault = manager.load(&new_id).unwrap();
+    assert!(matches!(vault.state, VaultState::Triggered { .. }));
+
+    let state = manager.global_state();
+    let state = state.read().unwrap();
+    assert_eq!(state.vaults.keys().collect::<Vec<_>>(), vec![&new_id]);
+}
+
+#[test]
+fn failed_update_changes_nothing() {
+    let dir = tempfile::tempdir().unwrap();
+    let manager = VaultManager::open(dir.path()).unwrap();
+    manager.create(&deposited(&owner_hex(), 1)).unwrap();
+
+    let result = manager.update(&outpoint(1), |vault| {
+        vault.amount = Amount::ZERO;
+        vault.complete_withdrawal(&FixedHeight(TRIGGER_HEIGHT), &funding(3, 20_000))
+    });
+    assert!(matches!(result, Err(DeFiHubError::InvalidVaultState { .. })));
+    assert_eq!(manager.load(&outpoint(1)).unwrap().amount, Amount::from_sat(VAULT_AMOUNT));
+}
+
+#[test]
+fn sync_picks_up_changes_from_other_handles() {
+    let dir = tempfile::tempdir().unwrap();
+    let daemon = VaultManager::open(dir.path()).unwrap();
+    let cli = VaultManager::open(dir.path()).unwrap();
+
+    cli.create(&deposited("alice", 1)).unwrap();
+    cli.create(&deposited("alice", 2)).unwrap();
+    assert!(daemon.global_state().read().unwrap().vaults.is_empty());
+
+    daemon.sync().unwrap();
+    assert_eq!(daemon.global_state().read().unwrap().vaults.len(), 2);
+
+    cli.remove(&outpoint(1)).unwrap();
+    daemon.sync().unwrap();
+    let state = daemon.global_state();
+    let state = state.read().unwrap();
+    assert_eq!(state.vaults.keys().collect::<Vec<_>>(), vec![&outpoint(2)]);
+}
+
+#[test]
+fn concurrent_updates_are_not_lost() {
+    let dir = tempfile::tempdir().unwrap();
+    VaultManager::open(dir.path()).unwrap().create(&deposited("alice", 1)).unwrap();
+
+    // 각 스레드가 별도의 관리자(= 별도의 잠금 파일 핸들)로 같은 금고를 수정한다
+    let handles: Vec<_> = (0..8)
+        .map(|_| {
+            let path = dir.path().to_path_buf();
+            thread::spawn(move || {
+                let manager = VaultManager::open(&path).unwrap();
+                for _ in 0..10 {
+                    manager
+                        .update(&outpoint(1), |vault| {
+                            vault.amount += Amount::from_sat(1);
+                            Ok(())
+                        })
+                        .unwrap();
+                    manager.list(&VaultFilter::default()).unwrap();
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    let manager = VaultManager::open(dir.path()).unwrap();
+    assert_eq!(manager.load(&outpoint(1)).unwrap().amount, Amount::from_sat(VAULT_AMOUNT + 80));
+    // 잠금 파일, 금고 파일, 백업만 남고 임시 파일은 없다
+    assert_eq!(std::fs::read_dir(dir.path().join(VAULTS_DIR)).unwrap().count(), 2 + BACKUP_COUNT);
+}
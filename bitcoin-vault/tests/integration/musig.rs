@@ -0,0 +1,184 @@
+use super::interpreter::sign_fee_inputs;
+use super::psbt::{destination, funding, TRIGGER_HEIGHT, VAULT_AMOUNT};
+use bitcoin::hashes::Hash;
+use bitcoin::psbt::Psbt;
+use bitcoin::secp256k1::{Keypair, SecretKey};
+use bitcoin::{Amount, Network, OutPoint, TxOut, Txid, XOnlyPublicKey};
+use bitcoin_vault::musig::{aggregate_key, load_json, save_json};
+use bitcoin_vault::*;
+use shared::{DeFiHubError, VaultState};
+use std::path::Path;
+
+fn cosigner(tag: u8) -> Keypair {
+    Keypair::from_secret_key(&SECP, &SecretKey::from_slice(&[tag; 32]).unwrap())
+}
+
+fn treasury() -> Vec<Keypair> {
+    vec![cosigner(21), cosigner(22), cosigner(23)]
+}
+
+fn keys(keypairs: &[Keypair]) -> Vec<XOnlyPublicKey> {
+    keypairs.iter().map(|keypair| keypair.x_only_public_key().0).collect()
+}
+
+fn treasury_vault() -> BitcoinVault {
+    let mut vault = BitcoinVault::new(Network::Regtest, 10, "treasury".to_string())
+        .unwrap()
+        .with_cosigners(&keys(&treasury()))
+        .unwrap();
+    vault
+        .record_deposit(OutPoint::new(Txid::from_byte_array([1; 32]), 1), Amount::from_sat(VAULT_AMOUNT))
+        .unwrap();
+    vault
+}
+
+/// 공동 소유자마다 별도 디렉토리(기기)를 두고 파일만 주고받아 서명
+fn sign_over_files(vault: &BitcoinVault, psbt: &Psbt, signers: &[Keypair], exchange: &Path) -> Psbt {
+    save_json(exchange.join("session.json"), &vault.musig_session(psbt).unwrap()).unwrap();
+
+    // 1단계: 각 기기에서 논스 생성, 비밀 논스는 기기에 남긴다
+    let devices: Vec<_> = signers.iter().map(|_| tempfile::tempdir().unwrap()).collect();
+    for (index, (signer, device)) in signers.iter().zip(&devices).enumerate() {
+        let session: MusigSession = load_json(exchange.join("session.json")).unwrap();
+        let (share, secret) = session.generate_nonce(signer).unwrap();
+        save_json(exchange.join(format!("nonce-{}.json", index)), &share).unwrap();
+        save_json(device.path().join("secret-nonce.json"), &secret).unwrap();
+    }
+    let nonces: Vec<NonceShare> = (0..signers.len())
+        .map(|index| load_json(exchange.join(format!("nonce-{}.json", index))).unwrap())
+        .collect();
+
+    // 2단계: 모든 공개 논스를 받아 부분 서명
+    for (index, (signer, device)) in signers.iter().zip(&devices).enumerate() {
+        let session: MusigSession = load_json(exchange.join("session.json")).unwrap();
+        let secret = SecretNonce::take(device.path().join("secret-nonce.json")).unwrap();
+        let partial = session.partial_sign(signer, secret, &nonces).unwrap();
+        save_json(exchange.join(format!("partial-{}.json", index)), &partial).unwrap();
+        assert!(!device.path().join("secret-nonce.json").exists());
+    }
+    let partials: Vec<PartialSignatureShare> = (0..signers.len())
+        .map(|index| load_json(exchange.join(format!("partial-{}.json", index))).unwrap())
+        .collect();
+
+    // 3단계: 코디네이터가 합쳐 PSBT에 넣는다 (제출 순서는 상관없다)
+    let session: MusigSession = load_json(exchange.join("session.json")).unwrap();
+    let mut reversed = partials;
+    reversed.reverse();
+    session.combine(&nonces, &reversed).unwrap()
+}
+
+fn finalize(mut psbt: Psbt) -> (bitcoin::Transaction, Vec<TxOut>) {
+    sign_fee_inputs(&mut psbt);
+    let prevouts = psbt.inputs.iter().map(|input| input.witness_utxo.clone().unwrap()).collect();
+    finalize_vault_input(&mut psbt, 0).unwrap();
+    (psbt.extract_tx_unchecked_fee_rate(), prevouts)
+}
+
+#[test]
+fn cosigners_aggregate_into_the_owner_key() {
+    let cosigners = keys(&treasury());
+    let vault = treasury_vault();
+    let mut shuffled = cosigners.clone();
+    shuffled.rotate_left(1);
+
+    assert_eq!(vault.owner_key, aggregate_key(&cosigners).unwrap());
+    assert_eq!(aggregate_key(&shuffled).unwrap(), vault.owner_key);
+    assert!(cosigners.iter().all(|key| vault.cosigners.contains(key)));
+    assert_eq!(vault.address, vault.covenant().address(Network::Regtest).unwrap());
+    assert_eq!(vault.covenant().signing_key(VaultLeaf::Trigger), Some(vault.owner_key));
+    assert_eq!(vault.covenant().spend_info().unwrap().internal_key(), nums_internal_key());
+
+    // 다른 구성은 다른 주소가 된다
+    let pair = BitcoinVault::new(Network::Regtest, 10, "treasury".to_string())
+        .unwrap()
+        .with_cosigners(&cosigners[..2])
+        .unwrap();
+    assert_ne!(pair.address, vault.address);
+
+    assert!(matches!(aggregate_key(&cosigners[..1]), Err(DeFiHubError::Musig(_))));
+    assert!(matches!(aggregate_key(&[cosigners[0], cosigners[0]]), Err(DeFiHubError::Musig(_))));
+
+    // 단일 키로 바꾸면 공동 소유자 목록도 지운다
+    let single = pair.with_owner_key(cosigners[0]).unwrap();
+    assert!(single.cosigners.is_empty());
+}
+
+#[test]
+fn trigger_and_cancel_verify_with_aggregate_signature() {
+    let exchange = tempfile::tempdir().unwrap();
+    let mut vault = treasury_vault();
+
+    let trigger = vault
+        .trigger_withdrawal(destination(), Amount::from_sat(VAULT_AMOUNT), &funding(2, 20_000), &FixedHeight(TRIGGER_HEIGHT))
+        .unwrap();
+    let signed = sign_over_files(&vault, &trigger, &treasury(), exchange.path());
+    let (tx, prevouts) = finalize(signed);
+    TapscriptInterpreter::new(&tx, 0, &prevouts).verify().unwrap();
+    assert!(matches!(vault.state, VaultState::Triggered { .. }));
+
+    let cancel = vault.cancel_withdrawal(&FixedHeight(TRIGGER_HEIGHT + 1), &funding(3, 20_000)).unwrap();
+    let signed = sign_over_files(&vault, &cancel, &treasury(), exchange.path());
+    let (tx, prevouts) = finalize(signed);
+    TapscriptInterpreter::new(&tx, 0, &prevouts).verify().unwrap();
+    assert_eq!(vault.state, VaultState::Inactive);
+}
+
+#[test]
+fn invalid_shares_are_rejected() {
+    let mut vault = treasury_vault();
+    let trigger = vault
+        .trigger_withdrawal(destination(), Amount::from_sat(VAULT_AMOUNT), &funding(2, 20_000), &FixedHeight(TRIGGER_HEIGHT))
+        .unwrap();
+    let signers = treasury();
+    let session = vault.musig_session(&trigger).unwrap();
+
+    // 공동 소유자가 아닌 키는 참여할 수 없다
+    let stranger = cosigner(24);
+    assert!(matches!(session.generate_nonce(&stranger), Err(DeFiHubError::Musig(_))));
+
+    let (nonces, secrets): (Vec<_>, Vec<_>) = signers.iter().map(|signer| session.generate_nonce(signer).unwrap()).unzip();
+    let mut secrets = secrets.into_iter();
+
+    // 논스가 빠지거나 다른 세션의 것이면 서명하지 않는다
+    let first = secrets.next().unwrap();
+    let other = vault.musig_session(&trigger).unwrap();
+    assert!(session.partial_sign(&signers[0], first, &nonces[..2]).is_err());
+    let (_, first) = session.generate_nonce(&signers[0]).unwrap();
+    assert!(other.partial_sign(&signers[0], first, &nonces).is_err());
+
+    // 비밀 논스와 공개 논스가 어긋나도 거부
+    let (_, mismatched) = session.generate_nonce(&signers[0]).unwrap();
+    assert!(session.partial_sign(&signers[0], mismatched, &nonces).is_err());
+
+    // 새 논스로 다시 시작해 부분 서명을 모은다
+    let (nonces, secrets): (Vec<_>, Vec<_>) = signers.iter().map(|signer| session.generate_nonce(signer).unwrap()).unzip();
+    let partials: Vec<_> = signers
+        .iter()
+        .zip(secrets)
+        .map(|(signer, secret)| session.partial_sign(signer, secret, &nonces).unwrap())
+        .collect();
+
+    // 다른 사람의 부분 서명을 자기 것처럼 내면 검증에서 걸린다
+    let mut forged = partials.clone();
+    forged[1].partial_signature = partials[2].partial_signature.clone();
+    match session.combine(&nonces, &forged) {
+        Err(DeFiHubError::Musig(reason)) => assert!(reason.contains("invalid partial signature"), "{}", reason),
+        other => panic!("expected invalid partial signature, got {:?}", other.map(|_| ())),
+    }
+    assert!(session.combine(&nonces, &partials[..2]).is_err());
+    session.combine(&nonces, &partials).unwrap();
+
+    // 비밀 논스 파일은 한 번 읽으면 사라진다
+    let device = tempfile::tempdir().unwrap();
+    let path = device.path().join("secret-nonce.json");
+    let (_, secret) = session.generate_nonce(&signers[0]).unwrap();
+    save_json(&path, &secret).unwrap();
+    SecretNonce::take(&path).unwrap();
+    assert!(SecretNonce::take(&path).is_err());
+    assert!(!format!("{:?}", secret).contains("secret:"));
+
+    // 단일 소유자 금고는 세션을 만들 수 없고, 다른 구성의 세션도 거부한다
+    let single = super::psbt::funded_vault();
+    assert!(matches!(single.musig_session(&trigger), Err(DeFiHubError::Musig(_))));
+    assert!(MusigSession::new(&trigger, 0, &keys(&signers)[..2]).is_err());
+}
@@ -0,0 +1,100 @@
+use super::psbt::{funded_vault, owner};
+use bitcoin::hashes::Hash;
+use bitcoin::{Amount, Network, OutPoint, Txid};
+use bitcoin_vault::*;
+use shared::bridge::FractalBitcoinBridge;
+use shared::persistence::{Versioned, SCHEMA_VERSION_KEY};
+use shared::{ChainNetwork, DeFiHubError, DEFAULT_TIMELOCK_BLOCKS};
+
+#[test]
+fn network_params_match_each_chain() {
+    for network in ChainNetwork::ALL {
+        let params = network.params();
+        assert_eq!(network.to_string().parse::<ChainNetwork>().unwrap(), network);
+        assert_eq!(serde_json::to_value(network).unwrap(), params.name);
+        assert!(params.default_confirmations > 0);
+    }
+    assert_eq!("mainnet".parse::<ChainNetwork>().unwrap(), ChainNetwork::Bitcoin);
+    assert!(matches!("litecoin".parse::<ChainNetwork>(), Err(DeFiHubError::UnsupportedChain(_))));
+
+    // 예전 설정/금고 파일의 bitcoin::Network 이름을 그대로 읽는다
+    for network in [Network::Bitcoin, Network::Testnet, Network::Signet, Network::Regtest] {
+        let legacy: ChainNetwork = serde_json::from_value(serde_json::to_value(network).unwrap()).unwrap();
+        assert_eq!(legacy, ChainNetwork::from(network));
+        assert_eq!(legacy.bitcoin_network(), network);
+    }
+
+    let fractal = ChainNetwork::Fractal.params();
+    assert_eq!(fractal.bitcoin_network, Network::Bitcoin);
+    assert_eq!((fractal.hrp, fractal.magic), (ChainNetwork::Bitcoin.params().hrp, ChainNetwork::Bitcoin.params().magic));
+    assert_eq!(fractal.block_interval_secs, 30);
+    assert!(fractal.op_cat && ChainNetwork::Inquisition.params().op_cat);
+    assert!(!ChainNetwork::Signet.params().op_cat && !ChainNetwork::Bitcoin.params().op_cat);
+    assert_eq!(ChainNetwork::Inquisition.params().magic, ChainNetwork::Signet.params().magic);
+    assert_eq!(ChainNetwork::Regtest.params().hrp, "bcrt");
+}
+
+#[test]
+fn timelock_math_follows_block_interval() {
+    // Bitcoin 20블록(200분)은 Fractal 30초 블록으로 400블록이다
+    assert_eq!(ChainNetwork::Bitcoin.default_timelock_blocks(), DEFAULT_TIMELOCK_BLOCKS);
+    assert_eq!(ChainNetwork::Fractal.default_timelock_blocks(), 400);
+    assert_eq!(ChainNetwork::Fractal.blocks_for(3 * 60), 6);
+    assert_eq!(ChainNetwork::Fractal.blocks_for(31), 2);
+    assert_eq!(ChainNetwork::Bitcoin.blocks_for(0), 0);
+    assert_eq!(ChainNetwork::Fractal.duration_secs(6), 180);
+}
+
+#[test]
+fn fractal_vaults_use_mainnet_addresses_and_fractal_confirmations() {
+    let owner_key = owner().x_only_public_key().0.to_string();
+    let fractal = BitcoinVault::new(ChainNetwork::Fractal, 400, owner_key.clone()).unwrap();
+    let mainnet = BitcoinVault::new(Network::Bitcoin, 400, owner_key).unwrap();
+    assert_eq!(fractal.chain, ChainNetwork::Fractal);
+    assert_eq!(fractal.network, Network::Bitcoin);
+    assert_eq!(fractal.address, mainnet.address);
+    assert!(fractal.address.to_string().starts_with("bc1p"));
+
+    // 확정 잔액은 네트워크 기본 확인 수 이상인 UTXO만 센다
+    let mut vault = funded_vault();
+    assert_eq!(vault.chain, ChainNetwork::Regtest);
+    assert_eq!(vault.confirmed_amount(), Amount::ZERO);
+    let second = OutPoint::new(Txid::from_byte_array([9; 32]), 0);
+    vault.record_deposit(second, Amount::from_sat(5_000)).unwrap();
+    vault.confirm_deposit(second, 100, &FixedHeight(100)).unwrap();
+    assert_eq!(vault.confirmed_amount(), Amount::from_sat(5_000));
+}
+
+#[test]
+fn fractal_bridge_reads_network_params() {
+    let bridge = FractalBitcoinBridge::new("http://localhost".to_string(), ChainNetwork::Fractal).unwrap();
+    assert_eq!(bridge.min_confirmations, 6);
+    assert_eq!(bridge.finality_secs(), 180);
+    assert_eq!(bridge.with_min_confirmations(20).finality_secs(), 600);
+    assert!(matches!(
+        FractalBitcoinBridge::new("http://localhost".to_string(), ChainNetwork::Bitcoin),
+        Err(DeFiHubError::UnsupportedChain(_))
+    ));
+    ChainNetwork::Fractal.require_op_cat().unwrap();
+    assert!(ChainNetwork::Bitcoin.require_op_cat().is_err());
+}
+
+#[test]
+fn vaults_saved_before_chain_field_migrate_from_network() {
+    let vault = funded_vault();
+    let mut json = serde_json::to_value(&vault).unwrap();
+    json.as_object_mut().unwrap().remove("chain");
+    json[SCHEMA_VERSION_KEY] = 2.into();
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("vault.json");
+    std::fs::write(&path, json.to_string()).unwrap();
+    let loaded = BitcoinVault::load_from_file(&path).unwrap();
+    assert_eq!(loaded.chain, ChainNetwork::Regtest);
+    assert_eq!(loaded.address, vault.address);
+
+    loaded.save_to_file(&path).unwrap();
+    let saved: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(saved[SCHEMA_VERSION_KEY], BitcoinVault::SCHEMA_VERSION);
+    assert_eq!(saved["chain"], "regtest");
+}
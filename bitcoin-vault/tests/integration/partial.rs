@@ -0,0 +1,158 @@
+use super::psbt::{assert_ground, destination, funded_vault, funding, sign_owner, witness_elements, TRIGGER_HEIGHT, VAULT_AMOUNT};
+use bitcoin::hashes::Hash;
+use bitcoin::{Amount, Network, OutPoint, Txid};
+use bitcoin_vault::script::{amount_parts, MAX_PARTIAL_VAULT_AMOUNT};
+use bitcoin_vault::*;
+use shared::{DeFiHubError, VaultState};
+
+const WITHDRAWAL: u64 = 30_000;
+
+#[test]
+fn partial_trigger_records_amount_in_marker() {
+    let mut vault = funded_vault();
+    let vault_spk = vault.address.script_pubkey();
+
+    let mut psbt = vault
+        .trigger_withdrawal(destination(), Amount::from_sat(WITHDRAWAL), &funding(2, 20_000), &FixedHeight(TRIGGER_HEIGHT))
+        .unwrap();
+    let tx = psbt.unsigned_tx.clone();
+
+    // 금고 전액이 그대로 재잠기고 마커에 출금 금액이 기록된다
+    assert_eq!(tx.output[0].value, Amount::from_sat(VAULT_AMOUNT));
+    assert_eq!(tx.output[0].script_pubkey, vault_spk);
+    assert_eq!(
+        tx.output[1].script_pubkey,
+        partial_target_marker(&destination().script_pubkey(), Amount::from_sat(WITHDRAWAL)).unwrap()
+    );
+    assert_eq!(trigger_target(&tx).unwrap(), destination().script_pubkey());
+    assert_eq!(trigger_withdrawal_amount(&tx).unwrap(), Some(Amount::from_sat(WITHDRAWAL)));
+    assert!(matches!(vault.state, VaultState::Triggered { amount, .. } if amount == Amount::from_sat(WITHDRAWAL)));
+    assert_eq!(vault.amount, Amount::from_sat(VAULT_AMOUNT));
+    assert_ground(&psbt);
+
+    let leaf_script = vault.covenant().leaf_script(VaultLeaf::PartialTrigger);
+    assert!(psbt.inputs[0].tap_scripts.values().any(|(script, _)| *script == leaf_script));
+    sign_owner(&mut psbt);
+    finalize_vault_input(&mut psbt, 0).unwrap();
+    assert_eq!(psbt.inputs[0].final_script_witness.as_ref().unwrap().len(), 13);
+}
+
+#[test]
+fn partial_complete_relocks_change_into_vault() {
+    let mut vault = funded_vault();
+    let vault_spk = vault.address.script_pubkey();
+    let trigger = vault
+        .trigger_withdrawal(destination(), Amount::from_sat(WITHDRAWAL), &funding(2, 20_000), &FixedHeight(TRIGGER_HEIGHT))
+        .unwrap();
+
+    let mut psbt = vault.complete_withdrawal(&FixedHeight(TRIGGER_HEIGHT + 10), &funding(3, 20_000)).unwrap();
+    let tx = psbt.unsigned_tx.clone();
+
+    assert_eq!(tx.input[0].previous_output, OutPoint::new(trigger.unsigned_tx.txid(), 0));
+    assert_eq!(tx.output[0].value, Amount::from_sat(WITHDRAWAL));
+    assert_eq!(tx.output[0].script_pubkey, destination().script_pubkey());
+    assert_eq!(tx.output[1].value, Amount::from_sat(VAULT_AMOUNT - WITHDRAWAL));
+    assert_eq!(tx.output[1].script_pubkey, vault_spk);
+    assert!(psbt.outputs[1].tap_tree.is_some());
+    assert_ground(&psbt);
+
+    // 잔돈 출력이 새 금고 UTXO가 된다
+    assert_eq!(vault.state, VaultState::Inactive);
+    assert_eq!(vault.id, OutPoint::new(tx.txid(), 1));
+    assert_eq!(vault.amount, Amount::from_sat(VAULT_AMOUNT - WITHDRAWAL));
+    assert!(vault.trigger_tx.is_none());
+    assert!(vault.is_active());
+
+    // 증인의 금액 조각은 세 출력 금액과 일치한다
+    let elements = witness_elements(&psbt);
+    let parts = |sats: u64| {
+        let (num, pad) = amount_parts(sats);
+        vec![num, pad]
+    };
+    assert_eq!(elements[10..12], parts(VAULT_AMOUNT - WITHDRAWAL)[..]);
+    assert_eq!(elements[12..14], parts(WITHDRAWAL)[..]);
+    assert_eq!(elements[14..16], parts(VAULT_AMOUNT)[..]);
+
+    assert!(psbt.inputs[0].tap_key_origins.is_empty());
+    finalize_vault_input(&mut psbt, 0).unwrap();
+    assert_eq!(psbt.inputs[0].final_script_witness.as_ref().unwrap().len(), 18);
+}
+
+#[test]
+fn change_can_be_withdrawn_again() {
+    let mut vault = funded_vault();
+    vault
+        .trigger_withdrawal(destination(), Amount::from_sat(WITHDRAWAL), &funding(2, 20_000), &FixedHeight(TRIGGER_HEIGHT))
+        .unwrap();
+    vault.complete_withdrawal(&FixedHeight(TRIGGER_HEIGHT + 10), &funding(3, 20_000)).unwrap();
+    let change_outpoint = vault.id;
+
+    let remaining = Amount::from_sat(VAULT_AMOUNT - WITHDRAWAL);
+    let trigger = vault
+        .trigger_withdrawal(destination(), remaining, &funding(4, 20_000), &FixedHeight(TRIGGER_HEIGHT + 20))
+        .unwrap();
+    assert_eq!(trigger.unsigned_tx.input[0].previous_output, change_outpoint);
+    assert_eq!(trigger_withdrawal_amount(&trigger.unsigned_tx).unwrap(), None);
+
+    let complete = vault.complete_withdrawal(&FixedHeight(TRIGGER_HEIGHT + 30), &funding(5, 20_000)).unwrap();
+    assert_eq!(complete.unsigned_tx.output[0].value, remaining);
+    assert_eq!(vault.state, VaultState::Completed);
+}
+
+#[test]
+fn cancel_relocks_full_amount_after_partial_trigger() {
+    let mut vault = funded_vault();
+    vault
+        .trigger_withdrawal(destination(), Amount::from_sat(WITHDRAWAL), &funding(2, 20_000), &FixedHeight(TRIGGER_HEIGHT))
+        .unwrap();
+
+    let psbt = vault.cancel_withdrawal(&FixedHeight(TRIGGER_HEIGHT + 1), &funding(3, 20_000)).unwrap();
+    assert_eq!(psbt.unsigned_tx.output[0].value, Amount::from_sat(VAULT_AMOUNT));
+    assert_eq!(vault.amount, Amount::from_sat(VAULT_AMOUNT));
+    assert_eq!(vault.state, VaultState::Inactive);
+}
+
+#[test]
+fn dust_and_oversized_partial_withdrawals_are_rejected() {
+    let mut vault = funded_vault();
+    let dust_change = Amount::from_sat(VAULT_AMOUNT - 100);
+    let err = vault
+        .trigger_withdrawal(destination(), dust_change, &funding(2, 20_000), &FixedHeight(TRIGGER_HEIGHT))
+        .unwrap_err();
+    assert!(matches!(err, DeFiHubError::BitcoinTransaction(_)));
+    assert!(vault
+        .trigger_withdrawal(destination(), Amount::from_sat(100), &funding(2, 20_000), &FixedHeight(TRIGGER_HEIGHT))
+        .is_err());
+    let err = vault
+        .trigger_withdrawal(destination(), Amount::ZERO, &funding(2, 20_000), &FixedHeight(TRIGGER_HEIGHT))
+        .unwrap_err();
+    assert!(matches!(err, DeFiHubError::InvalidAmount(_)), "{:?}", err);
+    assert_eq!(vault.state, VaultState::Inactive);
+
+    // 스크립트 산술 범위를 넘는 금고는 전액 출금만 가능하다
+    let owner_key = vault.owner.clone();
+    let mut whale = BitcoinVault::new(Network::Regtest, 10, owner_key).unwrap();
+    whale
+        .record_deposit(OutPoint::new(Txid::from_byte_array([1; 32]), 1), Amount::from_sat(MAX_PARTIAL_VAULT_AMOUNT + 1))
+        .unwrap();
+    assert!(whale
+        .trigger_withdrawal(destination(), Amount::from_sat(WITHDRAWAL), &funding(2, 20_000), &FixedHeight(TRIGGER_HEIGHT))
+        .is_err());
+    whale
+        .trigger_withdrawal(destination(), whale.amount, &funding(2, 20_000), &FixedHeight(TRIGGER_HEIGHT))
+        .unwrap();
+}
+
+#[test]
+fn amount_parts_are_minimal_script_numbers() {
+    assert_eq!(amount_parts(1), (vec![0x01], vec![0; 7]));
+    assert_eq!(amount_parts(0x80), (vec![0x80, 0x00], vec![0; 6]));
+    assert_eq!(amount_parts(100_000), (vec![0xa0, 0x86, 0x01], vec![0; 5]));
+    assert_eq!(amount_parts(MAX_PARTIAL_VAULT_AMOUNT), (vec![0xff, 0xff, 0xff, 0x7f], vec![0; 4]));
+
+    for sats in [1, 0x80, 0xffff, 100_000, MAX_PARTIAL_VAULT_AMOUNT] {
+        let (mut num, pad) = amount_parts(sats);
+        num.extend(pad);
+        assert_eq!(num, sats.to_le_bytes());
+    }
+}
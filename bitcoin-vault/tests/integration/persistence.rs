@@ -0,0 +1,158 @@
+use super::psbt::{funded_vault, VAULT_AMOUNT};
+use bitcoin::Amount;
+use bitcoin_vault::manager::VAULTS_DIR;
+use bitcoin_vault::*;
+use shared::persistence::{backup_path, Versioned, BACKUP_COUNT, SCHEMA_VERSION_KEY};
+use shared::state::GlobalState;
+use shared::DeFiHubError;
+use std::path::Path;
+
+fn read_json(path: &Path) -> serde_json::Value {
+    serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+}
+
+/// 금액만 다른 금고를 차례로 저장
+fn save_amounts(path: &Path, amounts: &[u64]) {
+    let mut vault = funded_vault();
+    for amount in amounts {
+        vault.amount = Amount::from_sat(*amount);
+        vault.save_to_file(path).unwrap();
+    }
+}
+
+#[test]
+fn saved_files_carry_schema_version() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("vault.json");
+    let vault = funded_vault();
+    vault.save_to_file(&path).unwrap();
+
+    assert_eq!(read_json(&path)[SCHEMA_VERSION_KEY], BitcoinVault::SCHEMA_VERSION);
+    let loaded = BitcoinVault::load_from_file(&path).unwrap();
+    assert_eq!(loaded.id, vault.id);
+    assert_eq!(loaded.utxos, vault.utxos);
+    assert!(!backup_path(&path, 1).exists());
+
+    let state_path = dir.path().join("state.json");
+    let state = GlobalState::new();
+    state.save_to_file(state_path.to_str().unwrap()).unwrap();
+    assert_eq!(read_json(&state_path)[SCHEMA_VERSION_KEY], GlobalState::SCHEMA_VERSION);
+    GlobalState::load_from_file(state_path.to_str().unwrap()).unwrap();
+}
+
+#[test]
+fn unversioned_files_are_migrated() {
+    let dir = tempfile::tempdir().unwrap();
+    let vault = funded_vault();
+
+    // 버전 필드도 UTXO 목록도 없는 예전 금고 파일
+    let mut json = serde_json::to_value(&vault).unwrap();
+    json.as_object_mut().unwrap().remove("utxos");
+    let path = dir.path().join("vault.json");
+    std::fs::write(&path, json.to_string()).unwrap();
+    let restored = BitcoinVault::load_from_file(&path).unwrap();
+    assert_eq!(restored.utxos.len(), 1);
+    assert_eq!(restored.utxos[0].outpoint, vault.id);
+    assert_eq!(restored.utxos[0].amount, Amount::from_sat(VAULT_AMOUNT));
+
+    // 다시 저장하면 현재 버전이 된다
+    restored.save_to_file(&path).unwrap();
+    assert_eq!(read_json(&path)[SCHEMA_VERSION_KEY], BitcoinVault::SCHEMA_VERSION);
+    assert_eq!(read_json(&path)["utxos"].as_array().unwrap().len(), 1);
+
+    let state_path = dir.path().join("state.json");
+    std::fs::write(&state_path, serde_json::to_string(&GlobalState::new()).unwrap()).unwrap();
+    GlobalState::load_from_file(state_path.to_str().unwrap()).unwrap();
+}
+
+#[test]
+fn newer_schema_is_rejected() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("vault.json");
+    let mut json = serde_json::to_value(funded_vault()).unwrap();
+    json[SCHEMA_VERSION_KEY] = (BitcoinVault::SCHEMA_VERSION + 1).into();
+    std::fs::write(&path, json.to_string()).unwrap();
+
+    let err = BitcoinVault::load_from_file(&path).unwrap_err();
+    assert!(matches!(&err, DeFiHubError::Configuration(message) if message.contains("newer than supported")), "{}", err);
+
+    // 유효한 예전 백업이 있어도 더 새 파일을 덮어 읽지 않는다
+    std::fs::remove_file(&path).unwrap();
+    save_amounts(&path, &[1_000, 2_000]);
+    std::fs::write(&path, json.to_string()).unwrap();
+    assert!(BitcoinVault::load_from_file(backup_path(&path, 1)).is_ok());
+    let err = BitcoinVault::load_from_file(&path).unwrap_err();
+    assert!(matches!(&err, DeFiHubError::Configuration(message) if message.contains("newer than supported")), "{}", err);
+}
+
+#[test]
+fn corrupted_file_falls_back_to_latest_valid_backup() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("vault.json");
+    save_amounts(&path, &[1_000, 2_000, 3_000]);
+    assert_eq!(BitcoinVault::load_from_file(&path).unwrap().amount, Amount::from_sat(3_000));
+
+    // 쓰다 만 파일
+    let content = std::fs::read_to_string(&path).unwrap();
+    std::fs::write(&path, &content[..content.len() / 2]).unwrap();
+    assert_eq!(BitcoinVault::load_from_file(&path).unwrap().amount, Amount::from_sat(2_000));
+
+    // 최신 백업까지 깨졌으면 그다음 백업
+    std::fs::write(backup_path(&path, 1), b"garbage").unwrap();
+    assert_eq!(BitcoinVault::load_from_file(&path).unwrap().amount, Amount::from_sat(1_000));
+
+    // 깨진 파일은 백업으로 돌리지 않는다
+    save_amounts(&path, &[4_000]);
+    assert_eq!(std::fs::read(backup_path(&path, 1)).unwrap(), b"garbage");
+
+    // 모두 깨졌으면 원래 파일의 에러
+    for n in 1..=BACKUP_COUNT {
+        let _ = std::fs::remove_file(backup_path(&path, n));
+    }
+    std::fs::write(&path, b"{").unwrap();
+    let err = BitcoinVault::load_from_file(&path).unwrap_err();
+    assert!(err.to_string().contains("vault.json"), "{}", err);
+}
+
+#[test]
+fn backups_rotate_and_are_capped() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("vault.json");
+    save_amounts(&path, &[1_000, 2_000, 3_000, 4_000, 5_000, 6_000]);
+
+    for n in 1..=BACKUP_COUNT {
+        let backup = BitcoinVault::load_from_file(backup_path(&path, n)).unwrap();
+        assert_eq!(backup.amount, Amount::from_sat(6_000 - 1_000 * n as u64));
+    }
+    assert!(!backup_path(&path, BACKUP_COUNT + 1).exists());
+
+    // 임시 파일은 남지 않는다
+    let files = std::fs::read_dir(dir.path()).unwrap().count();
+    assert_eq!(files, 1 + BACKUP_COUNT);
+}
+
+#[test]
+fn manager_keeps_versioned_files_and_removes_backups() {
+    let dir = tempfile::tempdir().unwrap();
+    let manager = VaultManager::open(dir.path()).unwrap();
+    let vault = funded_vault();
+    manager.create(&vault).unwrap();
+    manager.update(&vault.id, |vault| {
+        vault.owner = "bob".to_string();
+        Ok(())
+    })
+    .unwrap();
+
+    let path = dir
+        .path()
+        .join(VAULTS_DIR)
+        .join(format!("{}_{}.json", vault.id.txid, vault.id.vout));
+    assert_eq!(read_json(&path)[SCHEMA_VERSION_KEY], BitcoinVault::SCHEMA_VERSION);
+    assert!(backup_path(&path, 1).exists());
+    assert_eq!(manager.list(&VaultFilter::default()).unwrap().len(), 1);
+
+    manager.remove(&vault.id).unwrap();
+    assert!(!path.exists());
+    assert!(!backup_path(&path, 1).exists());
+    assert!(matches!(manager.load(&vault.id), Err(DeFiHubError::VaultNotFound)));
+}
@@ -0,0 +1,248 @@
+use bitcoin::hashes::Hash;
+use bitcoin::psbt::Psbt;
+use bitcoin::secp256k1::{Keypair, Message, SecretKey};
+use bitcoin::sighash::{Prevouts, SighashCache, TapSighashType};
+use bitcoin::taproot::{self, LeafVersion, TapLeafHash};
+use bitcoin::{Address, Amount, Network, OutPoint, ScriptBuf, TxOut, Txid};
+use bitcoin_vault::signature::{covenant_challenge, covenant_sigmsg, tap_sighash};
+use bitcoin_vault::*;
+use shared::{DeFiHubError, VaultState};
+
+pub(super) const VAULT_AMOUNT: u64 = 100_000;
+pub(super) const TRIGGER_HEIGHT: u32 = 800_000;
+
+pub(super) fn owner() -> Keypair {
+    Keypair::from_secret_key(&SECP, &SecretKey::from_slice(&[7u8; 32]).unwrap())
+}
+
+fn fee_script() -> ScriptBuf {
+    let key = Keypair::from_secret_key(&SECP, &SecretKey::from_slice(&[9u8; 32]).unwrap());
+    ScriptBuf::new_p2tr(&SECP, key.x_only_public_key().0, None)
+}
+
+pub(super) fn funding(tag: u8, sats: u64) -> FeeFunding {
+    FeeFunding::new(
+        vec![FeeInput {
+            outpoint: OutPoint::new(Txid::from_byte_array([tag; 32]), 0),
+            txout: TxOut {
+                value: Amount::from_sat(sats),
+                script_pubkey: fee_script(),
+            },
+        }],
+        fee_script(),
+    )
+}
+
+pub(super) fn destination() -> Address {
+    Address::from_script(&fee_script(), Network::Regtest).unwrap()
+}
+
+pub(super) fn funded_vault() -> BitcoinVault {
+    let owner_key = owner().x_only_public_key().0.to_string();
+    let mut vault = BitcoinVault::new(Network::Regtest, 10, owner_key).unwrap();
+    vault
+        .record_deposit(OutPoint::new(Txid::from_byte_array([1; 32]), 1), Amount::from_sat(VAULT_AMOUNT))
+        .unwrap();
+    vault
+}
+
+/// PSBT 입력들의 이전 출력
+pub(super) fn prevouts(psbt: &Psbt) -> Vec<TxOut> {
+    psbt.inputs.iter().map(|input| input.witness_utxo.clone().unwrap()).collect()
+}
+
+/// 커버넌트 입력의 그라인딩 결과가 챌린지 조건을 만족하는지 확인
+pub(super) fn assert_ground(psbt: &Psbt) {
+    let (_, (script, _)) = psbt.inputs[0].tap_scripts.iter().next().unwrap();
+    let leaf_hash = TapLeafHash::from_script(script, LeafVersion::TapScript);
+    let prevout = psbt.inputs[0].witness_utxo.clone().unwrap();
+
+    let sigmsg = covenant_sigmsg(&psbt.unsigned_tx, 0, &prevout, leaf_hash).unwrap();
+    let challenge = covenant_challenge(&tap_sighash(&sigmsg));
+    assert_eq!(challenge[31], 0x00);
+
+    let e_prefix = witness_elements(psbt)[0].clone();
+    assert_eq!(e_prefix, challenge[..31].to_vec());
+}
+
+pub(super) fn witness_elements(psbt: &Psbt) -> Vec<Vec<u8>> {
+    psbt.inputs[0]
+        .proprietary
+        .iter()
+        .filter(|(key, _)| key.prefix == PSBT_PROPRIETARY_PREFIX && key.subtype == PSBT_WITNESS_ELEMENT)
+        .map(|(_, value)| value.clone())
+        .collect()
+}
+
+pub(super) fn sign_owner(psbt: &mut Psbt) {
+    let (_, (script, _)) = psbt.inputs[0].tap_scripts.iter().next().unwrap();
+    let leaf_hash = TapLeafHash::from_script(script, LeafVersion::TapScript);
+    let prevouts = prevouts(psbt);
+    let sighash = SighashCache::new(&psbt.unsigned_tx)
+        .taproot_script_spend_signature_hash(0, &Prevouts::All(&prevouts), leaf_hash, TapSighashType::Default)
+        .unwrap();
+    let sig = SECP.sign_schnorr_no_aux_rand(&Message::from_digest(sighash.to_byte_array()), &owner());
+
+    psbt.inputs[0].tap_script_sigs.insert(
+        (owner().x_only_public_key().0, leaf_hash),
+        taproot::Signature { sig, hash_ty: TapSighashType::Default },
+    );
+}
+
+#[test]
+fn trigger_psbt_relocks_and_marks_target() {
+    let mut vault = funded_vault();
+    let vault_spk = vault.address.script_pubkey();
+    let funded_outpoint = vault.id;
+
+    let psbt = vault
+        .trigger_withdrawal(destination(), Amount::from_sat(VAULT_AMOUNT), &funding(2, 20_000), &FixedHeight(TRIGGER_HEIGHT))
+        .unwrap();
+    let tx = &psbt.unsigned_tx;
+
+    assert_eq!(tx.input[0].previous_output, funded_outpoint);
+    assert_eq!(tx.output[0].value, Amount::from_sat(VAULT_AMOUNT));
+    assert_eq!(tx.output[0].script_pubkey, vault_spk);
+    assert_eq!(tx.output[1].value, Amount::ZERO);
+    assert_eq!(tx.output[1].script_pubkey, target_marker(&destination().script_pubkey()).unwrap());
+    assert_eq!(trigger_target(tx).unwrap(), destination().script_pubkey());
+    assert!(psbt.outputs[0].tap_tree.is_some());
+
+    assert_eq!(vault.id, OutPoint::new(tx.txid(), 0));
+    assert_eq!(vault.trigger_tx.as_ref(), Some(tx));
+    assert!(matches!(vault.state, VaultState::Triggered { .. }));
+    assert_ground(&psbt);
+}
+
+#[test]
+fn fee_matches_requested_rate() {
+    let mut vault = funded_vault();
+    let mut psbt = vault
+        .trigger_withdrawal(destination(), Amount::from_sat(VAULT_AMOUNT), &funding(2, 20_000).with_fee_rate(3), &FixedHeight(TRIGGER_HEIGHT))
+        .unwrap();
+    let fee = psbt.fee().unwrap();
+
+    // 잔돈 출력 = 수수료 입력 - 수수료 - 앵커
+    let change = psbt.unsigned_tx.output.last().unwrap();
+    assert_eq!(change.script_pubkey, fee_script());
+    assert_eq!(change.value + fee + anchor_output().value, Amount::from_sat(20_000));
+    assert_eq!(psbt.unsigned_tx.output[2], anchor_output());
+
+    // 서명 후 실제 크기 기준 수수료율
+    sign_owner(&mut psbt);
+    psbt.inputs[1].final_script_witness = Some(bitcoin::Witness::from_slice(&[vec![0u8; 64]]));
+    finalize_vault_input(&mut psbt, 0).unwrap();
+    let tx = psbt.extract_tx_unchecked_fee_rate();
+    assert_eq!(fee.to_sat(), tx.vsize() as u64 * 3);
+}
+
+#[test]
+fn dust_change_is_dropped() {
+    let mut vault = funded_vault();
+    let psbt = vault
+        .trigger_withdrawal(destination(), Amount::from_sat(VAULT_AMOUNT), &funding(2, 4_400), &FixedHeight(TRIGGER_HEIGHT))
+        .unwrap();
+
+    assert_eq!(psbt.unsigned_tx.output.len(), 3);
+    assert_eq!(psbt.fee().unwrap(), Amount::from_sat(4_400) - anchor_output().value);
+}
+
+#[test]
+fn insufficient_fee_inputs_are_rejected() {
+    let mut vault = funded_vault();
+    let result = vault.trigger_withdrawal(destination(), Amount::from_sat(VAULT_AMOUNT), &funding(2, 1_000), &FixedHeight(TRIGGER_HEIGHT));
+
+    assert!(matches!(result, Err(DeFiHubError::InsufficientFunds { available: 1_000, .. })));
+    assert_eq!(vault.state, VaultState::Inactive);
+}
+
+#[test]
+fn oversized_and_unfunded_triggers_are_rejected() {
+    let mut vault = funded_vault();
+    assert!(matches!(
+        vault.trigger_withdrawal(destination(), Amount::from_sat(VAULT_AMOUNT + 1), &funding(2, 20_000), &FixedHeight(TRIGGER_HEIGHT)),
+        Err(DeFiHubError::InsufficientFunds { .. })
+    ));
+
+    let mut empty = BitcoinVault::new(Network::Regtest, 10, "alice".to_string()).unwrap();
+    assert!(empty
+        .trigger_withdrawal(destination(), Amount::ZERO, &funding(2, 20_000), &FixedHeight(TRIGGER_HEIGHT))
+        .is_err());
+}
+
+#[test]
+fn complete_psbt_spends_trigger_output_after_timelock() {
+    let mut vault = funded_vault();
+    let trigger = vault
+        .trigger_withdrawal(destination(), Amount::from_sat(VAULT_AMOUNT), &funding(2, 20_000), &FixedHeight(TRIGGER_HEIGHT))
+        .unwrap();
+
+    let mut psbt = vault.complete_withdrawal(&FixedHeight(TRIGGER_HEIGHT + 10), &funding(3, 20_000)).unwrap();
+    let tx = &psbt.unsigned_tx;
+
+    assert_eq!(tx.input[0].previous_output, OutPoint::new(trigger.unsigned_tx.txid(), 0));
+    assert_eq!(tx.input[0].sequence.0 & 0x0040_ffff, 10);
+    assert!(tx.input[0].sequence.is_relative_lock_time());
+    assert_eq!(tx.output[0].value, Amount::from_sat(VAULT_AMOUNT));
+    assert_eq!(tx.output[0].script_pubkey, destination().script_pubkey());
+    assert_eq!(vault.state, VaultState::Completed);
+    assert_ground(&psbt);
+
+    // 완료 리프는 서명이 필요 없다
+    assert!(psbt.inputs[0].tap_key_origins.is_empty());
+    finalize_vault_input(&mut psbt, 0).unwrap();
+    let witness = psbt.inputs[0].final_script_witness.as_ref().unwrap();
+    assert_eq!(witness.len(), 13);
+}
+
+#[test]
+fn cancel_psbt_returns_funds_to_vault() {
+    let mut vault = funded_vault();
+    let vault_spk = vault.address.script_pubkey();
+    vault
+        .trigger_withdrawal(destination(), Amount::from_sat(VAULT_AMOUNT), &funding(2, 20_000), &FixedHeight(TRIGGER_HEIGHT))
+        .unwrap();
+    let triggered = vault.id;
+
+    let psbt = vault.cancel_withdrawal(&FixedHeight(TRIGGER_HEIGHT + 1), &funding(3, 20_000)).unwrap();
+    let tx = &psbt.unsigned_tx;
+
+    assert_eq!(tx.input[0].previous_output, triggered);
+    assert_eq!(tx.output[0].value, Amount::from_sat(VAULT_AMOUNT));
+    assert_eq!(tx.output[0].script_pubkey, vault_spk);
+    assert_eq!(vault.id, OutPoint::new(tx.txid(), 0));
+    assert_eq!(vault.state, VaultState::Inactive);
+    assert!(vault.trigger_tx.is_none());
+    assert_ground(&psbt);
+}
+
+#[test]
+fn finalize_requires_owner_signature() {
+    let mut vault = funded_vault();
+    let mut psbt = vault
+        .trigger_withdrawal(destination(), Amount::from_sat(VAULT_AMOUNT), &funding(2, 20_000), &FixedHeight(TRIGGER_HEIGHT))
+        .unwrap();
+
+    assert!(finalize_vault_input(&mut psbt, 0).is_err());
+
+    sign_owner(&mut psbt);
+    finalize_vault_input(&mut psbt, 0).unwrap();
+
+    let input = &psbt.inputs[0];
+    let witness = input.final_script_witness.as_ref().unwrap();
+    assert_eq!(witness.len(), 12);
+    assert_eq!(witness.nth(9).unwrap().len(), 64);
+    assert!(input.tap_scripts.is_empty());
+    assert!(input.proprietary.is_empty());
+}
+
+#[test]
+fn psbt_round_trips_through_bytes() {
+    let mut vault = funded_vault();
+    let psbt = vault
+        .trigger_withdrawal(destination(), Amount::from_sat(VAULT_AMOUNT), &funding(2, 20_000), &FixedHeight(TRIGGER_HEIGHT))
+        .unwrap();
+
+    let decoded = Psbt::deserialize(&psbt.serialize()).unwrap();
+    assert_eq!(decoded, psbt);
+}
@@ -0,0 +1,215 @@
+use super::interpreter::signed;
+use super::psbt::{destination, funding, owner, TRIGGER_HEIGHT, VAULT_AMOUNT};
+use bitcoin::hashes::Hash;
+use bitcoin::{Amount, Network, OutPoint, Sequence, Txid};
+use bitcoin_vault::*;
+use shared::{DeFiHubError, VaultState};
+
+const CAP: u64 = 30_000;
+const PERIOD: u16 = 144;
+
+fn limited_vault() -> BitcoinVault {
+    let owner_key = owner().x_only_public_key().0.to_string();
+    let mut vault = BitcoinVault::new(Network::Regtest, 10, owner_key)
+        .unwrap()
+        .with_rate_limit(Amount::from_sat(CAP), PERIOD)
+        .unwrap();
+    vault
+        .record_deposit(OutPoint::new(Txid::from_byte_array([1; 32]), 1), Amount::from_sat(VAULT_AMOUNT))
+        .unwrap();
+    vault
+}
+
+#[test]
+fn rate_limited_covenant_keeps_only_partial_paths() {
+    let vault = limited_vault();
+    let covenant = vault.covenant();
+    let owner_key = owner().x_only_public_key().0.to_string();
+    let plain = BitcoinVault::new(Network::Regtest, 10, owner_key).unwrap();
+
+    assert_ne!(vault.address, plain.address);
+    assert_eq!(vault.address, covenant.address(Network::Regtest).unwrap());
+    assert_eq!(covenant.complete_timelock(), Timelock::Relative { blocks: PERIOD });
+
+    // 전액 트리거/완료와 병합 트리거로는 지출할 수 없다
+    for leaf in [VaultLeaf::Trigger, VaultLeaf::Complete, VaultLeaf::MergeTrigger] {
+        assert!(covenant.control_block(leaf).is_err(), "{:?}", leaf);
+    }
+    for leaf in [VaultLeaf::PartialTrigger, VaultLeaf::PartialComplete, VaultLeaf::Consolidate, VaultLeaf::Cancel] {
+        covenant.control_block(leaf).unwrap();
+    }
+    assert_eq!(covenant.tap_tree().unwrap().script_leaves().count(), 4);
+    assert_ne!(
+        covenant.leaf_script(VaultLeaf::PartialComplete),
+        plain.covenant().leaf_script(VaultLeaf::PartialComplete)
+    );
+
+    // 설정 값 검증
+    let unfunded = || BitcoinVault::new(Network::Regtest, 10, "carol".to_string()).unwrap();
+    assert!(unfunded().with_rate_limit(Amount::ZERO, PERIOD).is_err());
+    assert!(unfunded().with_rate_limit(Amount::from_sat(CAP), 0).is_err());
+    assert!(unfunded().with_rate_limit(Amount::from_sat(u64::from(u32::MAX)), PERIOD).is_err());
+    let absolute = BitcoinVault::with_timelock(Network::Regtest, Timelock::Absolute { height: 900_000 }, "carol".to_string()).unwrap();
+    assert!(matches!(absolute.with_rate_limit(Amount::from_sat(CAP), PERIOD), Err(DeFiHubError::Configuration(_))));
+    assert!(matches!(
+        limited_vault().with_rate_limit(Amount::from_sat(CAP), PERIOD),
+        Err(DeFiHubError::InvalidVaultState { .. })
+    ));
+}
+
+#[test]
+fn capped_withdrawal_verifies_after_one_period() {
+    let mut vault = limited_vault();
+    let trigger = vault
+        .trigger_withdrawal(destination(), Amount::from_sat(CAP), &funding(2, 20_000), &FixedHeight(TRIGGER_HEIGHT))
+        .unwrap();
+    let (tx, prevouts) = signed(trigger, true);
+    TapscriptInterpreter::new(&tx, 0, &prevouts).verify().unwrap();
+
+    // 금고 타임락(10)이 아니라 기간(144)이 지나야 완료할 수 있다
+    assert!(matches!(vault.state, VaultState::Triggered { timelock_blocks, .. } if timelock_blocks == PERIOD));
+    let err = vault
+        .complete_withdrawal(&FixedHeight(TRIGGER_HEIGHT + 10), &funding(3, 20_000))
+        .unwrap_err();
+    assert!(matches!(err, DeFiHubError::TimelockNotExpired { blocks_remaining } if blocks_remaining == 134));
+
+    let complete = vault
+        .complete_withdrawal(&FixedHeight(TRIGGER_HEIGHT + PERIOD as u32), &funding(3, 20_000))
+        .unwrap();
+    let (tx, prevouts) = signed(complete, false);
+    assert_eq!(tx.input[0].sequence.0 & 0xffff, PERIOD as u32);
+    assert_eq!(tx.output[0].value, Amount::from_sat(CAP));
+    assert_eq!(tx.output[1].script_pubkey, vault.address.script_pubkey());
+    TapscriptInterpreter::new(&tx, 0, &prevouts).verify().unwrap();
+
+    // 한 기간을 채우지 못하면 CSV에서 실패한다
+    let mut early = tx.clone();
+    early.input[0].sequence = Sequence((early.input[0].sequence.0 & !0xffff) | (PERIOD as u32 - 1));
+    let err = TapscriptInterpreter::new(&early, 0, &prevouts).verify().unwrap_err();
+    assert!(matches!(err.reason, ScriptFailure::CsvNotSatisfied(_)), "{}", err);
+
+    assert_eq!(vault.state, VaultState::Inactive);
+    assert_eq!(vault.amount, Amount::from_sat(VAULT_AMOUNT - CAP));
+}
+
+#[test]
+fn covenant_rejects_withdrawal_above_cap() {
+    let vault = limited_vault();
+    let covenant = vault.covenant();
+
+    // 금고 검사를 건너뛰고 한도보다 큰 부분 트리거를 직접 만든다 (탈취된 소유자 키)
+    let over = Amount::from_sat(CAP + 1);
+    let trigger = covenant
+        .partial_trigger_psbt(vault.id, vault.amount, over, &destination().script_pubkey(), &funding(2, 20_000))
+        .unwrap();
+    let (trigger_tx, prevouts) = signed(trigger, true);
+    TapscriptInterpreter::new(&trigger_tx, 0, &prevouts).verify().unwrap();
+
+    let complete = covenant.complete_psbt(&trigger_tx, &funding(3, 20_000)).unwrap();
+    let (tx, prevouts) = signed(complete, false);
+    let err = TapscriptInterpreter::new(&tx, 0, &prevouts).verify().unwrap_err();
+    assert_eq!(err.reason, ScriptFailure::VerifyFailed("OP_VERIFY".to_string()), "{}", err);
+
+    // 한도가 없는 같은 소유자의 금고는 같은 금액을 허용한다
+    let plain = VaultCovenant { rate_limit: None, ..covenant.clone() };
+    let trigger = plain
+        .partial_trigger_psbt(vault.id, vault.amount, over, &destination().script_pubkey(), &funding(2, 20_000))
+        .unwrap();
+    let (trigger_tx, _) = signed(trigger, true);
+    let complete = plain.complete_psbt(&trigger_tx, &funding(3, 20_000)).unwrap();
+    let (tx, prevouts) = signed(complete, false);
+    TapscriptInterpreter::new(&tx, 0, &prevouts).verify().unwrap();
+}
+
+#[test]
+fn trigger_rejects_requests_above_remaining_allowance() {
+    let mut vault = limited_vault();
+    let height = TRIGGER_HEIGHT;
+    let period_end = (height / PERIOD as u32 + 1) * PERIOD as u32;
+    assert_eq!(vault.remaining_allowance(height), Some(Amount::from_sat(CAP)));
+
+    // 한도를 넘거나 전액을 빼려는 요청은 금고를 건드리지 않는다
+    let err = vault
+        .trigger_withdrawal(destination(), Amount::from_sat(CAP + 1), &funding(2, 20_000), &FixedHeight(height))
+        .unwrap_err();
+    match err {
+        DeFiHubError::RateLimitExceeded { requested, remaining, resets_at } => {
+            assert_eq!((requested, remaining, resets_at), (CAP + 1, CAP, period_end));
+        }
+        other => panic!("expected RateLimitExceeded, got {:?}", other),
+    }
+    assert!(vault
+        .trigger_withdrawal(destination(), vault.amount, &funding(2, 20_000), &FixedHeight(height))
+        .is_err());
+    assert_eq!(vault.state, VaultState::Inactive);
+
+    // 취소하면 한도가 돌아온다
+    vault
+        .trigger_withdrawal(destination(), Amount::from_sat(20_000), &funding(2, 20_000), &FixedHeight(height))
+        .unwrap();
+    assert_eq!(vault.remaining_allowance(height), Some(Amount::from_sat(CAP - 20_000)));
+    vault.cancel_withdrawal(&FixedHeight(TRIGGER_HEIGHT + 1), &funding(3, 20_000)).unwrap();
+    assert_eq!(vault.remaining_allowance(height), Some(Amount::from_sat(CAP)));
+
+    // 같은 기간의 누적이 한도를 넘으면 거부하고, 다음 기간에는 다시 허용한다
+    vault
+        .trigger_withdrawal(destination(), Amount::from_sat(20_000), &funding(4, 20_000), &FixedHeight(height + 1))
+        .unwrap();
+    vault.complete_withdrawal(&FixedHeight(height + 1 + PERIOD as u32), &funding(5, 20_000)).unwrap();
+    assert!(matches!(
+        vault.trigger_withdrawal(destination(), Amount::from_sat(15_000), &funding(6, 20_000), &FixedHeight(period_end - 1)),
+        Err(DeFiHubError::RateLimitExceeded { remaining: 10_000, .. })
+    ));
+    assert_eq!(vault.state, VaultState::Inactive);
+    vault
+        .trigger_withdrawal(destination(), Amount::from_sat(15_000), &funding(6, 20_000), &FixedHeight(period_end))
+        .unwrap();
+    assert_eq!(vault.withdrawal_window.period, period_end / PERIOD as u32);
+    assert_eq!(vault.withdrawal_window.withdrawn, Amount::from_sat(15_000));
+
+    // 기간 카운터는 금고 파일에 남는다
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("vault.json");
+    vault.save_to_file(&path).unwrap();
+    let loaded = BitcoinVault::load_from_file(&path).unwrap();
+    assert_eq!(loaded.rate_limit, vault.rate_limit);
+    assert_eq!(loaded.withdrawal_window, vault.withdrawal_window);
+    assert_eq!(loaded.covenant(), vault.covenant());
+}
+
+#[test]
+fn extra_deposits_must_be_consolidated_before_withdrawing() {
+    let mut vault = limited_vault();
+    vault
+        .record_deposit(OutPoint::new(Txid::from_byte_array([2; 32]), 0), Amount::from_sat(VAULT_AMOUNT))
+        .unwrap();
+
+    // UTXO마다 한도가 따로 걸리므로 한도 이하 요청도 병합 전에는 거부한다
+    let err = vault
+        .trigger_withdrawal(destination(), Amount::from_sat(CAP), &funding(2, 20_000), &FixedHeight(TRIGGER_HEIGHT))
+        .unwrap_err();
+    assert!(matches!(err, DeFiHubError::BitcoinTransaction(message) if message.contains("consolidate")));
+    assert_eq!(vault.state, VaultState::Inactive);
+    assert_eq!(vault.remaining_allowance(TRIGGER_HEIGHT), Some(Amount::from_sat(CAP)));
+
+    // 병합하기 전에는 UTXO를 더 늘리는 예치도 받지 않는다
+    let third = OutPoint::new(Txid::from_byte_array([3; 32]), 0);
+    let err = vault.record_deposit(third, Amount::from_sat(VAULT_AMOUNT)).unwrap_err();
+    assert!(matches!(err, DeFiHubError::BitcoinTransaction(ref message) if message.contains("consolidate")), "{:?}", err);
+    assert_eq!((vault.utxos.len(), vault.amount), (2, Amount::from_sat(2 * VAULT_AMOUNT)));
+
+    vault.consolidate(2).unwrap();
+    assert_eq!(vault.utxos.len(), 1);
+    vault
+        .trigger_withdrawal(destination(), Amount::from_sat(CAP), &funding(2, 20_000), &FixedHeight(TRIGGER_HEIGHT))
+        .unwrap();
+
+    // 병합한 금고는 다시 추가 예치를 하나 받을 수 있다
+    let mut merged = limited_vault();
+    merged
+        .record_deposit(OutPoint::new(Txid::from_byte_array([2; 32]), 0), Amount::from_sat(VAULT_AMOUNT))
+        .unwrap();
+    merged.consolidate(2).unwrap();
+    merged.record_deposit(third, Amount::from_sat(VAULT_AMOUNT)).unwrap();
+    assert_eq!(merged.utxos.len(), 2);
+}
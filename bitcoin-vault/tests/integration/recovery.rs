@@ -0,0 +1,151 @@
+use super::psbt::{destination, funding, owner, TRIGGER_HEIGHT, VAULT_AMOUNT};
+use bitcoin::hashes::Hash;
+use bitcoin::psbt::Psbt;
+use bitcoin::secp256k1::{Keypair, Message, SecretKey};
+use bitcoin::sighash::{Prevouts, SighashCache, TapSighashType};
+use bitcoin::taproot::{self, LeafVersion, TapLeafHash};
+use bitcoin::{Address, Amount, Network, OutPoint, TxOut, Txid};
+use bitcoin_vault::*;
+use shared::{DeFiHubError, VaultState};
+
+pub(super) fn recovery() -> Keypair {
+    Keypair::from_secret_key(&SECP, &SecretKey::from_slice(&[11u8; 32]).unwrap())
+}
+
+pub(super) fn cold_address() -> Address {
+    Address::p2tr(&SECP, recovery().x_only_public_key().0, None, Network::Regtest)
+}
+
+pub(super) fn recoverable_vault() -> BitcoinVault {
+    let owner_key = owner().x_only_public_key().0.to_string();
+    let mut vault = BitcoinVault::new(Network::Regtest, 10, owner_key)
+        .unwrap()
+        .with_recovery(recovery().x_only_public_key().0, cold_address())
+        .unwrap();
+    vault
+        .record_deposit(OutPoint::new(Txid::from_byte_array([1; 32]), 1), Amount::from_sat(VAULT_AMOUNT))
+        .unwrap();
+    vault
+}
+
+pub(super) fn sign_recovery(psbt: &mut Psbt) {
+    let (_, (script, _)) = psbt.inputs[0].tap_scripts.iter().next().unwrap();
+    let leaf_hash = TapLeafHash::from_script(script, LeafVersion::TapScript);
+    let prevouts: Vec<TxOut> = psbt.inputs.iter().map(|input| input.witness_utxo.clone().unwrap()).collect();
+    let sighash = SighashCache::new(&psbt.unsigned_tx)
+        .taproot_script_spend_signature_hash(0, &Prevouts::All(&prevouts), leaf_hash, TapSighashType::Default)
+        .unwrap();
+    let sig = SECP.sign_schnorr_no_aux_rand(&Message::from_digest(sighash.to_byte_array()), &recovery());
+
+    psbt.inputs[0].tap_script_sigs.insert(
+        (recovery().x_only_public_key().0, leaf_hash),
+        taproot::Signature { sig, hash_ty: TapSighashType::Default },
+    );
+}
+
+#[test]
+fn recovery_path_adds_leaf_and_changes_address() {
+    let owner_key = owner().x_only_public_key().0.to_string();
+    let plain = BitcoinVault::new(Network::Regtest, 10, owner_key).unwrap();
+    let vault = recoverable_vault();
+    assert_ne!(plain.address, vault.address);
+
+    let covenant = vault.covenant();
+    let script = covenant.leaf_script(VaultLeaf::Recover);
+    assert!(script
+        .as_bytes()
+        .windows(cold_address().script_pubkey().len())
+        .any(|window| window == cold_address().script_pubkey().as_bytes()));
+    assert_eq!(covenant.signing_key(VaultLeaf::Recover), Some(recovery().x_only_public_key().0));
+
+    // 회수 경로가 없으면 회수 리프는 탭트리에 없다
+    assert!(plain.covenant().control_block(VaultLeaf::Recover).is_err());
+    for leaf in [
+        VaultLeaf::Trigger,
+        VaultLeaf::Complete,
+        VaultLeaf::Cancel,
+        VaultLeaf::Recover,
+        VaultLeaf::PartialTrigger,
+        VaultLeaf::PartialComplete,
+        VaultLeaf::Consolidate,
+        VaultLeaf::MergeTrigger,
+    ] {
+        let control_block = covenant.control_block(leaf).unwrap();
+        let output_key = covenant.spend_info().unwrap().output_key().to_inner();
+        assert!(control_block.verify_taproot_commitment(&SECP, output_key, &covenant.leaf_script(leaf)));
+    }
+}
+
+#[test]
+fn recover_sweeps_inactive_vault_to_cold_address() {
+    let mut vault = recoverable_vault();
+    let funded_outpoint = vault.id;
+
+    let mut psbt = vault.recover(&FixedHeight(TRIGGER_HEIGHT + 1), &funding(2, 20_000)).unwrap();
+    let tx = &psbt.unsigned_tx;
+
+    assert_eq!(tx.input[0].previous_output, funded_outpoint);
+    assert_eq!(tx.output[0].value, Amount::from_sat(VAULT_AMOUNT));
+    assert_eq!(tx.output[0].script_pubkey, cold_address().script_pubkey());
+    assert!(matches!(vault.state, VaultState::Recovered { amount, .. } if amount == Amount::from_sat(VAULT_AMOUNT)));
+    assert!(!vault.is_active());
+
+    // 소유자가 아니라 회수 키의 서명이 필요하다
+    let recovery_key = recovery().x_only_public_key().0;
+    assert_eq!(psbt.inputs[0].tap_key_origins.keys().collect::<Vec<_>>(), vec![&recovery_key]);
+    assert!(finalize_vault_input(&mut psbt.clone(), 0).is_err());
+
+    sign_recovery(&mut psbt);
+    finalize_vault_input(&mut psbt, 0).unwrap();
+    assert_eq!(psbt.inputs[0].final_script_witness.as_ref().unwrap().len(), 11);
+}
+
+#[test]
+fn recover_sweeps_triggered_vault_during_timelock() {
+    let mut vault = recoverable_vault();
+    let trigger = vault
+        .trigger_withdrawal(destination(), Amount::from_sat(VAULT_AMOUNT), &funding(2, 20_000), &FixedHeight(TRIGGER_HEIGHT))
+        .unwrap();
+
+    let psbt = vault.recover(&FixedHeight(TRIGGER_HEIGHT + 1), &funding(3, 20_000)).unwrap();
+    assert_eq!(psbt.unsigned_tx.input[0].previous_output, OutPoint::new(trigger.unsigned_tx.txid(), 0));
+    assert_eq!(psbt.unsigned_tx.output[0].script_pubkey, cold_address().script_pubkey());
+    assert!(vault.trigger_tx.is_none());
+
+    let err = vault
+        .complete_withdrawal(&FixedHeight(TRIGGER_HEIGHT + 10), &funding(4, 20_000))
+        .unwrap_err();
+    assert!(matches!(err, DeFiHubError::InvalidVaultState { .. }));
+    assert!(vault.recover(&FixedHeight(TRIGGER_HEIGHT + 1), &funding(4, 20_000)).is_err());
+}
+
+#[test]
+fn recovery_requires_configuration_before_deposit() {
+    let owner_key = owner().x_only_public_key().0.to_string();
+    let mut plain = BitcoinVault::new(Network::Regtest, 10, owner_key.clone()).unwrap();
+    plain
+        .record_deposit(OutPoint::new(Txid::from_byte_array([1; 32]), 1), Amount::from_sat(VAULT_AMOUNT))
+        .unwrap();
+    assert!(matches!(plain.recover(&FixedHeight(TRIGGER_HEIGHT + 1), &funding(2, 20_000)), Err(DeFiHubError::Configuration(_))));
+    assert_eq!(plain.state, VaultState::Inactive);
+
+    let late = plain.with_recovery(recovery().x_only_public_key().0, cold_address());
+    assert!(matches!(late, Err(DeFiHubError::InvalidVaultState { .. })));
+
+    let mainnet_cold = Address::p2tr(&SECP, recovery().x_only_public_key().0, None, Network::Bitcoin);
+    let wrong_network = BitcoinVault::new(Network::Regtest, 10, owner_key)
+        .unwrap()
+        .with_recovery(recovery().x_only_public_key().0, mainnet_cold);
+    assert!(matches!(wrong_network, Err(DeFiHubError::InvalidAddress(_))));
+}
+
+#[test]
+fn recovery_settings_round_trip_through_json() {
+    let vault = recoverable_vault();
+    let json = serde_json::to_string(&vault).unwrap();
+    let restored: BitcoinVault = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(restored.recovery_key, vault.recovery_key);
+    assert_eq!(restored.recovery_address, vault.recovery_address);
+    assert_eq!(restored.covenant(), vault.covenant());
+}
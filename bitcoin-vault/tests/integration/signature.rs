@@ -0,0 +1,166 @@
+use super::psbt::{destination, funded_vault, funding, witness_elements, TRIGGER_HEIGHT, VAULT_AMOUNT};
+use bitcoin::consensus::deserialize;
+use bitcoin::hashes::Hash;
+use bitcoin::secp256k1::{schnorr, Message};
+use bitcoin::sighash::{Prevouts, SighashCache, TapSighashType};
+use bitcoin::taproot::{LeafVersion, TapLeafHash};
+use bitcoin::{Amount, ScriptBuf, Transaction, TxOut, XOnlyPublicKey};
+use bitcoin_vault::script::G_X;
+use bitcoin_vault::signature::*;
+use bitcoin_vault::*;
+use serde_json::Value;
+
+/// BIP-341 키 경로 지출 테스트 벡터 (bitcoin/bips bip-0341/wallet-test-vectors.json)
+const BIP341_VECTORS: &str = include_str!("../data/bip341.json");
+
+struct KeyPathVector {
+    tx: Transaction,
+    utxos: Vec<TxOut>,
+    raw: Value,
+}
+
+fn key_path_vector() -> KeyPathVector {
+    let vectors: Value = serde_json::from_str(BIP341_VECTORS).unwrap();
+    let raw = vectors["keyPathSpending"][0].clone();
+
+    let tx: Transaction = deserialize(&hex_field(&raw["given"]["rawUnsignedTx"])).unwrap();
+    let utxos = raw["given"]["utxosSpent"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|utxo| TxOut {
+            value: Amount::from_sat(utxo["amountSats"].as_u64().unwrap()),
+            script_pubkey: ScriptBuf::from_bytes(hex_field(&utxo["scriptPubKey"])),
+        })
+        .collect();
+    KeyPathVector { tx, utxos, raw }
+}
+
+fn hex_field(value: &Value) -> Vec<u8> {
+    hex::decode(value.as_str().unwrap()).unwrap()
+}
+
+fn generator() -> XOnlyPublicKey {
+    XOnlyPublicKey::from_slice(&G_X).unwrap()
+}
+
+#[test]
+fn transaction_hashes_match_bip341_vectors() {
+    let vector = key_path_vector();
+    let expected = &vector.raw["intermediary"];
+
+    assert_eq!(sha_prevouts(&vector.tx).unwrap().to_vec(), hex_field(&expected["hashPrevouts"]));
+    assert_eq!(sha_amounts(&vector.utxos).unwrap().to_vec(), hex_field(&expected["hashAmounts"]));
+    assert_eq!(sha_scriptpubkeys(&vector.utxos).unwrap().to_vec(), hex_field(&expected["hashScriptPubkeys"]));
+    assert_eq!(sha_sequences(&vector.tx).unwrap().to_vec(), hex_field(&expected["hashSequences"]));
+    assert_eq!(sha_outputs(&vector.tx.output).unwrap().to_vec(), hex_field(&expected["hashOutputs"]));
+}
+
+#[test]
+fn sigmsg_matches_bip341_vectors() {
+    let vector = key_path_vector();
+    let spendings = vector.raw["inputSpending"].as_array().unwrap();
+    assert_eq!(spendings.len(), 7);
+
+    for spending in spendings {
+        let index = spending["given"]["txinIndex"].as_u64().unwrap() as usize;
+        let hash_type = TapSighashType::from_consensus_u8(spending["given"]["hashType"].as_u64().unwrap() as u8).unwrap();
+
+        let sigmsg = taproot_sigmsg(&vector.tx, index, &Prevouts::All(&vector.utxos), hash_type, None).unwrap();
+        assert_eq!(sigmsg, hex_field(&spending["intermediary"]["sigMsg"]), "input {}", index);
+        let sighash = tap_sighash(&sigmsg);
+        assert_eq!(sighash.to_vec(), hex_field(&spending["intermediary"]["sigHash"]), "input {}", index);
+
+        // 벡터의 서명이 계산한 sighash와 트윅된 출력 키로 검증된다
+        let witness = hex_field(&spending["expected"]["witness"][0]);
+        let signature = schnorr::Signature::from_slice(&witness[..64]).unwrap();
+        let output_key = XOnlyPublicKey::from_slice(&vector.utxos[index].script_pubkey.as_bytes()[2..]).unwrap();
+        SECP.verify_schnorr(&signature, &Message::from_digest(sighash), &output_key).unwrap();
+    }
+}
+
+#[test]
+fn anyonecanpay_sigmsg_needs_only_the_spent_output() {
+    let vector = key_path_vector();
+    for spending in vector.raw["inputSpending"].as_array().unwrap() {
+        let index = spending["given"]["txinIndex"].as_u64().unwrap() as usize;
+        let hash_type = TapSighashType::from_consensus_u8(spending["given"]["hashType"].as_u64().unwrap() as u8).unwrap();
+
+        let one = taproot_sigmsg(&vector.tx, index, &Prevouts::One(index, &vector.utxos[index]), hash_type, None);
+        if hash_type as u8 & 0x80 != 0 {
+            assert_eq!(one.unwrap(), hex_field(&spending["intermediary"]["sigMsg"]));
+        } else {
+            assert!(one.is_err());
+        }
+    }
+}
+
+#[test]
+fn script_path_sigmsg_matches_rust_bitcoin() {
+    let mut vault = funded_vault();
+    let psbt = vault
+        .trigger_withdrawal(destination(), Amount::from_sat(VAULT_AMOUNT), &funding(2, 20_000), &FixedHeight(TRIGGER_HEIGHT))
+        .unwrap();
+    let tx = &psbt.unsigned_tx;
+    let prevouts: Vec<TxOut> = psbt.inputs.iter().map(|input| input.witness_utxo.clone().unwrap()).collect();
+    let leaf_hash = vault.covenant().leaf_hash(VaultLeaf::Trigger);
+
+    let mut cache = SighashCache::new(tx);
+    for hash_type in [
+        TapSighashType::Default,
+        TapSighashType::All,
+        TapSighashType::None,
+        TapSighashType::Single,
+        TapSighashType::AllPlusAnyoneCanPay,
+        TapSighashType::NonePlusAnyoneCanPay,
+        TapSighashType::SinglePlusAnyoneCanPay,
+    ] {
+        let expected = cache
+            .taproot_script_spend_signature_hash(0, &Prevouts::All(&prevouts), leaf_hash, hash_type)
+            .unwrap();
+        let sigmsg = taproot_sigmsg(tx, 0, &Prevouts::All(&prevouts), hash_type, Some(leaf_hash)).unwrap();
+        assert_eq!(tap_sighash(&sigmsg), expected.to_byte_array(), "{:?}", hash_type);
+    }
+
+    let covenant = covenant_sigmsg(tx, 0, &prevouts[0], leaf_hash).unwrap();
+    let acp = taproot_sigmsg(tx, 0, &Prevouts::All(&prevouts), TapSighashType::AllPlusAnyoneCanPay, Some(leaf_hash));
+    assert_eq!(covenant, acp.unwrap());
+}
+
+#[test]
+fn fixed_nonce_signature_verifies_against_generator() {
+    let vector = key_path_vector();
+    for spending in vector.raw["inputSpending"].as_array().unwrap() {
+        let sighash: [u8; 32] = hex_field(&spending["intermediary"]["sigHash"]).try_into().unwrap();
+
+        let signature = fixed_nonce_signature(&sighash, TapSighashType::Default);
+        assert_eq!(signature.len(), 64);
+        assert_eq!(signature[..32], G_X);
+        let signature = schnorr::Signature::from_slice(&signature).unwrap();
+        SECP.verify_schnorr(&signature, &Message::from_digest(sighash), &generator()).unwrap();
+    }
+}
+
+#[test]
+fn ground_covenant_signature_is_cat_constructible() {
+    let mut vault = funded_vault();
+    let psbt = vault
+        .trigger_withdrawal(destination(), Amount::from_sat(VAULT_AMOUNT), &funding(2, 20_000), &FixedHeight(TRIGGER_HEIGHT))
+        .unwrap();
+    let (_, (script, _)) = psbt.inputs[0].tap_scripts.iter().next().unwrap();
+    let leaf_hash = TapLeafHash::from_script(script, LeafVersion::TapScript);
+    let prevout = psbt.inputs[0].witness_utxo.clone().unwrap();
+
+    let sigmsg = covenant_sigmsg(&psbt.unsigned_tx, 0, &prevout, leaf_hash).unwrap();
+    let sighash = tap_sighash(&sigmsg);
+    let signature = fixed_nonce_signature(&sighash, TapSighashType::AllPlusAnyoneCanPay);
+
+    // 스크립트가 조립하는 서명: G_x || e_prefix || 0x01 || hash_type
+    let mut expected = G_X.to_vec();
+    expected.extend_from_slice(&witness_elements(&psbt)[0]);
+    expected.extend_from_slice(&[0x01, 0x81]);
+    assert_eq!(signature, expected);
+
+    let schnorr = schnorr::Signature::from_slice(&signature[..64]).unwrap();
+    SECP.verify_schnorr(&schnorr, &Message::from_digest(sighash), &generator()).unwrap();
+}
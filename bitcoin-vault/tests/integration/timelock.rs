@@ -0,0 +1,328 @@
+use super::interpreter::signed;
+use super::psbt::{destination, funding, owner, witness_elements, TRIGGER_HEIGHT, VAULT_AMOUNT};
+use bitcoin::absolute::LockTime;
+use bitcoin::hashes::Hash;
+use bitcoin::opcodes::all::{OP_CLTV, OP_CSV, OP_DROP};
+use bitcoin::script::Instruction;
+use bitcoin::{Amount, Network, OutPoint, Sequence, Txid};
+use bitcoin_vault::watchtower::MEMORY_CHAIN_GENESIS_TIME;
+use bitcoin_vault::*;
+use shared::{ChainNetwork, DeFiHubError, DeFiResult, VaultState};
+use std::cell::Cell;
+
+/// 테스트용 체인 - 조회할 때마다 높이를 돌려주고, 필요하면 블록을 채굴한다
+struct MockChain {
+    height: Cell<u32>,
+}
+
+impl MockChain {
+    fn at(height: u32) -> Self {
+        Self { height: Cell::new(height) }
+    }
+
+    fn mine(&self, blocks: u32) {
+        self.height.set(self.height.get() + blocks);
+    }
+}
+
+impl ChainHeightSource for MockChain {
+    fn current_height(&self) -> DeFiResult<u32> {
+        Ok(self.height.get())
+    }
+}
+
+/// 항상 실패하는 RPC
+struct OfflineNode;
+
+impl ChainHeightSource for OfflineNode {
+    fn current_height(&self) -> DeFiResult<u32> {
+        Err(DeFiHubError::RpcConnection("connection refused".to_string()))
+    }
+}
+
+fn funded_vault(timelock: Timelock) -> BitcoinVault {
+    let owner_key = owner().x_only_public_key().0.to_string();
+    let mut vault = BitcoinVault::with_timelock(Network::Regtest, timelock, owner_key).unwrap();
+    vault
+        .record_deposit(OutPoint::new(Txid::from_byte_array([1; 32]), 1), Amount::from_sat(VAULT_AMOUNT))
+        .unwrap();
+    vault
+}
+
+fn trigger(vault: &mut BitcoinVault, chain: &MockChain) {
+    vault
+        .trigger_withdrawal(destination(), Amount::from_sat(VAULT_AMOUNT), &funding(2, 20_000), chain)
+        .unwrap();
+}
+
+#[test]
+fn blocks_remaining_counts_down_and_saturates() {
+    let relative = Timelock::Relative { blocks: 10 };
+    assert_eq!(relative.unlock_height(100), 110);
+    assert_eq!(relative.blocks_remaining(100, 100), 10);
+    assert_eq!(relative.blocks_remaining(100, 107), 3);
+    assert_eq!(relative.blocks_remaining(100, 110), 0);
+    assert_eq!(relative.blocks_remaining(100, 500), 0);
+
+    let absolute = Timelock::Absolute { height: 150 };
+    assert_eq!(absolute.unlock_height(100), 150);
+    assert_eq!(absolute.blocks_remaining(100, 120), 30);
+    assert_eq!(absolute.blocks_remaining(100, 151), 0);
+}
+
+#[test]
+fn trigger_records_chain_height() {
+    let chain = MockChain::at(TRIGGER_HEIGHT);
+    let mut vault = funded_vault(Timelock::Relative { blocks: 10 });
+    trigger(&mut vault, &chain);
+
+    match vault.state {
+        VaultState::Triggered { trigger_height, timelock_blocks, .. } => {
+            assert_eq!(trigger_height, TRIGGER_HEIGHT);
+            assert_eq!(timelock_blocks, 10);
+        }
+        ref state => panic!("unexpected state: {:?}", state),
+    }
+}
+
+#[test]
+fn complete_before_relative_timelock_reports_blocks_remaining() {
+    let chain = MockChain::at(TRIGGER_HEIGHT);
+    let mut vault = funded_vault(Timelock::Relative { blocks: 10 });
+    trigger(&mut vault, &chain);
+
+    chain.mine(7);
+    let err = vault.complete_withdrawal(&chain, &funding(3, 20_000)).unwrap_err();
+    assert!(matches!(err, DeFiHubError::TimelockNotExpired { blocks_remaining: 3 }));
+    assert!(matches!(vault.state, VaultState::Triggered { .. }));
+    assert!(vault.trigger_tx.is_some());
+
+    chain.mine(3);
+    vault.complete_withdrawal(&chain, &funding(3, 20_000)).unwrap();
+    assert_eq!(vault.state, VaultState::Completed);
+}
+
+#[test]
+fn height_source_errors_are_propagated() {
+    let mut vault = funded_vault(Timelock::Relative { blocks: 10 });
+    let err = vault
+        .trigger_withdrawal(destination(), Amount::from_sat(VAULT_AMOUNT), &funding(2, 20_000), &OfflineNode)
+        .unwrap_err();
+    assert!(matches!(err, DeFiHubError::RpcConnection(_)));
+    assert_eq!(vault.state, VaultState::Inactive);
+
+    trigger(&mut vault, &MockChain::at(TRIGGER_HEIGHT));
+    let err = vault.complete_withdrawal(&OfflineNode, &funding(3, 20_000)).unwrap_err();
+    assert!(matches!(err, DeFiHubError::RpcConnection(_)));
+}
+
+#[test]
+fn absolute_timelock_uses_cltv_leaf() {
+    let covenant = VaultCovenant::with_timelock(owner().x_only_public_key().0, Timelock::Absolute { height: 800_050 });
+    let script = covenant.leaf_script(VaultLeaf::Complete);
+    let instructions: Vec<_> = script.instructions().map(Result::unwrap).collect();
+
+    assert_eq!(instructions[0].script_num(), Some(800_050));
+    assert_eq!(instructions[1], Instruction::Op(OP_CLTV));
+    assert_eq!(instructions[2], Instruction::Op(OP_DROP));
+    assert!(!script.instructions().any(|i| i.unwrap() == Instruction::Op(OP_CSV)));
+
+    let relative = VaultCovenant::new(covenant.owner_key, 10);
+    assert_ne!(covenant.address(Network::Regtest).unwrap(), relative.address(Network::Regtest).unwrap());
+}
+
+#[test]
+fn complete_with_absolute_timelock_sets_lock_time() {
+    let chain = MockChain::at(TRIGGER_HEIGHT);
+    let mut vault = funded_vault(Timelock::Absolute { height: TRIGGER_HEIGHT + 50 });
+    trigger(&mut vault, &chain);
+    assert!(matches!(vault.state, VaultState::Triggered { timelock_blocks: 50, .. }));
+
+    chain.mine(49);
+    let err = vault.complete_withdrawal(&chain, &funding(3, 20_000)).unwrap_err();
+    assert!(matches!(err, DeFiHubError::TimelockNotExpired { blocks_remaining: 1 }));
+
+    chain.mine(1);
+    let psbt = vault.complete_withdrawal(&chain, &funding(3, 20_000)).unwrap();
+    let tx = &psbt.unsigned_tx;
+
+    assert_eq!(tx.lock_time, LockTime::from_height(TRIGGER_HEIGHT + 50).unwrap());
+    assert!(!tx.input[0].sequence.is_relative_lock_time());
+    assert!(tx.input[0].sequence.enables_absolute_lock_time());
+    // 완료 증인에는 완료 트랜잭션의 nLockTime이 그대로 들어간다
+    assert_eq!(witness_elements(&psbt)[3], (TRIGGER_HEIGHT + 50).to_le_bytes().to_vec());
+}
+
+#[test]
+fn absolute_timelock_must_be_a_block_height() {
+    let owner_key = owner().x_only_public_key().0.to_string();
+    let result = BitcoinVault::with_timelock(Network::Regtest, Timelock::Absolute { height: 500_000_000 }, owner_key);
+    assert!(matches!(result, Err(DeFiHubError::Configuration(_))));
+}
+
+#[test]
+fn zero_relative_delays_are_rejected() {
+    let owner_key = owner().x_only_public_key().0.to_string();
+    for timelock in [Timelock::Relative { blocks: 0 }, Timelock::RelativeTime { intervals: 0 }] {
+        assert!(matches!(timelock.validate(), Err(DeFiHubError::Configuration(_))), "{:?}", timelock);
+        let result = BitcoinVault::with_timelock(Network::Regtest, timelock, owner_key.clone());
+        assert!(matches!(result, Err(DeFiHubError::Configuration(_))), "{:?}", timelock);
+    }
+    assert!(matches!(BitcoinVault::new(Network::Regtest, 0, owner_key), Err(DeFiHubError::Configuration(_))));
+    Timelock::Relative { blocks: 1 }.validate().unwrap();
+    Timelock::RelativeTime { intervals: 1 }.validate().unwrap();
+}
+
+/// 트리거 시점 median-time-past (임의의 과거 시각)
+const TRIGGER_MTP: u32 = 1_700_000_000;
+
+#[test]
+fn durations_convert_per_network() {
+    assert_eq!(parse_duration("2h").unwrap(), 7_200);
+    assert_eq!(parse_duration("1d12h").unwrap(), 129_600);
+    assert_eq!(parse_duration("90m").unwrap(), 5_400);
+    assert_eq!(parse_duration("45").unwrap(), 45);
+    for invalid in ["", "2x", "h", "2h30", "-1h"] {
+        assert!(parse_duration(invalid).is_err(), "{}", invalid);
+    }
+
+    // 같은 2시간도 네트워크 블록 간격에 따라 블록 수가 다르다
+    assert_eq!(Timelock::relative_blocks(ChainNetwork::Bitcoin, 7_200).unwrap(), Timelock::Relative { blocks: 12 });
+    assert_eq!(Timelock::relative_blocks(ChainNetwork::Fractal, 7_200).unwrap(), Timelock::Relative { blocks: 240 });
+    assert!(Timelock::relative_blocks(ChainNetwork::Fractal, 30 * 86_400).is_err());
+
+    // median-time-past 상대 타임락은 512초 단위로 올림한다
+    assert_eq!(Timelock::relative_time(7_200).unwrap(), Timelock::RelativeTime { intervals: 15 });
+    assert_eq!(Timelock::relative_time(1_024).unwrap(), Timelock::RelativeTime { intervals: 2 });
+    assert!(Timelock::relative_time(u16::MAX as u64 * 512 + 1).is_err());
+
+    assert_eq!(parse_timestamp("2025-01-01T00:00:00Z").unwrap(), 1_735_689_600);
+    assert_eq!(parse_timestamp("1735689600").unwrap(), 1_735_689_600);
+    assert!(parse_timestamp("yesterday").is_err());
+    assert!(Timelock::AbsoluteTime { timestamp: 499_999_999 }.validate().is_err());
+}
+
+#[test]
+fn time_based_timelocks_encode_script_and_transaction_fields() {
+    let owner_key = owner().x_only_public_key().0;
+    let relative = Timelock::RelativeTime { intervals: 15 };
+    let script = VaultCovenant::with_timelock(owner_key, relative).leaf_script(VaultLeaf::Complete);
+    let instructions: Vec<_> = script.instructions().map(Result::unwrap).collect();
+    // 시간 타입 플래그(1 << 22)가 스크립트 값과 nSequence에 모두 들어간다
+    assert_eq!(instructions[0].script_num(), Some((1 << 22) | 15));
+    assert_eq!(instructions[1], Instruction::Op(OP_CSV));
+    assert_eq!(relative.relative_sequence(), Some(Sequence::from_512_second_intervals(15)));
+    assert_eq!(relative.lock_time(), LockTime::ZERO);
+
+    let absolute = Timelock::AbsoluteTime { timestamp: TRIGGER_MTP + 3_600 };
+    let script = VaultCovenant::with_timelock(owner_key, absolute).leaf_script(VaultLeaf::Complete);
+    let instructions: Vec<_> = script.instructions().map(Result::unwrap).collect();
+    assert_eq!(instructions[0].script_num(), Some((TRIGGER_MTP + 3_600) as i64));
+    assert_eq!(instructions[1], Instruction::Op(OP_CLTV));
+    assert_eq!(absolute.lock_time(), LockTime::from_time(TRIGGER_MTP + 3_600).unwrap());
+    assert_eq!(absolute.relative_sequence(), None);
+
+    // 네 가지 방식은 모두 다른 금고 주소를 만든다
+    let addresses: std::collections::HashSet<_> = [
+        Timelock::Relative { blocks: 15 },
+        relative,
+        Timelock::Absolute { height: 15 },
+        absolute,
+    ]
+    .into_iter()
+    .map(|timelock| VaultCovenant::with_timelock(owner_key, timelock).address(Network::Regtest).unwrap())
+    .collect();
+    assert_eq!(addresses.len(), 4);
+}
+
+#[test]
+fn relative_time_completion_waits_for_median_time_past() {
+    let mut vault = funded_vault(Timelock::RelativeTime { intervals: 15 });
+
+    // 시간 기반 타임락은 median-time-past를 모르는 높이 소스로 트리거할 수 없다
+    let err = vault
+        .trigger_withdrawal(destination(), Amount::from_sat(VAULT_AMOUNT), &funding(2, 20_000), &FixedHeight(TRIGGER_HEIGHT))
+        .unwrap_err();
+    assert!(matches!(err, DeFiHubError::Configuration(_)));
+    assert_eq!(vault.state, VaultState::Inactive);
+
+    let trigger_tip = FixedTip { height: TRIGGER_HEIGHT, median_time: TRIGGER_MTP };
+    let psbt = vault
+        .trigger_withdrawal(destination(), Amount::from_sat(VAULT_AMOUNT), &funding(2, 20_000), &trigger_tip)
+        .unwrap();
+    signed(psbt, true);
+    assert_eq!(vault.trigger_median_time, Some(TRIGGER_MTP));
+    // 15 × 512초 = 7680초 ≈ 13 블록 (600초 간격)
+    assert!(matches!(vault.state, VaultState::Triggered { timelock_blocks: 13, .. }));
+    assert_eq!(vault.blocks_remaining(TRIGGER_HEIGHT, TRIGGER_HEIGHT + 10), 3);
+
+    // 블록이 많이 쌓여도 median-time-past가 모자라면 완료할 수 없다
+    let early = FixedTip { height: TRIGGER_HEIGHT + 100, median_time: TRIGGER_MTP + 7_679 };
+    let err = vault.complete_withdrawal(&early, &funding(3, 20_000)).unwrap_err();
+    assert!(matches!(err, DeFiHubError::MedianTimeNotReached { seconds_remaining: 1 }), "{}", err);
+    assert!(matches!(vault.state, VaultState::Triggered { .. }));
+
+    let ready = FixedTip { height: TRIGGER_HEIGHT + 1, median_time: TRIGGER_MTP + 7_680 };
+    let psbt = vault.complete_withdrawal(&ready, &funding(3, 20_000)).unwrap();
+    assert_eq!(vault.state, VaultState::Completed);
+    assert_eq!(vault.trigger_median_time, None);
+
+    let (tx, prevouts) = signed(psbt, false);
+    assert!(tx.input[0].sequence.is_time_locked());
+    assert_eq!(tx.input[0].sequence.0 & 0xffff, 15);
+    assert_eq!(tx.lock_time, LockTime::ZERO);
+    TapscriptInterpreter::new(&tx, 0, &prevouts).verify().unwrap();
+
+    // 블록 기반 nSequence나 더 짧은 시간으로는 CSV를 통과하지 못한다
+    for sequence in [Sequence(tx.input[0].sequence.0 & !(1 << 22)), Sequence(tx.input[0].sequence.0 - 1)] {
+        let mut forged = tx.clone();
+        forged.input[0].sequence = sequence;
+        let err = TapscriptInterpreter::new(&forged, 0, &prevouts).verify().unwrap_err();
+        assert!(matches!(err.reason, ScriptFailure::CsvNotSatisfied(_)), "{}", err);
+    }
+}
+
+#[test]
+fn absolute_time_completion_sets_timestamp_lock_time() {
+    let unlock = TRIGGER_MTP + 3_600;
+    let mut vault = funded_vault(Timelock::AbsoluteTime { timestamp: unlock });
+    let psbt = vault
+        .trigger_withdrawal(
+            destination(),
+            Amount::from_sat(VAULT_AMOUNT),
+            &funding(2, 20_000),
+            &FixedTip { height: TRIGGER_HEIGHT, median_time: TRIGGER_MTP },
+        )
+        .unwrap();
+    signed(psbt, true);
+
+    // BIP-113: nLockTime이 median-time-past보다 작아야 하므로 같은 시각에서는 아직 잠겨 있다
+    let at_unlock = FixedTip { height: TRIGGER_HEIGHT + 6, median_time: unlock };
+    let err = vault.complete_withdrawal(&at_unlock, &funding(3, 20_000)).unwrap_err();
+    assert!(matches!(err, DeFiHubError::MedianTimeNotReached { seconds_remaining: 1 }), "{}", err);
+
+    let after = FixedTip { height: TRIGGER_HEIGHT + 6, median_time: unlock + 1 };
+    let psbt = vault.complete_withdrawal(&after, &funding(3, 20_000)).unwrap();
+    let (tx, prevouts) = signed(psbt, false);
+    assert_eq!(tx.lock_time, LockTime::from_time(unlock).unwrap());
+    assert!(tx.input[0].sequence.enables_absolute_lock_time());
+    TapscriptInterpreter::new(&tx, 0, &prevouts).verify().unwrap();
+
+    // 높이 타입 nLockTime으로는 시각 CLTV를 통과하지 못한다
+    let mut forged = tx.clone();
+    forged.lock_time = LockTime::from_height(TRIGGER_HEIGHT).unwrap();
+    let err = TapscriptInterpreter::new(&forged, 0, &prevouts).verify().unwrap_err();
+    assert!(matches!(err.reason, ScriptFailure::CltvNotSatisfied(_)), "{}", err);
+}
+
+#[test]
+fn memory_chain_reports_median_time_past() {
+    let chain = MemoryChain::new(100).with_block_interval(30);
+    assert_eq!(chain.median_time_past(100).unwrap(), MEMORY_CHAIN_GENESIS_TIME);
+    chain.mine_empty(20);
+    // 직전 11블록(110..=120)의 중앙값은 115번 블록 시각이다
+    assert_eq!(chain.median_time_past(120).unwrap(), MEMORY_CHAIN_GENESIS_TIME + 15 * 30);
+    assert_eq!(chain.median_time_past(102).unwrap(), MEMORY_CHAIN_GENESIS_TIME + 30);
+    assert!(chain.median_time_past(121).is_err());
+    assert!(FixedHeight(100).median_time_past(100).is_err());
+}
@@ -0,0 +1,276 @@
+use super::interpreter::sign_fee_inputs;
+use super::psbt::{destination, funded_vault, funding, owner, sign_owner, TRIGGER_HEIGHT, VAULT_AMOUNT};
+use super::recovery::{cold_address, recoverable_vault, sign_recovery};
+use bitcoin::psbt::Psbt;
+use bitcoin::secp256k1::{Keypair, SecretKey};
+use bitcoin::hashes::Hash;
+use bitcoin::{Address, Amount, Network, OutPoint, Transaction, TxOut, Txid};
+use bitcoin_vault::watchtower::WATCHTOWER_ACTOR;
+use bitcoin_vault::*;
+use shared::{DeFiHubError, DeFiResult, VaultState};
+
+type Tower = Watchtower<MemoryChain, fn(&BitcoinVault, Psbt) -> DeFiResult<Transaction>>;
+
+fn attacker_address() -> Address {
+    let key = Keypair::from_secret_key(&SECP, &SecretKey::from_slice(&[13u8; 32]).unwrap());
+    Address::p2tr(&SECP, key.x_only_public_key().0, None, Network::Regtest)
+}
+
+/// 취소는 소유자 키, 회수는 회수 키로 서명하고 로컬 인터프리터로 검증
+fn sign_response(vault: &BitcoinVault, mut psbt: Psbt) -> DeFiResult<Transaction> {
+    match vault.state {
+        VaultState::Recovered { .. } => sign_recovery(&mut psbt),
+        _ => sign_owner(&mut psbt),
+    }
+    sign_fee_inputs(&mut psbt);
+    finalize_vault_input(&mut psbt, 0)?;
+
+    let prevouts: Vec<TxOut> = psbt.inputs.iter().map(|input| input.witness_utxo.clone().unwrap()).collect();
+    let tx = psbt.extract_tx_unchecked_fee_rate();
+    for index in 0..tx.input.len() {
+        TapscriptInterpreter::new(&tx, index, &prevouts).verify()?;
+    }
+    Ok(tx)
+}
+
+fn refuse(_: &BitcoinVault, _: Psbt) -> DeFiResult<Transaction> {
+    Err(DeFiHubError::Internal("signer offline".to_string()))
+}
+
+/// 금고 사본으로 서명까지 끝낸 트리거 트랜잭션 (탈취된 소유자 키 흉내)
+fn signed_trigger(vault: &BitcoinVault, to: Address) -> Transaction {
+    let mut copy = vault.clone();
+    let amount = copy.amount;
+    let mut psbt = copy
+        .trigger_withdrawal(to, amount, &funding(2, 20_000), &FixedHeight(TRIGGER_HEIGHT))
+        .unwrap();
+    sign_owner(&mut psbt);
+    sign_fee_inputs(&mut psbt);
+    finalize_vault_input(&mut psbt, 0).unwrap();
+    psbt.extract_tx_unchecked_fee_rate()
+}
+
+fn tower(
+    vault: &BitcoinVault,
+    policy: WatchPolicy,
+    signer: fn(&BitcoinVault, Psbt) -> DeFiResult<Transaction>,
+) -> (Tower, VaultManager, tempfile::TempDir) {
+    let dir = tempfile::tempdir().unwrap();
+    let manager = VaultManager::open(dir.path()).unwrap();
+    manager.create(vault).unwrap();
+
+    let mut tower = Watchtower::new(MemoryChain::new(TRIGGER_HEIGHT), manager.clone(), signer, TRIGGER_HEIGHT + 1);
+    tower.watch(vault, policy);
+    (tower, manager, dir)
+}
+
+#[test]
+fn unauthorized_trigger_is_cancelled() {
+    let vault = funded_vault();
+    let policy = WatchPolicy::new(WatchResponse::Cancel, funding(4, 20_000)).allow(&destination());
+    let (mut tower, manager, _dir) = tower(&vault, policy, sign_response);
+
+    let trigger = signed_trigger(&vault, attacker_address());
+    tower.chain().mine_block(vec![trigger.clone()]);
+    let events = tower.poll().unwrap();
+
+    let mempool = tower.chain().mempool();
+    assert_eq!(mempool.len(), 1);
+    let cancel = &mempool[0];
+    assert_eq!(cancel.input[0].previous_output, OutPoint::new(trigger.txid(), 0));
+    assert_eq!(cancel.output[0].script_pubkey, vault.address.script_pubkey());
+    assert_eq!(
+        events,
+        vec![WatchEvent::Responded {
+            vault: OutPoint::new(cancel.txid(), 0),
+            trigger_txid: trigger.txid(),
+            destination: attacker_address().script_pubkey(),
+            response: WatchResponse::Cancel,
+            response_txid: cancel.txid(),
+            blocks_remaining: 9,
+        }]
+    );
+
+    let restored = manager.load(&OutPoint::new(cancel.txid(), 0)).unwrap();
+    assert_eq!(restored.state, VaultState::Inactive);
+    assert_eq!(restored.amount, Amount::from_sat(VAULT_AMOUNT));
+
+    // 트리거 발견과 취소가 감시탑 이름으로 이력에 남는다
+    let events = restored.history.events();
+    assert_eq!(events[events.len() - 2].action, VaultAction::TriggerObserved);
+    assert_eq!(events[events.len() - 2].block_height, Some(TRIGGER_HEIGHT));
+    assert_eq!(events[events.len() - 1].action, VaultAction::WithdrawalCancelled);
+    assert!(events[events.len() - 2..].iter().all(|event| event.actor == WATCHTOWER_ACTOR));
+    restored.verify_history().unwrap();
+    assert!(matches!(manager.load(&vault.id), Err(DeFiHubError::VaultNotFound)));
+
+    // 취소 트랜잭션 자체는 트리거가 아니므로 다시 대응하지 않는다
+    tower.chain().mine_block(Vec::new());
+    assert!(tower.poll().unwrap().is_empty());
+    assert_eq!(tower.next_height(), TRIGGER_HEIGHT + 3);
+}
+
+#[test]
+fn allowlisted_trigger_is_left_alone() {
+    let vault = funded_vault();
+    let policy = WatchPolicy::new(WatchResponse::Cancel, funding(4, 20_000)).allow(&destination());
+    let (mut tower, manager, _dir) = tower(&vault, policy, sign_response);
+
+    let trigger = signed_trigger(&vault, destination());
+    tower.chain().mine_empty(3);
+    tower.chain().mine_block(vec![trigger.clone()]);
+    let triggered = OutPoint::new(trigger.txid(), 0);
+    assert_eq!(
+        tower.poll().unwrap(),
+        vec![WatchEvent::AuthorizedTrigger {
+            vault: triggered,
+            trigger_txid: trigger.txid(),
+            destination: destination().script_pubkey(),
+        }]
+    );
+    assert!(tower.chain().mempool().is_empty());
+
+    // 다른 곳에서 만든 트리거도 금고에 기록되어 타임락 이후 완료할 수 있다
+    let observed = manager.load(&triggered).unwrap();
+    assert!(matches!(
+        observed.state,
+        VaultState::Triggered { trigger_height, .. } if trigger_height == TRIGGER_HEIGHT + 3
+    ));
+    assert_eq!(observed.trigger_tx, Some(trigger));
+    let early = manager.update(&triggered, |vault| vault.complete_withdrawal(&FixedHeight(TRIGGER_HEIGHT + 12), &funding(5, 20_000)));
+    assert!(matches!(early, Err(DeFiHubError::TimelockNotExpired { blocks_remaining: 1 })));
+    manager
+        .update(&triggered, |vault| vault.complete_withdrawal(&FixedHeight(TRIGGER_HEIGHT + 13), &funding(5, 20_000)))
+        .unwrap();
+}
+
+#[test]
+fn recovery_response_sweeps_to_cold_address() {
+    let vault = recoverable_vault();
+    let policy = WatchPolicy::new(WatchResponse::Recover, funding(4, 20_000));
+    let (mut tower, manager, _dir) = tower(&vault, policy, sign_response);
+
+    // 허용 목록이 비어 있으면 모든 트리거에 대응한다
+    let trigger = signed_trigger(&vault, destination());
+    tower.chain().mine_block(vec![trigger.clone()]);
+    let events = tower.poll().unwrap();
+    assert!(matches!(
+        &events[..],
+        [WatchEvent::Responded { response: WatchResponse::Recover, .. }]
+    ));
+
+    let sweep = &tower.chain().mempool()[0];
+    assert_eq!(sweep.output[0].script_pubkey, cold_address().script_pubkey());
+    assert_eq!(sweep.output[0].value, Amount::from_sat(VAULT_AMOUNT));
+    let recovered = manager.load(&OutPoint::new(trigger.txid(), 0)).unwrap();
+    assert!(matches!(recovered.state, VaultState::Recovered { .. }));
+}
+
+#[test]
+fn failed_response_is_reported_and_unwatched_vaults_are_ignored() {
+    let vault = funded_vault();
+    let policy = WatchPolicy::new(WatchResponse::Cancel, funding(4, 20_000));
+    let (mut tower, manager, _dir) = tower(&vault, policy, refuse);
+
+    // 서명 실패는 사건으로 남기고 다음 블록으로 넘어간다
+    let trigger = signed_trigger(&vault, attacker_address());
+    tower.chain().mine_block(vec![trigger.clone()]);
+    let events = tower.poll().unwrap();
+    assert!(matches!(
+        &events[..],
+        [WatchEvent::Failed { vault: failed, txid, error }]
+            if *failed == vault.id && *txid == trigger.txid() && error.contains("signer offline")
+    ));
+    assert_eq!(tower.next_height(), TRIGGER_HEIGHT + 2);
+    assert_eq!(manager.load(&vault.id).unwrap().state, VaultState::Inactive);
+    assert!(tower.chain().mempool().is_empty());
+
+    // 정책이 없는 금고의 트리거는 무시한다
+    let dir = tempfile::tempdir().unwrap();
+    let manager = VaultManager::open(dir.path()).unwrap();
+    manager.create(&vault).unwrap();
+    let mut tower: Tower = Watchtower::new(MemoryChain::new(TRIGGER_HEIGHT), manager.clone(), sign_response, TRIGGER_HEIGHT + 1);
+    tower.chain().mine_block(vec![signed_trigger(&vault, attacker_address())]);
+    assert!(tower.poll().unwrap().is_empty());
+    assert_eq!(manager.load(&vault.id).unwrap().state, VaultState::Inactive);
+}
+
+#[test]
+fn one_failing_vault_does_not_block_the_others() {
+    // 로컬 파일이 이미 완료로 기록된 금고는 트리거를 관찰할 수 없다
+    let mut broken = funded_vault();
+    let broken_trigger = signed_trigger(&broken, attacker_address());
+    broken.state = VaultState::Completed;
+    let policy = WatchPolicy::new(WatchResponse::Cancel, funding(4, 20_000));
+    let (mut tower, manager, _dir) = tower(&broken, policy, sign_response);
+
+    let owner_key = owner().x_only_public_key().0.to_string();
+    let mut healthy = BitcoinVault::new(Network::Regtest, 10, owner_key).unwrap();
+    healthy
+        .record_deposit(OutPoint::new(Txid::from_byte_array([3; 32]), 0), Amount::from_sat(VAULT_AMOUNT))
+        .unwrap();
+    manager.create(&healthy).unwrap();
+    let healthy_trigger = signed_trigger(&healthy, attacker_address());
+
+    tower.chain().mine_block(vec![broken_trigger.clone(), healthy_trigger.clone()]);
+    let events = tower.poll().unwrap();
+    assert_eq!(events.len(), 2);
+    assert!(matches!(
+        &events[0],
+        WatchEvent::Failed { vault, txid, .. } if *vault == broken.id && *txid == broken_trigger.txid()
+    ));
+    assert!(matches!(
+        &events[1],
+        WatchEvent::Responded { trigger_txid, response: WatchResponse::Cancel, .. } if *trigger_txid == healthy_trigger.txid()
+    ));
+    assert_eq!(tower.chain().mempool()[0].input[0].previous_output, OutPoint::new(healthy_trigger.txid(), 0));
+    assert_eq!(tower.next_height(), TRIGGER_HEIGHT + 2);
+}
+
+#[test]
+fn trigger_spending_a_utxo_replaced_by_a_local_psbt_is_cancelled() {
+    let vault = funded_vault();
+    let policy = WatchPolicy::new(WatchResponse::Cancel, funding(4, 20_000)).allow(&destination());
+    let (mut tower, manager, _dir) = tower(&vault, policy, sign_response);
+
+    // 소유자가 트리거 PSBT를 만들었지만 브로드캐스트하지 않았다
+    let pending = manager
+        .update(&vault.id, |vault| {
+            vault.trigger_withdrawal(destination(), Amount::from_sat(VAULT_AMOUNT), &funding(3, 20_000), &FixedHeight(TRIGGER_HEIGHT))?;
+            Ok(vault.id)
+        })
+        .unwrap();
+    assert_ne!(pending, vault.id);
+
+    // 공격자는 체인에 남아 있는 원래 금고 UTXO를 지출한다
+    let trigger = signed_trigger(&vault, attacker_address());
+    tower.chain().mine_block(vec![trigger.clone()]);
+    let events = tower.poll().unwrap();
+    assert!(matches!(
+        &events[..],
+        [WatchEvent::Responded { trigger_txid, response: WatchResponse::Cancel, .. }] if *trigger_txid == trigger.txid()
+    ));
+    let cancel = &tower.chain().mempool()[0];
+    assert_eq!(cancel.input[0].previous_output, OutPoint::new(trigger.txid(), 0));
+
+    let restored = manager.load(&OutPoint::new(cancel.txid(), 0)).unwrap();
+    assert_eq!(restored.state, VaultState::Inactive);
+    assert!(restored.previous_utxos.iter().all(|utxo| utxo.outpoint != vault.id));
+    restored.verify_history().unwrap();
+}
+
+#[test]
+fn memory_chain_rejects_conflicting_broadcasts() {
+    let chain = MemoryChain::new(TRIGGER_HEIGHT);
+    let vault = funded_vault();
+    let honest = signed_trigger(&vault, destination());
+    let hostile = signed_trigger(&vault, attacker_address());
+
+    assert_eq!(chain.broadcast(&honest).unwrap(), honest.txid());
+    assert!(chain.broadcast(&hostile).is_err());
+    assert_eq!(chain.mine_empty(2), TRIGGER_HEIGHT + 2);
+    assert_eq!(chain.block_transactions(TRIGGER_HEIGHT + 1).unwrap(), vec![honest]);
+    assert!(chain.block_transactions(TRIGGER_HEIGHT + 2).unwrap().is_empty());
+    assert!(chain.block_transactions(TRIGGER_HEIGHT + 3).is_err());
+    assert_eq!(chain.current_height().unwrap(), TRIGGER_HEIGHT + 2);
+}
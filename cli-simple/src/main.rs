@@ -1,6 +1,10 @@
 use clap::{Parser, Subcommand};
 use tracing::info;
 use anyhow::Result;
+use mini_rollup::amm::{self, PoolKey};
+use shared::state::LiquidityPool;
+use shared::TokenType;
+use std::collections::HashMap;
 
 /// Purrfect DeFi Hub - Cross-chain Bitcoin DeFi Platform
 #[derive(Parser)]
@@ -274,29 +278,58 @@ async fn handle_bridge_command(cmd: BridgeCommands) -> Result<()> {
     Ok(())
 }
 
+/// 데모 WBTC/USDC 풀 준비금 (15.25 WBTC / 425,750 USDC)
+const DEMO_POOL_WBTC_SATS: u64 = 1_525_000_000;
+const DEMO_POOL_USDC: u64 = 425_750;
+
+/// 데모 풀 - 롤업과 같은 `amm` 코드로 공급해 만든다 (수수료 0.3%)
+fn demo_pools() -> Result<HashMap<PoolKey, LiquidityPool>> {
+    let mut pool = amm::new_pool(TokenType::WBTC, TokenType::USDC);
+    amm::add_liquidity(&mut pool, DEMO_POOL_WBTC_SATS, DEMO_POOL_USDC)?;
+    Ok(HashMap::from([((pool.token_a.clone(), pool.token_b.clone()), pool)]))
+}
+
+/// 표시 단위 하나에 해당하는 최소 단위 수 (WBTC는 사토시)
+fn token_unit(token: &TokenType) -> f64 {
+    match token {
+        TokenType::WBTC => 100_000_000.0,
+        _ => 1.0,
+    }
+}
+
+/// 토큰쌍의 데모 풀 (없는 쌍은 에러)
+fn demo_pool(token_a: &str, token_b: &str) -> Result<(TokenType, LiquidityPool)> {
+    let (token_a, token_b): (TokenType, TokenType) = (token_a.parse()?, token_b.parse()?);
+    let pools = demo_pools()?;
+    let (_, pool) = amm::find_pool(&pools, &token_a, &token_b)?
+        .ok_or_else(|| anyhow::anyhow!("{}/{} 풀이 없습니다 (데모 풀은 WBTC/USDC만 지원합니다)", token_a, token_b))?;
+    Ok((token_a, pool.clone()))
+}
+
 async fn handle_defi_command(cmd: DefiCommands) -> Result<()> {
     match cmd {
         DefiCommands::Swap { from, to, amount } => {
-            let rate = 30000.0; // WBTC/USDC 예시 가격
-            let output = (amount as f64 * rate / 100_000_000.0) as u64;
+            let (from, pool) = demo_pool(&from, &to)?;
+            let quote = amm::quote_pool(&pool, &from, amount)?;
             
-            info!("💱 토큰 스왑");
-            info!("  {} → {}", from.to_uppercase(), to.to_uppercase());
-            info!("  입력: {} ({:.8} {})", amount, amount as f64 / 100_000_000.0, from.to_uppercase());
-            info!("  출력: {} {} (≈ ${:.2})", output, to.to_uppercase(), output as f64);
-            info!("  수수료: 0.3%");
-            info!("  슬리피지: 0.12%");
-            info!("✅ 스왑이 완료되었습니다!");
-            info!("  트랜잭션 ID: sw_abc123def456 (롤업 내)");
+            info!("💱 토큰 스왑 견적 (데모 풀)");
+            info!("  {} → {}", quote.token_in, quote.token_out);
+            info!("  입력: {} {}", quote.amount_in, quote.token_in);
+            info!("  출력: {} {}", quote.amount_out, quote.token_out);
+            info!("  수수료: {} {} ({:.1}%)", quote.fee, quote.token_in, pool.fee_rate * 100.0);
+            info!("  가격 영향: {:.2}%", quote.price_impact_bps as f64 / 100.0);
         }
         DefiCommands::Pool { token_a, token_b } => {
-            info!("📊 풀 정보: {} / {}", token_a.to_uppercase(), token_b.to_uppercase());
-            info!("  {} 보유량: 15.25000000", token_a.to_uppercase());
-            info!("  {} 보유량: 425,750", token_b.to_uppercase());
-            info!("  총 유동성: $12,750,250");
-            info!("  수수료율: 0.3%");
-            info!("  24h 거래량: $2,150,000");
-            info!("  APY: 15.3%");
+            let (base, pool) = demo_pool(&token_a, &token_b)?;
+            let quote = if base == pool.token_a { &pool.token_b } else { &pool.token_a };
+            info!("📊 풀 정보 (데모 풀): {} / {}", pool.token_a, pool.token_b);
+            info!("  {} 보유량: {}", pool.token_a, pool.reserve_a as f64 / token_unit(&pool.token_a));
+            info!("  {} 보유량: {}", pool.token_b, pool.reserve_b as f64 / token_unit(&pool.token_b));
+            if let Some(price) = amm::spot_price(&pool, &base) {
+                info!("  가격: 1 {} = {:.8} {}", base, price * token_unit(&base) / token_unit(quote), quote);
+            }
+            info!("  총 LP 지분: {}", pool.total_liquidity);
+            info!("  수수료율: {:.1}%", pool.fee_rate * 100.0);
         }
     }
     Ok(())
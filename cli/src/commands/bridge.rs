@@ -2,7 +2,7 @@ use crate::config::Config;
 use anyhow::Result;
 use tracing::info;
 
-use super::{BridgeCommands};
+use crate::BridgeCommands;
 
 pub async fn handle_bridge_command(cmd: BridgeCommands, _config: &Config) -> Result<()> {
     match cmd {
@@ -2,7 +2,7 @@ use crate::config::Config;
 use anyhow::Result;
 use tracing::info;
 
-use super::{ConfigCommands};
+use crate::ConfigCommands;
 
 pub async fn handle_config_command(cmd: ConfigCommands, config_path: &str) -> Result<()> {
     match cmd {
@@ -16,7 +16,7 @@ pub async fn handle_config_command(cmd: ConfigCommands, config_path: &str) -> Re
             info!("📋 현재 설정:");
             match Config::load(config_path) {
                 Ok(config) => {
-                    info!("  Bitcoin 네트워크: {:?}", config.bitcoin.network);
+                    info!("  Bitcoin 네트워크: {}", config.bitcoin.network);
                     info!("  RPC 엔드포인트: {}", config.bitcoin.rpc_endpoint);
                     info!("  배치 간격: {}초", config.rollup.batch_interval_seconds);
                     info!("  Solana 엔드포인트: {}", config.bridge.solana_endpoint);
@@ -1,37 +1,89 @@
+use crate::commands::rollup::load_rollup_state;
 use crate::config::Config;
 use anyhow::Result;
-use tracing::info;
+use mini_rollup::amm;
+use shared::{DeFiHubError, TokenType};
+use tracing::{info, warn};
 
-use super::{DefiCommands};
+use crate::DefiCommands;
 
-pub async fn handle_defi_command(cmd: DefiCommands, _config: &Config) -> Result<()> {
+pub async fn handle_defi_command(cmd: DefiCommands, config: &Config) -> Result<()> {
     match cmd {
         DefiCommands::Swap { from, to, amount, min_out } => {
-            info!("💱 토큰 스왑");
-            info!("  {} → {}", from, to);
+            let token_in: TokenType = from.parse()?;
+            let token_out: TokenType = to.parse()?;
+            let state = load_rollup_state(config)?;
+            let quote = amm::quote(&state, &token_in, &token_out, amount)?;
+            
+            info!("💱 토큰 스왑 견적");
+            info!("  {} → {}", token_in, token_out);
             info!("  입력: {}", amount);
+            info!("  예상 출력: {}", quote.amount_out);
             info!("  최소 출력: {}", min_out);
-            info!("✅ 스왑이 완료되었습니다!");
+            info!("  수수료: {} {}", quote.fee, token_in);
+            info!("  가격 영향: {:.2}%", quote.price_impact_bps as f64 / 100.0);
+            if quote.amount_out < min_out {
+                return Err(DeFiHubError::SlippageExceeded { expected: min_out, actual: quote.amount_out }.into());
+            }
+            if quote.price_impact_bps as f64 > config.defi.max_slippage * amm::FEE_DENOMINATOR as f64 {
+                warn!("⚠️  가격 영향이 최대 슬리피지({:.1}%)를 넘습니다", config.defi.max_slippage * 100.0);
+            }
+            info!("✅ 최소 출력을 만족합니다 (실행 시점의 풀 상태에 따라 달라질 수 있습니다)");
         }
         DefiCommands::ProvideLiquidity { token_a, token_b, amount_a, amount_b } => {
-            info!("💧 유동성 공급");
-            info!("  풀: {} / {}", token_a, token_b);
-            info!("  {}: {}", token_a, amount_a);
-            info!("  {}: {}", token_b, amount_b);
-            info!("✅ 유동성이 공급되었습니다!");
+            let token_a: TokenType = token_a.parse()?;
+            let token_b: TokenType = token_b.parse()?;
+            let state = load_rollup_state(config)?;
+            let (key, mut pool) = match amm::find_pool(&state.liquidity_pools, &token_a, &token_b)? {
+                Some((key, pool)) => (key, pool.clone()),
+                None => ((token_a.clone(), token_b.clone()), amm::new_pool(token_a.clone(), token_b.clone())),
+            };
+            let amounts = if key.0 == token_a { (amount_a, amount_b) } else { (amount_b, amount_a) };
+            let minted = amm::add_liquidity(&mut pool, amounts.0, amounts.1)?;
+            
+            info!("💧 유동성 공급 견적");
+            info!("  풀: {} / {}", key.0, key.1);
+            info!("  {}: {}", key.0, minted.amount_a);
+            info!("  {}: {}", key.1, minted.amount_b);
+            info!("  받을 지분: {} {}", minted.liquidity, amm::lp_token(&key));
+            if minted.locked > 0 {
+                info!("  영구 잠금 지분: {} (새 풀)", minted.locked);
+            }
+            info!("✅ 준비금 비율을 넘는 금액은 잔액에 남습니다 (실행 시점의 풀 상태에 따라 달라질 수 있습니다)");
         }
         DefiCommands::RemoveLiquidity { token_a, token_b, liquidity } => {
-            info!("💧 유동성 제거");
-            info!("  풀: {} / {}", token_a, token_b);
-            info!("  제거할 유동성: {}", liquidity);
-            info!("✅ 유동성이 제거되었습니다!");
+            let token_a: TokenType = token_a.parse()?;
+            let token_b: TokenType = token_b.parse()?;
+            let state = load_rollup_state(config)?;
+            let (key, pool) = amm::find_pool(&state.liquidity_pools, &token_a, &token_b)?
+                .ok_or(DeFiHubError::InsufficientLiquidity)?;
+            let (amount_a, amount_b) = amm::remove_liquidity(&mut pool.clone(), liquidity)?;
+            
+            info!("💧 유동성 제거 견적");
+            info!("  풀: {} / {}", key.0, key.1);
+            info!("  소각할 지분: {} {}", liquidity, amm::lp_token(&key));
+            info!("  돌려받을 {}: {}", key.0, amount_a);
+            info!("  돌려받을 {}: {}", key.1, amount_b);
+            info!("✅ 쌓인 스왑 수수료가 포함된 금액입니다 (실행 시점의 풀 상태에 따라 달라질 수 있습니다)");
         }
         DefiCommands::Pool { token_a, token_b } => {
+            let token_a: TokenType = token_a.parse()?;
+            let token_b: TokenType = token_b.parse()?;
+            let state = load_rollup_state(config)?;
+            
             info!("📊 풀 정보: {} / {}", token_a, token_b);
-            info!("  {} 보유량: 0", token_a);
-            info!("  {} 보유량: 0", token_b);
-            info!("  총 유동성: 0");
-            info!("  수수료율: 0.3%");
+            match amm::find_pool(&state.liquidity_pools, &token_a, &token_b)? {
+                Some((key, pool)) => {
+                    info!("  {} 보유량: {}", pool.token_a, pool.reserve_a);
+                    info!("  {} 보유량: {}", pool.token_b, pool.reserve_b);
+                    info!("  총 유동성 지분: {} {}", pool.total_liquidity, amm::lp_token(&key));
+                    info!("  수수료율: {:.2}%", pool.fee_rate * 100.0);
+                    if let Some(price) = amm::spot_price(pool, &token_a) {
+                        info!("  가격: 1 {} = {:.8} {}", token_a, price, token_b);
+                    }
+                }
+                None => info!("  풀이 없습니다 - 'defi provide-liquidity'로 먼저 유동성을 공급하세요"),
+            }
         }
     }
     Ok(())
@@ -0,0 +1,97 @@
+use crate::config::Config;
+use anyhow::{anyhow, Context, Result};
+use shared::constants::{KEYSTORE_FILE, PASSPHRASE_ENV_VAR};
+use shared::encryption::Passphrase;
+use shared::keystore::{KeyRole, Keystore};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+use crate::KeystoreCommands;
+
+pub async fn handle_keystore_command(cmd: KeystoreCommands, config: &Config) -> Result<()> {
+    match cmd {
+        KeystoreCommands::Init { force } => {
+            info!("🔑 HD 키 저장소 생성");
+
+            let path = keystore_path(config);
+            if path.exists() && !force {
+                return Err(anyhow!("키 저장소가 이미 있습니다: {} (덮어쓰려면 --force)", path.display()));
+            }
+
+            let passphrase = match unlock(config)? {
+                Some(passphrase) => Some(passphrase),
+                None => prompt_new_passphrase()?,
+            };
+            let keystore = Keystore::generate(config.bitcoin.network.bitcoin_network())?;
+            config.ensure_data_dir()?;
+            keystore.save_to_file(&path, passphrase.as_ref())?;
+
+            info!("  파일: {}", path.display());
+            info!("  마스터 지문: {}", keystore.fingerprint());
+            if passphrase.is_none() {
+                warn!("⚠️  키 저장소가 암호화되지 않았습니다");
+            }
+            info!("✅ 키 저장소가 생성되었습니다!");
+        }
+        KeystoreCommands::Show { index } => {
+            let keystore = load_keystore(config, unlock(config)?.as_ref())?;
+            info!("🔑 키 저장소 ({:?}, 지문 {})", keystore.network, keystore.fingerprint());
+            for role in KeyRole::ALL {
+                info!("  {} {}", role, keystore.derivation_path(role, index)?);
+                info!("    공개키: {}", keystore.public_key(role, index)?);
+                info!("    계정 xpub: {}", keystore.account_xpub(role)?);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 데이터 디렉토리의 키 저장소 경로
+pub fn keystore_path(config: &Config) -> PathBuf {
+    Path::new(&config.system.data_dir).join(KEYSTORE_FILE)
+}
+
+/// 키 저장소 로드 - 암호화되어 있는데 패스프레이즈가 없으면 입력받는다
+pub fn load_keystore(config: &Config, passphrase: Option<&Passphrase>) -> Result<Keystore> {
+    let path = keystore_path(config);
+    if !path.exists() {
+        return Err(anyhow!("키 저장소가 없습니다: {} ('keystore init'을 먼저 실행하세요)", path.display()));
+    }
+
+    let prompted = match passphrase {
+        None if shared::persistence::is_encrypted(&path) => Some(prompt_passphrase()?),
+        _ => None,
+    };
+    Keystore::load_from_file(&path, passphrase.or(prompted.as_ref()))
+        .with_context(|| format!("키 저장소를 열 수 없습니다: {}", path.display()))
+}
+
+/// 잠금 해제 패스프레이즈 - 환경 변수가 있으면 쓰고, 암호화 설정이 켜져 있으면 입력받는다
+pub fn unlock(config: &Config) -> Result<Option<Passphrase>> {
+    if let Ok(secret) = std::env::var(PASSPHRASE_ENV_VAR) {
+        return Ok(Some(Passphrase::new(secret)));
+    }
+    if config.system.encrypt_state {
+        return Ok(Some(prompt_passphrase()?));
+    }
+    Ok(None)
+}
+
+fn prompt_passphrase() -> Result<Passphrase> {
+    let secret = rpassword::prompt_password("🔐 패스프레이즈: ").context("패스프레이즈를 읽을 수 없습니다")?;
+    Ok(Passphrase::new(secret))
+}
+
+/// 새 패스프레이즈 두 번 입력 (비워 두면 암호화하지 않는다)
+fn prompt_new_passphrase() -> Result<Option<Passphrase>> {
+    let secret = rpassword::prompt_password("🔐 새 패스프레이즈 (비워 두면 암호화 안 함): ")
+        .context("패스프레이즈를 읽을 수 없습니다")?;
+    if secret.is_empty() {
+        return Ok(None);
+    }
+    let confirm = rpassword::prompt_password("🔐 패스프레이즈 확인: ").context("패스프레이즈를 읽을 수 없습니다")?;
+    if secret != confirm {
+        return Err(anyhow!("패스프레이즈가 일치하지 않습니다"));
+    }
+    Ok(Some(Passphrase::new(secret)))
+}
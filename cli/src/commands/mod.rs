@@ -4,10 +4,14 @@ pub mod bridge;
 pub mod defi;
 pub mod status;
 pub mod config;
+pub mod keystore;
+pub mod musig;
 
 pub use vault::*;
 pub use rollup::*;
 pub use bridge::*;
 pub use defi::*;
 pub use status::*;
-pub use config::*;
\ No newline at end of file
+pub use config::*;
+pub use keystore::*;
+pub use musig::*;
\ No newline at end of file
@@ -0,0 +1,101 @@
+use crate::config::Config;
+use anyhow::{anyhow, Context, Result};
+use bitcoin::psbt::Psbt;
+use bitcoin_vault::musig::{load_json, save_json};
+use bitcoin_vault::{MusigSession, NonceShare, PartialSignatureShare, SecretNonce};
+use shared::encryption::Passphrase;
+use shared::keystore::KeyRole;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+use super::{load_keystore, open_manager, select_vault, write_psbt};
+use crate::MusigCommands;
+
+pub async fn handle_musig_command(cmd: MusigCommands, config: &Config, passphrase: Option<Passphrase>) -> Result<()> {
+    match cmd {
+        MusigCommands::Start { psbt, vault, output } => {
+            info!("🤝 MuSig2 서명 세션 시작");
+
+            let bytes = std::fs::read(&psbt).with_context(|| format!("PSBT 파일을 읽을 수 없습니다: {}", psbt))?;
+            let psbt = Psbt::deserialize(&bytes).map_err(|e| anyhow!("잘못된 PSBT: {}", e))?;
+            let manager = open_manager(config, passphrase)?;
+            let id = select_vault(&manager, vault.as_deref(), &["Triggered", "Inactive"])?;
+            let session = manager.load(&id)?.musig_session(&psbt)?;
+            save_json(&output, &session)?;
+
+            info!("  세션 ID: {}", session.id);
+            info!("  txid: {}", psbt.unsigned_tx.txid());
+            info!("  집계 소유자 키: {}", session.aggregate_key()?);
+            for cosigner in &session.cosigners {
+                info!("    - {}", cosigner);
+            }
+            info!("📝 세션 저장: {} - 모든 공동 소유자에게 전달하세요", output);
+        }
+        MusigCommands::Nonce { session, key_index, output } => {
+            let session: MusigSession = load_json(&session)?;
+            describe(&session)?;
+
+            let keypair = load_keystore(config, passphrase.as_ref())?.keypair(KeyRole::Owner, key_index)?;
+            let (share, secret) = session.generate_nonce(&keypair)?;
+            config.ensure_data_dir()?;
+            let secret_path = secret_nonce_path(config, &session);
+            save_json(&secret_path, &secret)?;
+            save_json(&output, &share)?;
+
+            info!("  서명자: {}", share.signer);
+            info!("🔐 비밀 논스 보관: {} (이 기기 밖으로 옮기지 마세요)", secret_path.display());
+            info!("📝 공개 논스 저장: {} - 코디네이터에게 전달하세요", output);
+        }
+        MusigCommands::Sign { session, nonces, key_index, output } => {
+            let session: MusigSession = load_json(&session)?;
+            describe(&session)?;
+
+            let keypair = load_keystore(config, passphrase.as_ref())?.keypair(KeyRole::Owner, key_index)?;
+            let nonces = load_all::<NonceShare>(&nonces)?;
+            let secret_path = secret_nonce_path(config, &session);
+            let secret = SecretNonce::take(&secret_path)
+                .with_context(|| format!("비밀 논스가 없습니다: {} ('vault musig nonce'를 먼저 실행하세요)", secret_path.display()))?;
+            let partial = session.partial_sign(&keypair, secret, &nonces)?;
+            save_json(&output, &partial)?;
+
+            info!("  서명자: {}", partial.signer);
+            info!("✍️  부분 서명 저장: {} - 코디네이터에게 전달하세요", output);
+        }
+        MusigCommands::Combine { session, nonces, partials, output } => {
+            let session: MusigSession = load_json(&session)?;
+            let nonces = load_all::<NonceShare>(&nonces)?;
+            let partials = load_all::<PartialSignatureShare>(&partials)?;
+            let psbt = session.combine(&nonces, &partials)?;
+
+            write_psbt(config, Some(&output), "", &psbt)?;
+            info!("✅ 집계 서명이 추가되었습니다 - 수수료 입력 서명 후 완성하세요");
+        }
+    }
+    Ok(())
+}
+
+/// 데이터 디렉토리의 비밀 논스 파일 (세션마다 하나)
+fn secret_nonce_path(config: &Config, session: &MusigSession) -> PathBuf {
+    let id: String = session.id.chars().filter(char::is_ascii_hexdigit).take(16).collect();
+    Path::new(&config.system.data_dir).join(format!("musig_{}.secret.json", id))
+}
+
+/// 서명하기 전에 PSBT에서 직접 계산한 내용을 보여준다
+fn describe(session: &MusigSession) -> Result<()> {
+    let psbt = session.psbt()?;
+    info!("🤝 MuSig2 세션 {}", session.id);
+    info!("  txid: {}", psbt.unsigned_tx.txid());
+    for (index, output) in psbt.unsigned_tx.output.iter().enumerate() {
+        info!("    출력 {}: {} → {}", index, output.value, output.script_pubkey);
+    }
+    let sighash: String = session.sighash()?.iter().map(|byte| format!("{:02x}", byte)).collect();
+    info!("  sighash: {}", sighash);
+    Ok(())
+}
+
+fn load_all<T: serde::de::DeserializeOwned>(paths: &[String]) -> Result<Vec<T>> {
+    paths
+        .iter()
+        .map(|path| load_json(path).with_context(|| format!("파일을 읽을 수 없습니다: {}", path)))
+        .collect()
+}
@@ -1,10 +1,12 @@
 use crate::config::Config;
-use anyhow::Result;
-use tracing::info;
+use anyhow::{Context, Result};
+use shared::state::RollupState;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
 
-use super::{RollupCommands};
+use crate::RollupCommands;
 
-pub async fn handle_rollup_command(cmd: RollupCommands, _config: &Config) -> Result<()> {
+pub async fn handle_rollup_command(cmd: RollupCommands, config: &Config) -> Result<()> {
     match cmd {
         RollupCommands::Start => {
             info!("🚀 Mini-Rollup 시작");
@@ -23,10 +25,12 @@ pub async fn handle_rollup_command(cmd: RollupCommands, _config: &Config) -> Res
             info!("  다음 배치까지: 30초");
         }
         RollupCommands::Status => {
-            info!("📊 롤업 상태:");
-            info!("  현재 높이: 0");
-            info!("  상태 루트: 0x00000000...");
-            info!("  활성 상태: 실행 중");
+            let state = load_rollup_state(config)?;
+            info!("📊 롤업 상태: {}", rollup_state_path(config).display());
+            info!("  현재 높이: {}", state.current_state_root.height);
+            info!("  상태 루트: 0x{}", hex::encode(state.current_state_root.hash));
+            info!("  유동성 풀: {}개", state.liquidity_pools.len());
+            info!("  계정: {}개", state.balances.len());
         }
         RollupCommands::Balance { address, token } => {
             info!("💰 계정 잔액 조회:");
@@ -41,4 +45,19 @@ pub async fn handle_rollup_command(cmd: RollupCommands, _config: &Config) -> Res
         }
     }
     Ok(())
-}
\ No newline at end of file
+}
+/// 롤업 상태 파일 경로
+pub fn rollup_state_path(config: &Config) -> PathBuf {
+    Path::new(&config.system.data_dir).join(&config.rollup.state_file)
+}
+
+/// 배치 프로세서([`mini_rollup::BatchProcessor::open`])가 저장한 롤업 상태 로드 (파일이 없으면 빈 상태)
+pub fn load_rollup_state(config: &Config) -> Result<RollupState> {
+    let path = rollup_state_path(config);
+    if !path.exists() {
+        warn!("⚠️  롤업 상태 파일이 없습니다 ({}) - 처리된 배치가 없는 빈 상태를 씁니다", path.display());
+        return Ok(RollupState::new());
+    }
+    RollupState::load_from_file(&path)
+        .with_context(|| format!("롤업 상태 파일을 읽을 수 없습니다: {}", path.display()))
+}
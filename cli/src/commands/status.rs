@@ -1,5 +1,8 @@
+use crate::commands::{open_manager, unlock};
 use crate::config::Config;
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use bitcoin::Amount;
+use shared::VaultState;
 use tracing::info;
 
 /// 전체 시스템 상태 조회
@@ -8,12 +11,20 @@ pub async fn handle_status_command(config: &Config) -> Result<()> {
     
     // Bitcoin 금고 상태
     info!("📦 Bitcoin 금고:");
-    info!("  네트워크: {:?}", config.bitcoin.network);
+    info!("  네트워크: {}", config.bitcoin.network);
     info!("  RPC 엔드포인트: {}", config.bitcoin.rpc_endpoint);
     
-    // TODO: 실제 금고 상태 조회
-    info!("  활성 금고: 0개");
-    info!("  총 잠긴 BTC: 0.00000000 BTC");
+    let manager = open_manager(config, unlock(config)?)?;
+    let global_state = manager.global_state();
+    let global_state = global_state.read().map_err(|_| anyhow!("글로벌 상태 잠금 실패"))?;
+    let active: Vec<_> = global_state
+        .vaults
+        .values()
+        .filter(|vault| !matches!(vault.state, VaultState::Completed | VaultState::Recovered { .. }))
+        .collect();
+    let locked: Amount = active.iter().map(|vault| vault.amount).sum();
+    info!("  활성 금고: {}개", active.len());
+    info!("  총 잠긴 BTC: {}", locked);
     
     // 롤업 상태
     info!("🔄 Mini-Rollup:");
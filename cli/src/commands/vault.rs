@@ -1,39 +1,388 @@
 use crate::config::Config;
-use anyhow::Result;
-use tracing::info;
+use anyhow::{anyhow, Context, Result};
+use bitcoin::address::NetworkUnchecked;
+use bitcoin::psbt::Psbt;
+use bitcoin::{Address, Amount, Network, OutPoint, TxOut, XOnlyPublicKey};
+use bitcoin_vault::{
+    parse_descriptor, parse_duration, parse_timestamp, BitcoinVault, ChainHeightSource, FeeFunding, FeeInput, FixedHeight,
+    FixedTip, Timelock, VaultFilter, VaultManager, SEQUENCE_INTERVAL_SECS,
+};
+use bitcoincore_rpc::{Auth, Client};
+use shared::encryption::Passphrase;
+use shared::keystore::KeyRole;
+use shared::{ChainNetwork, DeFiResult, VaultState};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use tracing::{error, info, warn};
 
-use super::{VaultCommands};
+use super::{handle_musig_command, load_keystore, unlock};
+use crate::{PsbtArgs, VaultCommands};
+
+pub async fn handle_vault_command(cmd: VaultCommands, config: &Config) -> Result<()> {
+    let chain = config.bitcoin.network;
+    let network = chain.bitcoin_network();
+    let passphrase = unlock(config)?;
 
-pub async fn handle_vault_command(cmd: VaultCommands, _config: &Config) -> Result<()> {
     match cmd {
-        VaultCommands::Create { timelock, owner } => {
+        VaultCommands::Create {
+            timelock,
+            delay,
+            median_time,
+            lock_height,
+            lock_time,
+            owner,
+            recovery_key,
+            recovery_address,
+            key_index,
+            cosigners,
+            rate_limit,
+            rate_period,
+        } => {
+            let timelock = match (lock_height, lock_time, delay) {
+                (Some(height), _, _) => Timelock::Absolute { height },
+                (None, Some(time), _) => Timelock::AbsoluteTime { timestamp: parse_timestamp(&time)? },
+                (None, None, Some(delay)) => {
+                    let secs = parse_duration(&delay)?;
+                    if median_time {
+                        Timelock::relative_time(secs)?
+                    } else {
+                        Timelock::relative_blocks(chain, secs)?
+                    }
+                }
+                (None, None, None) => Timelock::Relative { blocks: timelock.unwrap_or(config.bitcoin.default_timelock_blocks) },
+            };
+
             info!("🔒 새 Bitcoin 금고 생성");
+            info!("  네트워크: {}", chain);
             info!("  소유자: {}", owner);
-            info!("  타임락: {} 블록", timelock);
+            info!("  타임락: {}", describe_timelock(timelock, chain));
+            if !chain.params().op_cat {
+                info!("  ⚠️  {}에서는 OP_CAT을 쓸 수 없어 커버넌트 경로를 지출할 수 없습니다", chain);
+            }
+
+            let mut vault = BitcoinVault::with_timelock(chain, timelock, owner)?;
+            let keystore = match key_index {
+                Some(index) => {
+                    let keystore = load_keystore(config, passphrase.as_ref())?;
+                    if cosigners.is_empty() {
+                        vault = vault.with_owner_key(keystore.public_key(KeyRole::Owner, index)?)?;
+                        info!("  소유자 키: {} ({})", vault.owner_key, keystore.derivation_path(KeyRole::Owner, index)?);
+                    }
+                    Some((keystore, index))
+                }
+                None => None,
+            };
+            if !cosigners.is_empty() {
+                let keys = cosigners
+                    .iter()
+                    .map(|key| XOnlyPublicKey::from_str(key).map_err(|e| anyhow!("잘못된 공동 소유자 키: {}", e)))
+                    .collect::<Result<Vec<_>>>()?;
+                vault = vault.with_cosigners(&keys)?;
+                info!("  공동 소유자: {}명 (MuSig2 {}-of-{})", keys.len(), keys.len(), keys.len());
+                info!("  집계 소유자 키: {}", vault.owner_key);
+            }
+            if let Some(address) = recovery_address {
+                // --recovery-key가 없으면 키 저장소의 회수 키를 쓴다
+                let key = match (recovery_key, &keystore) {
+                    (Some(key), _) => XOnlyPublicKey::from_str(&key).map_err(|e| anyhow!("잘못된 회수 키: {}", e))?,
+                    (None, Some((keystore, index))) => keystore.public_key(KeyRole::Recovery, *index)?,
+                    (None, None) => return Err(anyhow!("--recovery-key 또는 --key-index가 필요합니다")),
+                };
+                vault = vault.with_recovery(key, parse_address(&address, network)?)?;
+                info!("  회수 주소: {}", address);
+            }
+            if let (Some(cap), Some(period)) = (rate_limit, rate_period) {
+                vault = vault.with_rate_limit(Amount::from_sat(cap), period)?;
+                info!("  출금 한도: {} 블록마다 {} (완료 대기 {})", period, Amount::from_sat(cap), describe_timelock(vault.covenant().complete_timelock(), chain));
+            }
+            save_vault(config, &vault, passphrase.as_ref())?;
+
+            info!("  금고 주소: {}", vault.address);
             info!("✅ 금고가 성공적으로 생성되었습니다!");
         }
-        VaultCommands::Deposit { amount } => {
+        VaultCommands::Deposit { amount, outpoint, vault, height, tip } => {
             info!("💰 BTC 예치: {} 사토시", amount);
+
+            let outpoint = parse_outpoint(&outpoint)?;
+            let heights = match height {
+                Some(height) => Some((height, height_source(config, tip, None)?)),
+                None => None,
+            };
+            let deposit = |vault: &mut BitcoinVault| -> DeFiResult<()> {
+                vault.record_deposit(outpoint, Amount::from_sat(amount))?;
+                if let Some((height, heights)) = &heights {
+                    vault.confirm_deposit(outpoint, *height, heights.as_ref())?;
+                }
+                Ok(())
+            };
+
+            // --vault가 없으면 생성한 금고 주소의 관리 금고에 UTXO를 더하고, 아직 없으면 새로 등록한다
+            let manager = open_manager(config, passphrase.clone())?;
+            let id = match vault {
+                Some(id) => Some(parse_outpoint(&id)?),
+                None => {
+                    let template = load_vault(config, passphrase.as_ref())?;
+                    let existing = managed_vault_for(&manager, &template)?;
+                    if existing.is_none() {
+                        let mut vault = template;
+                        deposit(&mut vault)?;
+                        manager.create(&vault)?;
+                    }
+                    existing
+                }
+            };
+            let vault = match id {
+                Some(id) => manager.update(&id, |vault| {
+                    deposit(vault)?;
+                    Ok(vault.clone())
+                })?,
+                None => manager.load(&outpoint)?,
+            };
+
+            info!("  금고 UTXO: {}", outpoint);
+            info!("  금고 잔액: {} ({}개 UTXO)", vault.amount, vault.utxos.len());
             info!("✅ 예치가 완료되었습니다!");
         }
-        VaultCommands::Trigger { destination, amount } => {
+        VaultCommands::Consolidate { vault, fee_rate, output } => {
+            info!("🧲 금고 UTXO 병합");
+
+            let manager = open_manager(config, passphrase.clone())?;
+            let id = select_vault(&manager, vault.as_deref(), &["Inactive"])?;
+            let (consolidate, vault) = manager.update(&id, |vault| {
+                let psbt = vault.consolidate(fee_rate)?;
+                Ok((psbt, vault.clone()))
+            })?;
+
+            write_psbt(config, output.as_deref(), "vault_consolidate.psbt", &consolidate)?;
+            info!("  병합 UTXO: {} ({})", vault.utxos[0].outpoint, vault.utxos[0].amount);
+            info!("  남은 금고 UTXO: {}개", vault.utxos.len());
+            info!("✅ 병합 PSBT가 생성되었습니다!");
+        }
+        VaultCommands::Trigger { destination, amount, vault, psbt } => {
             info!("🚀 출금 트리거");
             info!("  대상 주소: {}", destination);
             info!("  금액: {} 사토시", amount);
-            info!("⏰ 타임락 시작 - 20블록 후 출금 가능");
+
+            let manager = open_manager(config, passphrase.clone())?;
+            let id = select_vault(&manager, vault.as_deref(), &["Inactive"])?;
+            let destination = parse_address(&destination, network)?;
+            let funding = parse_funding(&psbt, network)?;
+            let heights = height_source(config, psbt.height, psbt.median_time)?;
+            let (trigger, vault) = manager.update(&id, |vault| {
+                let psbt = vault.trigger_withdrawal(destination, Amount::from_sat(amount), &funding, heights.as_ref())?;
+                Ok((psbt, vault.clone()))
+            })?;
+
+            write_psbt(config, psbt.output.as_deref(), "vault_trigger.psbt", &trigger)?;
+            info!("  금고 UTXO: {} → {}", id, vault.id);
+            if amount < vault.amount.to_sat() {
+                info!("  부분 출금: 완료 시 {} 사토시가 금고에 다시 잠깁니다", vault.amount.to_sat() - amount);
+            }
+            if let VaultState::Triggered { trigger_height, .. } = vault.state {
+                info!("⏰ 트리거 높이 {} - {}", trigger_height, describe_unlock(&vault, trigger_height));
+            }
+        }
+        VaultCommands::Complete { vault, psbt } => {
+            let manager = open_manager(config, passphrase.clone())?;
+            let id = select_vault(&manager, vault.as_deref(), &["Triggered"])?;
+            let funding = parse_funding(&psbt, network)?;
+            let heights = height_source(config, psbt.height, psbt.median_time)?;
+            let (complete, vault) = manager.update(&id, |vault| {
+                let psbt = vault.complete_withdrawal(heights.as_ref(), &funding)?;
+                Ok((psbt, vault.clone()))
+            })?;
+
+            write_psbt(config, psbt.output.as_deref(), "vault_complete.psbt", &complete)?;
+            if vault.state == VaultState::Inactive {
+                info!("  잔돈 재잠금: {} 사토시 → 금고 UTXO {}", vault.amount.to_sat(), vault.id);
+            }
+            info!("✅ 출금 완료 PSBT가 생성되었습니다!");
+        }
+        VaultCommands::Cancel { vault, psbt } => {
+            let manager = open_manager(config, passphrase.clone())?;
+            let id = select_vault(&manager, vault.as_deref(), &["Triggered"])?;
+            let funding = parse_funding(&psbt, network)?;
+            let heights = height_source(config, psbt.height, psbt.median_time)?;
+            let (cancel, new_id) = manager.update(&id, |vault| {
+                let psbt = vault.cancel_withdrawal(heights.as_ref(), &funding)?;
+                Ok((psbt, vault.id))
+            })?;
+
+            write_psbt(config, psbt.output.as_deref(), "vault_cancel.psbt", &cancel)?;
+            info!("  금고 UTXO: {} → {}", id, new_id);
+            info!("❌ 출금 취소 PSBT가 생성되었습니다!");
         }
-        VaultCommands::Complete => {
-            info!("✅ 출금 완료!");
+        VaultCommands::Recover { vault, psbt } => {
+            info!("🚨 긴급 회수");
+
+            let manager = open_manager(config, passphrase.clone())?;
+            let id = select_vault(&manager, vault.as_deref(), &["Triggered", "Inactive"])?;
+            let funding = parse_funding(&psbt, network)?;
+            let heights = height_source(config, psbt.height, psbt.median_time)?;
+            let (recover, vault) = manager.update(&id, |vault| {
+                let psbt = vault.recover(heights.as_ref(), &funding)?;
+                Ok((psbt, vault.clone()))
+            })?;
+
+            write_psbt(config, psbt.output.as_deref(), "vault_recover.psbt", &recover)?;
+            if let Some(address) = &vault.recovery_address {
+                info!("  콜드 주소: {}", address);
+            }
+            info!("🧊 회수 PSBT가 생성되었습니다 - 회수 키로 서명하세요");
         }
-        VaultCommands::Cancel => {
-            info!("❌ 출금 취소됨");
+        VaultCommands::List { owner, state } => {
+            let mut filter = VaultFilter::default();
+            if let Some(owner) = owner {
+                filter = filter.owner(owner);
+            }
+            if let Some(state) = state {
+                filter = filter.state(state);
+            }
+
+            let vaults = open_manager(config, passphrase.clone())?.list(&filter)?;
+            info!("📋 금고 목록: {}개", vaults.len());
+            for vault in vaults {
+                info!("  {} | {} | {} | {}", vault.id, vault.state.name(), vault.amount, vault.owner);
+            }
         }
-        VaultCommands::Status => {
+        VaultCommands::Status { vault } => {
             info!("📊 금고 상태:");
-            info!("  상태: Inactive");
-            info!("  잔액: 0.00000000 BTC");
-            info!("  타임락: 해당없음");
+            let manager = open_manager(config, passphrase.clone())?;
+            let vault = match vault {
+                Some(id) => Some(manager.load(&parse_outpoint(&id)?)?),
+                None => load_vault(config, passphrase.as_ref()).ok(),
+            };
+            match vault {
+                Some(vault) => {
+                    info!("  주소: {}", vault.address);
+                    info!("  UTXO: {}", vault.id);
+                    for utxo in &vault.utxos {
+                        let confirmations = match utxo.height {
+                            Some(height) => format!("{} 확인 (높이 {})", utxo.confirmations, height),
+                            None => "미확인".to_string(),
+                        };
+                        info!("    - {} | {} | {}", utxo.outpoint, utxo.amount, confirmations);
+                    }
+                    info!("  상태: {:?}", vault.state);
+                    info!("  잔액: {}", vault.amount);
+                    info!(
+                        "  확정 잔액: {} ({} 확인 이상, {})",
+                        vault.confirmed_amount(),
+                        vault.chain.params().default_confirmations,
+                        vault.chain
+                    );
+                    info!("  타임락: {}", describe_timelock(vault.timelock, vault.chain));
+                    if let Some(limit) = vault.rate_limit {
+                        info!(
+                            "  출금 한도: {} 블록마다 {} (기간 {} 누적 {})",
+                            limit.period_blocks, limit.cap, vault.withdrawal_window.period, vault.withdrawal_window.withdrawn
+                        );
+                        if vault.utxos.len() > 1 {
+                            warn!("  ⚠️ UTXO {}개에 한도가 따로 걸려 있습니다 - 병합하기 전까지 출금과 예치를 받지 않습니다", vault.utxos.len());
+                        }
+                    }
+                    if let VaultState::Triggered { trigger_height, .. } = vault.state {
+                        info!("  출금 가능: {}", describe_unlock(&vault, trigger_height));
+                    }
+                }
+                None => {
+                    info!("  상태: Inactive");
+                    info!("  잔액: 0.00000000 BTC");
+                    info!("  타임락: 해당없음");
+                }
+            }
+
+            let global_state = manager.global_state();
+            let global_state = global_state.read().map_err(|_| anyhow!("글로벌 상태 잠금 실패"))?;
+            let locked: Amount = global_state.vaults.values().map(|info| info.amount).sum();
+            info!("  관리 중인 금고: {}개 ({})", global_state.vaults.len(), locked);
+        }
+        VaultCommands::History { vault, head } => {
+            // 관리 금고는 저장소가 금고 파일 밖에 보관한 앵커와도 대조한다
+            let (vault, anchored) = match vault {
+                Some(id) => {
+                    let manager = open_manager(config, passphrase.clone())?;
+                    let id = parse_outpoint(&id)?;
+                    let anchored = match manager.verify_history(&id) {
+                        Ok(anchor) => anchor,
+                        Err(e) => {
+                            error!("🚨 이력 변조 감지: {}", e);
+                            return Err(e.into());
+                        }
+                    };
+                    (manager.load(&id)?, anchored)
+                }
+                None => (load_vault(config, passphrase.as_ref())?, None),
+            };
+
+            info!("📜 금고 이력: {} ({}개)", vault.id, vault.history.len());
+            for event in vault.history.events() {
+                let height = event.block_height.map_or("-".to_string(), |height| height.to_string());
+                info!(
+                    "  #{} | {} | 높이 {} | {} | {:?} | {} → {}",
+                    event.sequence,
+                    event.timestamp.format("%Y-%m-%d %H:%M:%S"),
+                    height,
+                    event.actor,
+                    event.action,
+                    event.previous_state.name(),
+                    event.new_state.name()
+                );
+            }
+
+            let verified = match &head {
+                Some(head) => vault.history.verify_head(head).and_then(|_| vault.verify_history()),
+                None => vault.verify_history(),
+            };
+            if let Some(anchor) = &anchored {
+                info!("  저장소 앵커: #{} {}", anchor.sequence, anchor.head);
+            }
+            match verified {
+                Ok(()) => info!("✅ 이력 검증 성공 - head {}", vault.history.head()),
+                Err(e) => {
+                    error!("🚨 이력 변조 감지: {}", e);
+                    return Err(e.into());
+                }
+            }
+        }
+        VaultCommands::ExportDescriptor { vault, output } => {
+            let vault = match vault {
+                Some(id) => open_manager(config, passphrase.clone())?.load(&parse_outpoint(&id)?)?,
+                None => load_vault(config, passphrase.as_ref())?,
+            };
+            let covenant = vault.covenant();
+            let descriptor = covenant.descriptor()?;
+            let tree = covenant.tree_descriptor()?;
+
+            // 내보내기 전에 디스크립터가 금고 주소로 되돌아가는지 확인한다
+            for exported in [&descriptor, &tree] {
+                let address = Address::from_script(&parse_descriptor(exported)?, network)?;
+                if address != vault.address {
+                    return Err(anyhow!("디스크립터 주소 {}가 금고 주소 {}와 다릅니다", address, vault.address));
+                }
+            }
+
+            info!("🧾 금고 디스크립터: {}", vault.id);
+            info!("  주소: {}", vault.address);
+            info!("  감시 전용: {}", descriptor);
+            info!("  스크립트 트리 (감사용): {}", tree);
+
+            let request = serde_json::json!([{
+                "desc": descriptor,
+                "timestamp": vault.created_at.timestamp(),
+                "label": format!("purrfect-vault {}", vault.owner),
+                "watchonly": true,
+            }]);
+            match output {
+                Some(path) => {
+                    std::fs::write(&path, serde_json::to_string_pretty(&request)?)
+                        .with_context(|| format!("파일을 쓸 수 없습니다: {}", path))?;
+                    info!("📝 importdescriptors 요청 저장: {}", path);
+                }
+                None => info!("  bitcoin-cli importdescriptors '{}'", request),
+            }
         }
+        VaultCommands::Musig(cmd) => handle_musig_command(cmd, config, passphrase).await?,
         VaultCommands::EnableBitvmx { elf_path, min_verifiers } => {
             info!("🔧 BitVMX 연동 활성화");
             info!("  ELF 경로: {}", elf_path);
@@ -42,4 +391,170 @@ pub async fn handle_vault_command(cmd: VaultCommands, _config: &Config) -> Resul
         }
     }
     Ok(())
-}
\ No newline at end of file
+}
+
+/// 데이터 디렉토리의 금고 관리자 (패스프레이즈가 있으면 금고 파일을 암호화)
+pub fn open_manager(config: &Config, passphrase: Option<Passphrase>) -> Result<VaultManager> {
+    config.ensure_data_dir()?;
+    Ok(VaultManager::open_with_passphrase(&config.system.data_dir, passphrase)?)
+}
+
+/// `--vault`로 지정한 금고, 없으면 주어진 상태들 중 하나인 유일한 금고
+pub fn select_vault(manager: &VaultManager, vault: Option<&str>, states: &[&str]) -> Result<OutPoint> {
+    if let Some(id) = vault {
+        return parse_outpoint(id);
+    }
+
+    let mut candidates = Vec::new();
+    for state in states {
+        candidates.extend(manager.list(&VaultFilter::default().state(*state))?);
+    }
+    let states = states.join("/");
+    match candidates.as_slice() {
+        [vault] => Ok(vault.id),
+        [] => Err(anyhow!("{} 상태의 금고가 없습니다", states)),
+        _ => Err(anyhow!("{} 상태의 금고가 여러 개입니다 - --vault <txid:vout>로 지정하세요", states)),
+    }
+}
+
+/// 생성한 금고와 같은 커버넌트 주소를 쓰는 관리 금고 (출금이 끝난 금고는 제외)
+fn managed_vault_for(manager: &VaultManager, template: &BitcoinVault) -> Result<Option<OutPoint>> {
+    let filter = VaultFilter::default().script_pubkey(template.address.script_pubkey());
+    Ok(manager
+        .list(&filter)?
+        .into_iter()
+        .find(|vault| !matches!(vault.state, VaultState::Completed | VaultState::Recovered { .. }))
+        .map(|vault| vault.id))
+}
+
+pub fn parse_outpoint(outpoint: &str) -> Result<OutPoint> {
+    OutPoint::from_str(outpoint).map_err(|e| anyhow!("잘못된 UTXO 형식 (txid:vout): {}", e))
+}
+
+/// 생성한 (아직 예치 전) 금고 파일 경로
+fn vault_path(config: &Config) -> PathBuf {
+    Path::new(&config.system.data_dir).join(&config.bitcoin.vault_state_file)
+}
+
+fn load_vault(config: &Config, passphrase: Option<&Passphrase>) -> Result<BitcoinVault> {
+    let path = vault_path(config);
+    BitcoinVault::load_from_file_with(&path, passphrase)
+        .with_context(|| format!("금고 파일을 읽을 수 없습니다: {} ('vault create'를 먼저 실행하세요)", path.display()))
+}
+
+fn save_vault(config: &Config, vault: &BitcoinVault, passphrase: Option<&Passphrase>) -> Result<()> {
+    config.ensure_data_dir()?;
+    vault.save_to_file_with(vault_path(config), passphrase)?;
+    Ok(())
+}
+
+/// PSBT를 BIP-174 바이너리 파일로 저장
+pub fn write_psbt(config: &Config, output: Option<&str>, default_name: &str, psbt: &Psbt) -> Result<()> {
+    let path = match output {
+        Some(path) => PathBuf::from(path),
+        None => {
+            config.ensure_data_dir()?;
+            Path::new(&config.system.data_dir).join(default_name)
+        }
+    };
+    std::fs::write(&path, psbt.serialize())
+        .with_context(|| format!("PSBT 파일을 쓸 수 없습니다: {}", path.display()))?;
+
+    info!("📝 PSBT 저장: {}", path.display());
+    info!("  txid: {}", psbt.unsigned_tx.txid());
+    info!("  수수료: {}", psbt.fee()?);
+    Ok(())
+}
+
+/// 블록 높이 소스 - 높이를 지정하지 않으면 Bitcoin RPC에서 조회
+fn height_source(config: &Config, height: Option<u32>, median_time: Option<u32>) -> Result<Box<dyn ChainHeightSource>> {
+    match (height, median_time) {
+        (Some(height), Some(median_time)) => return Ok(Box::new(FixedTip { height, median_time })),
+        (Some(height), None) => return Ok(Box::new(FixedHeight(height))),
+        _ => {}
+    }
+
+    let auth = Auth::UserPass(config.bitcoin.rpc_username.clone(), config.bitcoin.rpc_password.clone());
+    let client = Client::new(&config.bitcoin.rpc_endpoint, auth)
+        .with_context(|| format!("Bitcoin RPC에 연결할 수 없습니다: {}", config.bitcoin.rpc_endpoint))?;
+    Ok(Box::new(client))
+}
+
+fn describe_timelock(timelock: Timelock, chain: ChainNetwork) -> String {
+    match timelock {
+        Timelock::Relative { blocks } => {
+            format!("{} 블록 (상대, {}에서 약 {})", blocks, chain, describe_duration(chain.duration_secs(blocks as u64)))
+        }
+        Timelock::RelativeTime { intervals } => format!(
+            "{} × 512초 (상대 median-time-past, {})",
+            intervals,
+            describe_duration(intervals as u64 * SEQUENCE_INTERVAL_SECS)
+        ),
+        Timelock::Absolute { height } => format!("블록 높이 {} (절대)", height),
+        Timelock::AbsoluteTime { timestamp } => format!("{} (절대 median-time-past)", describe_timestamp(timestamp)),
+    }
+}
+
+/// 트리거된 금고를 완료할 수 있는 시점
+fn describe_unlock(vault: &BitcoinVault, trigger_height: u32) -> String {
+    let timelock = vault.covenant().complete_timelock();
+    match vault.trigger_median_time.and_then(|time| timelock.unlock_time(time)) {
+        Some(unlock_time) => format!(
+            "median-time-past가 {}를 넘으면 출금 가능 (약 {} 블록)",
+            describe_timestamp(unlock_time.saturating_sub(1)),
+            vault.blocks_remaining(trigger_height, trigger_height)
+        ),
+        None => format!("블록 {}부터 출금 가능", timelock.unlock_height(trigger_height)),
+    }
+}
+
+fn describe_duration(secs: u64) -> String {
+    match secs {
+        0..=59 => format!("{}초", secs),
+        60..=3599 => format!("{}분", secs / 60),
+        3600..=86_399 => format!("{}시간 {}분", secs / 3600, secs % 3600 / 60),
+        _ => format!("{}일 {}시간", secs / 86_400, secs % 86_400 / 3600),
+    }
+}
+
+fn describe_timestamp(timestamp: u32) -> String {
+    chrono::DateTime::from_timestamp(timestamp as i64, 0).map_or_else(|| timestamp.to_string(), |time| time.to_rfc3339())
+}
+
+fn parse_address(address: &str, network: Network) -> Result<Address> {
+    Address::<NetworkUnchecked>::from_str(address)
+        .map_err(|e| anyhow!("잘못된 주소: {}", e))?
+        .require_network(network)
+        .map_err(|e| anyhow!("잘못된 네트워크 주소: {}", e))
+}
+
+/// `txid:vout:사토시:주소` 형식의 수수료 UTXO 목록 파싱
+fn parse_funding(args: &PsbtArgs, network: Network) -> Result<FeeFunding> {
+    let mut inputs = Vec::new();
+    let mut first_address = None;
+
+    for utxo in &args.fee_utxos {
+        let parts: Vec<&str> = utxo.splitn(4, ':').collect();
+        if parts.len() != 4 {
+            return Err(anyhow!("잘못된 수수료 UTXO 형식 (txid:vout:사토시:주소): {}", utxo));
+        }
+
+        let outpoint = OutPoint::from_str(&format!("{}:{}", parts[0], parts[1]))
+            .map_err(|e| anyhow!("잘못된 수수료 UTXO: {}", e))?;
+        let value = Amount::from_sat(parts[2].parse()?);
+        let address = parse_address(parts[3], network)?;
+
+        inputs.push(FeeInput {
+            outpoint,
+            txout: TxOut { value, script_pubkey: address.script_pubkey() },
+        });
+        first_address.get_or_insert(address);
+    }
+
+    let change_address = match &args.change_address {
+        Some(address) => parse_address(address, network)?,
+        None => first_address.ok_or_else(|| anyhow!("수수료 UTXO가 필요합니다"))?,
+    };
+
+    Ok(FeeFunding::new(inputs, change_address.script_pubkey()).with_fee_rate(args.fee_rate))
+}
@@ -1,6 +1,6 @@
-use bitcoin::Network;
 use serde::{Deserialize, Serialize};
 use anyhow::Result;
+use shared::ChainNetwork;
 use std::path::Path;
 
 /// 통합 DeFi 허브 설정
@@ -27,8 +27,8 @@ pub struct Config {
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct BitcoinConfig {
-    /// 비트코인 네트워크
-    pub network: Network,
+    /// 금고 네트워크 (bitcoin, testnet, signet, inquisition, regtest, fractal, fractal-testnet)
+    pub network: ChainNetwork,
     
     /// RPC 설정
     pub rpc_endpoint: String,
@@ -54,17 +54,11 @@ pub struct FractalBitcoinConfig {
     pub rpc_username: String,
     pub rpc_password: String,
     
-    /// Fractal 체인 ID
-    pub chain_id: u32,
-    
-    /// 블록 시간 (초)
-    pub block_time: u64,
-    
-    /// OP_CAT 지원 여부
-    pub op_cat_enabled: bool,
+    /// Fractal 네트워크 (블록 간격, 주소 형식, OP_CAT 여부는 네트워크 파라미터를 따른다)
+    #[serde(default = "default_fractal_network")]
+    pub network: ChainNetwork,
     
     /// 금고 설정
-    pub vault_address_prefix: String,
     pub default_timelock_blocks: u16,
     
     /// Fractal-Bitcoin 브릿지 설정
@@ -130,17 +124,25 @@ pub struct SystemConfig {
     
     /// 메트릭스 포트
     pub metrics_port: u16,
+    
+    /// 금고/상태/키 저장소 파일 암호화 (패스프레이즈를 입력받거나 환경 변수에서 읽는다)
+    #[serde(default)]
+    pub encrypt_state: bool,
+}
+
+fn default_fractal_network() -> ChainNetwork {
+    ChainNetwork::Fractal
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             bitcoin: BitcoinConfig {
-                network: Network::Regtest,
+                network: ChainNetwork::Regtest,
                 rpc_endpoint: "http://127.0.0.1:18443".to_string(),
                 rpc_username: "user".to_string(),
                 rpc_password: "pass".to_string(),
-                default_timelock_blocks: 20,
+                default_timelock_blocks: ChainNetwork::Regtest.default_timelock_blocks(),
                 vault_state_file: "vault_state.json".to_string(),
                 bitvmx_elf_path: "BitVMX-CPU/bitvmx-programs/vault_condition.elf".to_string(),
             },
@@ -149,13 +151,10 @@ impl Default for Config {
                 rpc_endpoint: "https://open-api-fractal.unisat.io".to_string(),
                 rpc_username: "".to_string(),
                 rpc_password: "".to_string(),
-                chain_id: 1,
-                block_time: 30, // Fractal은 30초 블록  
-                op_cat_enabled: true,
-                vault_address_prefix: "bc1".to_string(),
-                default_timelock_blocks: 6, // 30초 * 6 = 3분
+                network: ChainNetwork::Fractal,
+                default_timelock_blocks: ChainNetwork::Fractal.default_timelock_blocks(), // 30초 블록으로 Bitcoin 20블록과 같은 시간
                 bridge_contract_address: None,
-                min_confirmations: 6,
+                min_confirmations: ChainNetwork::Fractal.params().default_confirmations,
             },
             rollup: RollupConfig {
                 batch_interval_seconds: 30,
@@ -182,6 +181,7 @@ impl Default for Config {
                 data_dir: "./data".to_string(),
                 api_port: 8080,
                 metrics_port: 9090,
+                encrypt_state: false,
             },
         }
     }
@@ -212,13 +212,12 @@ impl Config {
     pub fn set_value(&mut self, key: &str, value: &str) -> Result<()> {
         match key {
             "bitcoin.network" => {
-                self.bitcoin.network = match value.to_lowercase().as_str() {
-                    "mainnet" => Network::Bitcoin,
-                    "testnet" => Network::Testnet,
-                    "regtest" => Network::Regtest,
-                    "signet" => Network::Signet,
-                    _ => return Err(anyhow::anyhow!("Invalid network: {}", value)),
-                };
+                let network: ChainNetwork = value.parse()?;
+                // 기본 타임락을 바꾸지 않았다면 새 네트워크의 블록 간격에 맞춘다
+                if self.bitcoin.default_timelock_blocks == self.bitcoin.network.default_timelock_blocks() {
+                    self.bitcoin.default_timelock_blocks = network.default_timelock_blocks();
+                }
+                self.bitcoin.network = network;
             },
             "bitcoin.rpc_endpoint" => self.bitcoin.rpc_endpoint = value.to_string(),
             "bitcoin.rpc_username" => self.bitcoin.rpc_username = value.to_string(),
@@ -226,6 +225,16 @@ impl Config {
             "bitcoin.default_timelock_blocks" => {
                 self.bitcoin.default_timelock_blocks = value.parse()?;
             },
+            "fractal.network" => {
+                let network: ChainNetwork = value.parse()?;
+                if !network.is_fractal() {
+                    return Err(anyhow::anyhow!("Not a Fractal network: {}", value));
+                }
+                if self.fractal.default_timelock_blocks == self.fractal.network.default_timelock_blocks() {
+                    self.fractal.default_timelock_blocks = network.default_timelock_blocks();
+                }
+                self.fractal.network = network;
+            },
             "rollup.batch_interval_seconds" => {
                 self.rollup.batch_interval_seconds = value.parse()?;
             },
@@ -250,6 +259,9 @@ impl Config {
             "system.api_port" => {
                 self.system.api_port = value.parse()?;
             },
+            "system.encrypt_state" => {
+                self.system.encrypt_state = value.parse()?;
+            },
             _ => return Err(anyhow::anyhow!("Unknown config key: {}", key)),
         }
         Ok(())
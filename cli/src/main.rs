@@ -1,5 +1,5 @@
-use clap::{Parser, Subcommand};
-use tracing::{info, error};
+use clap::{Args, Parser, Subcommand};
+use tracing::info;
 use anyhow::Result;
 
 mod commands;
@@ -43,13 +43,17 @@ enum Commands {
     #[command(subcommand)]
     Bridge(BridgeCommands),
     
-    /// DeFi 작업 (스왑, 유동성 등)
+    /// DeFi 견적 (스왑, 유동성 등 - 배치 프로세서가 저장한 롤업 상태 기준, 작업은 실행하지 않음)
     #[command(subcommand)]
     Defi(DefiCommands),
     
     /// 전체 시스템 상태 조회
     Status,
     
+    /// HD 키 저장소 관리
+    #[command(subcommand)]
+    Keystore(KeystoreCommands),
+    
     /// 설정 관리
     #[command(subcommand)]
     Config(ConfigCommands),
@@ -59,13 +63,55 @@ enum Commands {
 enum VaultCommands {
     /// 새 금고 생성
     Create {
-        /// 타임락 블록 수
-        #[arg(short, long, default_value = "20")]
-        timelock: u16,
+        /// 타임락 블록 수 (상대 타임락, OP_CSV, 기본값: 설정의 네트워크별 기본 타임락)
+        #[arg(short, long, conflicts_with = "delay")]
+        timelock: Option<u16>,
+        
+        /// 상대 타임락 기간 (예: 90m, 2h, 1d12h) - 네트워크 블록 간격으로 블록 수로 환산
+        #[arg(long)]
+        delay: Option<String>,
+        
+        /// --delay를 블록 수 대신 median-time-past로 적용 (512초 단위 OP_CSV)
+        #[arg(long, requires = "delay")]
+        median_time: bool,
+        
+        /// 절대 타임락 블록 높이 (OP_CLTV, 지정하면 --timelock 대신 사용)
+        #[arg(long, conflicts_with_all = ["delay", "lock_time"])]
+        lock_height: Option<u32>,
+        
+        /// 절대 타임락 시각 (유닉스 초 또는 RFC 3339, OP_CLTV, median-time-past 기준)
+        #[arg(long, conflicts_with = "delay")]
+        lock_time: Option<String>,
         
         /// 금고 소유자
         #[arg(short, long)]
         owner: String,
+        
+        /// 회수 키 (x-only 공개키 hex)
+        #[arg(long, requires = "recovery_address")]
+        recovery_key: Option<String>,
+        
+        /// 회수 자금을 받을 콜드 주소 (회수 키는 --recovery-key 또는 키 저장소)
+        #[arg(long)]
+        recovery_address: Option<String>,
+        
+        /// 키 저장소에서 소유자/회수 키를 유도할 인덱스
+        #[arg(long)]
+        key_index: Option<u32>,
+        
+        /// MuSig2 공동 소유자 키 (x-only 공개키 hex, 2개 이상 지정하면 n-of-n 집계 키를 소유자 키로 사용)
+        #[arg(long = "cosigner")]
+        cosigners: Vec<String>,
+        
+        /// 기간마다 출금할 수 있는 최대 금액 (사토시, 커버넌트가 금고 UTXO마다 강제)
+        ///
+        /// 추가 예치로 UTXO가 둘이 되면 `consolidate`로 병합하기 전까지 출금과 다음 예치를 받지 않는다.
+        #[arg(long, requires = "rate_period")]
+        rate_limit: Option<u64>,
+        
+        /// 출금 한도 기간 (블록, 예: 144)
+        #[arg(long, requires = "rate_limit")]
+        rate_period: Option<u16>,
     },
     
     /// BTC 예치
@@ -73,9 +119,40 @@ enum VaultCommands {
         /// 예치할 금액 (사토시)
         #[arg(short, long)]
         amount: u64,
+        
+        /// 예치 UTXO (txid:vout)
+        #[arg(long)]
+        outpoint: String,
+        
+        /// 입금을 추가할 관리 금고 UTXO (txid:vout, 지정하지 않으면 생성한 금고 주소의 관리 금고에 추가하고 없으면 새로 등록)
+        #[arg(long)]
+        vault: Option<String>,
+        
+        /// 예치 UTXO가 포함된 블록 높이
+        #[arg(long)]
+        height: Option<u32>,
+        
+        /// 현재 블록 높이 (확인 수 계산용, 기본값: Bitcoin RPC에서 조회)
+        #[arg(long, requires = "height")]
+        tip: Option<u32>,
+    },
+    
+    /// 금고 UTXO 병합 (병합 PSBT 생성, 수수료는 금고 금액에서 차감)
+    Consolidate {
+        /// 대상 금고 UTXO (txid:vout, 기본값: 해당 상태의 유일한 금고)
+        #[arg(long)]
+        vault: Option<String>,
+        
+        /// 수수료율 (sat/vB)
+        #[arg(long, default_value_t = shared::constants::DEFAULT_FEE_RATE)]
+        fee_rate: u64,
+        
+        /// PSBT 저장 경로 (기본값: 데이터 디렉토리)
+        #[arg(short, long)]
+        output: Option<String>,
     },
     
-    /// 출금 트리거
+    /// 출금 트리거 (트리거 PSBT 생성)
     Trigger {
         /// 출금 주소
         #[arg(short, long)]
@@ -84,16 +161,88 @@ enum VaultCommands {
         /// 출금 금액 (사토시)
         #[arg(short, long)]
         amount: u64,
+        
+        /// 대상 금고 UTXO (txid:vout, 기본값: 해당 상태의 유일한 금고)
+        #[arg(long)]
+        vault: Option<String>,
+        
+        #[command(flatten)]
+        psbt: PsbtArgs,
+    },
+    
+    /// 출금 완료 (완료 PSBT 생성)
+    Complete {
+        /// 대상 금고 UTXO (txid:vout, 기본값: 해당 상태의 유일한 금고)
+        #[arg(long)]
+        vault: Option<String>,
+        
+        #[command(flatten)]
+        psbt: PsbtArgs,
+    },
+    
+    /// 출금 취소 (취소 PSBT 생성)
+    Cancel {
+        /// 대상 금고 UTXO (txid:vout, 기본값: 해당 상태의 유일한 금고)
+        #[arg(long)]
+        vault: Option<String>,
+        
+        #[command(flatten)]
+        psbt: PsbtArgs,
     },
     
-    /// 출금 완료
-    Complete,
+    /// 긴급 회수 (회수 PSBT 생성, 콜드 주소로 전액 이동)
+    Recover {
+        /// 대상 금고 UTXO (txid:vout, 기본값: 해당 상태의 유일한 금고)
+        #[arg(long)]
+        vault: Option<String>,
+        
+        #[command(flatten)]
+        psbt: PsbtArgs,
+    },
     
-    /// 출금 취소
-    Cancel,
+    /// 금고 목록 조회
+    List {
+        /// 소유자로 필터링
+        #[arg(long)]
+        owner: Option<String>,
+        
+        /// 상태로 필터링 (inactive, triggered, completed, bridged)
+        #[arg(long)]
+        state: Option<String>,
+    },
     
     /// 금고 상태 조회
-    Status,
+    Status {
+        /// 조회할 금고 UTXO (txid:vout, 기본값: 마지막으로 생성한 금고)
+        #[arg(long)]
+        vault: Option<String>,
+    },
+    
+    /// 금고 상태 전환 이력 조회 및 검증 (관리 금고는 저장소의 이력 앵커와도 대조)
+    History {
+        /// 조회할 금고 UTXO (txid:vout, 기본값: 마지막으로 생성한 금고)
+        #[arg(long)]
+        vault: Option<String>,
+        
+        /// 외부에 보관한 이력 head 해시와 비교
+        #[arg(long)]
+        head: Option<String>,
+    },
+    
+    /// 감시 전용 지갑용 출력 디스크립터 내보내기 (Bitcoin Core importdescriptors)
+    ExportDescriptor {
+        /// 대상 금고 UTXO (txid:vout, 기본값: 마지막으로 생성한 금고)
+        #[arg(long)]
+        vault: Option<String>,
+        
+        /// importdescriptors 요청 JSON 저장 경로
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    
+    /// MuSig2 공동 소유자 서명 (파일 교환)
+    #[command(subcommand)]
+    Musig(MusigCommands),
     
     /// BitVMX 연동 활성화
     EnableBitvmx {
@@ -107,6 +256,105 @@ enum VaultCommands {
     },
 }
 
+/// 금고 PSBT 수수료 및 출력 옵션
+#[derive(Args)]
+struct PsbtArgs {
+    /// 수수료 UTXO (txid:vout:사토시:주소, 여러 번 지정 가능)
+    #[arg(long = "fee-utxo", required = true)]
+    fee_utxos: Vec<String>,
+    
+    /// 잔돈 주소 (기본값: 첫 번째 수수료 UTXO 주소)
+    #[arg(long)]
+    change_address: Option<String>,
+    
+    /// 수수료율 (sat/vB)
+    #[arg(long, default_value_t = shared::constants::DEFAULT_FEE_RATE)]
+    fee_rate: u64,
+    
+    /// PSBT 저장 경로 (기본값: 데이터 디렉토리)
+    #[arg(short, long)]
+    output: Option<String>,
+    
+    /// 현재 블록 높이 (기본값: Bitcoin RPC에서 조회)
+    #[arg(long)]
+    height: Option<u32>,
+    
+    /// --height 블록의 median-time-past (시간 기반 타임락 금고에 필요)
+    #[arg(long, requires = "height")]
+    median_time: Option<u32>,
+}
+
+#[derive(Subcommand)]
+enum MusigCommands {
+    /// 서명 세션 시작 (코디네이터) - 금고 PSBT의 소유자 서명 세션 파일 생성
+    Start {
+        /// 서명할 금고 PSBT 파일 (트리거/취소 등)
+        #[arg(short, long)]
+        psbt: String,
+        
+        /// 대상 금고 UTXO (txid:vout, 기본값: 해당 상태의 유일한 금고)
+        #[arg(long)]
+        vault: Option<String>,
+        
+        /// 세션 파일 저장 경로
+        #[arg(short, long, default_value = "musig_session.json")]
+        output: String,
+    },
+    
+    /// 1단계: 공개 논스 생성 (비밀 논스는 데이터 디렉토리에 보관)
+    Nonce {
+        /// 세션 파일
+        #[arg(short, long)]
+        session: String,
+        
+        /// 키 저장소 소유자 키 인덱스
+        #[arg(long, default_value = "0")]
+        key_index: u32,
+        
+        /// 공개 논스 저장 경로
+        #[arg(short, long)]
+        output: String,
+    },
+    
+    /// 2단계: 부분 서명 (모든 공동 소유자의 공개 논스 필요)
+    Sign {
+        /// 세션 파일
+        #[arg(short, long)]
+        session: String,
+        
+        /// 공개 논스 파일 (공동 소유자마다 하나씩, 여러 번 지정)
+        #[arg(long = "nonce", required = true)]
+        nonces: Vec<String>,
+        
+        /// 키 저장소 소유자 키 인덱스
+        #[arg(long, default_value = "0")]
+        key_index: u32,
+        
+        /// 부분 서명 저장 경로
+        #[arg(short, long)]
+        output: String,
+    },
+    
+    /// 3단계: 부분 서명을 합쳐 PSBT에 집계 서명 추가 (코디네이터)
+    Combine {
+        /// 세션 파일
+        #[arg(short, long)]
+        session: String,
+        
+        /// 공개 논스 파일 (여러 번 지정)
+        #[arg(long = "nonce", required = true)]
+        nonces: Vec<String>,
+        
+        /// 부분 서명 파일 (여러 번 지정)
+        #[arg(long = "partial", required = true)]
+        partials: Vec<String>,
+        
+        /// 서명된 PSBT 저장 경로
+        #[arg(short, long)]
+        output: String,
+    },
+}
+
 #[derive(Subcommand)]
 enum RollupCommands {
     /// 롤업 시작
@@ -118,7 +366,7 @@ enum RollupCommands {
     /// 배치 처리 상태
     Batch,
     
-    /// 롤업 상태 조회
+    /// 롤업 상태 조회 (배치 프로세서가 저장한 상태 파일)
     Status,
     
     /// 계정 잔액 조회
@@ -171,7 +419,7 @@ enum BridgeCommands {
 
 #[derive(Subcommand)]
 enum DefiCommands {
-    /// 토큰 스왑
+    /// 토큰 스왑 견적
     Swap {
         /// 입력 토큰
         #[arg(short, long)]
@@ -190,7 +438,7 @@ enum DefiCommands {
         min_out: u64,
     },
     
-    /// 유동성 공급
+    /// 유동성 공급 견적
     ProvideLiquidity {
         /// 토큰 A
         #[arg(long)]
@@ -209,7 +457,7 @@ enum DefiCommands {
         amount_b: u64,
     },
     
-    /// 유동성 제거
+    /// 유동성 제거 견적
     RemoveLiquidity {
         /// 토큰 A
         #[arg(long)]
@@ -219,7 +467,7 @@ enum DefiCommands {
         #[arg(long)]
         token_b: String,
         
-        /// 제거할 유동성 양
+        /// 소각할 유동성 지분 (LP 토큰)
         #[arg(long)]
         liquidity: u64,
     },
@@ -236,6 +484,23 @@ enum DefiCommands {
     },
 }
 
+#[derive(Subcommand)]
+enum KeystoreCommands {
+    /// 새 키 저장소 생성 (패스프레이즈로 암호화)
+    Init {
+        /// 기존 키 저장소 덮어쓰기
+        #[arg(long)]
+        force: bool,
+    },
+    
+    /// 역할별 키 경로와 공개키 표시
+    Show {
+        /// 키 인덱스
+        #[arg(short, long, default_value = "0")]
+        index: u32,
+    },
+}
+
 #[derive(Subcommand)]
 enum ConfigCommands {
     /// 설정 파일 생성
@@ -278,6 +543,7 @@ async fn main() -> Result<()> {
         Commands::Bridge(cmd) => handle_bridge_command(cmd, &config).await?,
         Commands::Defi(cmd) => handle_defi_command(cmd, &config).await?,
         Commands::Status => handle_status_command(&config).await?,
+        Commands::Keystore(cmd) => handle_keystore_command(cmd, &config).await?,
         Commands::Config(cmd) => handle_config_command(cmd, &cli.config).await?,
     }
     
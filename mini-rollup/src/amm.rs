@@ -0,0 +1,269 @@
+//! x*y=k 상수곱 AMM
+//!
+//! 수수료는 입력량에서 먼저 떼고(Uniswap V2 방식) 계산은 모두 정수(u128 중간값)로 한다.
+//! 출력량은 내림하므로 스왑 후 풀의 `reserve_a * reserve_b`는 줄지 않는다.
+//! [`quote`]는 상태를 바꾸지 않아 CLI 미리보기에 쓰고, [`swap`]은 풀 준비금을 갱신한다.
+//!
+//! 유동성 지분은 풀마다 [`lp_token`] 커스텀 토큰 잔액으로 기록하고, `total_liquidity`는 그 총 발행량이다.
+//! 첫 공급은 `sqrt(a * b)`만큼 발행하되 [`MIN_LIQUIDITY_AMOUNT`]는 [`LOCKED_LIQUIDITY_ACCOUNT`]에 영구히 잠그고,
+//! 이후 공급은 준비금 비율에 맞춘 양만 받아 `min(a / reserve_a, b / reserve_b) * total` 만큼 발행한다.
+//! 스왑 수수료는 준비금에 남으므로 지분을 소각하면 비율만큼의 준비금과 함께 쌓인 수수료를 돌려받는다.
+
+use shared::state::{LiquidityPool, RollupState};
+use shared::{DeFiHubError, DeFiResult, TokenType, MIN_LIQUIDITY_AMOUNT, SWAP_FEE_RATE};
+use std::collections::HashMap;
+
+/// 수수료/가격 영향 단위 (1 = 0.01%)
+pub const FEE_DENOMINATOR: u64 = 10_000;
+
+/// 유동성 풀 키 (토큰쌍)
+pub type PoolKey = (TokenType, TokenType);
+
+/// 첫 공급 때 잠그는 최소 유동성 지분을 보유하는 계정 (누구도 서명할 수 없다)
+pub const LOCKED_LIQUIDITY_ACCOUNT: &str = "lp:locked";
+
+/// 풀의 유동성 지분 토큰 (`LP:<A>/<B>`)
+pub fn lp_token(key: &PoolKey) -> TokenType {
+    TokenType::Custom(format!("LP:{}/{}", key.0, key.1))
+}
+
+/// 유동성 공급 결과 (풀 토큰 순서)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LiquidityMint {
+    /// 실제로 받은 토큰 A/B (준비금 비율에 맞춘 양)
+    pub amount_a: u64,
+    pub amount_b: u64,
+
+    /// 공급자에게 발행한 지분
+    pub liquidity: u64,
+
+    /// 잠근 지분 (첫 공급에만 [`MIN_LIQUIDITY_AMOUNT`])
+    pub locked: u64,
+}
+
+/// 스왑 견적
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SwapQuote {
+    pub token_in: TokenType,
+    pub token_out: TokenType,
+    pub amount_in: u64,
+    pub amount_out: u64,
+
+    /// 입력에서 뗀 수수료 (입력 토큰)
+    pub fee: u64,
+
+    /// 스왑 전 입력/출력 토큰 준비금
+    pub reserve_in: u64,
+    pub reserve_out: u64,
+
+    /// 현재 가격으로 받을 양 대비 덜 받는 비율 (수수료 포함, bp)
+    pub price_impact_bps: u64,
+}
+
+/// 풀 수수료율을 bp로 변환 (0 ~ 100%)
+pub fn fee_bps(fee_rate: f64) -> u64 {
+    (fee_rate * FEE_DENOMINATOR as f64).round().clamp(0.0, FEE_DENOMINATOR as f64) as u64
+}
+
+/// 준비금과 수수료(bp)로 출력량 계산
+pub fn get_amount_out(amount_in: u64, reserve_in: u64, reserve_out: u64, fee_bps: u64) -> DeFiResult<u64> {
+    if reserve_in == 0 || reserve_out == 0 {
+        return Err(DeFiHubError::InsufficientLiquidity);
+    }
+    let amount_in_with_fee = amount_in as u128 * (FEE_DENOMINATOR - fee_bps.min(FEE_DENOMINATOR)) as u128;
+    let numerator = amount_in_with_fee * reserve_out as u128;
+    let denominator = reserve_in as u128 * FEE_DENOMINATOR as u128 + amount_in_with_fee;
+    let amount_out = (numerator / denominator) as u64;
+    if amount_out == 0 {
+        return Err(DeFiHubError::InsufficientLiquidity);
+    }
+    Ok(amount_out)
+}
+
+/// 토큰쌍의 풀 (저장된 토큰 순서와 무관하게 찾는다)
+pub fn find_pool<'a>(
+    pools: &'a HashMap<PoolKey, LiquidityPool>,
+    token_a: &TokenType,
+    token_b: &TokenType,
+) -> DeFiResult<Option<(PoolKey, &'a LiquidityPool)>> {
+    if token_a == token_b {
+        return Err(invalid_pair(token_a, token_b));
+    }
+    let forward = (token_a.clone(), token_b.clone());
+    let reverse = (token_b.clone(), token_a.clone());
+    Ok([forward, reverse]
+        .into_iter()
+        .find_map(|key| pools.get(&key).map(|pool| (key, pool))))
+}
+
+/// 풀에서 `token_in`을 `amount_in`만큼 팔 때의 견적
+pub fn quote_pool(pool: &LiquidityPool, token_in: &TokenType, amount_in: u64) -> DeFiResult<SwapQuote> {
+    let (token_out, reserve_in, reserve_out) = if token_in == &pool.token_a {
+        (&pool.token_b, pool.reserve_a, pool.reserve_b)
+    } else if token_in == &pool.token_b {
+        (&pool.token_a, pool.reserve_b, pool.reserve_a)
+    } else {
+        return Err(invalid_pair(&pool.token_a, &pool.token_b));
+    };
+
+    let fee_bps = fee_bps(pool.fee_rate);
+    let amount_out = get_amount_out(amount_in, reserve_in, reserve_out, fee_bps)?;
+    let spot_out = amount_in as u128 * reserve_out as u128 / reserve_in as u128;
+    let price_impact_bps = match spot_out {
+        0 => 0,
+        spot => (spot.saturating_sub(amount_out as u128) * FEE_DENOMINATOR as u128 / spot) as u64,
+    };
+    Ok(SwapQuote {
+        token_in: token_in.clone(),
+        token_out: token_out.clone(),
+        amount_in,
+        amount_out,
+        fee: (amount_in as u128 * fee_bps as u128 / FEE_DENOMINATOR as u128) as u64,
+        reserve_in,
+        reserve_out,
+        price_impact_bps,
+    })
+}
+
+/// 롤업 상태의 풀로 스왑 견적 (상태를 바꾸지 않는다)
+pub fn quote(state: &RollupState, token_in: &TokenType, token_out: &TokenType, amount_in: u64) -> DeFiResult<SwapQuote> {
+    let (_, pool) = find_pool(&state.liquidity_pools, token_in, token_out)?.ok_or(DeFiHubError::InsufficientLiquidity)?;
+    quote_pool(pool, token_in, amount_in)
+}
+
+/// 스왑 실행 - 출력이 `min_amount_out`보다 적으면 풀을 바꾸지 않고 실패한다
+pub fn swap(pool: &mut LiquidityPool, token_in: &TokenType, amount_in: u64, min_amount_out: u64) -> DeFiResult<SwapQuote> {
+    let quote = quote_pool(pool, token_in, amount_in)?;
+    if quote.amount_out < min_amount_out {
+        return Err(DeFiHubError::SlippageExceeded {
+            expected: min_amount_out,
+            actual: quote.amount_out,
+        });
+    }
+
+    let reserve_in = quote.reserve_in.checked_add(amount_in).ok_or_else(reserve_overflow)?;
+    let reserve_out = quote.reserve_out - quote.amount_out;
+    if token_in == &pool.token_a {
+        (pool.reserve_a, pool.reserve_b) = (reserve_in, reserve_out);
+    } else {
+        (pool.reserve_b, pool.reserve_a) = (reserve_in, reserve_out);
+    }
+    Ok(quote)
+}
+
+/// `base` 1단위의 현재 가격 (상대 토큰 단위, 표시용)
+pub fn spot_price(pool: &LiquidityPool, base: &TokenType) -> Option<f64> {
+    let (reserve_base, reserve_quote) = if base == &pool.token_a {
+        (pool.reserve_a, pool.reserve_b)
+    } else if base == &pool.token_b {
+        (pool.reserve_b, pool.reserve_a)
+    } else {
+        return None;
+    };
+    (reserve_base > 0).then(|| reserve_quote as f64 / reserve_base as f64)
+}
+
+/// 빈 풀 생성 (기본 스왑 수수료)
+pub fn new_pool(token_a: TokenType, token_b: TokenType) -> LiquidityPool {
+    LiquidityPool {
+        token_a,
+        token_b,
+        reserve_a: 0,
+        reserve_b: 0,
+        total_liquidity: 0,
+        fee_rate: SWAP_FEE_RATE,
+    }
+}
+
+/// 유동성 공급 - 금액은 풀 토큰 순서이고, 비율을 넘는 쪽은 받지 않는다
+pub fn add_liquidity(pool: &mut LiquidityPool, amount_a: u64, amount_b: u64) -> DeFiResult<LiquidityMint> {
+    let mint = if pool.total_liquidity == 0 {
+        let shares = isqrt(amount_a as u128 * amount_b as u128);
+        if shares <= MIN_LIQUIDITY_AMOUNT as u128 {
+            return Err(DeFiHubError::RollupExecution(format!(
+                "Initial liquidity must mint more than {} shares",
+                MIN_LIQUIDITY_AMOUNT
+            )));
+        }
+        LiquidityMint {
+            amount_a,
+            amount_b,
+            liquidity: u64::try_from(shares - MIN_LIQUIDITY_AMOUNT as u128).map_err(|_| reserve_overflow())?,
+            locked: MIN_LIQUIDITY_AMOUNT,
+        }
+    } else {
+        if pool.reserve_a == 0 || pool.reserve_b == 0 {
+            return Err(DeFiHubError::InsufficientLiquidity);
+        }
+        let (reserve_a, reserve_b, total) = (pool.reserve_a as u128, pool.reserve_b as u128, pool.total_liquidity as u128);
+        let optimal_b = amount_a as u128 * reserve_b / reserve_a;
+        let (used_a, used_b) = if optimal_b <= amount_b as u128 {
+            (amount_a as u128, optimal_b)
+        } else {
+            (amount_b as u128 * reserve_a / reserve_b, amount_b as u128)
+        };
+        let shares = (used_a * total / reserve_a).min(used_b * total / reserve_b);
+        if shares == 0 {
+            return Err(DeFiHubError::RollupExecution("Liquidity amounts are too small to mint shares".to_string()));
+        }
+        LiquidityMint {
+            amount_a: used_a as u64,
+            amount_b: used_b as u64,
+            liquidity: u64::try_from(shares).map_err(|_| reserve_overflow())?,
+            locked: 0,
+        }
+    };
+
+    pool.reserve_a = pool.reserve_a.checked_add(mint.amount_a).ok_or_else(reserve_overflow)?;
+    pool.reserve_b = pool.reserve_b.checked_add(mint.amount_b).ok_or_else(reserve_overflow)?;
+    pool.total_liquidity = pool
+        .total_liquidity
+        .checked_add(mint.liquidity + mint.locked)
+        .ok_or_else(reserve_overflow)?;
+    Ok(mint)
+}
+
+/// 지분 소각 - 돌려줄 토큰 A/B (풀 토큰 순서, 쌓인 수수료 포함)
+pub fn remove_liquidity(pool: &mut LiquidityPool, liquidity: u64) -> DeFiResult<(u64, u64)> {
+    if liquidity == 0 || liquidity > pool.total_liquidity {
+        return Err(DeFiHubError::InsufficientLiquidity);
+    }
+    let total = pool.total_liquidity as u128;
+    let amount_a = (liquidity as u128 * pool.reserve_a as u128 / total) as u64;
+    let amount_b = (liquidity as u128 * pool.reserve_b as u128 / total) as u64;
+    if amount_a == 0 && amount_b == 0 {
+        return Err(DeFiHubError::InsufficientLiquidity);
+    }
+
+    pool.reserve_a -= amount_a;
+    pool.reserve_b -= amount_b;
+    pool.total_liquidity -= liquidity;
+    Ok((amount_a, amount_b))
+}
+
+/// 정수 제곱근 (내림)
+fn isqrt(value: u128) -> u128 {
+    if value < 2 {
+        return value;
+    }
+    // 뉴턴 방법 - 초기값은 항상 답 이상
+    let mut x = 1u128 << (value.ilog2() / 2 + 1);
+    loop {
+        let next = (x + value / x) / 2;
+        if next >= x {
+            return x;
+        }
+        x = next;
+    }
+}
+
+fn reserve_overflow() -> DeFiHubError {
+    DeFiHubError::RollupExecution("Pool reserve overflow".to_string())
+}
+
+fn invalid_pair(token_a: &TokenType, token_b: &TokenType) -> DeFiHubError {
+    DeFiHubError::InvalidTokenPair {
+        token_a: token_a.to_string(),
+        token_b: token_b.to_string(),
+    }
+}
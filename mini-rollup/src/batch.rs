@@ -1,45 +1,73 @@
-use shared::{BatchOperation, Operation, StateRoot, ExecutionResult, DeFiResult, DeFiHubError, TokenType};
+use shared::{account_address, BatchOperation, Operation, SignedOperation, StateRoot, ExecutionResult, DeFiResult, DeFiHubError};
+use shared::state::RollupState;
+use crate::executor::RollupExecutor;
+use std::collections::HashMap;
 use chrono::{DateTime, Utc, Duration};
 use uuid::Uuid;
 use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
 use tracing::{info, debug, warn};
 
 /// 30초마다 실행되는 배치 프로세서
 pub struct BatchProcessor {
     /// 대기 중인 작업들
-    pending_operations: VecDeque<Operation>,
+    pending_operations: VecDeque<SignedOperation>,
     
     /// 처리된 배치들 (최근 100개)
     processed_batches: VecDeque<BatchOperation>,
     
-    /// 현재 상태 루트
-    current_state_root: StateRoot,
+    /// 처리된 배치의 작업별 실행 결과
+    execution_results: HashMap<Uuid, Vec<ExecutionResult>>,
+    
+    /// 롤업 상태 (현재 상태 루트 포함)
+    state: RollupState,
     
     /// 다음 배치 처리 시간
     next_batch_time: DateTime<Utc>,
     
     /// 최대 배치 크기
     max_batch_size: usize,
+    
+    /// 배치마다 롤업 상태를 저장하는 파일
+    state_file: Option<PathBuf>,
+}
+
+impl Default for BatchProcessor {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl BatchProcessor {
     /// 새로운 배치 프로세서 생성
     pub fn new() -> Self {
+        Self::with_state(RollupState::new())
+    }
+    
+    /// 기존 롤업 상태에서 이어서 처리하는 배치 프로세서 생성
+    pub fn with_state(state: RollupState) -> Self {
         Self {
             pending_operations: VecDeque::new(),
             processed_batches: VecDeque::new(),
-            current_state_root: StateRoot {
-                hash: [0; 32],
-                height: 0,
-                timestamp: Utc::now(),
-            },
+            execution_results: HashMap::new(),
+            state,
             next_batch_time: Utc::now() + Duration::seconds(30),
             max_batch_size: 1000,
+            state_file: None,
         }
     }
     
+    /// 롤업 상태 파일에서 이어서 처리하고 배치마다 그 파일에 저장하는 배치 프로세서 (파일이 없으면 빈 상태)
+    pub fn open<P: AsRef<Path>>(path: P) -> DeFiResult<Self> {
+        let path = path.as_ref();
+        let state = if path.exists() { RollupState::load_from_file(path)? } else { RollupState::new() };
+        let mut processor = Self::with_state(state);
+        processor.state_file = Some(path.to_path_buf());
+        Ok(processor)
+    }
+    
     /// 작업 추가
-    pub fn add_operation(&mut self, operation: Operation) {
+    pub fn add_operation(&mut self, operation: SignedOperation) {
         debug!("Adding operation to batch queue: {:?}", operation);
         self.pending_operations.push_back(operation);
     }
@@ -68,65 +96,81 @@ impl BatchProcessor {
             }
         }
         
-        // 새로운 상태 루트 계산
-        let new_state_root = self.calculate_new_state_root(&operations)?;
+        // 작업 실행 후 새로운 상태 루트 계산 (배치 시각은 루트의 타임스탬프에만 쓴다)
+        let timestamp = Utc::now();
+        let previous_state_root = self.state.current_state_root.clone();
+        let mut next = self.state.clone();
+        let execution = RollupExecutor::execute_batch(&mut next, &operations, timestamp);
+        let new_state_root = execution.new_state_root;
+        next.current_state_root = new_state_root.clone();
+        
+        // 상태 파일에 저장하지 못하면 작업을 대기열에 되돌리고 상태는 그대로 둔다
+        if let Some(path) = &self.state_file {
+            if let Err(e) = next.save_to_file(path) {
+                for op in operations.into_iter().rev() {
+                    self.pending_operations.push_front(op);
+                }
+                return Err(e);
+            }
+        }
+        let failed = execution.results.iter().filter(|result| !result.success).count();
+        if failed > 0 {
+            warn!("{} of {} operations failed", failed, operations.len());
+        }
         
         // 배치 생성
         let batch = BatchOperation {
             id: Uuid::new_v4(),
             operations,
-            timestamp: Utc::now(),
-            previous_state_root: self.current_state_root.clone(),
+            timestamp,
+            previous_state_root,
             new_state_root: new_state_root.clone(),
             signature: None, // TODO: 서명 추가
         };
         
         // 상태 업데이트
-        self.current_state_root = new_state_root;
+        self.state = next;
         self.next_batch_time = Utc::now() + Duration::seconds(30);
         
         // 처리된 배치 저장 (최근 100개만 유지)
         self.processed_batches.push_back(batch.clone());
+        self.execution_results.insert(batch.id, execution.results);
         if self.processed_batches.len() > 100 {
-            self.processed_batches.pop_front();
+            if let Some(oldest) = self.processed_batches.pop_front() {
+                self.execution_results.remove(&oldest.id);
+            }
         }
         
         info!("Batch processed successfully: {}", batch.id);
         Ok(batch)
     }
     
-    /// 새로운 상태 루트 계산
-    fn calculate_new_state_root(&self, operations: &[Operation]) -> DeFiResult<StateRoot> {
-        use sha2::{Sha256, Digest};
-        
-        let mut hasher = Sha256::new();
-        
-        // 이전 상태 루트 해시
-        hasher.update(&self.current_state_root.hash);
-        
-        // 모든 작업들의 해시
-        for operation in operations {
-            let op_bytes = serde_json::to_vec(operation)
-                .map_err(|e| DeFiHubError::Serialization(e.to_string()))?;
-            hasher.update(&op_bytes);
-        }
-        
-        // 타임스탬프 추가
-        let timestamp = Utc::now();
-        hasher.update(&timestamp.timestamp().to_le_bytes());
+    /// `previous_state_root` 상태에서 배치를 다시 실행해 `new_state_root`를 재현하는지 검증
+    ///
+    /// 성공하면 `state`가 배치 이후 상태가 되고, 실패하면 `state`는 그대로다.
+    pub fn replay_batch(state: &mut RollupState, batch: &BatchOperation) -> DeFiResult<()> {
+        let previous = &batch.previous_state_root;
+        ensure_root(&previous.hash, &state.state_hash())?;
+        ensure_height(previous.height, state.current_state_root.height)?;
         
-        let hash: [u8; 32] = hasher.finalize().into();
+        let mut next = state.clone();
+        let new_state_root = RollupExecutor::execute_batch(&mut next, &batch.operations, batch.timestamp).new_state_root;
+        ensure_root(&batch.new_state_root.hash, &new_state_root.hash)?;
+        ensure_height(batch.new_state_root.height, new_state_root.height)?;
         
-        Ok(StateRoot {
-            hash,
-            height: self.current_state_root.height + 1,
-            timestamp,
-        })
+        *state = next;
+        state.current_state_root = new_state_root;
+        Ok(())
+    }
+    
+    /// 롤업 상태 조회
+    pub fn state(&self) -> &RollupState {
+        &self.state
     }
     
     /// 현재 상태 조회
     pub fn get_current_state(&self) -> &StateRoot {
-        &self.current_state_root
+        &self.state.current_state_root
     }
     
     /// 대기 중인 작업 수
@@ -153,6 +197,11 @@ impl BatchProcessor {
             .collect()
     }
     
+    /// 배치의 작업별 실행 결과 조회
+    pub fn get_execution_results(&self, batch_id: &Uuid) -> Option<&[ExecutionResult]> {
+        self.execution_results.get(batch_id).map(Vec::as_slice)
+    }
+    
     /// 특정 배치 조회
     pub fn get_batch(&self, batch_id: &Uuid) -> Option<&BatchOperation> {
         self.processed_batches
@@ -179,12 +228,34 @@ impl BatchProcessor {
             total_operations,
             pending_operations: self.pending_operations.len(),
             avg_operations_per_batch,
-            current_height: self.current_state_root.height,
+            current_height: self.state.current_state_root.height,
             next_batch_in_seconds: self.time_until_next_batch(),
         }
     }
 }
 
+/// 상태 루트 해시가 기대값과 같은지 확인
+fn ensure_root(expected: &[u8; 32], actual: &[u8; 32]) -> DeFiResult<()> {
+    if expected != actual {
+        return Err(DeFiHubError::InvalidStateRoot {
+            expected: hex::encode(expected),
+            actual: hex::encode(actual),
+        });
+    }
+    Ok(())
+}
+
+/// 상태 루트 높이가 기대값과 같은지 확인
+fn ensure_height(expected: u64, actual: u64) -> DeFiResult<()> {
+    if expected != actual {
+        return Err(DeFiHubError::InvalidStateRoot {
+            expected: format!("height {}", expected),
+            actual: format!("height {}", actual),
+        });
+    }
+    Ok(())
+}
+
 /// 배치 처리 통계
 #[derive(Debug, Clone)]
 pub struct BatchStatistics {
@@ -205,41 +276,81 @@ impl OperationValidator {
         match operation {
             Operation::Deposit { amount, .. } => {
                 if amount.to_sat() == 0 {
-                    return Err(DeFiHubError::Internal("Deposit amount cannot be zero".to_string()));
+                    return Err(DeFiHubError::InvalidAmount("Deposit amount cannot be zero".to_string()));
                 }
             },
             Operation::Withdraw { amount, .. } => {
                 if amount.to_sat() == 0 {
-                    return Err(DeFiHubError::Internal("Withdrawal amount cannot be zero".to_string()));
+                    return Err(DeFiHubError::InvalidAmount("Withdrawal amount cannot be zero".to_string()));
                 }
             },
             Operation::Swap { amount_in, min_amount_out, .. } => {
                 if *amount_in == 0 {
-                    return Err(DeFiHubError::Internal("Swap input amount cannot be zero".to_string()));
+                    return Err(DeFiHubError::InvalidAmount("Swap input amount cannot be zero".to_string()));
                 }
                 if *min_amount_out == 0 {
-                    return Err(DeFiHubError::Internal("Minimum output amount cannot be zero".to_string()));
+                    return Err(DeFiHubError::InvalidAmount("Minimum output amount cannot be zero".to_string()));
                 }
             },
             Operation::ProvideLiquidity { amount_a, amount_b, .. } => {
                 if *amount_a == 0 || *amount_b == 0 {
-                    return Err(DeFiHubError::Internal("Liquidity amounts cannot be zero".to_string()));
+                    return Err(DeFiHubError::InvalidAmount("Liquidity amounts cannot be zero".to_string()));
                 }
             },
+            Operation::RemoveLiquidity { liquidity, .. } => {
+                if *liquidity == 0 {
+                    return Err(DeFiHubError::InvalidAmount("Liquidity to remove cannot be zero".to_string()));
+                }
+            },
+        }
+        
+        Ok(())
+    }
+    
+    /// 서명된 작업 검증 - 서명자가 작업의 서명 계정이고, 서명이 맞고, 논스가 계정의 다음 논스인지
+    pub fn validate_signed_operation(state: &RollupState, signed: &SignedOperation) -> DeFiResult<()> {
+        Self::validate_operation(&signed.operation)?;
+        let account = signed.account();
+        let signer = Self::signing_account(state, &signed.operation)?;
+        if signer != account {
+            return Err(DeFiHubError::InvalidSignature(match signed.operation {
+                Operation::Deposit { .. } => format!("deposits must be signed by the bridge key {}, not {}", signer, account),
+                _ => format!("signer {} does not own account {}", account, signer),
+            }));
         }
+        signed.verify_signature()?;
         
+        // 이미 쓴 논스(재전송)와 건너뛴 논스를 모두 거부한다
+        let expected = state.get_nonce(&account);
+        if signed.nonce != expected {
+            return Err(DeFiHubError::InvalidNonce { expected, actual: signed.nonce });
+        }
         Ok(())
     }
     
-    /// 배치 내 작업들이 충돌하지 않는지 검증
-    pub fn validate_batch_operations(operations: &[Operation]) -> DeFiResult<()> {
+    /// 작업에 서명해야 하는 계정 - 예치는 브릿지 키, 나머지는 잔액이 빠지는 계정
+    pub fn signing_account(state: &RollupState, operation: &Operation) -> DeFiResult<String> {
+        match operation {
+            Operation::Deposit { .. } => state
+                .bridge_key
+                .as_ref()
+                .map(account_address)
+                .ok_or_else(|| DeFiHubError::BridgeVerification("No bridge key is configured for deposits".to_string())),
+            _ => Ok(operation.account().to_string()),
+        }
+    }
+    
+    /// 배치 내 작업들을 순서대로 검증 (같은 계정의 논스는 배치 안에서 이어져야 한다)
+    pub fn validate_batch_operations(state: &RollupState, operations: &[SignedOperation]) -> DeFiResult<()> {
         // TODO: 더 정교한 검증 로직 구현
         // - 이중 지출 검사
         // - 잔액 검증
         // - 의존성 검사 등
         
+        let mut state = state.clone();
         for operation in operations {
-            Self::validate_operation(operation)?;
+            Self::validate_signed_operation(&state, operation)?;
+            state.increment_nonce(&operation.account());
         }
         
         Ok(())
@@ -0,0 +1,286 @@
+//! 롤업 실행기 - 배치의 작업을 `RollupState`에 적용한다
+//!
+//! 작업마다 바꾼 잔액/풀의 이전 값을 기록해 두고, 작업이 실패하면 기록을 거꾸로 되돌린다.
+//! 실패한 작업은 상태를 바꾸지 않고 결과만 남기므로 같은 배치의 다른 작업에 영향을 주지 않는다.
+//! 배치의 작업은 서명된 작업이며, 서명과 논스가 맞으면 실행이 실패해도 논스는 소비된다.
+//! 예치는 롤업에 연결된 금고의 금액과 같아야 하고, 금고마다 한 번만 잔액이 된다.
+
+use crate::amm::{self, PoolKey};
+use crate::batch::OperationValidator;
+use shared::state::{LiquidityPool, RollupState};
+use shared::{DeFiHubError, DeFiResult, Event, ExecutionResult, Operation, SignedOperation, StateRoot, TokenType};
+use chrono::{DateTime, Utc};
+use tracing::{debug, warn};
+
+/// 작업마다 드는 기본 가스
+pub const BASE_GAS: u64 = 1_000;
+
+/// 잔액/풀 쓰기 한 번당 가스
+pub const WRITE_GAS: u64 = 500;
+
+/// 배치 실행 결과
+#[derive(Clone, Debug)]
+pub struct BatchExecution {
+    /// 배치 실행 후 상태 루트
+    pub new_state_root: StateRoot,
+
+    /// 작업별 결과 (배치의 작업 순서)
+    pub results: Vec<ExecutionResult>,
+}
+
+/// 롤업 실행기
+pub struct RollupExecutor;
+
+impl RollupExecutor {
+    /// 배치의 작업들을 순서대로 실행 (`timestamp`는 배치 시각)
+    ///
+    /// 작업별 결과의 `new_state_root`는 배치 전체를 실행한 뒤의 루트다.
+    pub fn execute_batch(state: &mut RollupState, operations: &[SignedOperation], timestamp: DateTime<Utc>) -> BatchExecution {
+        let mut results: Vec<ExecutionResult> = operations
+            .iter()
+            .map(|operation| match Self::execute_signed_operation(state, operation) {
+                Ok((events, gas_used)) => ExecutionResult {
+                    success: true,
+                    gas_used,
+                    new_state_root: state.current_state_root.clone(),
+                    events,
+                    error: None,
+                },
+                Err(err) => {
+                    warn!("Operation failed and was rolled back: {}", err);
+                    ExecutionResult {
+                        success: false,
+                        gas_used: BASE_GAS,
+                        new_state_root: state.current_state_root.clone(),
+                        events: Vec::new(),
+                        error: Some(err.to_string()),
+                    }
+                }
+            })
+            .collect();
+
+        let new_state_root = state.next_state_root(timestamp);
+        for result in &mut results {
+            result.new_state_root = new_state_root.clone();
+        }
+        BatchExecution { new_state_root, results }
+    }
+
+    /// 서명된 작업 하나 실행 - 서명/논스가 틀리면 상태를 바꾸지 않고, 맞으면 논스를 올린 뒤 실행한다
+    ///
+    /// 실행이 실패해도 올린 논스는 되돌리지 않아 같은 서명을 다시 쓸 수 없다.
+    pub fn execute_signed_operation(state: &mut RollupState, signed: &SignedOperation) -> DeFiResult<(Vec<Event>, u64)> {
+        OperationValidator::validate_signed_operation(state, signed)?;
+        state.increment_nonce(&signed.account());
+        Self::execute_operation(state, &signed.operation)
+    }
+
+    /// 인증된 작업 하나 실행 - 성공하면 이벤트와 가스 사용량, 실패하면 상태를 되돌리고 에러
+    pub fn execute_operation(state: &mut RollupState, operation: &Operation) -> DeFiResult<(Vec<Event>, u64)> {
+        debug!("Executing operation: {:?}", operation);
+        let mut execution = Execution::new(state);
+        match execution.apply(operation) {
+            Ok(()) => {
+                let gas_used = BASE_GAS + WRITE_GAS * execution.writes() as u64;
+                Ok((execution.events, gas_used))
+            }
+            Err(err) => {
+                execution.rollback();
+                Err(err)
+            }
+        }
+    }
+}
+
+/// 작업 하나의 실행 중 상태 (되돌리기 기록과 이벤트)
+struct Execution<'a> {
+    state: &'a mut RollupState,
+    balances: Vec<(String, TokenType, Option<u64>)>,
+    pools: Vec<(PoolKey, Option<LiquidityPool>)>,
+    events: Vec<Event>,
+}
+
+impl<'a> Execution<'a> {
+    fn new(state: &'a mut RollupState) -> Self {
+        Self {
+            state,
+            balances: Vec::new(),
+            pools: Vec::new(),
+            events: Vec::new(),
+        }
+    }
+
+    fn apply(&mut self, operation: &Operation) -> DeFiResult<()> {
+        OperationValidator::validate_operation(operation)?;
+        match operation {
+            Operation::Deposit { vault_outpoint, amount, recipient } => {
+                match self.state.bridged_vaults.get(vault_outpoint) {
+                    None => {
+                        return Err(DeFiHubError::BridgeVerification(format!("Vault {} is not bridged", vault_outpoint)));
+                    }
+                    Some(bridged) if bridged != amount => {
+                        return Err(DeFiHubError::BridgeVerification(format!(
+                            "Deposit of {} does not match the {} locked in vault {}",
+                            amount, bridged, vault_outpoint
+                        )));
+                    }
+                    Some(_) => {}
+                }
+                if self.state.consumed_deposits.contains(vault_outpoint) {
+                    return Err(DeFiHubError::BridgeVerification(format!(
+                        "Vault {} was already deposited",
+                        vault_outpoint
+                    )));
+                }
+                self.credit(recipient, &TokenType::WBTC, amount.to_sat())?;
+                self.state.consumed_deposits.insert(*vault_outpoint);
+                self.events.push(Event::Deposit {
+                    user: recipient.clone(),
+                    amount: *amount,
+                    rollup_address: recipient.clone(),
+                });
+            }
+            Operation::Withdraw { rollup_address, amount, destination } => {
+                if destination.is_empty() {
+                    return Err(DeFiHubError::InvalidAddress("Withdrawal destination is empty".to_string()));
+                }
+                self.debit(rollup_address, &TokenType::WBTC, amount.to_sat())?;
+                self.events.push(Event::Withdrawal {
+                    user: rollup_address.clone(),
+                    amount: *amount,
+                    bitcoin_address: destination.clone(),
+                });
+            }
+            Operation::Swap { from_token, to_token, amount_in, min_amount_out, user } => {
+                let (key, mut pool) = self.pool(from_token, to_token)?.ok_or(DeFiHubError::InsufficientLiquidity)?;
+                let quote = amm::swap(&mut pool, from_token, *amount_in, *min_amount_out)?;
+                self.debit(user, from_token, *amount_in)?;
+                self.credit(user, to_token, quote.amount_out)?;
+                self.put_pool(key, pool);
+                self.events.push(Event::Swap {
+                    user: user.clone(),
+                    token_in: from_token.clone(),
+                    token_out: to_token.clone(),
+                    amount_in: *amount_in,
+                    amount_out: quote.amount_out,
+                });
+            }
+            Operation::ProvideLiquidity { token_a, token_b, amount_a, amount_b, provider } => {
+                let (key, mut pool) = match self.pool(token_a, token_b)? {
+                    Some(existing) => existing,
+                    None => ((token_a.clone(), token_b.clone()), amm::new_pool(token_a.clone(), token_b.clone())),
+                };
+                let forward = &key.0 == token_a;
+                let (amount_for_a, amount_for_b) = if forward { (*amount_a, *amount_b) } else { (*amount_b, *amount_a) };
+                let minted = amm::add_liquidity(&mut pool, amount_for_a, amount_for_b)?;
+
+                let lp_token = amm::lp_token(&key);
+                let pool_account = pool_account(&key);
+                for (token, amount) in [(&key.0, minted.amount_a), (&key.1, minted.amount_b)] {
+                    self.debit(provider, token, amount)?;
+                    self.transfer_event(provider, &pool_account, token, amount);
+                }
+                self.credit(provider, &lp_token, minted.liquidity)?;
+                self.transfer_event(&pool_account, provider, &lp_token, minted.liquidity);
+                if minted.locked > 0 {
+                    self.credit(amm::LOCKED_LIQUIDITY_ACCOUNT, &lp_token, minted.locked)?;
+                    self.transfer_event(&pool_account, amm::LOCKED_LIQUIDITY_ACCOUNT, &lp_token, minted.locked);
+                }
+                self.put_pool(key, pool);
+            }
+            Operation::RemoveLiquidity { token_a, token_b, liquidity, provider } => {
+                let (key, mut pool) = self.pool(token_a, token_b)?.ok_or(DeFiHubError::InsufficientLiquidity)?;
+                let lp_token = amm::lp_token(&key);
+                let pool_account = pool_account(&key);
+                self.debit(provider, &lp_token, *liquidity)?;
+                self.transfer_event(provider, &pool_account, &lp_token, *liquidity);
+
+                let (amount_a, amount_b) = amm::remove_liquidity(&mut pool, *liquidity)?;
+                for (token, amount) in [(&key.0, amount_a), (&key.1, amount_b)] {
+                    self.credit(provider, token, amount)?;
+                    self.transfer_event(&pool_account, provider, token, amount);
+                }
+                self.put_pool(key, pool);
+            }
+        }
+        Ok(())
+    }
+
+    /// 토큰쌍의 풀 사본
+    fn pool(&self, token_a: &TokenType, token_b: &TokenType) -> DeFiResult<Option<(PoolKey, LiquidityPool)>> {
+        Ok(amm::find_pool(&self.state.liquidity_pools, token_a, token_b)?.map(|(key, pool)| (key, pool.clone())))
+    }
+
+    fn transfer_event(&mut self, from: &str, to: &str, token: &TokenType, amount: u64) {
+        self.events.push(Event::Transfer {
+            from: from.to_string(),
+            to: to.to_string(),
+            token: token.clone(),
+            amount,
+        });
+    }
+
+    fn credit(&mut self, account: &str, token: &TokenType, amount: u64) -> DeFiResult<()> {
+        let balance = checked_add(self.state.get_balance(account, token), amount)?;
+        self.set_balance(account, token, balance);
+        Ok(())
+    }
+
+    fn debit(&mut self, account: &str, token: &TokenType, amount: u64) -> DeFiResult<()> {
+        let available = self.state.get_balance(account, token);
+        if available < amount {
+            return Err(DeFiHubError::InsufficientFunds { required: amount, available });
+        }
+        self.set_balance(account, token, available - amount);
+        Ok(())
+    }
+
+    fn set_balance(&mut self, account: &str, token: &TokenType, amount: u64) {
+        let previous = self.state.balances.get(account).and_then(|tokens| tokens.get(token)).copied();
+        self.balances.push((account.to_string(), token.clone(), previous));
+        self.state.set_balance(account.to_string(), token.clone(), amount);
+    }
+
+    fn put_pool(&mut self, key: PoolKey, pool: LiquidityPool) {
+        let previous = self.state.liquidity_pools.insert(key.clone(), pool);
+        self.pools.push((key, previous));
+    }
+
+    fn writes(&self) -> usize {
+        self.balances.len() + self.pools.len()
+    }
+
+    /// 기록한 이전 값을 역순으로 되돌린다
+    fn rollback(self) {
+        for (key, previous) in self.pools.into_iter().rev() {
+            match previous {
+                Some(pool) => self.state.liquidity_pools.insert(key, pool),
+                None => self.state.liquidity_pools.remove(&key),
+            };
+        }
+        for (account, token, previous) in self.balances.into_iter().rev() {
+            match previous {
+                Some(amount) => self.state.set_balance(account, token, amount),
+                None => {
+                    if let Some(tokens) = self.state.balances.get_mut(&account) {
+                        tokens.remove(&token);
+                        if tokens.is_empty() {
+                            self.state.balances.remove(&account);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// 이벤트에 쓰는 풀 계정 이름
+fn pool_account(key: &PoolKey) -> String {
+    format!("pool:{}/{}", key.0, key.1)
+}
+
+fn checked_add(balance: u64, amount: u64) -> DeFiResult<u64> {
+    balance
+        .checked_add(amount)
+        .ok_or_else(|| DeFiHubError::RollupExecution("Balance overflow".to_string()))
+}
@@ -1,9 +1,7 @@
+pub mod amm;
 pub mod batch;
 pub mod executor;
-pub mod state;
-pub mod manager;
 
+pub use amm::SwapQuote;
 pub use batch::*;
-pub use executor::*;
-pub use state::*;
-pub use manager::RollupManager;
\ No newline at end of file
+pub use executor::*;
\ No newline at end of file
@@ -0,0 +1,199 @@
+use super::state_root::{
+    account, bridge_deposit, bridged_vault, deposit, keypair, sample_state, signed, vault_outpoint, BRIDGED_VAULTS,
+};
+use chrono::Utc;
+use mini_rollup::{BatchProcessor, OperationValidator, RollupExecutor};
+use shared::state::RollupState;
+use shared::{account_address, DeFiHubError, Operation, SignedOperation, TokenType};
+
+fn swap(user: &str, amount_in: u64) -> Operation {
+    Operation::Swap {
+        from_token: TokenType::WBTC,
+        to_token: TokenType::USDC,
+        amount_in,
+        min_amount_out: 1,
+        user: user.to_string(),
+    }
+}
+
+#[test]
+fn accounts_are_x_only_keys_and_signatures_cover_the_operation() {
+    let alice = account("alice");
+    let public_key = keypair("alice").x_only_public_key().0;
+    assert_eq!(account_address(&public_key), alice);
+    assert_eq!(alice, hex::encode(public_key.serialize()));
+    assert_eq!(alice.len(), 64);
+
+    let operation = signed("alice", swap(&alice, 1_000), 0);
+    assert_eq!(operation.account(), alice);
+    operation.verify_signature().unwrap();
+
+    // 같은 작업과 논스는 같은 서명이 되고, 직렬화해도 검증된다
+    let json = serde_json::to_string(&operation).unwrap();
+    let decoded: SignedOperation = serde_json::from_str(&json).unwrap();
+    decoded.verify_signature().unwrap();
+    assert_eq!(decoded.signature, signed("alice", swap(&alice, 1_000), 0).signature);
+
+    // 금액, 논스, 공개키 중 하나라도 바꾸면 서명이 맞지 않는다
+    let mut tampered = operation.clone();
+    tampered.operation = swap(&alice, 1_001);
+    assert!(matches!(tampered.verify_signature(), Err(DeFiHubError::InvalidSignature(_))));
+    let mut tampered = operation.clone();
+    tampered.nonce = 1;
+    assert!(tampered.verify_signature().is_err());
+    let mut tampered = operation;
+    tampered.public_key = keypair("bob").x_only_public_key().0;
+    assert!(tampered.verify_signature().is_err());
+    assert_ne!(swap(&alice, 1_000).encode(), swap(&account("bob"), 1_000).encode());
+}
+
+#[test]
+fn validator_rejects_foreign_signers_stale_nonces_and_gaps() {
+    let (alice, bob) = (account("alice"), account("bob"));
+    let mut state = sample_state();
+
+    // bob이 alice의 잔액을 쓰는 작업에 서명할 수 없다
+    let err = OperationValidator::validate_signed_operation(&state, &signed("bob", swap(&alice, 1_000), 0)).unwrap_err();
+    assert!(matches!(err, DeFiHubError::InvalidSignature(_)), "{:?}", err);
+
+    // 논스를 건너뛸 수 없다
+    let gap = signed("alice", swap(&alice, 1_000), 1);
+    let err = OperationValidator::validate_signed_operation(&state, &gap).unwrap_err();
+    assert!(matches!(err, DeFiHubError::InvalidNonce { expected: 0, actual: 1 }), "{:?}", err);
+
+    let first = signed("alice", swap(&alice, 1_000), 0);
+    RollupExecutor::execute_signed_operation(&mut state, &first).unwrap();
+    assert_eq!(state.get_nonce(&alice), 1);
+    assert_eq!(state.get_nonce(&bob), 0);
+
+    // 같은 서명을 다시 내면 지난 논스라 거부되고 상태가 바뀌지 않는다
+    let before = state.clone();
+    let err = RollupExecutor::execute_signed_operation(&mut state, &first).unwrap_err();
+    assert!(matches!(err, DeFiHubError::InvalidNonce { expected: 1, actual: 0 }), "{:?}", err);
+    assert_eq!(state.state_hash(), before.state_hash());
+    RollupExecutor::execute_signed_operation(&mut state, &gap).unwrap();
+    assert_eq!(state.get_nonce(&alice), 2);
+
+    // 배치 안에서는 같은 계정의 논스가 이어져야 한다
+    let fresh = sample_state();
+    let batch = [signed("alice", swap(&alice, 10), 0), signed("alice", swap(&alice, 10), 1)];
+    OperationValidator::validate_batch_operations(&fresh, &batch).unwrap();
+    let duplicated = [batch[0].clone(), batch[0].clone()];
+    assert!(matches!(
+        OperationValidator::validate_batch_operations(&fresh, &duplicated),
+        Err(DeFiHubError::InvalidNonce { expected: 1, actual: 0 })
+    ));
+
+    // 0 금액은 프로그램 오류가 아닌 잘못된 입력이다
+    let err = OperationValidator::validate_signed_operation(&fresh, &signed("alice", swap(&alice, 0), 0)).unwrap_err();
+    assert!(matches!(err, DeFiHubError::InvalidAmount(_)), "{:?}", err);
+}
+
+#[test]
+fn captured_operations_cannot_be_replayed_in_later_batches() {
+    let (carol, bridge) = (account("carol"), account("bridge"));
+    let mut processor = BatchProcessor::with_state(sample_state());
+    let claim = bridge_deposit(&carol, 0, 0);
+    processor.add_operation(claim.clone());
+    processor.process_batch().unwrap();
+    assert_eq!(processor.state().get_balance(&carol, &TokenType::WBTC), 50_000);
+
+    // 다음 배치에 같은 작업을 다시 넣어도 실행되지 않는다
+    processor.add_operation(claim);
+    let replayed = processor.process_batch().unwrap();
+    let results = processor.get_execution_results(&replayed.id).unwrap();
+    assert!(!results[0].success);
+    assert!(results[0].error.as_deref().unwrap().contains("Invalid nonce"));
+    assert_eq!(processor.state().get_balance(&carol, &TokenType::WBTC), 50_000);
+    assert_eq!(processor.state().get_nonce(&bridge), 1);
+    assert_eq!(processor.state().get_nonce(&carol), 0);
+
+    // 잘못 서명된 작업은 배치에 넣어도 논스를 소비하지 않는다
+    let mut forged = bridge_deposit(&carol, 1, 1);
+    forged.signature = bridge_deposit(&carol, 1, 2).signature;
+    let mut state = processor.state().clone();
+    let execution = RollupExecutor::execute_batch(&mut state, &[forged], Utc::now());
+    assert!(execution.results[0].error.as_deref().unwrap().contains("Invalid operation signature"));
+    assert_eq!(state.get_nonce(&bridge), 1);
+}
+
+#[test]
+fn deposits_must_be_signed_by_the_bridge() {
+    let carol = account("carol");
+    let mut state = sample_state();
+
+    // 받는 계정이 직접 서명한 예치는 잔액을 만들 수 없다
+    let err = OperationValidator::validate_signed_operation(&state, &signed("carol", deposit(&carol, 0), 0)).unwrap_err();
+    assert!(matches!(err, DeFiHubError::InvalidSignature(ref reason) if reason.contains("bridge key")), "{:?}", err);
+    let execution = RollupExecutor::execute_batch(&mut state, &[signed("carol", deposit(&carol, 0), 0)], Utc::now());
+    assert!(!execution.results[0].success);
+    assert_eq!(state.get_balance(&carol, &TokenType::WBTC), 0);
+    assert_eq!(state.get_nonce(&carol), 0);
+
+    // 브릿지 키가 없으면 예치를 받지 않는다
+    let mut keyless = sample_state();
+    keyless.bridge_key = None;
+    let err = OperationValidator::validate_signed_operation(&keyless, &bridge_deposit(&carol, 0, 0)).unwrap_err();
+    assert!(matches!(err, DeFiHubError::BridgeVerification(_)), "{:?}", err);
+    OperationValidator::validate_signed_operation(&state, &bridge_deposit(&carol, 0, 0)).unwrap();
+}
+
+#[test]
+fn deposits_must_match_an_unconsumed_bridged_vault() {
+    let carol = account("carol");
+    let mut state = sample_state();
+    RollupExecutor::execute_signed_operation(&mut state, &bridge_deposit(&carol, 0, 0)).unwrap();
+    assert_eq!(state.get_balance(&carol, &TokenType::WBTC), 50_000);
+    assert!(state.consumed_deposits.contains(&vault_outpoint(0)));
+
+    // 같은 outpoint를 다시 예치할 수 없다 (브릿지가 새 논스로 서명해도)
+    let root = state.state_hash();
+    let err = RollupExecutor::execute_signed_operation(&mut state, &bridge_deposit(&carol, 0, 1)).unwrap_err();
+    assert!(matches!(err, DeFiHubError::BridgeVerification(ref reason) if reason.contains("already deposited")), "{:?}", err);
+    assert_eq!(state.get_balance(&carol, &TokenType::WBTC), 50_000);
+
+    // 연결되지 않은 금고나 잠긴 금액과 다른 예치도 거부한다
+    let unknown = bridge_deposit(&carol, BRIDGED_VAULTS, 2);
+    let err = RollupExecutor::execute_signed_operation(&mut state, &unknown).unwrap_err();
+    assert!(matches!(err, DeFiHubError::BridgeVerification(ref reason) if reason.contains("not bridged")), "{:?}", err);
+    let inflated = signed(
+        "bridge",
+        Operation::Deposit {
+            vault_outpoint: vault_outpoint(1),
+            amount: bitcoin::Amount::from_sat(50_001),
+            recipient: carol.clone(),
+        },
+        3,
+    );
+    let err = RollupExecutor::execute_signed_operation(&mut state, &inflated).unwrap_err();
+    assert!(matches!(err, DeFiHubError::BridgeVerification(ref reason) if reason.contains("does not match")), "{:?}", err);
+    assert_eq!(state.get_balance(&carol, &TokenType::WBTC), 50_000);
+    assert_eq!(state.consumed_deposits.len(), 1);
+
+    // 실패한 예치는 논스만 올리고 소비 집합은 그대로라 루트에는 논스만 반영된다
+    let mut expected = state.clone();
+    expected.nonces.insert(account("bridge"), 1);
+    assert_eq!(expected.state_hash(), root);
+
+    // `Bridged`가 아닌 금고나 금액이 다른 재등록은 받지 않는다
+    let mut vault = bridged_vault(vault_outpoint(0), 40_000);
+    assert!(matches!(state.register_bridged_vault(&vault), Err(DeFiHubError::BridgeVerification(_))));
+    vault.state = shared::VaultState::Inactive;
+    vault.outpoint = vault_outpoint(BRIDGED_VAULTS);
+    assert!(matches!(state.register_bridged_vault(&vault), Err(DeFiHubError::InvalidVaultState { .. })));
+}
+
+#[test]
+fn states_saved_without_nonces_still_load() {
+    let mut state = sample_state();
+    state.balances.remove(&account("bob"));
+    let saved_hash = state.state_hash();
+    state.increment_nonce(&account("alice"));
+    let mut json = serde_json::to_value(&state).unwrap();
+    assert_eq!(json["nonces"][account("alice")], 1);
+
+    json.as_object_mut().unwrap().remove("nonces");
+    let loaded: RollupState = serde_json::from_value(json).unwrap();
+    assert!(loaded.nonces.is_empty());
+    assert_eq!(loaded.state_hash(), saved_hash);
+}
@@ -0,0 +1,95 @@
+use super::state_root::{pool, sample_state};
+use mini_rollup::amm::{self, LiquidityMint, SwapQuote};
+use shared::{DeFiHubError, TokenType, MIN_LIQUIDITY_AMOUNT};
+
+#[test]
+fn quotes_use_integer_constant_product_with_fees() {
+    let state = sample_state();
+    let quote = amm::quote(&state, &TokenType::WBTC, &TokenType::USDC, 1_000).unwrap();
+    assert_eq!(
+        quote,
+        SwapQuote {
+            token_in: TokenType::WBTC,
+            token_out: TokenType::USDC,
+            amount_in: 1_000,
+            amount_out: 27_198,
+            fee: 3,
+            reserve_in: 10_000,
+            reserve_out: 300_000,
+            price_impact_bps: 934,
+        }
+    );
+    assert_eq!(amm::fee_bps(0.003), 30);
+    assert_eq!(amm::get_amount_out(100, 10_000, 300_000, 0).unwrap(), 2_970);
+
+    // 견적은 상태를 바꾸지 않고, 반대 방향도 같은 풀로 계산한다
+    assert_eq!(state.liquidity_pools[&(TokenType::WBTC, TokenType::USDC)].reserve_a, 10_000);
+    let reverse = amm::quote(&state, &TokenType::USDC, &TokenType::WBTC, 1_200).unwrap();
+    assert_eq!((reverse.amount_out, reverse.reserve_in), (39, 300_000));
+    let price = amm::spot_price(&state.liquidity_pools[&(TokenType::WBTC, TokenType::USDC)], &TokenType::WBTC);
+    assert_eq!(price, Some(30.0));
+}
+
+#[test]
+fn quotes_reject_missing_or_exhausted_liquidity() {
+    let state = sample_state();
+    let cat = TokenType::Custom("CAT".to_string());
+    assert!(matches!(amm::quote(&state, &cat, &TokenType::WBTC, 1), Err(DeFiHubError::InsufficientLiquidity)));
+    assert!(matches!(amm::quote(&state, &cat, &cat, 1), Err(DeFiHubError::InvalidTokenPair { .. })));
+
+    // 출력이 0으로 내림되거나 준비금이 비면 유동성 부족이다
+    assert!(matches!(amm::quote(&state, &TokenType::USDC, &TokenType::WBTC, 30), Err(DeFiHubError::InsufficientLiquidity)));
+    assert!(matches!(amm::get_amount_out(1_000, 0, 300_000, 30), Err(DeFiHubError::InsufficientLiquidity)));
+    assert!(matches!(amm::quote_pool(&pool(1, 1), &cat, 1), Err(DeFiHubError::InvalidTokenPair { .. })));
+}
+
+#[test]
+fn swaps_enforce_slippage_and_never_shrink_k() {
+    let mut pool = pool(10_000, 300_000);
+    let before = pool.clone();
+    let err = amm::swap(&mut pool, &TokenType::WBTC, 1_000, 27_199).unwrap_err();
+    assert!(matches!(err, DeFiHubError::SlippageExceeded { expected: 27_199, actual: 27_198 }), "{:?}", err);
+    assert_eq!((pool.reserve_a, pool.reserve_b), (before.reserve_a, before.reserve_b));
+
+    let mut k = pool.reserve_a as u128 * pool.reserve_b as u128;
+    for (token_in, amount_in) in [(TokenType::WBTC, 1_000), (TokenType::USDC, 50_000), (TokenType::WBTC, 7), (TokenType::USDC, 1)] {
+        let quote = amm::quote_pool(&pool, &token_in, amount_in);
+        match amm::swap(&mut pool, &token_in, amount_in, 1) {
+            Ok(executed) => assert_eq!(executed, quote.unwrap()),
+            Err(err) => assert!(matches!(err, DeFiHubError::InsufficientLiquidity), "{:?}", err),
+        }
+        let next = pool.reserve_a as u128 * pool.reserve_b as u128;
+        assert!(next >= k, "k shrank after swapping {} {}", amount_in, token_in);
+        k = next;
+    }
+}
+
+#[test]
+fn lp_shares_follow_geometric_mean_and_ratio_rules() {
+    let mut pool = amm::new_pool(TokenType::WBTC, TokenType::USDC);
+    assert!(amm::add_liquidity(&mut pool, 1_000, 1_000).is_err());
+    assert_eq!(pool.total_liquidity, 0);
+
+    let first = amm::add_liquidity(&mut pool, 10_000, 300_000).unwrap();
+    assert_eq!(first, LiquidityMint { amount_a: 10_000, amount_b: 300_000, liquidity: 53_772, locked: MIN_LIQUIDITY_AMOUNT });
+    assert_eq!(pool.total_liquidity, 54_772);
+
+    // 비율보다 많은 쪽은 받지 않는다
+    let second = amm::add_liquidity(&mut pool, 1_000, 90_000).unwrap();
+    assert_eq!((second.amount_a, second.amount_b, second.liquidity, second.locked), (1_000, 30_000, 5_477, 0));
+    assert!(matches!(amm::add_liquidity(&mut pool, 1, 1), Err(DeFiHubError::RollupExecution(_))));
+
+    // 스왑 수수료가 준비금에 쌓여 지분당 sqrt(a * b)가 커진다
+    let before = (pool.reserve_a as u128 * pool.reserve_b as u128, pool.total_liquidity as u128);
+    let quote = amm::swap(&mut pool, &TokenType::WBTC, 2_000, 1).unwrap();
+    amm::swap(&mut pool, &TokenType::USDC, quote.amount_out, 1).unwrap();
+    let after = (pool.reserve_a as u128 * pool.reserve_b as u128, pool.total_liquidity as u128);
+    assert_eq!(before.1, after.1);
+    assert!(after.0 > before.0);
+
+    let (amount_a, amount_b) = amm::remove_liquidity(&mut pool, first.liquidity).unwrap();
+    assert!(amount_a as u128 * amount_b as u128 > 10_000u128 * 300_000 * 53_772 / 54_772 * 53_772 / 54_772);
+    assert_eq!(pool.total_liquidity, MIN_LIQUIDITY_AMOUNT + second.liquidity);
+    let too_much = pool.total_liquidity + 1;
+    assert!(matches!(amm::remove_liquidity(&mut pool, too_much), Err(DeFiHubError::InsufficientLiquidity)));
+}
@@ -0,0 +1,194 @@
+use super::state_root::{account, bridge_deposit, deposit, sample_state, signed};
+use bitcoin::Amount;
+use chrono::Utc;
+use mini_rollup::{amm, BatchProcessor, RollupExecutor, BASE_GAS, WRITE_GAS};
+use shared::{DeFiHubError, Event, Operation, TokenType, MIN_LIQUIDITY_AMOUNT};
+
+fn withdraw(account: &str, sats: u64) -> Operation {
+    Operation::Withdraw {
+        rollup_address: account.to_string(),
+        amount: Amount::from_sat(sats),
+        destination: "bcrt1qdestination".to_string(),
+    }
+}
+
+fn swap(user: &str, from: TokenType, to: TokenType, amount_in: u64, min_amount_out: u64) -> Operation {
+    Operation::Swap {
+        from_token: from,
+        to_token: to,
+        amount_in,
+        min_amount_out,
+        user: user.to_string(),
+    }
+}
+
+#[test]
+fn deposits_and_withdrawals_move_wbtc_balances() {
+    let mut state = sample_state();
+    let (events, gas_used) = RollupExecutor::execute_operation(&mut state, &deposit("carol", 0)).unwrap();
+    assert_eq!(state.get_balance("carol", &TokenType::WBTC), 50_000);
+    assert_eq!(gas_used, BASE_GAS + WRITE_GAS);
+    assert!(matches!(&events[..], [Event::Deposit { rollup_address, amount, .. }]
+        if rollup_address == "carol" && *amount == Amount::from_sat(50_000)));
+
+    let (events, _) = RollupExecutor::execute_operation(&mut state, &withdraw("carol", 20_000)).unwrap();
+    assert_eq!(state.get_balance("carol", &TokenType::WBTC), 30_000);
+    assert!(matches!(&events[..], [Event::Withdrawal { user, bitcoin_address, .. }]
+        if user == "carol" && bitcoin_address == "bcrt1qdestination"));
+
+    // 잔액보다 많이 출금할 수 없다
+    let err = RollupExecutor::execute_operation(&mut state, &withdraw("carol", 30_001)).unwrap_err();
+    assert!(matches!(err, DeFiHubError::InsufficientFunds { required: 30_001, available: 30_000 }), "{:?}", err);
+    assert!(RollupExecutor::execute_operation(&mut state, &withdraw("dave", 1)).is_err());
+    assert!(!state.balances.contains_key("dave"));
+    assert_eq!(state.get_balance("carol", &TokenType::WBTC), 30_000);
+}
+
+#[test]
+fn swaps_follow_constant_product_in_both_directions() {
+    let (alice, bob) = (account("alice"), account("bob"));
+    let mut state = sample_state();
+    let (events, _) =
+        RollupExecutor::execute_operation(&mut state, &swap(&alice, TokenType::WBTC, TokenType::USDC, 1_000, 27_000)).unwrap();
+    assert!(matches!(&events[..], [Event::Swap { amount_in: 1_000, amount_out: 27_198, .. }]));
+    assert_eq!(state.get_balance(&alice, &TokenType::WBTC), 49_000);
+    assert_eq!(state.get_balance(&alice, &TokenType::USDC), 1_200 + 27_198);
+    let pool = &state.liquidity_pools[&(TokenType::WBTC, TokenType::USDC)];
+    assert_eq!((pool.reserve_a, pool.reserve_b), (11_000, 300_000 - 27_198));
+
+    // 풀이 (WBTC, USDC)로 저장되어 있어도 반대 방향으로 스왑한다
+    let mut state = sample_state();
+    RollupExecutor::execute_operation(&mut state, &swap(&alice, TokenType::USDC, TokenType::WBTC, 1_200, 1)).unwrap();
+    let pool = &state.liquidity_pools[&(TokenType::WBTC, TokenType::USDC)];
+    assert_eq!((pool.reserve_a, pool.reserve_b), (10_000 - 39, 301_200));
+    assert_eq!(state.get_balance(&alice, &TokenType::WBTC), 50_039);
+
+    // 최소 출력량을 못 채우거나 풀이 없으면 실패한다
+    let err = RollupExecutor::execute_operation(&mut state, &swap(&alice, TokenType::WBTC, TokenType::USDC, 1_000, 30_000))
+        .unwrap_err();
+    assert!(matches!(err, DeFiHubError::SlippageExceeded { expected: 30_000, .. }), "{:?}", err);
+    let cat = TokenType::Custom("CAT".to_string());
+    assert!(matches!(
+        RollupExecutor::execute_operation(&mut state, &swap(&bob, cat.clone(), TokenType::WBTC, 7, 1)),
+        Err(DeFiHubError::InsufficientLiquidity)
+    ));
+    assert!(matches!(
+        RollupExecutor::execute_operation(&mut state, &swap(&bob, cat.clone(), cat, 7, 1)),
+        Err(DeFiHubError::InvalidTokenPair { .. })
+    ));
+}
+
+#[test]
+fn liquidity_providers_mint_and_burn_lp_shares() {
+    let bob = account("bob");
+    let mut state = sample_state();
+    let cat = TokenType::Custom("CAT".to_string());
+    state.set_balance(bob.clone(), cat.clone(), 20_000);
+    state.set_balance(bob.clone(), TokenType::WBTC, 50_000);
+    let provide = |token_a: TokenType, token_b: TokenType, amount_a, amount_b| Operation::ProvideLiquidity {
+        token_a,
+        token_b,
+        amount_a,
+        amount_b,
+        provider: bob.clone(),
+    };
+    let remove = |liquidity| Operation::RemoveLiquidity {
+        token_a: TokenType::WBTC,
+        token_b: cat.clone(),
+        liquidity,
+        provider: bob.clone(),
+    };
+    let lp = amm::lp_token(&(cat.clone(), TokenType::WBTC));
+    assert_eq!(lp, TokenType::Custom("LP:CAT/WBTC".to_string()));
+
+    // 첫 공급: sqrt(10,000 * 40,000) = 20,000 중 1,000은 잠근다
+    let (events, _) = RollupExecutor::execute_operation(&mut state, &provide(cat.clone(), TokenType::WBTC, 10_000, 40_000)).unwrap();
+    assert_eq!(state.get_balance(&bob, &lp), 19_000);
+    assert_eq!(state.get_balance(amm::LOCKED_LIQUIDITY_ACCOUNT, &lp), MIN_LIQUIDITY_AMOUNT);
+    assert!(matches!(&events[..], [
+        Event::Transfer { to, amount: 10_000, .. },
+        Event::Transfer { amount: 40_000, .. },
+        Event::Transfer { from, amount: 19_000, .. },
+        Event::Transfer { amount: MIN_LIQUIDITY_AMOUNT, .. },
+    ] if to == "pool:CAT/WBTC" && from == "pool:CAT/WBTC"));
+
+    // 반대 순서로 공급해도 같은 풀에 비율만큼만 넣는다 (CAT 5,000 중 2,000만 쓴다)
+    RollupExecutor::execute_operation(&mut state, &provide(TokenType::WBTC, cat.clone(), 8_000, 5_000)).unwrap();
+    let pool = &state.liquidity_pools[&(cat.clone(), TokenType::WBTC)];
+    assert_eq!((pool.reserve_a, pool.reserve_b, pool.total_liquidity), (12_000, 48_000, 24_000));
+    assert!(!state.liquidity_pools.contains_key(&(TokenType::WBTC, cat.clone())));
+    assert_eq!(state.get_balance(&bob, &cat), 8_000);
+    assert_eq!(state.get_balance(&bob, &TokenType::WBTC), 2_000);
+    assert_eq!(state.get_balance(&bob, &lp), 23_000);
+
+    // 가진 지분보다 많이 소각할 수 없고, 전부 소각하면 잠근 지분만 남는다
+    let err = RollupExecutor::execute_operation(&mut state, &remove(23_001)).unwrap_err();
+    assert!(matches!(err, DeFiHubError::InsufficientFunds { required: 23_001, available: 23_000 }), "{:?}", err);
+    RollupExecutor::execute_operation(&mut state, &remove(23_000)).unwrap();
+    assert_eq!(state.get_balance(&bob, &lp), 0);
+    assert_eq!(state.get_balance(&bob, &cat), 8_000 + 11_500);
+    assert_eq!(state.get_balance(&bob, &TokenType::WBTC), 2_000 + 46_000);
+    let pool = &state.liquidity_pools[&(cat.clone(), TokenType::WBTC)];
+    assert_eq!((pool.reserve_a, pool.reserve_b, pool.total_liquidity), (500, 2_000, MIN_LIQUIDITY_AMOUNT));
+
+    // 첫 공급이 잠금 지분을 넘지 못하면 실패한다
+    let err = RollupExecutor::execute_operation(&mut state, &provide(cat.clone(), TokenType::USDC, 1_000, 1_000)).unwrap_err();
+    assert!(matches!(err, DeFiHubError::RollupExecution(_)), "{:?}", err);
+    assert!(!state.liquidity_pools.contains_key(&(cat, TokenType::USDC)));
+}
+
+#[test]
+fn failed_operations_roll_back_without_affecting_the_batch() {
+    let (alice, carol) = (account("alice"), account("carol"));
+    let mut state = sample_state();
+    let before = state.clone();
+    let operations = vec![
+        bridge_deposit(&carol, 0, 0),
+        // USDC가 부족해 WBTC를 뺀 뒤 실패한다 - WBTC와 풀이 원래대로 돌아와야 한다
+        signed(
+            "alice",
+            Operation::ProvideLiquidity {
+                token_a: TokenType::WBTC,
+                token_b: TokenType::USDC,
+                amount_a: 1_000,
+                amount_b: 5_000,
+                provider: alice.clone(),
+            },
+            0,
+        ),
+        signed("alice", swap(&alice, TokenType::WBTC, TokenType::USDC, 1_000, 1_000_000), 1),
+        signed("carol", withdraw(&carol, 10_000), 0),
+        signed("carol", withdraw(&carol, 0), 1),
+    ];
+    let execution = RollupExecutor::execute_batch(&mut state, &operations, Utc::now());
+
+    let success: Vec<bool> = execution.results.iter().map(|result| result.success).collect();
+    assert_eq!(success, [true, false, false, true, false]);
+    assert!(execution.results[1].error.as_deref().unwrap().contains("Insufficient funds"));
+    assert!(execution.results[1].events.is_empty());
+    assert_eq!(execution.results[1].gas_used, BASE_GAS);
+    assert!(execution.results.iter().all(|result| result.new_state_root == execution.new_state_root));
+
+    assert_eq!(state.get_balance(&carol, &TokenType::WBTC), 40_000);
+    assert_eq!(state.get_balance(&alice, &TokenType::WBTC), 50_000);
+    assert_eq!(state.balances[&alice], before.balances[&alice]);
+    // 실행에 실패해도 서명이 맞는 작업은 논스를 소비하고, 검증에 실패한 작업은 소비하지 않는다
+    assert_eq!((state.get_nonce(&alice), state.get_nonce(&carol)), (2, 1));
+    let pool = &state.liquidity_pools[&(TokenType::WBTC, TokenType::USDC)];
+    assert_eq!((pool.reserve_a, pool.reserve_b), (10_000, 300_000));
+    assert_eq!(execution.new_state_root.hash, state.state_hash());
+    assert_eq!(execution.new_state_root.height, 1);
+
+    // 처리기도 같은 결과를 남기고, 재실행하면 실패한 작업까지 그대로 재현한다
+    let mut processor = BatchProcessor::with_state(before.clone());
+    for operation in operations {
+        processor.add_operation(operation);
+    }
+    let batch = processor.process_batch().unwrap();
+    assert_eq!(batch.new_state_root.hash, execution.new_state_root.hash);
+    let results = processor.get_execution_results(&batch.id).unwrap();
+    assert_eq!(results.iter().filter(|result| result.success).count(), 2);
+    let mut replica = before;
+    BatchProcessor::replay_batch(&mut replica, &batch).unwrap();
+    assert_eq!(replica.state_hash(), processor.state().state_hash());
+}
@@ -0,0 +1,9 @@
+//! mini-rollup 통합 테스트
+//!
+//! 실행: `cargo test -p mini-rollup --test integration`
+
+mod accounts;
+mod amm;
+mod executor;
+mod persistence;
+mod state_root;
@@ -0,0 +1,156 @@
+use super::state_root::{account, bridge_deposit, deposit, sample_state};
+use mini_rollup::BatchProcessor;
+use shared::persistence::{Versioned, SCHEMA_VERSION_KEY};
+use shared::state::{GlobalState, LiquidityPool, RollupState};
+use shared::TokenType;
+use std::path::Path;
+
+fn read_json(path: &Path) -> serde_json::Value {
+    serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+}
+
+/// 서명된 작업 이전 형식의 처리 배치 (작업이 `Operation` 그대로 들어 있다)
+fn unsigned_batch(state: &RollupState) -> serde_json::Value {
+    serde_json::json!({
+        "id": "00000000-0000-0000-0000-000000000001",
+        "operations": [deposit(&account("carol"), 0)],
+        "timestamp": state.current_state_root.timestamp,
+        "previous_state_root": state.current_state_root,
+        "new_state_root": state.current_state_root,
+        "signature": null,
+    })
+}
+
+#[test]
+fn batches_of_unsigned_operations_are_dropped_on_migration() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut state = sample_state();
+    state.balances.remove(&account("bob"));
+
+    let rollup_path = dir.path().join("rollup_state.json");
+    let mut json = serde_json::to_value(&state).unwrap();
+    json["processed_batches"] = serde_json::json!([unsigned_batch(&state)]);
+    json[SCHEMA_VERSION_KEY] = 1.into();
+    std::fs::write(&rollup_path, json.to_string()).unwrap();
+    let loaded = RollupState::load_from_file(&rollup_path).unwrap();
+    assert!(loaded.processed_batches.is_empty());
+    assert_eq!(loaded.state_hash(), state.state_hash());
+    assert_eq!(loaded.get_balance(&account("alice"), &TokenType::WBTC), 50_000);
+
+    // 글로벌 상태 파일 안의 롤업 상태도 같다
+    let state_path = dir.path().join("state.json");
+    let mut global = GlobalState::new();
+    global.rollup = state.clone();
+    let mut json = serde_json::to_value(&global).unwrap();
+    json["rollup"]["processed_batches"] = serde_json::json!([unsigned_batch(&state)]);
+    json[SCHEMA_VERSION_KEY] = 2.into();
+    std::fs::write(&state_path, json.to_string()).unwrap();
+    let loaded = GlobalState::load_from_file(state_path.to_str().unwrap()).unwrap();
+    assert!(loaded.rollup.processed_batches.is_empty());
+    assert_eq!(loaded.rollup.state_hash(), state.state_hash());
+
+    // 마이그레이션 없이는 예전 배치를 읽을 수 없다
+    json[SCHEMA_VERSION_KEY] = GlobalState::SCHEMA_VERSION.into();
+    std::fs::write(&state_path, json.to_string()).unwrap();
+    assert!(GlobalState::load_from_file(state_path.to_str().unwrap()).is_err());
+}
+
+#[test]
+fn rollup_pools_persist_as_list() {
+    let dir = tempfile::tempdir().unwrap();
+    let state_path = dir.path().join("state.json");
+    let mut state = GlobalState::new();
+    let pool = LiquidityPool {
+        token_a: TokenType::WBTC,
+        token_b: TokenType::Custom("CAT".to_string()),
+        reserve_a: 10_000,
+        reserve_b: 5_000,
+        total_liquidity: 7_071,
+        fee_rate: 0.003,
+    };
+    state.rollup.liquidity_pools.insert((pool.token_a.clone(), pool.token_b.clone()), pool);
+    state.rollup.set_balance("alice".to_string(), TokenType::USDC, 42);
+    state.save_to_file(state_path.to_str().unwrap()).unwrap();
+
+    let json = read_json(&state_path);
+    assert_eq!(json["rollup"]["liquidity_pools"][0]["reserve_a"], 10_000);
+    let loaded = GlobalState::load_from_file(state_path.to_str().unwrap()).unwrap();
+    assert_eq!(loaded.rollup.state_hash(), state.rollup.state_hash());
+    assert_eq!(loaded.rollup.liquidity_pools[&(TokenType::WBTC, TokenType::Custom("CAT".to_string()))].reserve_b, 5_000);
+
+    // 버전 1 파일의 빈 풀 맵은 목록으로 바뀐다
+    let mut json = serde_json::to_value(GlobalState::new()).unwrap();
+    json["rollup"]["liquidity_pools"] = serde_json::json!({});
+    json[SCHEMA_VERSION_KEY] = 1.into();
+    std::fs::write(&state_path, json.to_string()).unwrap();
+    assert!(GlobalState::load_from_file(state_path.to_str().unwrap()).unwrap().rollup.liquidity_pools.is_empty());
+
+    // 롤업 상태만 따로 저장할 수도 있다
+    let rollup_path = dir.path().join("rollup_state.json");
+    state.rollup.save_to_file(&rollup_path).unwrap();
+    assert_eq!(read_json(&rollup_path)[SCHEMA_VERSION_KEY], RollupState::SCHEMA_VERSION);
+    assert_eq!(RollupState::load_from_file(&rollup_path).unwrap().state_hash(), state.rollup.state_hash());
+}
+
+#[test]
+fn batch_processor_saves_state_after_each_batch() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("rollup_state.json");
+    sample_state().save_to_file(&path).unwrap();
+
+    let mut processor = BatchProcessor::open(&path).unwrap();
+    assert_eq!(processor.state().state_hash(), sample_state().state_hash());
+    processor.add_operation(bridge_deposit(&account("carol"), 2, 0));
+    let batch = processor.process_batch().unwrap();
+    let saved = RollupState::load_from_file(&path).unwrap();
+    assert_eq!(saved.current_state_root, batch.new_state_root);
+    assert_eq!(saved.get_balance(&account("carol"), &TokenType::WBTC), 50_000);
+    assert_eq!(saved.get_balance(&account("bob"), &TokenType::Custom("CAT".to_string())), 7);
+
+    // 다시 열면 저장된 상태에서 이어서 처리한다
+    let mut reopened = BatchProcessor::open(&path).unwrap();
+    reopened.add_operation(bridge_deposit(&account("carol"), 3, 1));
+    let next = reopened.process_batch().unwrap();
+    assert_eq!(next.previous_state_root, batch.new_state_root);
+    assert_eq!(RollupState::load_from_file(&path).unwrap().get_balance(&account("carol"), &TokenType::WBTC), 100_000);
+
+    // 저장하지 못한 배치는 상태를 바꾸지 않고 작업을 대기열에 되돌린다
+    let mut broken = BatchProcessor::open(dir.path().join("missing").join("rollup_state.json")).unwrap();
+    broken.add_operation(bridge_deposit(&account("carol"), 2, 0));
+    assert!(broken.process_batch().is_err());
+    assert_eq!(broken.pending_operations_count(), 1);
+    assert_eq!(broken.get_current_state().height, 0);
+}
+
+#[test]
+fn custom_token_balances_round_trip_exactly() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("rollup_state.json");
+    let mut state = sample_state();
+    let alice = account("alice");
+    let tokens = [
+        TokenType::Custom("wbtc".to_string()),
+        TokenType::Custom("USDC".to_string()),
+        TokenType::Custom(String::new()),
+        TokenType::Custom("custom:CAT".to_string()),
+        mini_rollup::amm::lp_token(&(TokenType::WBTC, TokenType::USDC)),
+    ];
+    for (amount, token) in (1..).zip(&tokens) {
+        state.set_balance(alice.clone(), token.clone(), amount);
+    }
+    state.save_to_file(&path).unwrap();
+
+    // 내장 토큰과 이름이 겹치는 커스텀 토큰도 섞이지 않고, 상태 루트도 그대로다
+    let loaded = RollupState::load_from_file(&path).unwrap();
+    assert_eq!(loaded.balances, state.balances);
+    assert_eq!(loaded.get_balance(&alice, &TokenType::WBTC), 50_000);
+    assert_eq!(loaded.state_hash(), state.state_hash());
+    assert_eq!(read_json(&path)["balances"][&alice]["WBTC"], 50_000);
+    assert_eq!(read_json(&path)["balances"][&alice]["custom:wbtc"], 1);
+
+    // 알 수 없는 토큰 키는 읽지 않는다
+    let mut json = read_json(&path);
+    json["balances"][&alice]["DOGE"] = 1.into();
+    std::fs::write(&path, json.to_string()).unwrap();
+    assert!(shared::persistence::load_exact::<RollupState>(&path, None).is_err());
+}
@@ -0,0 +1,299 @@
+use bitcoin::hashes::Hash;
+use bitcoin::secp256k1::{Keypair, Secp256k1};
+use bitcoin::{Amount, OutPoint, Txid};
+use chrono::Utc;
+use mini_rollup::BatchProcessor;
+use shared::merkle::{self, EMPTY_HASH};
+use shared::state::{LiquidityPool, RollupState, VaultInfo};
+use shared::{
+    account_address, DeFiHubError, MerkleProof, Operation, SignedOperation, SparseMerkleTree, TokenType, VaultState,
+};
+
+/// 이름에서 정한 테스트 계정 키
+pub fn keypair(name: &str) -> Keypair {
+    Keypair::from_seckey_slice(&Secp256k1::new(), &merkle::hash_parts(&[name.as_bytes()])).unwrap()
+}
+
+/// 이름의 테스트 계정 주소
+pub fn account(name: &str) -> String {
+    account_address(&keypair(name).x_only_public_key().0)
+}
+
+/// 이름의 계정 키로 작업에 서명
+pub fn signed(name: &str, operation: Operation, nonce: u64) -> SignedOperation {
+    SignedOperation::sign(operation, nonce, &keypair(name))
+}
+
+pub fn pool(reserve_a: u64, reserve_b: u64) -> LiquidityPool {
+    LiquidityPool {
+        token_a: TokenType::WBTC,
+        token_b: TokenType::USDC,
+        reserve_a,
+        reserve_b,
+        total_liquidity: 1_000,
+        fee_rate: 0.003,
+    }
+}
+
+/// 테스트용 브릿지 금고 수 (`[1; 32]:0` ~ `[1; 32]:3`, 각 50,000 sat)
+pub const BRIDGED_VAULTS: u32 = 4;
+
+pub fn vault_outpoint(vout: u32) -> OutPoint {
+    OutPoint::new(Txid::from_byte_array([1; 32]), vout)
+}
+
+/// `vout`번 브릿지 금고의 예치
+pub fn deposit(recipient: &str, vout: u32) -> Operation {
+    Operation::Deposit {
+        vault_outpoint: vault_outpoint(vout),
+        amount: Amount::from_sat(50_000),
+        recipient: recipient.to_string(),
+    }
+}
+
+/// 브릿지 키로 서명한 예치
+pub fn bridge_deposit(recipient: &str, vout: u32, nonce: u64) -> SignedOperation {
+    signed("bridge", deposit(recipient, vout), nonce)
+}
+
+/// 롤업에 연결된 금고 정보
+pub fn bridged_vault(outpoint: OutPoint, sats: u64) -> VaultInfo {
+    VaultInfo {
+        outpoint,
+        amount: Amount::from_sat(sats),
+        state: VaultState::Bridged {
+            rollup_state_root: RollupState::new().current_state_root,
+            last_sync: Utc::now(),
+        },
+        owner: account("owner"),
+        created_at: Utc::now(),
+        utxos: Vec::new(),
+        previous_outpoints: Vec::new(),
+    }
+}
+
+pub fn sample_state() -> RollupState {
+    let (alice, bob) = (account("alice"), account("bob"));
+    let mut state = RollupState::new();
+    state.set_balance(alice.clone(), TokenType::WBTC, 50_000);
+    state.set_balance(alice.clone(), TokenType::USDC, 1_200);
+    state.set_balance(bob.clone(), TokenType::Custom("CAT".to_string()), 7);
+    state.liquidity_pools.insert((TokenType::WBTC, TokenType::USDC), pool(10_000, 300_000));
+    state.bridge_key = Some(keypair("bridge").x_only_public_key().0);
+    for vout in 0..BRIDGED_VAULTS {
+        state.register_bridged_vault(&bridged_vault(vault_outpoint(vout), 50_000)).unwrap();
+    }
+    state.current_state_root.hash = state.state_hash();
+    state
+}
+
+#[test]
+fn sparse_merkle_root_depends_only_on_leaves() {
+    let mut tree = SparseMerkleTree::new();
+    assert_eq!(tree.root(), EMPTY_HASH);
+
+    tree.insert([0x80; 32], [1; 32]);
+    tree.insert([0x01; 32], [2; 32]);
+    let root = tree.root();
+    assert_ne!(root, EMPTY_HASH);
+
+    // 삽입 순서와 무관하다
+    let mut reversed = SparseMerkleTree::new();
+    reversed.insert([0x01; 32], [2; 32]);
+    reversed.insert([0x80; 32], [1; 32]);
+    assert_eq!(reversed.root(), root);
+
+    // 값이 바뀌면 루트가 바뀌고, 빈 값으로 지우면 이전 루트로 돌아간다
+    tree.insert([0x01; 32], [3; 32]);
+    assert_ne!(tree.root(), root);
+    tree.insert([0x7f; 32], [4; 32]);
+    tree.insert([0x7f; 32], EMPTY_HASH);
+    tree.insert([0x01; 32], [2; 32]);
+    assert_eq!((tree.root(), tree.len()), (root, 2));
+}
+
+#[test]
+fn merkle_proofs_show_leaf_values_and_absent_leaves() {
+    let mut tree = SparseMerkleTree::new();
+    let absent = [0x42; 32];
+    assert!(tree.prove(&absent).verify(&EMPTY_HASH, &absent, &EMPTY_HASH));
+
+    let leaves = [([0x80; 32], [1; 32]), ([0x01; 32], [2; 32]), ([0x81; 32], [3; 32])];
+    for (key, value) in leaves {
+        tree.insert(key, value);
+    }
+    let root = tree.root();
+    for (key, value) in leaves {
+        let proof = tree.prove(&key);
+        assert!(proof.verify(&root, &key, &value));
+        assert!(!proof.verify(&root, &key, &[9; 32]));
+        assert!(!proof.verify(&root, &key, &EMPTY_HASH));
+    }
+
+    // 없는 리프는 빈 값으로만 증명되고, 다른 키의 증명으로 대신할 수 없다
+    let proof = tree.prove(&absent);
+    assert!(proof.verify(&root, &absent, &EMPTY_HASH));
+    assert!(!proof.verify(&root, &absent, &[1; 32]));
+    assert!(!tree.prove(&[0x80; 32]).verify(&root, &absent, &EMPTY_HASH));
+
+    // 리프가 바뀌면 이전 증명은 새 루트에 맞지 않고, 직렬화해도 그대로다
+    let json = serde_json::to_string(&proof).unwrap();
+    assert_eq!(serde_json::from_str::<MerkleProof>(&json).unwrap(), proof);
+    tree.insert(absent, [4; 32]);
+    assert!(!proof.verify(&tree.root(), &absent, &EMPTY_HASH));
+    assert!(tree.prove(&absent).verify(&tree.root(), &absent, &[4; 32]));
+}
+
+#[test]
+fn balance_proofs_verify_against_the_state_root() {
+    let (alice, carol) = (account("alice"), account("carol"));
+    let state = sample_state();
+    let root = state.state_hash();
+
+    let key = RollupState::balance_leaf_key(&alice, &TokenType::WBTC);
+    let proof = state.prove_balance(&alice, &TokenType::WBTC);
+    assert!(proof.verify(&root, &key, &RollupState::balance_leaf_value(50_000)));
+    assert!(!proof.verify(&root, &key, &RollupState::balance_leaf_value(0)));
+
+    // 잔액이 없는 계정은 0 잔액(빈 리프)으로 증명된다
+    let key = RollupState::balance_leaf_key(&carol, &TokenType::WBTC);
+    assert_eq!(RollupState::balance_leaf_value(0), EMPTY_HASH);
+    assert!(state.prove_balance(&carol, &TokenType::WBTC).verify(&root, &key, &EMPTY_HASH));
+    assert_ne!(key, RollupState::balance_leaf_key(&carol, &TokenType::USDC));
+}
+
+#[test]
+fn state_root_commits_to_balances_and_pools() {
+    let (alice, bob) = (account("alice"), account("bob"));
+    let state = sample_state();
+    assert_eq!(RollupState::new().state_hash(), EMPTY_HASH);
+    assert_eq!(RollupState::new().state_hash(), RollupState::new().current_state_root.hash);
+    assert_eq!(state.current_state_root.hash, state.state_hash());
+    // 잔액 3 + 풀 1 + 브릿지 키 1 + 연결된 금고 4
+    assert_eq!(state.state_tree().len(), 9);
+
+    // 같은 내용을 다른 순서로 채워도 (HashMap 순서와 무관하게) 같은 루트다
+    let mut other = RollupState::new();
+    other.liquidity_pools.insert((TokenType::WBTC, TokenType::USDC), pool(10_000, 300_000));
+    other.set_balance(bob.clone(), TokenType::Custom("CAT".to_string()), 7);
+    other.set_balance(alice.clone(), TokenType::USDC, 1_200);
+    other.set_balance(alice.clone(), TokenType::WBTC, 50_000);
+    other.set_balance("carol".to_string(), TokenType::WBTC, 0);
+    for vout in (0..BRIDGED_VAULTS).rev() {
+        other.register_bridged_vault(&bridged_vault(vault_outpoint(vout), 50_000)).unwrap();
+    }
+    other.bridge_key = state.bridge_key;
+    assert_eq!(other.state_hash(), state.state_hash());
+
+    // 잔액, 토큰, 주소, 풀 준비금이 바뀌면 루트가 바뀐다
+    let root = state.state_hash();
+    let mut changed = state.clone();
+    changed.set_balance(alice.clone(), TokenType::WBTC, 49_999);
+    assert_ne!(changed.state_hash(), root);
+    let mut changed = state.clone();
+    changed.set_balance(bob.clone(), TokenType::Custom("CAT".to_string()), 0);
+    changed.set_balance(bob.clone(), TokenType::Custom("DOG".to_string()), 7);
+    assert_ne!(changed.state_hash(), root);
+    let mut changed = state.clone();
+    let balances = changed.balances.remove(&alice).unwrap();
+    changed.balances.insert(account("alicf"), balances);
+    assert_ne!(changed.state_hash(), root);
+    let mut changed = state.clone();
+    changed.liquidity_pools.insert((TokenType::WBTC, TokenType::USDC), pool(10_001, 300_000));
+    assert_ne!(changed.state_hash(), root);
+
+    // 계정 논스도 리프가 된다 (0 논스는 빈 리프)
+    let mut changed = state.clone();
+    changed.nonces.insert(bob.clone(), 0);
+    assert_eq!(changed.state_hash(), root);
+    changed.increment_nonce(&bob);
+    assert_ne!(changed.state_hash(), root);
+    assert_eq!(changed.state_tree().len(), 10);
+
+    // 예치 결과를 정하는 브릿지 키, 연결된 금고와 금액, 소비된 예치 outpoint도 리프가 된다
+    let mut changed = state.clone();
+    changed.register_bridged_vault(&bridged_vault(vault_outpoint(BRIDGED_VAULTS), 1_000)).unwrap();
+    let registered = changed.state_hash();
+    assert_ne!(registered, root);
+    changed.consumed_deposits.insert(vault_outpoint(0));
+    assert_ne!(changed.state_hash(), registered);
+    assert_eq!(changed.state_tree().len(), 11);
+    let mut changed = state.clone();
+    changed.bridged_vaults.insert(vault_outpoint(0), bitcoin::Amount::from_sat(50_001));
+    assert_ne!(changed.state_hash(), root);
+    let mut changed = state.clone();
+    changed.bridge_key = Some(keypair("mallory").x_only_public_key().0);
+    assert_ne!(changed.state_hash(), root);
+    changed.bridge_key = None;
+    assert_ne!(changed.state_hash(), root);
+}
+
+#[test]
+fn batches_replay_to_identical_state_roots() {
+    let (alice, bob) = (account("alice"), account("bob"));
+    let mut processor = BatchProcessor::with_state(sample_state());
+    processor.add_operation(bridge_deposit(&alice, 0, 0));
+    let first = processor.process_batch().unwrap();
+    processor.add_operation(bridge_deposit(&bob, 1, 1));
+    let second = processor.process_batch().unwrap();
+
+    assert_eq!(second.new_state_root.hash, processor.state().state_hash());
+    assert_ne!(first.new_state_root.hash, sample_state().state_hash());
+    assert_eq!(first.new_state_root.timestamp, first.timestamp);
+    assert_eq!((first.new_state_root.height, second.new_state_root.height), (1, 2));
+    assert_eq!(second.previous_state_root, first.new_state_root);
+
+    // 다른 시각에 같은 작업을 처리한 노드도 같은 해시를 만든다
+    std::thread::sleep(std::time::Duration::from_millis(5));
+    let mut other = BatchProcessor::with_state(sample_state());
+    other.add_operation(bridge_deposit(&alice, 0, 0));
+    let later = other.process_batch().unwrap();
+    assert_ne!(later.timestamp, first.timestamp);
+    assert_eq!(later.new_state_root.hash, first.new_state_root.hash);
+
+    // 다른 노드가 같은 이전 상태에서 재실행하면 새 루트를 그대로 재현한다
+    let mut replica = sample_state();
+    BatchProcessor::replay_batch(&mut replica, &first).unwrap();
+    assert_eq!(replica.current_state_root, first.new_state_root);
+    BatchProcessor::replay_batch(&mut replica, &second).unwrap();
+    assert_eq!(&replica.current_state_root, processor.get_current_state());
+    assert_eq!(replica.get_balance(&bob, &TokenType::WBTC), 50_000);
+}
+
+#[test]
+fn replay_rejects_mismatched_roots() {
+    let alice = account("alice");
+    let mut processor = BatchProcessor::with_state(sample_state());
+    processor.add_operation(bridge_deposit(&alice, 0, 0));
+    let batch = processor.process_batch().unwrap();
+
+    // 이전 상태가 다르면 재실행하지 않는다
+    let mut diverged = sample_state();
+    diverged.set_balance(alice.clone(), TokenType::WBTC, 1);
+    let err = BatchProcessor::replay_batch(&mut diverged, &batch).unwrap_err();
+    assert!(matches!(err, DeFiHubError::InvalidStateRoot { .. }), "{:?}", err);
+    assert_eq!(diverged.current_state_root.height, 0);
+
+    // 예치를 받을 금고가 다른 상태도 루트가 달라 재실행하지 않는다
+    let mut unbridged = sample_state();
+    unbridged.bridged_vaults.remove(&vault_outpoint(0));
+    let err = BatchProcessor::replay_batch(&mut unbridged, &batch).unwrap_err();
+    assert!(matches!(err, DeFiHubError::InvalidStateRoot { .. }), "{:?}", err);
+
+    // 조작된 새 루트나 높이는 거부하고 상태를 바꾸지 않는다
+    let mut forged = batch.clone();
+    forged.new_state_root.hash[0] ^= 1;
+    let mut replica = sample_state();
+    assert!(matches!(
+        BatchProcessor::replay_batch(&mut replica, &forged),
+        Err(DeFiHubError::InvalidStateRoot { .. })
+    ));
+    let mut forged = batch.clone();
+    forged.new_state_root.height = 5;
+    assert!(BatchProcessor::replay_batch(&mut replica, &forged).is_err());
+    assert_eq!(replica.current_state_root.height, 0);
+
+    // 이미 적용한 배치를 다시 적용할 수 없다
+    BatchProcessor::replay_batch(&mut replica, &batch).unwrap();
+    assert!(BatchProcessor::replay_batch(&mut replica, &batch).is_err());
+}
@@ -0,0 +1,127 @@
+//! 롤업 계정과 서명된 작업
+//!
+//! 계정 주소는 x-only Schnorr 공개키의 hex 인코딩이다. 사용자는 작업의 정규 인코딩과
+//! 계정 논스를 BIP-340으로 서명하고, 논스는 계정마다 0부터 하나씩 늘어난다.
+//! 예치는 L1 입금을 확인한 브릿지 키가 서명하며 논스도 브릿지 계정의 것을 쓴다.
+
+use crate::state::{encode_token, length_prefixed};
+use crate::{merkle, DeFiHubError, DeFiResult, Operation};
+use bitcoin::secp256k1::{schnorr, Keypair, Message, Secp256k1, XOnlyPublicKey};
+use serde::{Deserialize, Serialize};
+
+/// 작업 서명 메시지 도메인
+const OPERATION_DOMAIN: &[u8] = b"rollup/operation";
+
+/// x-only 공개키의 롤업 계정 주소 (소문자 hex 64자)
+pub fn account_address(public_key: &XOnlyPublicKey) -> String {
+    hex::encode(public_key.serialize())
+}
+
+/// 계정 키로 서명된 작업
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SignedOperation {
+    pub operation: Operation,
+
+    /// 서명한 계정의 논스 (계정의 다음 논스와 같아야 한다)
+    pub nonce: u64,
+
+    /// 서명한 계정의 x-only 공개키
+    pub public_key: XOnlyPublicKey,
+
+    /// `signing_hash`에 대한 BIP-340 서명
+    pub signature: schnorr::Signature,
+}
+
+impl SignedOperation {
+    /// 작업과 논스에 서명
+    pub fn sign(operation: Operation, nonce: u64, keypair: &Keypair) -> Self {
+        let (public_key, _) = keypair.x_only_public_key();
+        let message = Message::from_digest(Self::signing_hash(&operation, nonce, &public_key));
+        let signature = Secp256k1::signing_only().sign_schnorr_no_aux_rand(&message, keypair);
+        Self {
+            operation,
+            nonce,
+            public_key,
+            signature,
+        }
+    }
+
+    /// 서명 대상 해시 (도메인 || 공개키 || 논스 || 작업 인코딩)
+    pub fn signing_hash(operation: &Operation, nonce: u64, public_key: &XOnlyPublicKey) -> [u8; 32] {
+        merkle::hash_parts(&[
+            OPERATION_DOMAIN,
+            &public_key.serialize(),
+            &nonce.to_le_bytes(),
+            &operation.encode(),
+        ])
+    }
+
+    /// 서명한 계정 주소
+    pub fn account(&self) -> String {
+        account_address(&self.public_key)
+    }
+
+    /// 서명이 공개키와 작업, 논스에 맞는지 확인
+    pub fn verify_signature(&self) -> DeFiResult<()> {
+        let message = Message::from_digest(Self::signing_hash(&self.operation, self.nonce, &self.public_key));
+        Secp256k1::verification_only()
+            .verify_schnorr(&self.signature, &message, &self.public_key)
+            .map_err(|err| DeFiHubError::InvalidSignature(err.to_string()))
+    }
+}
+
+impl Operation {
+    /// 작업의 주체 계정 (잔액이 빠지는 계정, 예치는 L1 입금을 받는 계정 - 예치의 서명자는 아니다)
+    pub fn account(&self) -> &str {
+        match self {
+            Operation::Deposit { recipient, .. } => recipient,
+            Operation::Withdraw { rollup_address, .. } => rollup_address,
+            Operation::Swap { user, .. } => user,
+            Operation::ProvideLiquidity { provider, .. } => provider,
+            Operation::RemoveLiquidity { provider, .. } => provider,
+        }
+    }
+
+    /// 서명에 쓰는 정규 인코딩 (태그 1바이트 + 필드, 정수는 LE, 문자열은 길이 접두)
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        match self {
+            Operation::Deposit { vault_outpoint, amount, recipient } => {
+                out.push(0);
+                out.extend_from_slice(&bitcoin::consensus::serialize(vault_outpoint));
+                out.extend_from_slice(&amount.to_sat().to_le_bytes());
+                out.extend_from_slice(&length_prefixed(recipient.as_bytes()));
+            }
+            Operation::Withdraw { rollup_address, amount, destination } => {
+                out.push(1);
+                out.extend_from_slice(&length_prefixed(rollup_address.as_bytes()));
+                out.extend_from_slice(&amount.to_sat().to_le_bytes());
+                out.extend_from_slice(&length_prefixed(destination.as_bytes()));
+            }
+            Operation::Swap { from_token, to_token, amount_in, min_amount_out, user } => {
+                out.push(2);
+                encode_token(&mut out, from_token);
+                encode_token(&mut out, to_token);
+                out.extend_from_slice(&amount_in.to_le_bytes());
+                out.extend_from_slice(&min_amount_out.to_le_bytes());
+                out.extend_from_slice(&length_prefixed(user.as_bytes()));
+            }
+            Operation::ProvideLiquidity { token_a, token_b, amount_a, amount_b, provider } => {
+                out.push(3);
+                encode_token(&mut out, token_a);
+                encode_token(&mut out, token_b);
+                out.extend_from_slice(&amount_a.to_le_bytes());
+                out.extend_from_slice(&amount_b.to_le_bytes());
+                out.extend_from_slice(&length_prefixed(provider.as_bytes()));
+            }
+            Operation::RemoveLiquidity { token_a, token_b, liquidity, provider } => {
+                out.push(4);
+                encode_token(&mut out, token_a);
+                encode_token(&mut out, token_b);
+                out.extend_from_slice(&liquidity.to_le_bytes());
+                out.extend_from_slice(&length_prefixed(provider.as_bytes()));
+            }
+        }
+        out
+    }
+}
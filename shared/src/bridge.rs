@@ -1,4 +1,4 @@
-use crate::{BridgeMessage, BridgeOperation, ChainId, DeFiResult, DeFiHubError, TokenType};
+use crate::{BridgeMessage, BridgeOperation, ChainId, ChainNetwork, DeFiResult, DeFiHubError};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 
@@ -12,7 +12,7 @@ pub trait CrossChainBridge: Send + Sync {
     async fn send_message(&self, message: BridgeMessage) -> DeFiResult<String>;
     
     /// 메시지 수신 확인
-    async fn verify_message(&self, message: &BridgeMessage) -> DeFiResult<bool>;
+    async fn verify_message(&self, _message: &BridgeMessage) -> DeFiResult<bool>;
     
     /// 체인 연결 상태 확인
     async fn is_connected(&self, chain_id: &ChainId) -> DeFiResult<bool>;
@@ -30,8 +30,8 @@ pub struct SolanaBridge {
 /// Fractal Bitcoin 브릿지 구현
 pub struct FractalBitcoinBridge {
     pub rpc_endpoint: String,
-    pub chain_id: u32,
-    pub op_cat_enabled: bool,
+    /// Fractal 네트워크 (OP_CAT 여부, 블록 간격)
+    pub network: ChainNetwork,
     pub min_confirmations: u32,
 }
 
@@ -63,7 +63,7 @@ impl CrossChainBridge for SolanaBridge {
         }
     }
     
-    async fn verify_message(&self, message: &BridgeMessage) -> DeFiResult<bool> {
+    async fn verify_message(&self, _message: &BridgeMessage) -> DeFiResult<bool> {
         // 메시지 서명 및 논스 검증
         // TODO: 실제 검증 로직 구현
         Ok(true)
@@ -103,25 +103,25 @@ impl SolanaBridge {
         Self { endpoint, program_id }
     }
     
-    async fn lock_btc_on_bitcoin(&self, message: BridgeMessage) -> DeFiResult<String> {
+    async fn lock_btc_on_bitcoin(&self, _message: BridgeMessage) -> DeFiResult<String> {
         // BTC 금고에 락하는 트랜잭션 생성 및 브로드캐스트
         // TODO: 실제 구현
         Ok("btc_tx_hash".to_string())
     }
     
-    async fn mint_bbtc_on_solana(&self, message: BridgeMessage) -> DeFiResult<String> {
+    async fn mint_bbtc_on_solana(&self, _message: BridgeMessage) -> DeFiResult<String> {
         // Solana에서 bBTC 민트 트랜잭션 생성 및 전송
         // TODO: 실제 구현
         Ok("solana_tx_signature".to_string())
     }
     
-    async fn burn_bbtc_on_solana(&self, message: BridgeMessage) -> DeFiResult<String> {
+    async fn burn_bbtc_on_solana(&self, _message: BridgeMessage) -> DeFiResult<String> {
         // Solana에서 bBTC 번 트랜잭션 생성 및 전송
         // TODO: 실제 구현
         Ok("solana_burn_signature".to_string())
     }
     
-    async fn unlock_btc_on_bitcoin(&self, message: BridgeMessage) -> DeFiResult<String> {
+    async fn unlock_btc_on_bitcoin(&self, _message: BridgeMessage) -> DeFiResult<String> {
         // 비트코인 금고에서 BTC 언락 트랜잭션 생성 및 브로드캐스트
         // TODO: 실제 구현
         Ok("btc_unlock_hash".to_string())
@@ -188,13 +188,27 @@ impl CrossChainBridge for FractalBitcoinBridge {
 }
 
 impl FractalBitcoinBridge {
-    pub fn new(rpc_endpoint: String, chain_id: u32, op_cat_enabled: bool, min_confirmations: u32) -> Self {
-        Self { 
-            rpc_endpoint, 
-            chain_id, 
-            op_cat_enabled, 
-            min_confirmations 
+    /// 확인 수는 네트워크 기본값을 쓴다
+    pub fn new(rpc_endpoint: String, network: ChainNetwork) -> DeFiResult<Self> {
+        if !network.is_fractal() {
+            return Err(DeFiHubError::UnsupportedChain(format!("{} is not a Fractal network", network)));
         }
+        Ok(Self {
+            rpc_endpoint,
+            network,
+            min_confirmations: network.params().default_confirmations,
+        })
+    }
+    
+    /// 확인 수 지정
+    pub fn with_min_confirmations(mut self, min_confirmations: u32) -> Self {
+        self.min_confirmations = min_confirmations;
+        self
+    }
+    
+    /// 확인 수만큼 블록이 쌓이는 데 걸리는 예상 시간 (초)
+    pub fn finality_secs(&self) -> u64 {
+        self.network.duration_secs(self.min_confirmations as u64)
     }
     
     async fn lock_btc_on_fractal(&self, message: BridgeMessage) -> DeFiResult<String> {
@@ -211,8 +225,6 @@ impl FractalBitcoinBridge {
     
     async fn create_fractal_lock_transaction(&self, amount: u64, recipient: &str) -> DeFiResult<String> {
         // 실제 Fractal Bitcoin API를 사용한 트랜잭션 생성
-        let client = reqwest::Client::new();
-        
         // 1. 현재 블록 높이 확인
         let current_height = self.get_fractal_block_height().await?;
         
@@ -226,6 +238,7 @@ impl FractalBitcoinBridge {
         );
         
         // 3. 실제 환경에서는 여기서 트랜잭션을 브로드캐스트
+        // let client = reqwest::Client::new();
         // let broadcast_url = format!("{}/v1/indexer/tx/broadcast", self.rpc_endpoint);
         // client.post(&broadcast_url).json(&tx_data).send().await?;
         
@@ -250,11 +263,9 @@ impl FractalBitcoinBridge {
         Ok(format!("fractal_unlock_tx_{}", message.id))
     }
     
-    async fn verify_op_cat_covenant(&self, message: &BridgeMessage) -> DeFiResult<bool> {
+    async fn verify_op_cat_covenant(&self, _message: &BridgeMessage) -> DeFiResult<bool> {
         // OP_CAT 코버넌트 조건 검증
-        if !self.op_cat_enabled {
-            return Err(DeFiHubError::UnsupportedChain("OP_CAT not enabled".to_string()));
-        }
+        self.network.require_op_cat()?;
         // TODO: 실제 코버넌트 검증 로직
         Ok(true)
     }
@@ -328,6 +339,12 @@ pub struct BridgeManager {
     bridges: Vec<Box<dyn CrossChainBridge>>,
 }
 
+impl Default for BridgeManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl BridgeManager {
     pub fn new() -> Self {
         Self {
@@ -1,6 +1,6 @@
 use bitcoin::Amount;
 
-/// DeFi 허브 시스템 상수들
+// DeFi 허브 시스템 상수들
 
 // === Bitcoin Layer 상수 ===
 pub const DEFAULT_TIMELOCK_BLOCKS: u16 = 20;
@@ -53,7 +53,10 @@ pub mod chains {
 
 // === 테스트 모드 상수 ===
 pub const TEST_MODE_ENV_VAR: &str = "PURRFECT_TEST_MODE";
-pub const DEMO_PRIVATE_KEY: &str = "cVt4o7BGAig1UXywgGSmARhxMdzP5qvQsxKkSsc1XEkw3tDTQFpy";
+
+// === 키 저장소 & 암호화 ===
+pub const KEYSTORE_FILE: &str = "keystore.json";
+pub const PASSPHRASE_ENV_VAR: &str = "PURRFECT_PASSPHRASE";
 
 // === 로깅 & 모니터링 ===
 pub const LOG_LEVEL_ENV_VAR: &str = "PURRFECT_LOG_LEVEL";
@@ -0,0 +1,219 @@
+//! 상태 파일 암호화 컨테이너
+//!
+//! 패스프레이즈에서 scrypt로 256비트 키를 유도하고 XChaCha20-Poly1305로 평문 JSON 전체를 암호화한다.
+//! 컨테이너 자체도 JSON 객체이므로 [`persistence`](crate::persistence)의 원자적 저장과 백업이
+//! 그대로 적용되며, 스키마 버전은 복호화한 평문 안에 들어 있다.
+
+use crate::{DeFiHubError, DeFiResult};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+use zeroize::Zeroizing;
+
+/// 컨테이너 식별자 (`format` 필드)
+pub const CONTAINER_FORMAT: &str = "purrfect-encrypted";
+
+/// 컨테이너 형식 버전
+pub const CONTAINER_VERSION: u32 = 1;
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+const KEY_LEN: usize = 32;
+
+/// scrypt 매개변수
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KdfParams {
+    /// log2(N)
+    pub log_n: u8,
+    pub r: u32,
+    pub p: u32,
+}
+
+impl Default for KdfParams {
+    /// scrypt 권장값 (N = 2^15, r = 8, p = 1)
+    fn default() -> Self {
+        Self { log_n: 15, r: 8, p: 1 }
+    }
+}
+
+impl KdfParams {
+    fn derive(&self, secret: &str, salt: &[u8]) -> DeFiResult<Zeroizing<[u8; KEY_LEN]>> {
+        let params = scrypt::Params::new(self.log_n, self.r, self.p, KEY_LEN)
+            .map_err(|e| DeFiHubError::Configuration(format!("Invalid scrypt parameters: {}", e)))?;
+        let mut key = Zeroizing::new([0u8; KEY_LEN]);
+        scrypt::scrypt(secret.as_bytes(), salt, &params, key.as_mut())
+            .map_err(|e| DeFiHubError::Internal(format!("Key derivation failed: {}", e)))?;
+        Ok(key)
+    }
+}
+
+/// 암호화된 파일 내용
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct EncryptedContainer {
+    /// 항상 [`CONTAINER_FORMAT`]
+    pub format: String,
+
+    /// 컨테이너 형식 버전
+    pub version: u32,
+
+    /// 키 유도 매개변수
+    pub kdf: KdfParams,
+
+    /// scrypt 솔트 (hex)
+    pub salt: String,
+
+    /// XChaCha20-Poly1305 논스 (hex)
+    pub nonce: String,
+
+    /// 암호문과 인증 태그 (hex)
+    pub ciphertext: String,
+}
+
+impl EncryptedContainer {
+    /// JSON 값이 암호화 컨테이너인지 확인
+    pub fn detect(value: &Value) -> bool {
+        value.get("format").and_then(Value::as_str) == Some(CONTAINER_FORMAT)
+    }
+
+    /// 헤더 (형식, 버전, KDF, 솔트) - 추가 인증 데이터로 암호문에 묶인다
+    fn header(&self) -> Vec<u8> {
+        let mut header = format!("{}/{}/", self.format, self.version).into_bytes();
+        header.push(self.kdf.log_n);
+        header.extend_from_slice(&self.kdf.r.to_le_bytes());
+        header.extend_from_slice(&self.kdf.p.to_le_bytes());
+        header.extend_from_slice(self.salt.as_bytes());
+        header
+    }
+}
+
+/// 잠금 해제 패스프레이즈
+///
+/// 유도한 키를 솔트별로 기억하므로, 같은 패스프레이즈로 여러 파일을 저장하고 읽어도
+/// scrypt는 솔트마다 한 번만 실행된다.
+#[derive(Clone)]
+pub struct Passphrase {
+    secret: Zeroizing<String>,
+    kdf: KdfParams,
+    keys: Arc<Mutex<Vec<DerivedKey>>>,
+}
+
+struct DerivedKey {
+    kdf: KdfParams,
+    salt: [u8; SALT_LEN],
+    key: Zeroizing<[u8; KEY_LEN]>,
+}
+
+impl std::fmt::Debug for Passphrase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Passphrase").field("kdf", &self.kdf).finish_non_exhaustive()
+    }
+}
+
+impl Passphrase {
+    /// 기본 scrypt 매개변수를 쓰는 패스프레이즈
+    pub fn new(secret: impl Into<String>) -> Self {
+        Self {
+            secret: Zeroizing::new(secret.into()),
+            kdf: KdfParams::default(),
+            keys: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// 새로 암호화할 때 쓸 scrypt 매개변수 (복호화는 컨테이너의 값을 따른다)
+    pub fn with_kdf(mut self, kdf: KdfParams) -> Self {
+        self.kdf = kdf;
+        self
+    }
+
+    /// 평문 암호화 - 논스는 매번 새로 뽑는다
+    pub fn encrypt(&self, plaintext: &[u8]) -> DeFiResult<EncryptedContainer> {
+        if self.secret.is_empty() {
+            return Err(DeFiHubError::Configuration("Passphrase must not be empty".to_string()));
+        }
+
+        let (salt, key) = self.encryption_key()?;
+        let mut nonce = [0u8; NONCE_LEN];
+        random_bytes(&mut nonce)?;
+
+        let mut container = EncryptedContainer {
+            format: CONTAINER_FORMAT.to_string(),
+            version: CONTAINER_VERSION,
+            kdf: self.kdf,
+            salt: hex::encode(salt),
+            nonce: hex::encode(nonce),
+            ciphertext: String::new(),
+        };
+        let header = container.header();
+        let ciphertext = XChaCha20Poly1305::new(Key::from_slice(key.as_ref()))
+            .encrypt(XNonce::from_slice(&nonce), Payload { msg: plaintext, aad: &header })
+            .map_err(|_| DeFiHubError::Internal("Encryption failed".to_string()))?;
+        container.ciphertext = hex::encode(ciphertext);
+        Ok(container)
+    }
+
+    /// 컨테이너 복호화 - 패스프레이즈가 틀리거나 내용이 바뀌었으면 [`DeFiHubError::DecryptionFailed`]
+    pub fn decrypt(&self, container: &EncryptedContainer) -> DeFiResult<Zeroizing<Vec<u8>>> {
+        if container.format != CONTAINER_FORMAT || container.version != CONTAINER_VERSION {
+            return Err(DeFiHubError::Configuration(format!(
+                "Unsupported encrypted container {} v{}",
+                container.format, container.version
+            )));
+        }
+
+        let salt: [u8; SALT_LEN] = decode_hex(&container.salt)?;
+        let nonce: [u8; NONCE_LEN] = decode_hex(&container.nonce)?;
+        let ciphertext = hex::decode(&container.ciphertext).map_err(|_| DeFiHubError::DecryptionFailed)?;
+        let key = self.key(container.kdf, salt)?;
+
+        XChaCha20Poly1305::new(Key::from_slice(key.as_ref()))
+            .decrypt(XNonce::from_slice(&nonce), Payload { msg: &ciphertext, aad: &container.header() })
+            .map(Zeroizing::new)
+            .map_err(|_| DeFiHubError::DecryptionFailed)
+    }
+
+    /// 이미 유도한 키가 있으면 그 솔트를 다시 쓰고, 없으면 새 솔트로 유도
+    fn encryption_key(&self) -> DeFiResult<([u8; SALT_LEN], Zeroizing<[u8; KEY_LEN]>)> {
+        let cached = self
+            .cache()?
+            .iter()
+            .find(|derived| derived.kdf == self.kdf)
+            .map(|derived| (derived.salt, derived.key.clone()));
+        if let Some(cached) = cached {
+            return Ok(cached);
+        }
+
+        let mut salt = [0u8; SALT_LEN];
+        random_bytes(&mut salt)?;
+        Ok((salt, self.key(self.kdf, salt)?))
+    }
+
+    fn key(&self, kdf: KdfParams, salt: [u8; SALT_LEN]) -> DeFiResult<Zeroizing<[u8; KEY_LEN]>> {
+        if let Some(derived) = self.cache()?.iter().find(|derived| derived.kdf == kdf && derived.salt == salt) {
+            return Ok(derived.key.clone());
+        }
+
+        let key = kdf.derive(&self.secret, &salt)?;
+        self.cache()?.push(DerivedKey { kdf, salt, key: key.clone() });
+        Ok(key)
+    }
+
+    fn cache(&self) -> DeFiResult<std::sync::MutexGuard<'_, Vec<DerivedKey>>> {
+        self.keys
+            .lock()
+            .map_err(|_| DeFiHubError::Internal("Passphrase key cache poisoned".to_string()))
+    }
+}
+
+/// OS 난수
+pub fn random_bytes(buf: &mut [u8]) -> DeFiResult<()> {
+    getrandom::getrandom(buf).map_err(|e| DeFiHubError::Internal(format!("OS randomness unavailable: {}", e)))
+}
+
+fn decode_hex<const N: usize>(value: &str) -> DeFiResult<[u8; N]> {
+    hex::decode(value)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(DeFiHubError::DecryptionFailed)
+}
@@ -10,6 +10,9 @@ pub enum DeFiHubError {
     #[error("Insufficient funds: required {required}, available {available}")]
     InsufficientFunds { required: u64, available: u64 },
     
+    #[error("Invalid amount: {0}")]
+    InvalidAmount(String),
+    
     #[error("Invalid Bitcoin address: {0}")]
     InvalidAddress(String),
     
@@ -17,11 +20,26 @@ pub enum DeFiHubError {
     #[error("Vault not found")]
     VaultNotFound,
     
+    #[error("Vault already exists: {0}")]
+    VaultAlreadyExists(String),
+    
     #[error("Vault in invalid state: {current}, expected: {expected}")]
     InvalidVaultState { current: String, expected: String },
     
     #[error("Timelock not expired: {blocks_remaining} blocks remaining")]
-    TimelockNotExpired { blocks_remaining: u16 },
+    TimelockNotExpired { blocks_remaining: u32 },
+
+    #[error("Timelock not expired: median time past is {seconds_remaining} seconds short")]
+    MedianTimeNotReached { seconds_remaining: u32 },
+
+    #[error("Withdrawal rate limit exceeded: requested {requested}, remaining {remaining} until block {resets_at}")]
+    RateLimitExceeded { requested: u64, remaining: u64, resets_at: u32 },
+
+    #[error("Vault history tampered at entry {index}: {reason}")]
+    HistoryTampered { index: usize, reason: String },
+    
+    #[error("MuSig2 signing failed: {0}")]
+    Musig(String),
     
     // 롤업 관련 에러
     #[error("Rollup execution failed: {0}")]
@@ -33,6 +51,12 @@ pub enum DeFiHubError {
     #[error("Batch processing failed: {0}")]
     BatchProcessing(String),
     
+    #[error("Invalid operation signature: {0}")]
+    InvalidSignature(String),
+    
+    #[error("Invalid nonce: expected {expected}, got {actual}")]
+    InvalidNonce { expected: u64, actual: u64 },
+    
     // 브릿지 관련 에러
     #[error("Unsupported chain: {0}")]
     UnsupportedChain(String),
@@ -60,6 +84,13 @@ pub enum DeFiHubError {
     #[error("Network timeout: {0}")]
     NetworkTimeout(String),
     
+    // 암호화 관련 에러
+    #[error("Passphrase required to unlock {0}")]
+    PassphraseRequired(String),
+    
+    #[error("Decryption failed: wrong passphrase or corrupted file")]
+    DecryptionFailed,
+    
     // 일반적인 에러
     #[error("Serialization error: {0}")]
     Serialization(String),
@@ -0,0 +1,196 @@
+//! BIP32 HD 키 저장소
+//!
+//! 시드 하나에서 금고 소유자, 회수, 롤업 키를 역할별 강화 계정으로 유도한다.
+//! 경로는 BIP86과 같은 `m/86'/<코인>'/<계정>'/0/<인덱스>`이며 코인 타입은 메인넷 0, 그 외 1,
+//! 계정은 역할 번호([`KeyRole::account`])다. 따라서 소유자 키는 BIP86 지갑의 첫 계정과 같다.
+//!
+//! 저장 파일에는 마스터 확장 개인키가 들어가므로 [`Keystore::save_to_file`]에
+//! 패스프레이즈를 주어 암호화해 두는 것을 권장한다.
+
+use crate::encryption::{random_bytes, Passphrase};
+use crate::persistence::{self, Versioned};
+use crate::{DeFiHubError, DeFiResult};
+use bitcoin::bip32::{ChildNumber, DerivationPath, Xpriv, Xpub};
+use bitcoin::secp256k1::{Keypair, Secp256k1};
+use bitcoin::{Network, XOnlyPublicKey};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use zeroize::Zeroizing;
+
+/// BIP86 목적 번호
+pub const BIP86_PURPOSE: u32 = 86;
+
+/// 새로 만드는 시드 길이 (바이트)
+pub const SEED_LEN: usize = 32;
+
+/// 키 역할
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum KeyRole {
+    /// 금고 소유자 (트리거/취소 서명)
+    Owner,
+    /// 금고 회수 경로
+    Recovery,
+    /// 롤업 계정
+    Rollup,
+}
+
+impl KeyRole {
+    /// 모든 역할
+    pub const ALL: [KeyRole; 3] = [KeyRole::Owner, KeyRole::Recovery, KeyRole::Rollup];
+
+    /// 역할의 BIP32 계정 번호
+    pub fn account(self) -> u32 {
+        match self {
+            KeyRole::Owner => 0,
+            KeyRole::Recovery => 1,
+            KeyRole::Rollup => 2,
+        }
+    }
+}
+
+impl std::fmt::Display for KeyRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            KeyRole::Owner => "owner",
+            KeyRole::Recovery => "recovery",
+            KeyRole::Rollup => "rollup",
+        };
+        f.write_str(name)
+    }
+}
+
+impl std::str::FromStr for KeyRole {
+    type Err = DeFiHubError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        KeyRole::ALL
+            .into_iter()
+            .find(|role| role.to_string().eq_ignore_ascii_case(s))
+            .ok_or_else(|| DeFiHubError::Configuration(format!("Unknown key role: {}", s)))
+    }
+}
+
+/// HD 키 저장소
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Keystore {
+    /// 키를 쓸 네트워크 (코인 타입과 확장키 접두어를 정한다)
+    pub network: Network,
+
+    /// 마스터 확장 개인키
+    master: Xpriv,
+
+    /// 생성 시간
+    pub created_at: DateTime<Utc>,
+}
+
+impl std::fmt::Debug for Keystore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Keystore")
+            .field("network", &self.network)
+            .field("created_at", &self.created_at)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Keystore {
+    /// OS 난수 시드로 새 키 저장소 생성
+    pub fn generate(network: Network) -> DeFiResult<Self> {
+        let mut seed = Zeroizing::new([0u8; SEED_LEN]);
+        random_bytes(seed.as_mut())?;
+        Self::from_seed(network, seed.as_ref())
+    }
+
+    /// 시드 (BIP39 시드 포함, 16~64바이트)에서 키 저장소 생성
+    pub fn from_seed(network: Network, seed: &[u8]) -> DeFiResult<Self> {
+        if !(16..=64).contains(&seed.len()) {
+            return Err(DeFiHubError::Configuration(format!(
+                "Seed must be 16 to 64 bytes, got {}",
+                seed.len()
+            )));
+        }
+        let master = Xpriv::new_master(network, seed)
+            .map_err(|e| DeFiHubError::Configuration(format!("Invalid seed: {}", e)))?;
+        Ok(Self {
+            network,
+            master,
+            created_at: Utc::now(),
+        })
+    }
+
+    /// 역할 계정 경로 `m/86'/<코인>'/<계정>'`
+    pub fn account_path(&self, role: KeyRole) -> DerivationPath {
+        let coin_type = if self.network == Network::Bitcoin { 0 } else { 1 };
+        DerivationPath::from(vec![
+            ChildNumber::Hardened { index: BIP86_PURPOSE },
+            ChildNumber::Hardened { index: coin_type },
+            ChildNumber::Hardened { index: role.account() },
+        ])
+    }
+
+    /// 키 경로 `m/86'/<코인>'/<계정>'/0/<인덱스>`
+    pub fn derivation_path(&self, role: KeyRole, index: u32) -> DeFiResult<DerivationPath> {
+        let index = ChildNumber::from_normal_idx(index)
+            .map_err(|e| DeFiHubError::Configuration(format!("Invalid key index: {}", e)))?;
+        Ok(self.account_path(role).extend([ChildNumber::Normal { index: 0 }, index]))
+    }
+
+    /// 역할과 인덱스의 키 쌍
+    pub fn keypair(&self, role: KeyRole, index: u32) -> DeFiResult<Keypair> {
+        let secp = Secp256k1::new();
+        let path = self.derivation_path(role, index)?;
+        let xpriv = self.master.derive_priv(&secp, &path).map_err(derivation_error)?;
+        Ok(xpriv.to_keypair(&secp))
+    }
+
+    /// 역할과 인덱스의 x-only 공개키
+    pub fn public_key(&self, role: KeyRole, index: u32) -> DeFiResult<XOnlyPublicKey> {
+        Ok(self.keypair(role, index)?.x_only_public_key().0)
+    }
+
+    /// 금고 소유자 키
+    pub fn owner_key(&self, index: u32) -> DeFiResult<Keypair> {
+        self.keypair(KeyRole::Owner, index)
+    }
+
+    /// 금고 회수 키
+    pub fn recovery_key(&self, index: u32) -> DeFiResult<Keypair> {
+        self.keypair(KeyRole::Recovery, index)
+    }
+
+    /// 롤업 계정 키
+    pub fn rollup_key(&self, index: u32) -> DeFiResult<Keypair> {
+        self.keypair(KeyRole::Rollup, index)
+    }
+
+    /// 역할 계정의 확장 공개키 (감시 전용 지갑/디스크립터용)
+    pub fn account_xpub(&self, role: KeyRole) -> DeFiResult<Xpub> {
+        let secp = Secp256k1::new();
+        let xpriv = self.master.derive_priv(&secp, &self.account_path(role)).map_err(derivation_error)?;
+        Ok(Xpub::from_priv(&secp, &xpriv))
+    }
+
+    /// 마스터 키 지문
+    pub fn fingerprint(&self) -> bitcoin::bip32::Fingerprint {
+        self.master.fingerprint(&Secp256k1::new())
+    }
+
+    /// 파일에서 로드 (암호화된 파일은 패스프레이즈가 필요하다)
+    pub fn load_from_file<P: AsRef<Path>>(path: P, passphrase: Option<&Passphrase>) -> DeFiResult<Self> {
+        persistence::load_with(path.as_ref(), passphrase)
+    }
+
+    /// 파일에 저장 (패스프레이즈가 있으면 암호화)
+    pub fn save_to_file<P: AsRef<Path>>(&self, path: P, passphrase: Option<&Passphrase>) -> DeFiResult<()> {
+        persistence::save_with(self, path.as_ref(), passphrase)
+    }
+}
+
+impl Versioned for Keystore {
+    const SCHEMA_VERSION: u32 = 1;
+    const KIND: &'static str = "keystore";
+}
+
+fn derivation_error(e: bitcoin::bip32::Error) -> DeFiHubError {
+    DeFiHubError::Internal(format!("Key derivation failed: {}", e))
+}
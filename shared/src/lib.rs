@@ -3,7 +3,16 @@ pub mod errors;
 pub mod constants;
 pub mod state;
 pub mod bridge;
+pub mod persistence;
+pub mod encryption;
+pub mod keystore;
+pub mod network;
+pub mod merkle;
+pub mod account;
 
 pub use types::*;
 pub use errors::*;
-pub use constants::*;
\ No newline at end of file
+pub use constants::*;
+pub use network::{ChainNetwork, NetworkParams};
+pub use merkle::{MerkleProof, SparseMerkleTree};
+pub use account::{account_address, SignedOperation};
\ No newline at end of file
@@ -0,0 +1,155 @@
+//! 롤업 상태 루트용 희소 머클 트리 (Sparse Merkle Tree)
+//!
+//! 키는 256비트 경로(상위 비트부터 왼쪽/오른쪽)이고 값은 리프 데이터의 해시다.
+//! 빈 서브트리의 해시는 모든 높이에서 [`EMPTY_HASH`]이므로 빈 트리의 루트도 `[0; 32]`다.
+//! 루트는 키/값 집합만으로 정해지고 삽입 순서나 시각과 무관하다.
+//! [`MerkleProof`]는 루트만 아는 쪽에서 리프 하나의 값(또는 리프가 없음)을 확인하게 해 준다.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+/// 트리 깊이 (키 비트 수)
+pub const TREE_DEPTH: usize = 256;
+
+/// 빈 리프/빈 서브트리 해시
+pub const EMPTY_HASH: [u8; 32] = [0; 32];
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+/// 희소 머클 트리
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SparseMerkleTree {
+    leaves: BTreeMap<[u8; 32], [u8; 32]>,
+}
+
+impl SparseMerkleTree {
+    /// 빈 트리 생성
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 리프 설정 (값 해시가 [`EMPTY_HASH`]면 리프를 지운다)
+    pub fn insert(&mut self, key: [u8; 32], value_hash: [u8; 32]) {
+        if value_hash == EMPTY_HASH {
+            self.leaves.remove(&key);
+        } else {
+            self.leaves.insert(key, value_hash);
+        }
+    }
+
+    /// 리프 값 해시 조회
+    pub fn get(&self, key: &[u8; 32]) -> Option<&[u8; 32]> {
+        self.leaves.get(key)
+    }
+
+    /// 비어 있지 않은 리프 수
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    /// 빈 트리인지
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    /// 루트 해시
+    pub fn root(&self) -> [u8; 32] {
+        // BTreeMap 순서는 키의 상위 비트부터 비교한 순서와 같다
+        let leaves: Vec<_> = self.leaves.iter().map(|(key, value)| (*key, *value)).collect();
+        subtree_root(&leaves, 0)
+    }
+
+    /// `key` 리프의 증명 (리프가 없으면 비어 있음을 보이는 증명)
+    pub fn prove(&self, key: &[u8; 32]) -> MerkleProof {
+        let leaves: Vec<_> = self.leaves.iter().map(|(key, value)| (*key, *value)).collect();
+        let mut siblings = BTreeMap::new();
+        let mut path = &leaves[..];
+        for depth in 0..TREE_DEPTH {
+            if path.is_empty() {
+                // 경로 아래가 비었으면 남은 형제도 모두 경로 밖의 빈 서브트리다
+                break;
+            }
+            let split = path.partition_point(|(key, _)| !bit(key, depth));
+            let (on_path, other) = if bit(key, depth) {
+                (&path[split..], &path[..split])
+            } else {
+                (&path[..split], &path[split..])
+            };
+            let sibling = subtree_root(other, depth + 1);
+            if sibling != EMPTY_HASH {
+                siblings.insert(depth as u16, sibling);
+            }
+            path = on_path;
+        }
+        MerkleProof { siblings }
+    }
+}
+
+/// 리프 하나의 머클 증명 - 루트에서 리프까지 경로 옆 서브트리 해시
+///
+/// 빈 서브트리는 싣지 않는다. 값 해시를 [`EMPTY_HASH`]로 검증하면 리프가 없다는 증명이다.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct MerkleProof {
+    /// 깊이별 형제 서브트리 해시 (비어 있지 않은 것만)
+    siblings: BTreeMap<u16, [u8; 32]>,
+}
+
+impl MerkleProof {
+    /// `key` 리프의 값 해시가 `value_hash`일 때의 루트
+    pub fn compute_root(&self, key: &[u8; 32], value_hash: &[u8; 32]) -> [u8; 32] {
+        let mut hash = if value_hash == &EMPTY_HASH {
+            EMPTY_HASH
+        } else {
+            hash_parts(&[&[LEAF_PREFIX], key, value_hash])
+        };
+        for depth in (0..TREE_DEPTH).rev() {
+            let sibling = self.siblings.get(&(depth as u16)).unwrap_or(&EMPTY_HASH);
+            hash = if bit(key, depth) {
+                node_hash(sibling, &hash)
+            } else {
+                node_hash(&hash, sibling)
+            };
+        }
+        hash
+    }
+
+    /// `root` 트리에서 `key` 리프의 값 해시가 `value_hash`인지
+    pub fn verify(&self, root: &[u8; 32], key: &[u8; 32], value_hash: &[u8; 32]) -> bool {
+        &self.compute_root(key, value_hash) == root
+    }
+}
+
+/// 도메인과 데이터 조각들로 리프 키/값 해시를 만든다
+pub fn hash_parts(parts: &[&[u8]]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.finalize().into()
+}
+
+fn subtree_root(leaves: &[([u8; 32], [u8; 32])], depth: usize) -> [u8; 32] {
+    match leaves {
+        [] => EMPTY_HASH,
+        [(key, value)] if depth == TREE_DEPTH => hash_parts(&[&[LEAF_PREFIX], key, value]),
+        _ => {
+            let split = leaves.partition_point(|(key, _)| !bit(key, depth));
+            let left = subtree_root(&leaves[..split], depth + 1);
+            let right = subtree_root(&leaves[split..], depth + 1);
+            node_hash(&left, &right)
+        }
+    }
+}
+
+fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    if left == &EMPTY_HASH && right == &EMPTY_HASH {
+        return EMPTY_HASH;
+    }
+    hash_parts(&[&[NODE_PREFIX], left, right])
+}
+
+fn bit(key: &[u8; 32], depth: usize) -> bool {
+    key[depth / 8] & (0x80 >> (depth % 8)) != 0
+}
@@ -0,0 +1,219 @@
+//! 금고가 동작하는 체인 네트워크와 네트워크별 파라미터
+//!
+//! `bitcoin::Network`에는 Fractal Bitcoin이 없으므로 [`ChainNetwork`]가 Bitcoin 계열 네트워크를 모두 나타내고,
+//! 주소 인코딩에 쓸 `bitcoin::Network`는 [`NetworkParams::bitcoin_network`]로 얻는다.
+//! Fractal은 Bitcoin 메인넷의 주소 형식(`bc1`)과 메시지 시작 바이트를 그대로 쓰고 블록 간격만 30초다.
+//! Inquisition은 OP_CAT 등 제안된 소프트포크를 활성화한 기본 signet이다.
+
+use crate::constants::DEFAULT_TIMELOCK_BLOCKS;
+use crate::errors::{DeFiHubError, DeFiResult};
+use bitcoin::Network;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Bitcoin 메인넷 평균 블록 간격 (초)
+pub const BITCOIN_BLOCK_INTERVAL_SECS: u64 = 600;
+
+/// Bitcoin 계열 체인 네트워크
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChainNetwork {
+    /// Bitcoin 메인넷
+    #[serde(alias = "mainnet")]
+    Bitcoin,
+    /// Bitcoin 테스트넷 3
+    Testnet,
+    /// 기본 signet (Bitcoin Core 정책)
+    Signet,
+    /// Bitcoin Inquisition 노드로 접속한 기본 signet (OP_CAT 활성)
+    Inquisition,
+    /// 로컬 regtest
+    Regtest,
+    /// Fractal Bitcoin 메인넷
+    #[serde(alias = "fractal-mainnet")]
+    Fractal,
+    /// Fractal Bitcoin 테스트넷
+    FractalTestnet,
+}
+
+/// 네트워크별 파라미터
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NetworkParams {
+    /// 설정 파일과 CLI에서 쓰는 이름
+    pub name: &'static str,
+
+    /// 주소 인코딩에 쓰는 `bitcoin::Network`
+    pub bitcoin_network: Network,
+
+    /// segwit 주소 HRP
+    pub hrp: &'static str,
+
+    /// P2P 메시지 시작 바이트
+    pub magic: [u8; 4],
+
+    /// 평균 블록 간격 (초)
+    pub block_interval_secs: u64,
+
+    /// OP_CAT 사용 가능 여부 (금고 커버넌트에 필요)
+    pub op_cat: bool,
+
+    /// 예치를 확정으로 볼 기본 확인 수
+    pub default_confirmations: u32,
+}
+
+const BITCOIN: NetworkParams = NetworkParams {
+    name: "bitcoin",
+    bitcoin_network: Network::Bitcoin,
+    hrp: "bc",
+    magic: [0xf9, 0xbe, 0xb4, 0xd9],
+    block_interval_secs: BITCOIN_BLOCK_INTERVAL_SECS,
+    op_cat: false,
+    default_confirmations: 6,
+};
+
+const TESTNET: NetworkParams = NetworkParams {
+    name: "testnet",
+    bitcoin_network: Network::Testnet,
+    hrp: "tb",
+    magic: [0x0b, 0x11, 0x09, 0x07],
+    block_interval_secs: BITCOIN_BLOCK_INTERVAL_SECS,
+    op_cat: false,
+    default_confirmations: 3,
+};
+
+const SIGNET: NetworkParams = NetworkParams {
+    name: "signet",
+    bitcoin_network: Network::Signet,
+    hrp: "tb",
+    magic: [0x0a, 0x03, 0xcf, 0x40],
+    block_interval_secs: BITCOIN_BLOCK_INTERVAL_SECS,
+    op_cat: false,
+    default_confirmations: 3,
+};
+
+const INQUISITION: NetworkParams = NetworkParams {
+    name: "inquisition",
+    op_cat: true,
+    ..SIGNET
+};
+
+const REGTEST: NetworkParams = NetworkParams {
+    name: "regtest",
+    bitcoin_network: Network::Regtest,
+    hrp: "bcrt",
+    magic: [0xfa, 0xbf, 0xb5, 0xda],
+    block_interval_secs: BITCOIN_BLOCK_INTERVAL_SECS,
+    op_cat: true,
+    default_confirmations: 1,
+};
+
+const FRACTAL: NetworkParams = NetworkParams {
+    name: "fractal",
+    bitcoin_network: Network::Bitcoin,
+    hrp: "bc",
+    magic: [0xf9, 0xbe, 0xb4, 0xd9],
+    block_interval_secs: 30,
+    op_cat: true,
+    default_confirmations: 6,
+};
+
+const FRACTAL_TESTNET: NetworkParams = NetworkParams {
+    name: "fractal-testnet",
+    default_confirmations: 3,
+    ..FRACTAL
+};
+
+impl ChainNetwork {
+    /// 모든 네트워크
+    pub const ALL: [ChainNetwork; 7] = [
+        ChainNetwork::Bitcoin,
+        ChainNetwork::Testnet,
+        ChainNetwork::Signet,
+        ChainNetwork::Inquisition,
+        ChainNetwork::Regtest,
+        ChainNetwork::Fractal,
+        ChainNetwork::FractalTestnet,
+    ];
+
+    /// 네트워크 파라미터
+    pub fn params(self) -> &'static NetworkParams {
+        match self {
+            ChainNetwork::Bitcoin => &BITCOIN,
+            ChainNetwork::Testnet => &TESTNET,
+            ChainNetwork::Signet => &SIGNET,
+            ChainNetwork::Inquisition => &INQUISITION,
+            ChainNetwork::Regtest => &REGTEST,
+            ChainNetwork::Fractal => &FRACTAL,
+            ChainNetwork::FractalTestnet => &FRACTAL_TESTNET,
+        }
+    }
+
+    /// 주소 인코딩에 쓰는 `bitcoin::Network`
+    pub fn bitcoin_network(self) -> Network {
+        self.params().bitcoin_network
+    }
+
+    /// Fractal Bitcoin 네트워크인지
+    pub fn is_fractal(self) -> bool {
+        matches!(self, ChainNetwork::Fractal | ChainNetwork::FractalTestnet)
+    }
+
+    /// OP_CAT이 없으면 [`DeFiHubError::UnsupportedChain`]
+    pub fn require_op_cat(self) -> DeFiResult<()> {
+        if self.params().op_cat {
+            return Ok(());
+        }
+        Err(DeFiHubError::UnsupportedChain(format!("OP_CAT is not enabled on {}", self)))
+    }
+
+    /// `secs`초 이상이 지나는 데 필요한 블록 수 (올림)
+    pub fn blocks_for(self, secs: u64) -> u64 {
+        secs.div_ceil(self.params().block_interval_secs)
+    }
+
+    /// `blocks` 블록의 예상 소요 시간 (초)
+    pub fn duration_secs(self, blocks: u64) -> u64 {
+        blocks.saturating_mul(self.params().block_interval_secs)
+    }
+
+    /// 기본 타임락 - Bitcoin의 [`DEFAULT_TIMELOCK_BLOCKS`]와 같은 시간을 이 네트워크의 블록 수로 환산
+    pub fn default_timelock_blocks(self) -> u16 {
+        let secs = DEFAULT_TIMELOCK_BLOCKS as u64 * BITCOIN_BLOCK_INTERVAL_SECS;
+        u16::try_from(self.blocks_for(secs)).unwrap_or(u16::MAX)
+    }
+}
+
+impl From<Network> for ChainNetwork {
+    fn from(network: Network) -> Self {
+        match network {
+            Network::Testnet => ChainNetwork::Testnet,
+            Network::Signet => ChainNetwork::Signet,
+            Network::Regtest => ChainNetwork::Regtest,
+            _ => ChainNetwork::Bitcoin,
+        }
+    }
+}
+
+impl fmt::Display for ChainNetwork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.params().name)
+    }
+}
+
+impl FromStr for ChainNetwork {
+    type Err = DeFiHubError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.to_lowercase();
+        match name.as_str() {
+            "mainnet" | "main" => return Ok(ChainNetwork::Bitcoin),
+            "fractal-mainnet" => return Ok(ChainNetwork::Fractal),
+            _ => {}
+        }
+        Self::ALL
+            .into_iter()
+            .find(|network| network.params().name == name)
+            .ok_or_else(|| DeFiHubError::UnsupportedChain(format!("Unknown network: {}", s)))
+    }
+}
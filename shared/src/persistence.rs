@@ -0,0 +1,246 @@
+//! 상태 파일의 원자적 저장, 스키마 버전, 백업
+//!
+//! 저장은 같은 디렉토리의 임시 파일에 쓰고 fsync한 뒤 이름을 바꿔 교체하므로
+//! 중간에 중단되어도 이전 파일이나 새 파일 중 하나만 남는다. 교체 전의 파일은
+//! `<파일>.bak.1`(최신) ~ `<파일>.bak.N`으로 돌려 가며 보관한다.
+//!
+//! 모든 파일의 최상위 객체에는 `schema_version`이 들어가며, 이 필드가 없는 예전 파일은
+//! 버전 0으로 보고 [`Versioned::migrate`] 훅으로 한 단계씩 현재 버전까지 올린다.
+//! 읽을 수 없거나 깨진 파일은 가장 최근의 유효한 백업으로 대신한다. 패스프레이즈 누락,
+//! 복호화 실패, 더 새 스키마 버전은 백업으로 덮지 않고 그대로 돌려준다.
+//!
+//! `_with` 변형에 [`Passphrase`]를 주면 파일을 [`EncryptedContainer`]로 암호화해 저장한다.
+//! 읽을 때는 컨테이너를 자동으로 알아보며, 암호화하지 않은 예전 파일도 그대로 읽는다.
+//! 암호화해 저장하면 평문 백업은 지워서 예전 평문 내용이 디스크에 남지 않게 한다.
+
+use crate::encryption::{EncryptedContainer, Passphrase};
+use crate::{DeFiHubError, DeFiResult};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use zeroize::Zeroizing;
+
+/// 스키마 버전 필드 이름
+pub const SCHEMA_VERSION_KEY: &str = "schema_version";
+
+/// 보관하는 백업 수
+pub const BACKUP_COUNT: usize = 3;
+
+/// 버전이 있는 상태 파일
+pub trait Versioned: Serialize + DeserializeOwned {
+    /// 현재 스키마 버전
+    const SCHEMA_VERSION: u32;
+
+    /// 에러 메시지에 쓰는 파일 종류 (예: "vault")
+    const KIND: &'static str;
+
+    /// `from` 버전 JSON을 `from + 1` 버전으로 바꾸는 마이그레이션 훅
+    fn migrate(from: u32, value: &mut Value) -> DeFiResult<()> {
+        let _ = (from, value);
+        Ok(())
+    }
+}
+
+/// `n`번째 백업 경로 (`n`은 1부터, 1이 가장 최근)
+pub fn backup_path(path: &Path, n: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".bak.{}", n));
+    PathBuf::from(name)
+}
+
+/// 원자적으로 저장 - 기존 파일이 유효한 JSON이면 백업으로 돌린 뒤 교체한다
+pub fn save<T: Versioned>(value: &T, path: &Path) -> DeFiResult<()> {
+    save_with(value, path, None)
+}
+
+/// [`save`]와 같지만 패스프레이즈가 있으면 암호화해 저장
+pub fn save_with<T: Versioned>(value: &T, path: &Path, passphrase: Option<&Passphrase>) -> DeFiResult<()> {
+    let mut json = serde_json::to_value(value)?;
+    let object = json.as_object_mut().ok_or_else(|| {
+        DeFiHubError::Configuration(format!("{} state must serialize to a JSON object", T::KIND))
+    })?;
+    object.insert(SCHEMA_VERSION_KEY.to_string(), Value::from(T::SCHEMA_VERSION));
+    let content = match passphrase {
+        Some(passphrase) => {
+            let plaintext = Zeroizing::new(serde_json::to_vec(&json)?);
+            serde_json::to_string_pretty(&passphrase.encrypt(&plaintext)?)?
+        }
+        None => serde_json::to_string_pretty(&json)?,
+    };
+
+    let write_error = |e: std::io::Error| DeFiHubError::Configuration(format!("Failed to write {} file: {}", T::KIND, e));
+    if read_json(path).is_ok() {
+        rotate_backups(path).map_err(write_error)?;
+    }
+
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let mut file = tempfile::NamedTempFile::new_in(dir).map_err(write_error)?;
+    file.write_all(content.as_bytes())
+        .and_then(|_| file.as_file().sync_all())
+        .map_err(write_error)?;
+    file.persist(path).map_err(|e| write_error(e.error))?;
+    if passphrase.is_some() {
+        remove_plaintext_backups(path).map_err(write_error)?;
+    }
+    sync_dir(dir).map_err(write_error)
+}
+
+/// 불러오기 - 파일이 없거나 깨졌으면 가장 최근의 유효한 백업을 쓴다
+pub fn load<T: Versioned>(path: &Path) -> DeFiResult<T> {
+    load_with(path, None)
+}
+
+/// [`load`]와 같지만 암호화된 파일을 패스프레이즈로 연다
+pub fn load_with<T: Versioned>(path: &Path, passphrase: Option<&Passphrase>) -> DeFiResult<T> {
+    let error = match load_file(path, passphrase) {
+        Ok(value) => return Ok(value),
+        Err(LoadError::Fatal(error)) => return Err(error),
+        Err(LoadError::Corrupt(error)) => error,
+    };
+
+    for n in 1..=BACKUP_COUNT {
+        let backup = backup_path(path, n);
+        match load_file(&backup, passphrase) {
+            Ok(value) => {
+                tracing::warn!("{} is unreadable ({}), recovered from {}", path.display(), error, backup.display());
+                return Ok(value);
+            }
+            Err(LoadError::Fatal(error)) => return Err(error),
+            Err(LoadError::Corrupt(_)) => {}
+        }
+    }
+    Err(error)
+}
+
+/// 백업 없이 한 파일만 불러오기 (복호화, 버전 확인, 마이그레이션 포함)
+pub fn load_exact<T: Versioned>(path: &Path, passphrase: Option<&Passphrase>) -> DeFiResult<T> {
+    load_file(path, passphrase).map_err(LoadError::into_inner)
+}
+
+/// 한 파일을 불러오다 난 에러
+enum LoadError {
+    /// 읽을 수 없거나 내용이 깨진 파일 - 백업으로 대신할 수 있다
+    Corrupt(DeFiHubError),
+
+    /// 패스프레이즈 누락, 복호화 실패, 더 새 스키마 - 백업으로 덮으면 안 된다
+    Fatal(DeFiHubError),
+}
+
+impl LoadError {
+    fn into_inner(self) -> DeFiHubError {
+        match self {
+            LoadError::Corrupt(error) | LoadError::Fatal(error) => error,
+        }
+    }
+}
+
+fn load_file<T: Versioned>(path: &Path, passphrase: Option<&Passphrase>) -> Result<T, LoadError> {
+    let corrupt = |error: serde_json::Error| LoadError::Corrupt(error.into());
+    let mut json = read_json(path).map_err(|e| {
+        LoadError::Corrupt(DeFiHubError::Configuration(format!(
+            "Failed to read {} file {}: {}",
+            T::KIND,
+            path.display(),
+            e
+        )))
+    })?;
+    if EncryptedContainer::detect(&json) {
+        let passphrase = passphrase.ok_or_else(|| {
+            LoadError::Fatal(DeFiHubError::PassphraseRequired(format!("{} file {}", T::KIND, path.display())))
+        })?;
+        let container: EncryptedContainer = serde_json::from_value(json).map_err(corrupt)?;
+        let plaintext = passphrase.decrypt(&container).map_err(LoadError::Fatal)?;
+        json = serde_json::from_slice(&plaintext).map_err(corrupt)?;
+    }
+    let version = match json.get(SCHEMA_VERSION_KEY) {
+        None => 0,
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or_else(|| {
+                LoadError::Corrupt(DeFiHubError::Configuration(format!(
+                    "Invalid {} schema version: {}",
+                    T::KIND,
+                    version
+                )))
+            })?,
+    };
+    if version > T::SCHEMA_VERSION {
+        return Err(LoadError::Fatal(DeFiHubError::Configuration(format!(
+            "{} file {} has schema version {}, newer than supported version {}",
+            T::KIND,
+            path.display(),
+            version,
+            T::SCHEMA_VERSION
+        ))));
+    }
+
+    for from in version..T::SCHEMA_VERSION {
+        T::migrate(from, &mut json).map_err(LoadError::Corrupt)?;
+    }
+    if let Some(object) = json.as_object_mut() {
+        object.remove(SCHEMA_VERSION_KEY);
+    }
+    serde_json::from_value(json).map_err(corrupt)
+}
+
+/// 파일이 암호화 컨테이너인지 확인
+pub fn is_encrypted(path: &Path) -> bool {
+    read_json(path).is_ok_and(|json| EncryptedContainer::detect(&json))
+}
+
+/// 파일과 그 백업 모두 삭제
+pub fn remove(path: &Path) -> std::io::Result<()> {
+    std::fs::remove_file(path)?;
+    for n in 1..=BACKUP_COUNT {
+        match std::fs::remove_file(backup_path(path, n)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn read_json(path: &Path) -> std::io::Result<Value> {
+    let content = std::fs::read(path)?;
+    serde_json::from_slice(&content).map_err(std::io::Error::other)
+}
+
+/// `.bak.N-1` → `.bak.N`, …, 현재 파일 → `.bak.1`
+fn rotate_backups(path: &Path) -> std::io::Result<()> {
+    for n in (1..BACKUP_COUNT).rev() {
+        let from = backup_path(path, n);
+        if from.exists() {
+            std::fs::rename(&from, backup_path(path, n + 1))?;
+        }
+    }
+    std::fs::copy(path, backup_path(path, 1))?;
+    Ok(())
+}
+
+/// 암호화 컨테이너가 아닌 백업 삭제 (평문으로 저장하던 때의 백업이나 깨진 백업)
+fn remove_plaintext_backups(path: &Path) -> std::io::Result<()> {
+    for n in 1..=BACKUP_COUNT {
+        let backup = backup_path(path, n);
+        if backup.exists() && !is_encrypted(&backup) {
+            std::fs::remove_file(&backup)?;
+        }
+    }
+    Ok(())
+}
+
+/// 이름 바꾸기가 디스크에 남도록 디렉토리도 fsync
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> std::io::Result<()> {
+    std::fs::File::open(dir)?.sync_all()
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> std::io::Result<()> {
+    Ok(())
+}
@@ -1,7 +1,12 @@
-use crate::{StateRoot, VaultState, BatchOperation, BridgeMessage, DeFiResult, TokenType};
+use crate::{StateRoot, VaultState, BatchOperation, BridgeMessage, DeFiHubError, DeFiResult, TokenType};
+use crate::encryption::Passphrase;
+use crate::merkle::{self, MerkleProof, SparseMerkleTree};
+use crate::persistence::{self, Versioned};
+use bitcoin::secp256k1::XOnlyPublicKey;
 use bitcoin::{Amount, OutPoint};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
 use chrono::{DateTime, Utc};
 
 /// 전체 DeFi 허브의 글로벌 상태
@@ -28,6 +33,43 @@ pub struct VaultInfo {
     pub state: VaultState,
     pub owner: String,
     pub created_at: DateTime<Utc>,
+    /// 금고를 구성하는 입금 UTXO들
+    #[serde(default)]
+    pub utxos: Vec<VaultUtxo>,
+    /// 로컬 PSBT로 교체되기 전의 금고 UTXO들 (체인에는 아직 남아 있을 수 있다)
+    #[serde(default)]
+    pub previous_outpoints: Vec<OutPoint>,
+}
+
+/// 금고 입금 UTXO
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct VaultUtxo {
+    pub outpoint: OutPoint,
+    pub amount: Amount,
+    /// 포함된 블록 높이 (미확인이면 `None`)
+    pub height: Option<u32>,
+    /// 확인 수
+    pub confirmations: u32,
+}
+
+impl VaultUtxo {
+    /// 미확인 입금 UTXO 생성
+    pub fn new(outpoint: OutPoint, amount: Amount) -> Self {
+        Self {
+            outpoint,
+            amount,
+            height: None,
+            confirmations: 0,
+        }
+    }
+
+    /// 현재 체인 높이 기준으로 확인 수 갱신
+    pub fn refresh_confirmations(&mut self, current_height: u32) {
+        self.confirmations = match self.height {
+            Some(height) if current_height >= height => current_height - height + 1,
+            _ => 0,
+        };
+    }
 }
 
 /// 롤업 상태
@@ -36,12 +78,30 @@ pub struct RollupState {
     /// 현재 상태 루트
     pub current_state_root: StateRoot,
     
-    /// 계정 잔액들 (rollup 내 주소 → 토큰 → 잔액)
+    /// 계정 잔액들 (rollup 내 주소 → 토큰 → 잔액, 파일에는 변형을 구분한 토큰 키로 저장)
+    #[serde(with = "token_balances")]
     pub balances: HashMap<String, HashMap<TokenType, u64>>,
     
-    /// 유동성 풀들 (토큰쌍 → 유동성 정보)
+    /// 유동성 풀들 (토큰쌍 → 유동성 정보, 파일에는 풀 목록으로 저장)
+    #[serde(with = "pool_list")]
     pub liquidity_pools: HashMap<(TokenType, TokenType), LiquidityPool>,
     
+    /// 계정 논스 (주소 → 다음 서명된 작업이 써야 할 논스)
+    #[serde(default)]
+    pub nonces: HashMap<String, u64>,
+    
+    /// 예치 작업에 서명하는 브릿지(시퀀서) 키 (없으면 예치를 받지 않는다)
+    #[serde(default)]
+    pub bridge_key: Option<XOnlyPublicKey>,
+    
+    /// 롤업에 연결된 금고 (금고 outpoint → 잠긴 금액)
+    #[serde(default)]
+    pub bridged_vaults: HashMap<OutPoint, Amount>,
+    
+    /// 이미 잔액으로 만든 금고 outpoint (같은 입금을 두 번 예치할 수 없다)
+    #[serde(default)]
+    pub consumed_deposits: BTreeSet<OutPoint>,
+    
     /// 처리된 배치들
     pub processed_batches: Vec<BatchOperation>,
     
@@ -83,6 +143,12 @@ pub struct ChainConnection {
     pub last_sync: DateTime<Utc>,
 }
 
+impl Default for GlobalState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl GlobalState {
     /// 새로운 글로벌 상태 생성
     pub fn new() -> Self {
@@ -94,21 +160,24 @@ impl GlobalState {
         }
     }
     
-    /// 상태를 파일에서 로드
+    /// 상태를 파일에서 로드 (깨진 파일은 최근 백업으로 대신한다)
     pub fn load_from_file(path: &str) -> DeFiResult<Self> {
-        let content = std::fs::read_to_string(path)
-            .map_err(|e| crate::DeFiHubError::Configuration(format!("Failed to read state file: {}", e)))?;
-        
-        let state: GlobalState = serde_json::from_str(&content)?;
-        Ok(state)
+        persistence::load(Path::new(path))
     }
     
-    /// 상태를 파일에 저장
+    /// 상태를 파일에 원자적으로 저장
     pub fn save_to_file(&self, path: &str) -> DeFiResult<()> {
-        let content = serde_json::to_string_pretty(self)?;
-        std::fs::write(path, content)
-            .map_err(|e| crate::DeFiHubError::Configuration(format!("Failed to write state file: {}", e)))?;
-        Ok(())
+        persistence::save(self, Path::new(path))
+    }
+    
+    /// 암호화되었을 수 있는 상태 파일 로드
+    pub fn load_from_file_with(path: &str, passphrase: Option<&Passphrase>) -> DeFiResult<Self> {
+        persistence::load_with(Path::new(path), passphrase)
+    }
+    
+    /// 패스프레이즈가 있으면 암호화해 저장
+    pub fn save_to_file_with(&self, path: &str, passphrase: Option<&Passphrase>) -> DeFiResult<()> {
+        persistence::save_with(self, Path::new(path), passphrase)
     }
     
     /// 금고 추가
@@ -136,6 +205,52 @@ impl GlobalState {
     }
 }
 
+impl Versioned for GlobalState {
+    /// 1: `schema_version` 필드 도입 (내용은 버전 0과 같다)
+    /// 2: 롤업 유동성 풀을 토큰쌍 맵 대신 풀 목록으로 저장
+    /// 3: 롤업 배치 작업을 서명된 작업으로 저장 (예전 배치는 버린다)
+    const SCHEMA_VERSION: u32 = 3;
+    const KIND: &'static str = "state";
+
+    fn migrate(from: u32, value: &mut serde_json::Value) -> DeFiResult<()> {
+        match from {
+            1 => pool_list::migrate(&mut value["rollup"]),
+            2 => drop_unsigned_batches(&mut value["rollup"]),
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Versioned for RollupState {
+    /// 2: 배치 작업을 서명된 작업으로 저장 (예전 배치는 버린다)
+    const SCHEMA_VERSION: u32 = 2;
+    const KIND: &'static str = "rollup";
+
+    fn migrate(from: u32, value: &mut serde_json::Value) -> DeFiResult<()> {
+        if from == 1 {
+            drop_unsigned_batches(value);
+        }
+        Ok(())
+    }
+}
+
+/// 서명 없는 작업으로 저장된 예전 처리 배치를 버린다
+///
+/// 예전 배치에는 서명과 논스가 없어 [`SignedOperation`](crate::SignedOperation)으로 옮길 수 없다.
+/// 잔액과 풀에는 이미 반영되어 있으므로 처리 기록만 사라진다.
+fn drop_unsigned_batches(rollup: &mut serde_json::Value) {
+    if let Some(batches) = rollup.get_mut("processed_batches") {
+        *batches = serde_json::Value::Array(Vec::new());
+    }
+}
+
+impl Default for RollupState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl RollupState {
     pub fn new() -> Self {
         Self {
@@ -146,11 +261,25 @@ impl RollupState {
             },
             balances: HashMap::new(),
             liquidity_pools: HashMap::new(),
+            nonces: HashMap::new(),
+            bridge_key: None,
+            bridged_vaults: HashMap::new(),
+            consumed_deposits: BTreeSet::new(),
             processed_batches: Vec::new(),
             next_batch_time: Utc::now() + chrono::Duration::seconds(30),
         }
     }
     
+    /// 롤업 상태 파일 로드 (깨진 파일은 최근 백업으로 대신한다)
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> DeFiResult<Self> {
+        persistence::load(path.as_ref())
+    }
+    
+    /// 롤업 상태 파일에 원자적으로 저장
+    pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> DeFiResult<()> {
+        persistence::save(self, path.as_ref())
+    }
+    
     /// 계정 잔액 조회
     pub fn get_balance(&self, address: &str, token: &TokenType) -> u64 {
         self.balances
@@ -164,9 +293,256 @@ impl RollupState {
     pub fn set_balance(&mut self, address: String, token: TokenType, amount: u64) {
         self.balances
             .entry(address)
-            .or_insert_with(HashMap::new)
+            .or_default()
             .insert(token, amount);
     }
+    
+    /// 계정의 다음 논스 조회
+    pub fn get_nonce(&self, address: &str) -> u64 {
+        self.nonces.get(address).copied().unwrap_or(0)
+    }
+    
+    /// 계정 논스를 하나 올린다
+    pub fn increment_nonce(&mut self, address: &str) {
+        *self.nonces.entry(address.to_string()).or_insert(0) += 1;
+    }
+    
+    /// `Bridged` 상태의 금고를 예치할 수 있는 금고로 등록
+    pub fn register_bridged_vault(&mut self, vault: &VaultInfo) -> DeFiResult<()> {
+        if !matches!(vault.state, VaultState::Bridged { .. }) {
+            return Err(DeFiHubError::InvalidVaultState {
+                current: vault.state.name().to_string(),
+                expected: "Bridged".to_string(),
+            });
+        }
+        match self.bridged_vaults.get(&vault.outpoint) {
+            Some(amount) if *amount != vault.amount => Err(DeFiHubError::BridgeVerification(format!(
+                "Vault {} is already bridged with {}",
+                vault.outpoint, amount
+            ))),
+            _ => {
+                self.bridged_vaults.insert(vault.outpoint, vault.amount);
+                Ok(())
+            }
+        }
+    }
+    
+    /// 상태 트리에서 계정의 토큰 잔액 리프 키
+    pub fn balance_leaf_key(address: &str, token: &TokenType) -> [u8; 32] {
+        let mut key = length_prefixed(address.as_bytes());
+        encode_token(&mut key, token);
+        merkle::hash_parts(&[BALANCE_LEAF, &key])
+    }
+
+    /// 잔액 리프 값 해시 (0 잔액은 빈 리프)
+    pub fn balance_leaf_value(amount: u64) -> [u8; 32] {
+        if amount == 0 {
+            return merkle::EMPTY_HASH;
+        }
+        merkle::hash_parts(&[BALANCE_LEAF, &amount.to_le_bytes()])
+    }
+
+    /// 현재 상태 트리에서 계정의 토큰 잔액을 보이는 증명
+    pub fn prove_balance(&self, address: &str, token: &TokenType) -> MerkleProof {
+        self.state_tree().prove(&Self::balance_leaf_key(address, token))
+    }
+
+    /// 잔액, 유동성 풀, 계정 논스, 브릿지 키와 연결된 금고, 소비된 예치를 리프로 하는 상태 트리
+    ///
+    /// 예치 실행 결과를 정하는 값은 모두 리프로 들어가므로 같은 루트에서 같은 배치를 실행하면 같은 결과가 된다.
+    /// 0 잔액/논스는 빈 리프와 같다.
+    pub fn state_tree(&self) -> SparseMerkleTree {
+        let mut tree = SparseMerkleTree::new();
+        for (address, tokens) in &self.balances {
+            for (token, amount) in tokens {
+                if *amount == 0 {
+                    continue;
+                }
+                tree.insert(Self::balance_leaf_key(address, token), Self::balance_leaf_value(*amount));
+            }
+        }
+        for (address, nonce) in &self.nonces {
+            if *nonce == 0 {
+                continue;
+            }
+            tree.insert(
+                merkle::hash_parts(&[NONCE_LEAF, &length_prefixed(address.as_bytes())]),
+                merkle::hash_parts(&[NONCE_LEAF, &nonce.to_le_bytes()]),
+            );
+        }
+        if let Some(bridge_key) = &self.bridge_key {
+            tree.insert(
+                merkle::hash_parts(&[BRIDGE_KEY_LEAF]),
+                merkle::hash_parts(&[BRIDGE_KEY_LEAF, &bridge_key.serialize()]),
+            );
+        }
+        for (outpoint, amount) in &self.bridged_vaults {
+            tree.insert(
+                merkle::hash_parts(&[BRIDGED_LEAF, &bitcoin::consensus::serialize(outpoint)]),
+                merkle::hash_parts(&[BRIDGED_LEAF, &amount.to_sat().to_le_bytes()]),
+            );
+        }
+        for outpoint in &self.consumed_deposits {
+            let key = bitcoin::consensus::serialize(outpoint);
+            tree.insert(merkle::hash_parts(&[DEPOSIT_LEAF, &key]), merkle::hash_parts(&[DEPOSIT_LEAF, &key]));
+        }
+        for ((token_a, token_b), pool) in &self.liquidity_pools {
+            let mut key = Vec::new();
+            encode_token(&mut key, token_a);
+            encode_token(&mut key, token_b);
+            tree.insert(merkle::hash_parts(&[POOL_LEAF, &key]), merkle::hash_parts(&[POOL_LEAF, &pool.encode()]));
+        }
+        tree
+    }
+    
+    /// 상태 트리의 루트 해시 (실행 결과를 정하는 상태만 반영하고 시각은 들어가지 않는다)
+    pub fn state_hash(&self) -> [u8; 32] {
+        self.state_tree().root()
+    }
+    
+    /// 현재 잔액/풀로 다음 높이의 상태 루트를 만든다 (`timestamp`는 배치 시각)
+    pub fn next_state_root(&self, timestamp: DateTime<Utc>) -> StateRoot {
+        StateRoot {
+            hash: self.state_hash(),
+            height: self.current_state_root.height + 1,
+            timestamp,
+        }
+    }
+}
+
+/// 상태 트리 리프 도메인
+const BALANCE_LEAF: &[u8] = b"rollup/balance";
+const POOL_LEAF: &[u8] = b"rollup/pool";
+const NONCE_LEAF: &[u8] = b"rollup/nonce";
+const DEPOSIT_LEAF: &[u8] = b"rollup/deposit";
+const BRIDGED_LEAF: &[u8] = b"rollup/bridged";
+const BRIDGE_KEY_LEAF: &[u8] = b"rollup/bridge_key";
+
+impl LiquidityPool {
+    /// 상태 트리 리프 값 인코딩
+    fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        encode_token(&mut out, &self.token_a);
+        encode_token(&mut out, &self.token_b);
+        for value in [self.reserve_a, self.reserve_b, self.total_liquidity, self.fee_rate.to_bits()] {
+            out.extend_from_slice(&value.to_le_bytes());
+        }
+        out
+    }
+}
+
+/// 유동성 풀 맵을 풀 목록으로 직렬화 (JSON 객체 키는 문자열이어야 한다)
+mod pool_list {
+    use super::LiquidityPool;
+    use crate::TokenType;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::collections::HashMap;
+
+    type Pools = HashMap<(TokenType, TokenType), LiquidityPool>;
+
+    pub fn serialize<S: Serializer>(pools: &Pools, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut list: Vec<_> = pools.values().collect();
+        list.sort_by_key(|pool| (pool.token_a.to_string(), pool.token_b.to_string()));
+        list.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Pools, D::Error> {
+        let list = Vec::<LiquidityPool>::deserialize(deserializer)?;
+        Ok(list
+            .into_iter()
+            .map(|pool| ((pool.token_a.clone(), pool.token_b.clone()), pool))
+            .collect())
+    }
+
+    /// 맵으로 저장된 예전 `liquidity_pools`를 목록으로 바꾼다
+    pub fn migrate(rollup: &mut serde_json::Value) {
+        if let Some(pools) = rollup.get_mut("liquidity_pools") {
+            if let Some(map) = pools.as_object() {
+                *pools = serde_json::Value::Array(map.values().cloned().collect());
+            }
+        }
+    }
+}
+
+/// 토큰별 잔액 맵을 문자열 키로 직렬화 (커스텀 토큰도 JSON 객체 키가 되도록)
+///
+/// [`encode_token`]처럼 변형을 구분해 `WBTC`/`USDC`는 그대로, 커스텀 토큰은 `custom:<심볼>`로 쓴다.
+/// 심볼은 바꾸지 않으므로 `Custom("wbtc")`나 빈 심볼도 그대로 돌아오고, `WBTC`/`USDC` 키는 예전 형식과 같다.
+mod token_balances {
+    use crate::TokenType;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::collections::{BTreeMap, HashMap};
+
+    type Balances = HashMap<String, HashMap<TokenType, u64>>;
+
+    const CUSTOM_PREFIX: &str = "custom:";
+
+    fn key(token: &TokenType) -> String {
+        match token {
+            TokenType::WBTC => "WBTC".to_string(),
+            TokenType::USDC => "USDC".to_string(),
+            TokenType::Custom(symbol) => format!("{}{}", CUSTOM_PREFIX, symbol),
+        }
+    }
+
+    fn token(key: &str) -> Option<TokenType> {
+        match key {
+            "WBTC" => Some(TokenType::WBTC),
+            "USDC" => Some(TokenType::USDC),
+            _ => key.strip_prefix(CUSTOM_PREFIX).map(|symbol| TokenType::Custom(symbol.to_string())),
+        }
+    }
+
+    pub fn serialize<S: Serializer>(balances: &Balances, serializer: S) -> Result<S::Ok, S::Error> {
+        let by_key: BTreeMap<&String, BTreeMap<String, u64>> = balances
+            .iter()
+            .map(|(account, tokens)| (account, tokens.iter().map(|(token, amount)| (key(token), *amount)).collect()))
+            .collect();
+        by_key.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Balances, D::Error> {
+        let by_key = HashMap::<String, HashMap<String, u64>>::deserialize(deserializer)?;
+        by_key
+            .into_iter()
+            .map(|(account, tokens)| {
+                let tokens = tokens
+                    .into_iter()
+                    .map(|(key, amount)| {
+                        token(&key)
+                            .map(|token| (token, amount))
+                            .ok_or_else(|| serde::de::Error::custom(format!("invalid token key: {:?}", key)))
+                    })
+                    .collect::<Result<_, _>>()?;
+                Ok((account, tokens))
+            })
+            .collect()
+    }
+}
+
+/// 길이(u32 LE)를 앞에 붙인 바이트
+pub(crate) fn length_prefixed(bytes: &[u8]) -> Vec<u8> {
+    let mut out = (bytes.len() as u32).to_le_bytes().to_vec();
+    out.extend_from_slice(bytes);
+    out
+}
+
+/// 토큰의 정규 인코딩 (태그 1바이트 + 커스텀 심볼)
+pub(crate) fn encode_token(out: &mut Vec<u8>, token: &TokenType) {
+    match token {
+        TokenType::WBTC => out.push(0),
+        TokenType::USDC => out.push(1),
+        TokenType::Custom(symbol) => {
+            out.push(2);
+            out.extend_from_slice(&length_prefixed(symbol.as_bytes()));
+        }
+    }
+}
+
+impl Default for BridgeState {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl BridgeState {
@@ -2,8 +2,9 @@ use bitcoin::{Amount, OutPoint};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
+use crate::account::SignedOperation;
 
-/// DeFi 허브의 핵심 상태 타입들
+// DeFi 허브의 핵심 상태 타입들
 
 /// BTC 금고 상태
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -15,10 +16,18 @@ pub enum VaultState {
         withdrawal_address: String,
         amount: Amount,
         trigger_time: DateTime<Utc>,
+        /// 트리거 시점의 블록 높이
+        trigger_height: u32,
         timelock_blocks: u16,
     },
     /// 출금 완료
     Completed,
+    /// 회수 키로 콜드 주소에 회수됨
+    Recovered {
+        recovery_address: String,
+        amount: Amount,
+        recovery_time: DateTime<Utc>,
+    },
     /// BitVMX 롤업과 연결된 상태
     Bridged {
         rollup_state_root: StateRoot,
@@ -26,11 +35,24 @@ pub enum VaultState {
     },
 }
 
+impl VaultState {
+    /// 상태 이름 (필터링/표시용)
+    pub fn name(&self) -> &'static str {
+        match self {
+            VaultState::Inactive => "Inactive",
+            VaultState::Triggered { .. } => "Triggered",
+            VaultState::Completed => "Completed",
+            VaultState::Recovered { .. } => "Recovered",
+            VaultState::Bridged { .. } => "Bridged",
+        }
+    }
+}
+
 /// 미니 롤업 배치 처리
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct BatchOperation {
     pub id: Uuid,
-    pub operations: Vec<Operation>,
+    pub operations: Vec<SignedOperation>,
     pub timestamp: DateTime<Utc>,
     pub previous_state_root: StateRoot,
     pub new_state_root: StateRoot,
@@ -68,6 +90,13 @@ pub enum Operation {
         amount_b: u64,
         provider: String,
     },
+    /// 유동성 회수 (LP 지분 소각)
+    RemoveLiquidity {
+        token_a: TokenType,
+        token_b: TokenType,
+        liquidity: u64,
+        provider: String,
+    },
 }
 
 /// 토큰 타입 (롤업 내)
@@ -81,6 +110,30 @@ pub enum TokenType {
     Custom(String),
 }
 
+impl std::str::FromStr for TokenType {
+    type Err = crate::DeFiHubError;
+
+    /// `WBTC`/`USDC`는 대소문자를 가리지 않고, 그 밖의 심볼은 커스텀 토큰으로 읽는다
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_uppercase().as_str() {
+            "" => Err(crate::DeFiHubError::Configuration("Token symbol is empty".to_string())),
+            "WBTC" => Ok(TokenType::WBTC),
+            "USDC" => Ok(TokenType::USDC),
+            _ => Ok(TokenType::Custom(s.trim().to_string())),
+        }
+    }
+}
+
+impl std::fmt::Display for TokenType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TokenType::WBTC => f.write_str("WBTC"),
+            TokenType::USDC => f.write_str("USDC"),
+            TokenType::Custom(symbol) => f.write_str(symbol),
+        }
+    }
+}
+
 /// 상태 루트
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct StateRoot {